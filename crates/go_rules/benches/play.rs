@@ -0,0 +1,35 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use go_rules::{GameConfig, GameState, Move, Ruleset};
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+/// Play `moves` random legal moves on a 19x19 board, as bulk import or self-play would.
+fn random_game(moves: usize, seed: u64) -> GameState {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut game = GameState::new(GameConfig {
+        size: 19,
+        ruleset: Ruleset::Chinese,
+        ..Default::default()
+    });
+    for _ in 0..moves {
+        let color = game.to_move();
+        let point = game.legal_moves().choose(&mut rng).copied();
+        if game.play(Move { color, point }).is_err() || point.is_none() {
+            break;
+        }
+    }
+    game
+}
+
+fn bench_play(c: &mut Criterion) {
+    c.bench_function("random_game_200_moves", |b| {
+        b.iter(|| random_game(black_box(200), 7))
+    });
+
+    let midgame = random_game(150, 11);
+    c.bench_function("legal_moves_midgame", |b| {
+        b.iter(|| black_box(&midgame).legal_moves())
+    });
+}
+
+criterion_group!(benches, bench_play);
+criterion_main!(benches);
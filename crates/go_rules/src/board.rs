@@ -2,12 +2,26 @@ use std::sync::Arc;
 
 use crate::{color::Color, error::RuleViolation, point::Point, zobrist::ZobristTable};
 
+/// Marks an empty intersection in the chain tables.
+const NO_CHAIN: usize = usize::MAX;
+
+/// Go board that tracks chains incrementally.
+///
+/// Every stone belongs to a chain, stored as a circular linked list through `next` with a
+/// representative stone in `head`. The representative carries the chain's stone count and
+/// pseudo-liberties: the number of (stone, empty neighbour) pairs. A chain has no liberties
+/// exactly when its pseudo-liberty count is zero, so captures and suicide are detected
+/// without flood fills.
 #[derive(Clone, Debug)]
 pub struct Board {
     size: usize,
     cells: Vec<Option<Color>>,
     zobrist: Arc<ZobristTable>,
     hash: u64,
+    head: Vec<usize>,
+    next: Vec<usize>,
+    chain_len: Vec<u32>,
+    pseudo_liberties: Vec<u32>,
 }
 
 #[derive(Debug)]
@@ -25,6 +39,10 @@ impl Board {
             cells,
             zobrist,
             hash: 0,
+            head: vec![NO_CHAIN; size * size],
+            next: (0..size * size).collect(),
+            chain_len: vec![0; size * size],
+            pseudo_liberties: vec![0; size * size],
         }
     }
 
@@ -40,7 +58,13 @@ impl Board {
 
     #[inline]
     pub fn position_key(&self, to_move: Color) -> u64 {
-        self.hash ^ self.zobrist.to_move(to_move.index())
+        self.position_key_for(self.hash, to_move)
+    }
+
+    /// Position key for a board hash obtained from [`Board::hash_after_play`].
+    #[inline]
+    pub fn position_key_for(&self, hash: u64, to_move: Color) -> u64 {
+        hash ^ self.zobrist.to_move(to_move.index())
     }
 
     #[inline]
@@ -53,14 +77,17 @@ impl Board {
         self.get(point).is_none()
     }
 
+    /// Put a stone on, or take it off, a single intersection without capturing anything.
     pub fn set(&mut self, index: usize, color: Option<Color>) {
-        if let Some(current) = self.cells[index] {
-            self.hash ^= self.zobrist.stone(index, current.index());
+        if self.cells[index] == color {
+            return;
         }
-        if let Some(new_color) = color {
-            self.hash ^= self.zobrist.stone(index, new_color.index());
+        if self.cells[index].is_some() {
+            self.remove_stone(index);
+        }
+        if let Some(color) = color {
+            self.add_stone(index, color);
         }
-        self.cells[index] = color;
     }
 
     pub fn after_play(
@@ -68,76 +95,130 @@ impl Board {
         color: Color,
         point: Point,
     ) -> Result<(Self, Vec<Point>), RuleViolation> {
+        self.after_play_with(color, point, false)
+    }
+
+    /// Like [`Board::after_play`], but when `allow_suicide` is set a move that leaves its own
+    /// group without liberties removes that group. The removed stones are returned as captured
+    /// and include `point` itself. Single-stone suicide is always rejected.
+    pub fn after_play_with(
+        &self,
+        color: Color,
+        point: Point,
+        allow_suicide: bool,
+    ) -> Result<(Self, Vec<Point>), RuleViolation> {
+        let mut next = self.clone();
+        let captured = next.play(color, point, allow_suicide)?;
+        Ok((next, captured))
+    }
+
+    /// Play a stone in place and return the captured stones; see [`Board::after_play_with`].
+    pub fn play(
+        &mut self,
+        color: Color,
+        point: Point,
+        allow_suicide: bool,
+    ) -> Result<Vec<Point>, RuleViolation> {
+        self.hash_after_play(color, point, allow_suicide)?;
         let index = point.to_index(self.size);
-        if index >= self.cells.len() {
+        self.add_stone(index, color);
+
+        let mut captured = Vec::new();
+        for neighbor in self.neighbors(index) {
+            if self.cells[neighbor] == Some(color.opponent())
+                && self.pseudo_liberties[self.head[neighbor]] == 0
+            {
+                self.remove_chain(self.head[neighbor], &mut captured);
+            }
+        }
+        if self.pseudo_liberties[self.head[index]] == 0 {
+            self.remove_chain(self.head[index], &mut captured);
+        }
+        Ok(captured
+            .into_iter()
+            .map(|stone| Point::from_index(stone, self.size))
+            .collect())
+    }
+
+    /// Check a move and return the board hash it would produce, without touching the board.
+    /// Only chains that would be captured are walked, so this is cheap enough to run on every
+    /// empty point.
+    pub fn hash_after_play(
+        &self,
+        color: Color,
+        point: Point,
+        allow_suicide: bool,
+    ) -> Result<u64, RuleViolation> {
+        let index = point.to_index(self.size);
+        if point.x as usize >= self.size || index >= self.cells.len() {
             return Err(RuleViolation::OutOfBounds { point });
         }
         if self.cells[index].is_some() {
             return Err(RuleViolation::Occupied { point });
         }
 
-        let mut next = self.clone();
-        next.set(index, Some(color));
-
-        let neighbors: Vec<usize> = self.neighbors(index).collect();
-        let mut captured_points: Vec<Point> = Vec::new();
-        for neighbor in neighbors {
-            if let Some(stone_color) = next.cells[neighbor] {
-                if stone_color == color {
-                    continue;
-                }
-                let group = next.group_at(neighbor);
-                if group.liberties.is_empty() {
-                    for stone_idx in group.stones {
-                        next.set(stone_idx, None);
-                        captured_points.push(Point::from_index(stone_idx, next.size));
-                    }
+        // Neighbouring chains and how many of their pseudo-liberties `point` accounts for.
+        let mut chains = [(NO_CHAIN, 0u32); 4];
+        let mut chain_count = 0;
+        let mut has_liberty = false;
+        for neighbor in self.neighbors(index) {
+            if self.cells[neighbor].is_none() {
+                has_liberty = true;
+                continue;
+            }
+            let head = self.head[neighbor];
+            match chains[..chain_count].iter_mut().find(|(h, _)| *h == head) {
+                Some((_, shared)) => *shared += 1,
+                None => {
+                    chains[chain_count] = (head, 1);
+                    chain_count += 1;
                 }
             }
         }
 
-        let own_group = next.group_at(index);
-        if own_group.liberties.is_empty() {
+        let mut hash = self.hash ^ self.zobrist.stone(index, color.index());
+        let mut has_friend = false;
+        for &(head, shared) in &chains[..chain_count] {
+            let outside = self.pseudo_liberties[head] > shared;
+            if self.cells[head] == Some(color) {
+                has_friend = true;
+                has_liberty |= outside;
+            } else if !outside {
+                hash ^= self.chain_hash(head);
+                has_liberty = true;
+            }
+        }
+        if has_liberty {
+            return Ok(hash);
+        }
+        if !allow_suicide || !has_friend {
             return Err(RuleViolation::Suicide { point });
         }
 
-        Ok((next, captured_points))
+        // Multi-stone suicide: the new stone and every friendly neighbour disappear.
+        hash ^= self.zobrist.stone(index, color.index());
+        for &(head, _) in &chains[..chain_count] {
+            if self.cells[head] == Some(color) {
+                hash ^= self.chain_hash(head);
+            }
+        }
+        Ok(hash)
     }
 
     pub fn group_at(&self, start: usize) -> GroupInfo {
-        let color = match self.cells[start] {
-            Some(color) => color,
-            None => {
-                return GroupInfo {
-                    stones: Vec::new(),
-                    liberties: Vec::new(),
-                }
-            }
-        };
+        if self.cells[start].is_none() {
+            return GroupInfo {
+                stones: Vec::new(),
+                liberties: Vec::new(),
+            };
+        }
 
-        let mut stack = vec![start];
-        let mut visited = vec![false; self.cells.len()];
-        visited[start] = true;
-        let mut stones = Vec::new();
+        let stones = self.chain_stones(self.head[start]);
         let mut liberties = Vec::new();
-
-        while let Some(index) = stack.pop() {
-            stones.push(index);
-            for neighbor in self.neighbors(index) {
-                match self.cells[neighbor] {
-                    Some(neighbor_color) if neighbor_color == color => {
-                        if !visited[neighbor] {
-                            visited[neighbor] = true;
-                            stack.push(neighbor);
-                        }
-                    }
-                    Some(_) => {}
-                    None => {
-                        if !visited[neighbor] {
-                            visited[neighbor] = true;
-                            liberties.push(neighbor);
-                        }
-                    }
+        for &stone in &stones {
+            for neighbor in self.neighbors(stone) {
+                if self.cells[neighbor].is_none() && !liberties.contains(&neighbor) {
+                    liberties.push(neighbor);
                 }
             }
         }
@@ -193,23 +274,144 @@ impl Board {
         &self.cells
     }
 
-    pub fn neighbors(&self, index: usize) -> impl Iterator<Item = usize> + '_ {
+    pub fn neighbors(&self, index: usize) -> impl Iterator<Item = usize> {
         let x = index % self.size;
         let y = index / self.size;
         let size = self.size;
-        let mut neighbors = Vec::with_capacity(4);
-        if x > 0 {
-            neighbors.push(index - 1);
+        let mut neighbors = [0; 4];
+        let mut count = 0;
+        for (present, neighbor) in [
+            (x > 0, index.wrapping_sub(1)),
+            (x + 1 < size, index + 1),
+            (y > 0, index.wrapping_sub(size)),
+            (y + 1 < size, index + size),
+        ] {
+            if present {
+                neighbors[count] = neighbor;
+                count += 1;
+            }
+        }
+        neighbors.into_iter().take(count)
+    }
+
+    fn set_cell(&mut self, index: usize, color: Option<Color>) {
+        if let Some(current) = self.cells[index] {
+            self.hash ^= self.zobrist.stone(index, current.index());
+        }
+        if let Some(new_color) = color {
+            self.hash ^= self.zobrist.stone(index, new_color.index());
+        }
+        self.cells[index] = color;
+    }
+
+    fn chain_stones(&self, head: usize) -> Vec<usize> {
+        let mut stones = Vec::with_capacity(self.chain_len[head] as usize);
+        let mut stone = head;
+        loop {
+            stones.push(stone);
+            stone = self.next[stone];
+            if stone == head {
+                return stones;
+            }
+        }
+    }
+
+    fn chain_hash(&self, head: usize) -> u64 {
+        let color = self.cells[head].expect("chain head holds a stone").index();
+        let mut hash = 0;
+        let mut stone = head;
+        loop {
+            hash ^= self.zobrist.stone(stone, color);
+            stone = self.next[stone];
+            if stone == head {
+                return hash;
+            }
         }
-        if x + 1 < size {
-            neighbors.push(index + 1);
+    }
+
+    fn add_stone(&mut self, index: usize, color: Color) {
+        self.set_cell(index, Some(color));
+        for neighbor in self.neighbors(index) {
+            if self.cells[neighbor].is_some() {
+                self.pseudo_liberties[self.head[neighbor]] -= 1;
+            }
+        }
+        self.link_stone(index);
+    }
+
+    /// Make `index` a chain of its own and merge it with adjacent chains of the same colour.
+    fn link_stone(&mut self, index: usize) {
+        let color = self.cells[index];
+        self.head[index] = index;
+        self.next[index] = index;
+        self.chain_len[index] = 1;
+        self.pseudo_liberties[index] = self
+            .neighbors(index)
+            .filter(|&neighbor| self.cells[neighbor].is_none())
+            .count() as u32;
+        for neighbor in self.neighbors(index) {
+            let (own, other) = (self.head[index], self.head[neighbor]);
+            if self.cells[neighbor] == color && other != NO_CHAIN && other != own {
+                self.merge_chains(own, other);
+            }
         }
-        if y > 0 {
-            neighbors.push(index - size);
+    }
+
+    fn merge_chains(&mut self, a: usize, b: usize) {
+        let (keep, absorb) = if self.chain_len[a] >= self.chain_len[b] {
+            (a, b)
+        } else {
+            (b, a)
+        };
+        let mut stone = absorb;
+        loop {
+            self.head[stone] = keep;
+            stone = self.next[stone];
+            if stone == absorb {
+                break;
+            }
         }
-        if y + 1 < size {
-            neighbors.push(index + size);
+        self.next.swap(keep, absorb);
+        self.chain_len[keep] += self.chain_len[absorb];
+        self.pseudo_liberties[keep] += self.pseudo_liberties[absorb];
+    }
+
+    /// Take a whole chain off the board, appending its stones to `removed`.
+    fn remove_chain(&mut self, head: usize, removed: &mut Vec<usize>) {
+        let stones = self.chain_stones(head);
+        for &stone in &stones {
+            self.set_cell(stone, None);
+            self.head[stone] = NO_CHAIN;
+            self.next[stone] = stone;
+        }
+        for &stone in &stones {
+            for neighbor in self.neighbors(stone) {
+                if self.cells[neighbor].is_some() {
+                    self.pseudo_liberties[self.head[neighbor]] += 1;
+                }
+            }
+        }
+        removed.extend(stones);
+    }
+
+    /// Take a single stone off; the rest of its chain may split, so it is relinked stone by
+    /// stone.
+    fn remove_stone(&mut self, index: usize) {
+        let stones = self.chain_stones(self.head[index]);
+        for &stone in &stones {
+            self.head[stone] = NO_CHAIN;
+            self.next[stone] = stone;
+        }
+        self.set_cell(index, None);
+        for neighbor in self.neighbors(index) {
+            if self.cells[neighbor].is_some() && self.head[neighbor] != NO_CHAIN {
+                self.pseudo_liberties[self.head[neighbor]] += 1;
+            }
+        }
+        for stone in stones {
+            if stone != index {
+                self.link_stone(stone);
+            }
         }
-        neighbors.into_iter()
     }
 }
@@ -1,6 +1,6 @@
 use serde::Serialize;
 
-use crate::{color::Color, point::Point};
+use crate::{color::Color, game::GamePhase, point::Point, tree::NodeId};
 
 #[derive(Debug, thiserror::Error, Clone, Serialize)]
 #[serde(tag = "kind", rename_all = "snake_case")]
@@ -9,12 +9,34 @@ pub enum RuleViolation {
     Occupied { point: Point },
     #[error("move at {point:?} is suicidal")]
     Suicide { point: Point },
+    #[error("move at {point:?} retakes a ko immediately")]
+    Ko { point: Point },
     #[error("move repeats a previous board state (superko)")]
     SuperKo,
+    #[error("game ended without result after a repeated position")]
+    NoResult,
     #[error("move out of bounds at {point:?}")]
     OutOfBounds { point: Point },
     #[error("move from wrong player; expected {expected:?}")]
     WrongPlayer { expected: Color },
+    #[error("move {requested} is out of range; {available} moves available")]
+    MoveOutOfRange { requested: usize, available: usize },
+    #[error("handicap of {stones} stones is not supported on a {size}x{size} board")]
+    InvalidHandicap { stones: u8, size: usize },
+    #[error("{remaining} handicap stones must be placed before play starts")]
+    HandicapPending { remaining: u8 },
+    #[error("no handicap stones are left to place")]
+    NoHandicapPending,
+    #[error("moves cannot be played during the {phase:?} phase")]
+    NotPlaying { phase: GamePhase },
+    #[error("dead stones can only be marked during scoring, not the {phase:?} phase")]
+    NotScoring { phase: GamePhase },
+    #[error("no stone at {point:?}")]
+    EmptyPoint { point: Point },
+    #[error("game tree has no node {node}")]
+    UnknownNode { node: NodeId },
+    #[error("the root node of a game tree cannot be deleted")]
+    DeleteRoot,
 }
 
 impl RuleViolation {
@@ -22,9 +44,20 @@ impl RuleViolation {
         match self {
             RuleViolation::Occupied { .. } => "occupied",
             RuleViolation::Suicide { .. } => "suicide",
+            RuleViolation::Ko { .. } => "ko",
             RuleViolation::SuperKo => "superko",
+            RuleViolation::NoResult => "no_result",
             RuleViolation::OutOfBounds { .. } => "out_of_bounds",
             RuleViolation::WrongPlayer { .. } => "wrong_player",
+            RuleViolation::MoveOutOfRange { .. } => "move_out_of_range",
+            RuleViolation::InvalidHandicap { .. } => "invalid_handicap",
+            RuleViolation::HandicapPending { .. } => "handicap_pending",
+            RuleViolation::NoHandicapPending => "no_handicap_pending",
+            RuleViolation::NotPlaying { .. } => "not_playing",
+            RuleViolation::NotScoring { .. } => "not_scoring",
+            RuleViolation::EmptyPoint { .. } => "empty_point",
+            RuleViolation::UnknownNode { .. } => "unknown_node",
+            RuleViolation::DeleteRoot => "delete_root",
         }
     }
 }
@@ -1,15 +1,81 @@
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashSet};
 
 use serde::{Deserialize, Serialize};
 
-use crate::{board::Board, color::Color, error::RuleViolation, point::Point};
+use crate::{
+    board::Board,
+    color::Color,
+    error::RuleViolation,
+    handicap::{fixed_handicap_points, HandicapPlacement},
+    ownership::OwnershipEstimate,
+    point::Point,
+    ruleset::{KoRule, Ruleset, ScoringMethod},
+    scoring::{score_position, PointScore, ScoreInput},
+    tree::Setup,
+};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
-#[serde(default)]
+#[serde(from = "StoredConfig")]
 pub struct GameConfig {
     pub size: usize,
     pub komi: f64,
-    pub superko: bool,
+    /// Overrides the ruleset's ko rule when set.
+    pub ko_rule: Option<KoRule>,
+    pub handicap: u8,
+    pub handicap_placement: HandicapPlacement,
+    pub ruleset: Ruleset,
+}
+
+impl GameConfig {
+    pub fn effective_ko_rule(&self) -> KoRule {
+        self.ko_rule.unwrap_or(self.ruleset.ko_rule())
+    }
+}
+
+/// [`GameConfig`] as sent by clients, which may still use the `superko` flag that
+/// [`GameConfig::ko_rule`] replaced: `false` asks for simple ko, `true` for the ruleset's rule.
+#[derive(Deserialize)]
+#[serde(default)]
+struct StoredConfig {
+    size: usize,
+    komi: f64,
+    ko_rule: Option<KoRule>,
+    superko: Option<bool>,
+    handicap: u8,
+    handicap_placement: HandicapPlacement,
+    ruleset: Ruleset,
+}
+
+impl Default for StoredConfig {
+    fn default() -> Self {
+        let config = GameConfig::default();
+        Self {
+            size: config.size,
+            komi: config.komi,
+            ko_rule: config.ko_rule,
+            superko: None,
+            handicap: config.handicap,
+            handicap_placement: config.handicap_placement,
+            ruleset: config.ruleset,
+        }
+    }
+}
+
+impl From<StoredConfig> for GameConfig {
+    fn from(stored: StoredConfig) -> Self {
+        let ko_rule = match (stored.ko_rule, stored.superko) {
+            (None, Some(false)) => Some(KoRule::Simple),
+            (ko_rule, _) => ko_rule,
+        };
+        Self {
+            size: stored.size,
+            komi: stored.komi,
+            ko_rule,
+            handicap: stored.handicap,
+            handicap_placement: stored.handicap_placement,
+            ruleset: stored.ruleset,
+        }
+    }
 }
 
 impl Default for GameConfig {
@@ -17,12 +83,15 @@ impl Default for GameConfig {
         Self {
             size: 19,
             komi: 6.5,
-            superko: true,
+            ko_rule: None,
+            handicap: 0,
+            handicap_placement: HandicapPlacement::Fixed,
+            ruleset: Ruleset::default(),
         }
     }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Move {
     pub color: Color,
     pub point: Option<Point>,
@@ -53,6 +122,8 @@ pub struct MoveOutcome {
     pub captures: Captures,
     pub to_move: Color,
     pub game_over: bool,
+    pub no_result: bool,
+    pub phase: GamePhase,
     pub consecutive_passes: u8,
     pub last_move: MoveRecordSnapshot,
     pub legal_moves: Vec<Point>,
@@ -60,12 +131,112 @@ pub struct MoveOutcome {
 
 #[derive(Clone, Debug, Serialize)]
 pub struct ScoreSummary {
+    pub method: ScoringMethod,
     pub black_score: f64,
     pub white_score: f64,
     pub territory_black: u32,
     pub territory_white: u32,
+    pub stones_black: u32,
+    pub stones_white: u32,
+    /// Black stones marked dead, counted as prisoners for White under territory scoring.
+    pub dead_black: u32,
+    pub dead_white: u32,
     pub captures: Captures,
     pub komi: f64,
+    pub handicap_compensation: f64,
+    /// Per-intersection breakdown in board index order.
+    pub points: Vec<PointScore>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GamePhase {
+    Playing,
+    /// Both players passed; dead stones are being marked.
+    Scoring,
+    /// Both players accepted the dead stones, or the game ended without result.
+    Finished,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ScoringSnapshot {
+    pub phase: GamePhase,
+    pub dead_stones: Vec<Point>,
+    pub black_accepted: bool,
+    pub white_accepted: bool,
+    pub score: ScoreSummary,
+}
+
+/// How a finished game was decided.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum GameResult {
+    Resignation {
+        winner: Color,
+    },
+    /// An agreed score; no winner means jigo.
+    Score {
+        winner: Option<Color>,
+        margin: f64,
+    },
+    /// The loser ran out of time; decided outside the rules, for instance by a match clock.
+    Time {
+        winner: Color,
+    },
+    /// The loser forfeited, for instance by not showing up or by an illegal engine move.
+    Forfeit {
+        winner: Color,
+    },
+    NoResult,
+}
+
+impl GameResult {
+    pub fn winner(&self) -> Option<Color> {
+        match self {
+            GameResult::Resignation { winner }
+            | GameResult::Time { winner }
+            | GameResult::Forfeit { winner } => Some(*winner),
+            GameResult::Score { winner, .. } => *winner,
+            GameResult::NoResult => None,
+        }
+    }
+}
+
+/// The result in SGF `RE` notation, such as `B+R`, `W+6.5`, `W+T`, `B+F`, `0` or `Void`.
+impl std::fmt::Display for GameResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let letter = |color: Color| match color {
+            Color::Black => 'B',
+            Color::White => 'W',
+        };
+        match self {
+            GameResult::Resignation { winner } => write!(f, "{}+R", letter(*winner)),
+            GameResult::Time { winner } => write!(f, "{}+T", letter(*winner)),
+            GameResult::Forfeit { winner } => write!(f, "{}+F", letter(*winner)),
+            GameResult::Score {
+                winner: Some(winner),
+                margin,
+            } => write!(f, "{}+{margin}", letter(*winner)),
+            GameResult::Score { winner: None, .. } => f.write_str("0"),
+            GameResult::NoResult => f.write_str("Void"),
+        }
+    }
+}
+
+/// A change to a game between its moves, kept so that the game can be replayed.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GameEvent {
+    /// Stones added or removed by a game record, less any pending handicap stones it placed.
+    Setup(Setup),
+    /// Play resumed from the scoring phase.
+    Resume,
+}
+
+#[derive(Clone, Debug, Default)]
+struct ScoringState {
+    dead: BTreeSet<usize>,
+    accepted: [bool; 2],
+    final_score: Option<ScoreSummary>,
 }
 
 #[derive(Clone, Debug)]
@@ -77,28 +248,236 @@ pub struct GameState {
     config: GameConfig,
     consecutive_passes: u8,
     moves: Vec<MoveRecord>,
+    redo: Vec<Undone>,
+    handicap_stones: Vec<Point>,
+    handicap_pending: u8,
+    scoring: Option<ScoringState>,
+    /// A result decided outside scoring, such as a resignation.
+    declared: Option<GameResult>,
+    /// Events with the number of moves played before them.
+    events: Vec<(usize, GameEvent)>,
+    /// What each setup in `events` replaced, in the same order.
+    setups: Vec<SetupRecord>,
+}
+
+/// The intersections a setup changed, with their earlier contents, so that undo can restore
+/// them.
+#[derive(Clone, Debug)]
+struct SetupRecord {
+    replaced: Vec<(usize, Option<Color>)>,
+    /// Position key this setup added to the repetition history, if it was not already present.
+    inserted_key: Option<u64>,
+}
+
+/// An undone move with the events that followed it, replayed together by redo.
+#[derive(Clone, Debug)]
+struct Undone {
+    mv: Move,
+    events: Vec<GameEvent>,
 }
 
 #[derive(Clone, Debug)]
 struct MoveRecord {
     mv: Move,
     captured: Vec<Point>,
+    previous_passes: u8,
+    board_hash_before: u64,
+    /// Position key this move added to the superko history, if it was not already present.
+    inserted_key: Option<u64>,
 }
 
 impl GameState {
+    /// Create a game from `config`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the configured handicap cannot be placed; use [`GameState::try_new`] for
+    /// configurations that come from user input.
     pub fn new(config: GameConfig) -> Self {
+        Self::try_new(config).expect("invalid handicap for board size")
+    }
+
+    /// Create a game from `config`, placing fixed handicap stones or opening the free
+    /// placement phase when a handicap is configured.
+    pub fn try_new(config: GameConfig) -> Result<Self, RuleViolation> {
         let board = Board::new(config.size);
-        let mut history = HashSet::new();
-        history.insert(board.position_key(Color::Black));
-        Self {
+        let mut game = Self {
             board,
             to_move: Color::Black,
-            history,
+            history: HashSet::new(),
             captures: Captures::default(),
             config,
             consecutive_passes: 0,
             moves: Vec::new(),
+            redo: Vec::new(),
+            handicap_stones: Vec::new(),
+            handicap_pending: 0,
+            scoring: None,
+            declared: None,
+            events: Vec::new(),
+            setups: Vec::new(),
+        };
+        game.history
+            .insert(game.history_key(game.board.hash(), Color::Black));
+
+        let stones = game.config.handicap;
+        if stones < 2 {
+            return Ok(game);
+        }
+        let invalid = RuleViolation::InvalidHandicap {
+            stones,
+            size: game.config.size,
+        };
+        match game.config.handicap_placement {
+            HandicapPlacement::Fixed => {
+                let points = fixed_handicap_points(game.config.size, stones).ok_or(invalid)?;
+                for point in points {
+                    game.board
+                        .set(point.to_index(game.config.size), Some(Color::Black));
+                    game.handicap_stones.push(point);
+                }
+                game.start_after_handicap();
+            }
+            HandicapPlacement::Free => {
+                if stones as usize >= game.config.size * game.config.size {
+                    return Err(invalid);
+                }
+                game.handicap_pending = stones;
+            }
+        }
+        Ok(game)
+    }
+
+    /// Hand the first move to White and seed the superko history with the handicap position.
+    fn start_after_handicap(&mut self) {
+        self.to_move = Color::White;
+        self.history.clear();
+        self.history
+            .insert(self.history_key(self.board.hash(), Color::White));
+    }
+
+    /// Key recorded in the repetition history: positional superko ignores whose turn it is.
+    fn history_key(&self, hash: u64, to_move: Color) -> u64 {
+        match self.config.effective_ko_rule() {
+            KoRule::PositionalSuperko => hash,
+            KoRule::Simple | KoRule::SituationalSuperko | KoRule::NoResult => {
+                self.board.position_key_for(hash, to_move)
+            }
+        }
+    }
+
+    fn prisoners_mut(&mut self, color: Color) -> &mut u32 {
+        match color {
+            Color::Black => &mut self.captures.black,
+            Color::White => &mut self.captures.white,
+        }
+    }
+
+    /// Place one of Black's stones during the free handicap phase.
+    pub fn place_handicap_stone(&mut self, point: Point) -> Result<(), RuleViolation> {
+        if self.handicap_pending == 0 {
+            return Err(RuleViolation::NoHandicapPending);
+        }
+        let size = self.board.size();
+        if point.x as usize >= size || point.y as usize >= size {
+            return Err(RuleViolation::OutOfBounds { point });
+        }
+        if !self.board.is_empty(point) {
+            return Err(RuleViolation::Occupied { point });
+        }
+
+        self.board.set(point.to_index(size), Some(Color::Black));
+        self.handicap_stones.push(point);
+        self.handicap_pending -= 1;
+        if self.handicap_pending == 0 {
+            self.start_after_handicap();
+        }
+        Ok(())
+    }
+
+    /// Apply setup stones from a game record. Stones are placed or removed without captures,
+    /// and the resulting position joins the repetition history. Black stones added while free
+    /// handicap stones are pending count as those handicap stones.
+    pub fn apply_setup(&mut self, setup: &Setup) -> Result<(), RuleViolation> {
+        let size = self.board.size();
+        let mut black = setup.black.as_slice();
+        while let (Some((&point, rest)), true) = (black.split_first(), self.handicap_pending > 0) {
+            self.place_handicap_stone(point)?;
+            black = rest;
+        }
+        let changes = [
+            (black, Some(Color::Black)),
+            (&setup.white, Some(Color::White)),
+            (&setup.empty, None),
+        ];
+        let outside = changes
+            .iter()
+            .flat_map(|(points, _)| points.iter())
+            .find(|point| point.x as usize >= size || point.y as usize >= size);
+        if let Some(&point) = outside {
+            return Err(RuleViolation::OutOfBounds { point });
+        }
+        let mut replaced = Vec::new();
+        for (points, color) in changes {
+            for point in points {
+                let index = point.to_index(size);
+                replaced.push((index, self.board.intersections()[index]));
+                self.board.set(index, color);
+            }
         }
+        if let Some(color) = setup.to_move {
+            self.to_move = color;
+        }
+        self.scoring = None;
+        self.consecutive_passes = 0;
+        let key = self.history_key(self.board.hash(), self.to_move);
+        let inserted_key = self.history.insert(key).then_some(key);
+        let applied = Setup {
+            black: black.to_vec(),
+            ..setup.clone()
+        };
+        if !applied.is_empty() {
+            self.events
+                .push((self.moves.len(), GameEvent::Setup(applied)));
+            self.setups.push(SetupRecord {
+                replaced,
+                inserted_key,
+            });
+        }
+        Ok(())
+    }
+
+    /// Apply a move from a game record: either colour may move, Black's moves place any
+    /// pending free handicap stones, and play may continue after both players passed.
+    pub fn play_recorded(&mut self, mv: Move) -> Result<(), RuleViolation> {
+        if let (Some(point), true) = (mv.point, self.handicap_pending > 0) {
+            if mv.color == Color::Black {
+                return self.place_handicap_stone(point);
+            }
+        }
+        if self.phase() == GamePhase::Scoring {
+            self.resume_play()?;
+        }
+        self.to_move = mv.color;
+        self.commit(mv).map(|_| ())
+    }
+
+    /// Handicap stones placed so far, in placement order.
+    pub fn handicap_stones(&self) -> &[Point] {
+        &self.handicap_stones
+    }
+
+    /// Free handicap stones Black still has to place before White can move.
+    pub fn handicap_pending(&self) -> u8 {
+        self.handicap_pending
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn ownership_estimate(&self) -> OwnershipEstimate {
+        self.board.estimate_ownership()
     }
 
     pub fn board_snapshot(&self) -> BoardSnapshot {
@@ -127,6 +506,15 @@ impl GameState {
     pub fn legal_moves(&self) -> Vec<Point> {
         let mut legal = Vec::new();
         let size = self.board.size();
+        if self.scoring.is_some() {
+            return legal;
+        }
+        if self.handicap_pending > 0 {
+            return (0..size * size)
+                .filter(|&index| self.board.intersections()[index].is_none())
+                .map(|index| Point::from_index(index, size))
+                .collect();
+        }
         for index in 0..size * size {
             if self.board.intersections()[index].is_some() {
                 continue;
@@ -136,40 +524,83 @@ impl GameState {
                 color: self.to_move,
                 point: Some(point),
             };
-            if self.simulate(&mv).is_ok() {
+            if self.check(&mv).is_ok() {
                 legal.push(point);
             }
         }
         legal
     }
 
-    fn simulate(&self, mv: &Move) -> Result<(Board, Vec<Point>, u64), RuleViolation> {
+    /// Validate `mv` and return the history key of the position it produces.
+    fn check(&self, mv: &Move) -> Result<u64, RuleViolation> {
+        if self.handicap_pending > 0 {
+            return Err(RuleViolation::HandicapPending {
+                remaining: self.handicap_pending,
+            });
+        }
+        if self.no_result() {
+            return Err(RuleViolation::NoResult);
+        }
+        if self.scoring.is_some() || self.declared.is_some() {
+            return Err(RuleViolation::NotPlaying {
+                phase: self.phase(),
+            });
+        }
         if mv.color != self.to_move {
             return Err(RuleViolation::WrongPlayer {
                 expected: self.to_move,
             });
         }
         match mv.point {
-            None => {
-                let key = self.board.position_key(self.to_move.opponent());
-                Ok((self.board.clone(), Vec::new(), key))
-            }
+            None => Ok(self.history_key(self.board.hash(), self.to_move.opponent())),
             Some(point) => {
                 if point.x as usize >= self.board.size() || point.y as usize >= self.board.size() {
                     return Err(RuleViolation::OutOfBounds { point });
                 }
-                let (next_board, captured) = self.board.after_play(mv.color, point)?;
-                let key = next_board.position_key(self.to_move.opponent());
-                if self.config.superko && self.history.contains(&key) {
-                    return Err(RuleViolation::SuperKo);
+                let next_hash = self.board.hash_after_play(
+                    mv.color,
+                    point,
+                    self.config.ruleset.allows_suicide(),
+                )?;
+                let key = self.history_key(next_hash, self.to_move.opponent());
+                match self.config.effective_ko_rule() {
+                    KoRule::Simple | KoRule::NoResult => {
+                        let retakes_ko = self
+                            .moves
+                            .last()
+                            .is_some_and(|last| last.board_hash_before == next_hash);
+                        if retakes_ko {
+                            return Err(RuleViolation::Ko { point });
+                        }
+                    }
+                    KoRule::PositionalSuperko | KoRule::SituationalSuperko => {
+                        if self.history.contains(&key) {
+                            return Err(RuleViolation::SuperKo);
+                        }
+                    }
                 }
-                Ok((next_board, captured, key))
+                Ok(key)
             }
         }
     }
 
     pub fn play(&mut self, mv: Move) -> Result<MoveOutcome, RuleViolation> {
-        let (next_board, captured, key) = self.simulate(&mv)?;
+        let last_move = self.commit(mv)?;
+        self.redo.clear();
+        Ok(self.outcome(last_move))
+    }
+
+    fn commit(&mut self, mv: Move) -> Result<MoveRecordSnapshot, RuleViolation> {
+        let key = self.check(&mv)?;
+        let board_hash_before = self.board.hash();
+        let captured = match mv.point {
+            Some(point) => {
+                self.board
+                    .play(mv.color, point, self.config.ruleset.allows_suicide())?
+            }
+            None => Vec::new(),
+        };
+        let previous_passes = self.consecutive_passes;
 
         if mv.point.is_none() {
             self.consecutive_passes = self.consecutive_passes.saturating_add(1);
@@ -177,68 +608,378 @@ impl GameState {
             self.consecutive_passes = 0;
         }
 
-        // update captures
-        if mv.point.is_some() {
-            match mv.color {
-                Color::Black => self.captures.black += captured.len() as u32,
-                Color::White => self.captures.white += captured.len() as u32,
-            }
+        // update captures; a suicide hands the removed stones to the opponent
+        if let Some(point) = mv.point {
+            let capturer = if captured.contains(&point) {
+                mv.color.opponent()
+            } else {
+                mv.color
+            };
+            *self.prisoners_mut(capturer) += captured.len() as u32;
+        } else if self.config.ruleset.pass_stones() {
+            *self.prisoners_mut(mv.color.opponent()) += 1;
         }
 
-        self.board = next_board;
         self.to_move = self.to_move.opponent();
-        self.history.insert(key);
+        let inserted_key = self.history.insert(key).then_some(key);
 
         let record = MoveRecord {
             mv: mv.clone(),
             captured: captured.clone(),
+            previous_passes,
+            board_hash_before,
+            inserted_key,
         };
         self.moves.push(record);
+        if self.is_over() && !self.no_result() {
+            // Start the marking from the offline estimate; the players correct it from there.
+            let size = self.board.size();
+            let estimate = self.board.estimate_ownership();
+            self.scoring = Some(ScoringState {
+                dead: estimate
+                    .dead_stones
+                    .iter()
+                    .map(|point| point.to_index(size))
+                    .collect(),
+                ..ScoringState::default()
+            });
+        }
 
-        let outcome = MoveOutcome {
+        Ok(MoveRecordSnapshot {
+            mv,
+            captured,
+            move_number: self.moves.len(),
+        })
+    }
+
+    fn outcome(&self, last_move: MoveRecordSnapshot) -> MoveOutcome {
+        MoveOutcome {
             board: self.board_snapshot(),
             captures: self.captures.clone(),
             to_move: self.to_move,
-            game_over: self.consecutive_passes >= 2,
+            game_over: self.is_over(),
+            no_result: self.no_result(),
+            phase: self.phase(),
             consecutive_passes: self.consecutive_passes,
-            last_move: MoveRecordSnapshot {
-                mv,
-                captured,
-                move_number: self.moves.len(),
-            },
+            last_move,
             legal_moves: self.legal_moves(),
+        }
+    }
+
+    /// Take back the last move and any setup that followed it, restoring board, captures,
+    /// turn, pass count and superko history exactly. The move stays available to
+    /// [`GameState::redo`] until a new move is played, together with the events that followed
+    /// it. A resignation or declared result is withdrawn along with the move.
+    pub fn undo(&mut self) -> Option<MoveRecordSnapshot> {
+        let record = self.moves.pop()?;
+        let move_number = self.moves.len() + 1;
+        self.scoring = None;
+        self.declared = None;
+        let played = self.moves.len();
+        let followed = self.events.partition_point(|&(count, _)| count <= played);
+        let events: Vec<GameEvent> = self
+            .events
+            .split_off(followed)
+            .into_iter()
+            .map(|(_, event)| event)
+            .collect();
+        for _ in events
+            .iter()
+            .filter(|event| matches!(event, GameEvent::Setup(_)))
+        {
+            let setup = self.setups.pop().expect("every setup event has a record");
+            for &(index, color) in setup.replaced.iter().rev() {
+                self.board.set(index, color);
+            }
+            if let Some(key) = setup.inserted_key {
+                self.history.remove(&key);
+            }
+        }
+
+        if let Some(point) = record.mv.point {
+            let size = self.board.size();
+            let suicide = record.captured.contains(&point);
+            let (removed_color, capturer) = if suicide {
+                (record.mv.color, record.mv.color.opponent())
+            } else {
+                (record.mv.color.opponent(), record.mv.color)
+            };
+            for captured in &record.captured {
+                self.board.set(captured.to_index(size), Some(removed_color));
+            }
+            self.board.set(point.to_index(size), None);
+            *self.prisoners_mut(capturer) -= record.captured.len() as u32;
+        } else if self.config.ruleset.pass_stones() {
+            *self.prisoners_mut(record.mv.color.opponent()) -= 1;
+        }
+
+        if let Some(key) = record.inserted_key {
+            self.history.remove(&key);
+        }
+        self.consecutive_passes = record.previous_passes;
+        self.to_move = record.mv.color;
+        self.redo.push(Undone {
+            mv: record.mv.clone(),
+            events,
+        });
+
+        Some(MoveRecordSnapshot {
+            mv: record.mv,
+            captured: record.captured,
+            move_number,
+        })
+    }
+
+    /// Replay the most recently undone move and the events that followed it, such as play
+    /// resumed after both players passed. Returns `None` when there is nothing to redo; a move
+    /// that cannot be replayed stays available.
+    pub fn redo(&mut self) -> Result<Option<MoveRecordSnapshot>, RuleViolation> {
+        let Some(undone) = self.redo.pop() else {
+            return Ok(None);
+        };
+        let last_move = match self.commit(undone.mv.clone()) {
+            Ok(last_move) => last_move,
+            Err(err) => {
+                self.redo.push(undone);
+                return Err(err);
+            }
         };
+        let replayed = undone.events.iter().try_for_each(|event| match event {
+            GameEvent::Setup(setup) => self.apply_setup(setup),
+            GameEvent::Resume => self.resume_play(),
+        });
+        if let Err(err) = replayed {
+            self.undo();
+            *self.redo.last_mut().expect("undo keeps the move") = undone;
+            return Err(err);
+        }
+        Ok(Some(last_move))
+    }
 
-        Ok(outcome)
+    /// Seek to the position after `move_number` moves, undoing or redoing as needed.
+    pub fn goto_move(&mut self, move_number: usize) -> Result<(), RuleViolation> {
+        let available = self.moves.len() + self.redo.len();
+        if move_number > available {
+            return Err(RuleViolation::MoveOutOfRange {
+                requested: move_number,
+                available,
+            });
+        }
+        while self.moves.len() > move_number {
+            self.undo();
+        }
+        while self.moves.len() < move_number {
+            self.redo()?.ok_or(RuleViolation::MoveOutOfRange {
+                requested: move_number,
+                available,
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Number of undone moves that can still be replayed with [`GameState::redo`].
+    pub fn redo_count(&self) -> usize {
+        self.redo.len()
     }
 
     pub fn move_count(&self) -> usize {
         self.moves.len()
     }
 
-    pub fn score(&self) -> ScoreSummary {
-        let territory = self.board.territory_map();
-        let mut territory_black = 0u32;
-        let mut territory_white = 0u32;
-        for owner in territory {
-            match owner {
-                Some(Color::Black) => territory_black += 1,
-                Some(Color::White) => territory_white += 1,
-                None => {}
+    /// Whether consecutive passes have ended the game. With pass stones (AGA) White must make
+    /// the final pass, so a Black pass answering White's pass needs one more White pass.
+    pub fn is_over(&self) -> bool {
+        if self.no_result() || self.declared.is_some() {
+            return true;
+        }
+        if self.consecutive_passes < 2 {
+            return false;
+        }
+        !self.config.ruleset.pass_stones()
+            || self
+                .moves
+                .last()
+                .is_some_and(|record| record.mv.color == Color::White)
+    }
+
+    /// Whether the last move repeated an earlier position under [`KoRule::NoResult`], which
+    /// ends the game without a result.
+    pub fn no_result(&self) -> bool {
+        self.config.effective_ko_rule() == KoRule::NoResult
+            && self
+                .moves
+                .last()
+                .is_some_and(|record| record.mv.point.is_some() && record.inserted_key.is_none())
+    }
+
+    pub fn phase(&self) -> GamePhase {
+        if self.no_result() || self.declared.is_some() {
+            return GamePhase::Finished;
+        }
+        match &self.scoring {
+            None => GamePhase::Playing,
+            Some(scoring) if scoring.final_score.is_some() => GamePhase::Finished,
+            Some(_) => GamePhase::Scoring,
+        }
+    }
+
+    fn scoring_mut(&mut self) -> Result<&mut ScoringState, RuleViolation> {
+        let phase = self.phase();
+        match self.scoring.as_mut() {
+            Some(scoring) if phase == GamePhase::Scoring => Ok(scoring),
+            _ => Err(RuleViolation::NotScoring { phase }),
+        }
+    }
+
+    /// Mark the chain at `point` dead, or alive again if it was already marked. Any change
+    /// withdraws both players' acceptance.
+    pub fn toggle_dead(&mut self, point: Point) -> Result<ScoringSnapshot, RuleViolation> {
+        let size = self.board.size();
+        if point.x as usize >= size || point.y as usize >= size {
+            return Err(RuleViolation::OutOfBounds { point });
+        }
+        let chain = self.board.group_at(point.to_index(size)).stones;
+        if chain.is_empty() {
+            return Err(RuleViolation::EmptyPoint { point });
+        }
+
+        let scoring = self.scoring_mut()?;
+        if scoring.dead.contains(&chain[0]) {
+            for index in chain {
+                scoring.dead.remove(&index);
             }
+        } else {
+            scoring.dead.extend(chain);
         }
+        scoring.accepted = [false; 2];
+        Ok(self.scoring_snapshot().expect("scoring phase is active"))
+    }
 
-        let black_score = territory_black as f64 + self.captures.black as f64;
-        let white_score = territory_white as f64 + self.captures.white as f64 + self.config.komi;
+    /// Record that `color` agrees with the current dead stones. Once both players have
+    /// accepted, the score is final.
+    pub fn accept_score(&mut self, color: Color) -> Result<ScoringSnapshot, RuleViolation> {
+        let scoring = self.scoring_mut()?;
+        scoring.accepted[color.index()] = true;
+        if scoring.accepted == [true; 2] {
+            let score = self.score();
+            if let Some(scoring) = self.scoring.as_mut() {
+                scoring.final_score = Some(score);
+            }
+        }
+        Ok(self.scoring_snapshot().expect("scoring phase is active"))
+    }
 
-        ScoreSummary {
-            black_score,
-            white_score,
-            territory_black,
-            territory_white,
-            captures: self.captures.clone(),
-            komi: self.config.komi,
+    /// Leave the scoring phase because the players disagree about the dead stones. Play
+    /// continues with the player to move; two more passes are needed to end the game.
+    pub fn resume_play(&mut self) -> Result<(), RuleViolation> {
+        self.scoring_mut()?;
+        self.scoring = None;
+        self.consecutive_passes = 0;
+        self.events.push((self.moves.len(), GameEvent::Resume));
+        Ok(())
+    }
+
+    /// End the game with `color` resigning, during play or while stones are being marked.
+    pub fn resign(&mut self, color: Color) -> Result<(), RuleViolation> {
+        self.declare_result(GameResult::Resignation {
+            winner: color.opponent(),
+        })
+    }
+
+    pub fn resigned(&self) -> Option<Color> {
+        match self.declared {
+            Some(GameResult::Resignation { winner }) => Some(winner.opponent()),
+            _ => None,
+        }
+    }
+
+    /// End the game with a result decided outside scoring, such as a loss on time, a forfeit
+    /// or a game stopped without a result.
+    pub fn declare_result(&mut self, result: GameResult) -> Result<(), RuleViolation> {
+        let phase = self.phase();
+        if phase == GamePhase::Finished {
+            return Err(RuleViolation::NotPlaying { phase });
         }
+        self.scoring = None;
+        self.declared = Some(result);
+        Ok(())
+    }
+
+    /// The result given by [`GameState::resign`] or [`GameState::declare_result`].
+    pub fn declared_result(&self) -> Option<&GameResult> {
+        self.declared.as_ref()
+    }
+
+    /// Setups and resumed play, each with the number of moves played before it.
+    pub fn events(&self) -> &[(usize, GameEvent)] {
+        &self.events
+    }
+
+    /// The result once the game has finished.
+    pub fn result(&self) -> Option<GameResult> {
+        if let Some(result) = &self.declared {
+            return Some(result.clone());
+        }
+        if self.no_result() {
+            return Some(GameResult::NoResult);
+        }
+        let score = self.final_score()?;
+        let margin = score.black_score - score.white_score;
+        let winner = if margin > 0.0 {
+            Some(Color::Black)
+        } else if margin < 0.0 {
+            Some(Color::White)
+        } else {
+            None
+        };
+        Some(GameResult::Score {
+            winner,
+            margin: margin.abs(),
+        })
+    }
+
+    /// Stones currently marked dead.
+    pub fn dead_stones(&self) -> Vec<Point> {
+        let size = self.board.size();
+        self.scoring
+            .iter()
+            .flat_map(|scoring| scoring.dead.iter())
+            .map(|&index| Point::from_index(index, size))
+            .collect()
+    }
+
+    /// The agreed score once both players accepted the dead stones.
+    pub fn final_score(&self) -> Option<&ScoreSummary> {
+        self.scoring.as_ref()?.final_score.as_ref()
+    }
+
+    pub fn scoring_snapshot(&self) -> Option<ScoringSnapshot> {
+        let scoring = self.scoring.as_ref()?;
+        Some(ScoringSnapshot {
+            phase: self.phase(),
+            dead_stones: self.dead_stones(),
+            black_accepted: scoring.accepted[Color::Black.index()],
+            white_accepted: scoring.accepted[Color::White.index()],
+            score: self.score(),
+        })
+    }
+
+    /// Score the position, removing any stones marked dead during the scoring phase.
+    pub fn score(&self) -> ScoreSummary {
+        self.score_with_dead(&self.dead_stones())
+    }
+
+    /// Score the position with the stones at `dead` removed and counted for their opponent.
+    pub fn score_with_dead(&self, dead: &[Point]) -> ScoreSummary {
+        let ruleset = self.config.ruleset;
+        score_position(ScoreInput {
+            board: &self.board,
+            dead,
+            captures: &self.captures,
+            method: ruleset.scoring(),
+            komi: self.config.komi,
+            handicap_compensation: ruleset.handicap_compensation(self.handicap_stones.len() as u8),
+        })
     }
 
     #[cfg(test)]
@@ -0,0 +1,76 @@
+use serde::{Deserialize, Serialize};
+
+use crate::point::Point;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HandicapPlacement {
+    /// Stones go on the standard star points before the first move.
+    #[default]
+    Fixed,
+    /// Black chooses where to put the stones before White's first move.
+    Free,
+}
+
+/// Largest fixed handicap supported on a board of the given size (GTP `fixed_handicap` limits).
+pub fn max_fixed_handicap(size: usize) -> u8 {
+    match size {
+        0..=6 => 0,
+        7 => 4,
+        _ if size.is_multiple_of(2) => 4,
+        _ => 9,
+    }
+}
+
+/// Star points for a fixed handicap, in the order given by the GTP specification.
+///
+/// Returns an empty list for handicaps below two and `None` when the board cannot hold the
+/// requested number of stones.
+pub fn fixed_handicap_points(size: usize, stones: u8) -> Option<Vec<Point>> {
+    if stones < 2 {
+        return Some(Vec::new());
+    }
+    if stones > max_fixed_handicap(size) {
+        return None;
+    }
+
+    let edge = if size >= 13 { 3 } else { 2 };
+    let low = edge;
+    let high = size - 1 - edge;
+    let mid = size / 2;
+
+    // Coordinates run from the top-left corner, so GTP's D4 is the lower-left star point.
+    let lower_left = (low, high);
+    let upper_right = (high, low);
+    let upper_left = (low, low);
+    let lower_right = (high, high);
+    let center = (mid, mid);
+    let left_side = (low, mid);
+    let right_side = (high, mid);
+    let bottom_side = (mid, high);
+    let top_side = (mid, low);
+
+    let corners = [lower_left, upper_right, upper_left, lower_right];
+    let mut coords: Vec<(usize, usize)> = match stones {
+        2..=4 => corners[..stones as usize].to_vec(),
+        5 => vec![lower_left, upper_right, upper_left, lower_right, center],
+        6 | 7 => {
+            let mut coords = corners.to_vec();
+            coords.extend([left_side, right_side]);
+            coords
+        }
+        _ => {
+            let mut coords = corners.to_vec();
+            coords.extend([left_side, right_side, bottom_side, top_side]);
+            coords
+        }
+    };
+    if stones == 7 || stones == 9 {
+        coords.push(center);
+    }
+
+    coords
+        .into_iter()
+        .map(|(x, y)| Point::new(x, y).ok())
+        .collect()
+}
@@ -2,17 +2,27 @@ mod board;
 mod color;
 mod error;
 mod game;
+mod handicap;
+mod ownership;
 mod point;
+mod ruleset;
+mod scoring;
+mod tree;
 mod zobrist;
 
 pub use board::Board;
 pub use color::Color;
 pub use error::RuleViolation;
 pub use game::{
-    BoardSnapshot, Captures, GameConfig, GameState, Move, MoveOutcome, MoveRecordSnapshot,
-    ScoreSummary,
+    BoardSnapshot, Captures, GameConfig, GameEvent, GamePhase, GameResult, GameState, Move,
+    MoveOutcome, MoveRecordSnapshot, ScoreSummary, ScoringSnapshot,
 };
+pub use handicap::{fixed_handicap_points, max_fixed_handicap, HandicapPlacement};
+pub use ownership::OwnershipEstimate;
 pub use point::Point;
+pub use ruleset::{KoRule, Ruleset, ScoringMethod};
+pub use scoring::PointScore;
+pub use tree::{Bookmark, GameTree, Markup, MarkupKind, Node, NodeId, Setup};
 
 #[cfg(test)]
 mod tests {
@@ -100,4 +110,1158 @@ mod tests {
         assert_eq!(score.black_score, 0.0);
         assert!((score.white_score - config.komi).abs() < f64::EPSILON);
     }
+
+    fn play_at(game: &mut GameState, x: usize, y: usize) -> MoveOutcome {
+        let color = game.to_move();
+        game.play(Move {
+            color,
+            point: Some(Point::new(x, y).unwrap()),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn undo_restores_captures_and_superko_history() {
+        let mut game = GameState::new(GameConfig {
+            size: 5,
+            ..Default::default()
+        });
+
+        // Build a ko: black captures at (2,1), white may not immediately retake at (1,1).
+        play_at(&mut game, 1, 0);
+        play_at(&mut game, 2, 0);
+        play_at(&mut game, 0, 1);
+        play_at(&mut game, 3, 1);
+        play_at(&mut game, 1, 2);
+        play_at(&mut game, 2, 2);
+        play_at(&mut game, 4, 4);
+        play_at(&mut game, 1, 1);
+        let before_capture = game.board_snapshot().intersections;
+        let outcome = play_at(&mut game, 2, 1);
+        assert_eq!(outcome.last_move.captured, vec![Point::new(1, 1).unwrap()]);
+        assert_eq!(game.captures().black, 1);
+
+        let undone = game.undo().unwrap();
+        assert_eq!(undone.move_number, 9);
+        assert_eq!(game.board_snapshot().intersections, before_capture);
+        assert_eq!(game.captures().black, 0);
+        assert_eq!(game.to_move(), Color::Black);
+        assert_eq!(game.move_count(), 8);
+        assert_eq!(game.redo_count(), 1);
+
+        // Redo recaptures and the ko ban on the immediate retake is back in force.
+        let redone = game.redo().unwrap().unwrap();
+        assert_eq!(redone.captured, vec![Point::new(1, 1).unwrap()]);
+        assert_eq!(game.captures().black, 1);
+        let err = game
+            .play(Move {
+                color: Color::White,
+                point: Some(Point::new(1, 1).unwrap()),
+            })
+            .unwrap_err();
+        assert!(matches!(err, RuleViolation::SuperKo));
+    }
+
+    #[test]
+    fn undo_restores_pass_count_and_new_move_clears_redo() {
+        let mut game = GameState::new(GameConfig {
+            size: 9,
+            ..Default::default()
+        });
+        play_at(&mut game, 2, 2);
+        play_at(&mut game, 6, 2);
+        game.play(Move {
+            color: Color::Black,
+            point: None,
+        })
+        .unwrap();
+        let outcome = game
+            .play(Move {
+                color: Color::White,
+                point: None,
+            })
+            .unwrap();
+        assert!(outcome.game_over);
+
+        game.undo().unwrap();
+        assert_eq!(game.consecutive_passes(), 1);
+        assert_eq!(game.to_move(), Color::White);
+
+        play_at(&mut game, 6, 6);
+        assert_eq!(game.consecutive_passes(), 0);
+        assert_eq!(game.redo_count(), 0);
+        assert!(game.redo().unwrap().is_none());
+    }
+
+    #[test]
+    fn goto_move_scrubs_backwards_and_forwards() {
+        let mut game = GameState::new(GameConfig {
+            size: 9,
+            ..Default::default()
+        });
+        let moves = [(2, 2), (6, 6), (2, 6), (6, 2), (4, 4)];
+        let mut positions = vec![game.board_snapshot().intersections];
+        for (x, y) in moves {
+            play_at(&mut game, x, y);
+            positions.push(game.board_snapshot().intersections);
+        }
+
+        game.goto_move(0).unwrap();
+        assert_eq!(game.board_snapshot().intersections, positions[0]);
+        assert_eq!(game.to_move(), Color::Black);
+        assert_eq!(game.redo_count(), moves.len());
+
+        game.goto_move(3).unwrap();
+        assert_eq!(game.board_snapshot().intersections, positions[3]);
+        assert_eq!(game.to_move(), Color::White);
+        assert_eq!(game.move_history().len(), 3);
+
+        let err = game.goto_move(6).unwrap_err();
+        assert!(matches!(
+            err,
+            RuleViolation::MoveOutOfRange {
+                requested: 6,
+                available: 5
+            }
+        ));
+
+        game.goto_move(5).unwrap();
+        assert_eq!(game.board_snapshot().intersections, positions[5]);
+    }
+
+    #[test]
+    fn undo_and_redo_keep_resumed_play() {
+        let mut game = GameState::new(GameConfig {
+            size: 9,
+            ..Default::default()
+        });
+        play_at(&mut game, 4, 4);
+        while game.phase() == GamePhase::Playing {
+            let color = game.to_move();
+            game.play(Move { color, point: None }).unwrap();
+        }
+        game.resume_play().unwrap();
+        play_at(&mut game, 2, 2);
+        let moves = game.move_count();
+        let position = game.board_snapshot().intersections;
+
+        game.goto_move(0).unwrap();
+        assert_eq!(game.redo_count(), moves);
+        game.goto_move(moves).unwrap();
+        assert_eq!(game.move_count(), moves);
+        assert_eq!(game.redo_count(), 0);
+        assert_eq!(game.phase(), GamePhase::Playing);
+        assert_eq!(game.board_snapshot().intersections, position);
+        assert_eq!(game.events(), [(moves - 1, GameEvent::Resume)]);
+
+        // Redoing the final pass on its own brings back the resumption with it.
+        game.undo().unwrap();
+        game.undo().unwrap();
+        assert_eq!(game.phase(), GamePhase::Playing);
+        game.redo().unwrap().unwrap();
+        assert_eq!(game.phase(), GamePhase::Playing);
+        game.redo().unwrap().unwrap();
+        assert_eq!(game.board_snapshot().intersections, position);
+    }
+
+    #[test]
+    fn undo_takes_back_setup_stones() {
+        let mut game = GameState::new(GameConfig {
+            size: 9,
+            ..Default::default()
+        });
+        play_at(&mut game, 4, 4);
+        let first = (game.board().intersections().to_vec(), game.board().hash());
+        play_at(&mut game, 2, 2);
+        game.apply_setup(&Setup {
+            black: vec![Point::new(6, 6).unwrap()],
+            white: vec![Point::new(4, 4).unwrap()],
+            empty: vec![Point::new(2, 2).unwrap(), Point::new(6, 6).unwrap()],
+            to_move: Some(Color::White),
+        })
+        .unwrap();
+        let setup = game.board().intersections().to_vec();
+        play_at(&mut game, 0, 0);
+        let last = game.board().intersections().to_vec();
+
+        game.undo().unwrap();
+        assert_eq!(game.board().intersections(), setup.as_slice());
+        // The setup goes with the move it followed.
+        game.undo().unwrap();
+        assert_eq!(
+            (game.board().intersections().to_vec(), game.board().hash()),
+            first
+        );
+        assert_eq!((game.to_move(), game.events().len()), (Color::White, 0));
+
+        game.goto_move(3).unwrap();
+        assert_eq!(game.board().intersections(), last.as_slice());
+        assert_eq!(game.events().len(), 1);
+    }
+
+    #[test]
+    fn redo_keeps_a_move_that_can_no_longer_be_played() {
+        let mut game = GameState::new(GameConfig {
+            size: 9,
+            ..Default::default()
+        });
+        play_at(&mut game, 4, 4);
+        game.undo().unwrap();
+        game.resign(Color::Black).unwrap();
+        assert!(matches!(game.redo(), Err(RuleViolation::NotPlaying { .. })));
+        assert_eq!((game.move_count(), game.redo_count()), (0, 1));
+        assert!(game.goto_move(1).is_err());
+        assert_eq!(game.redo_count(), 1);
+    }
+
+    #[test]
+    fn fixed_handicap_uses_star_points_and_white_moves_first() {
+        let game = GameState::new(GameConfig {
+            size: 19,
+            handicap: 4,
+            ..Default::default()
+        });
+        let mut stones: Vec<(u8, u8)> = game.handicap_stones().iter().map(|p| (p.x, p.y)).collect();
+        stones.sort();
+        assert_eq!(stones, vec![(3, 3), (3, 15), (15, 3), (15, 15)]);
+        assert_eq!(game.to_move(), Color::White);
+
+        let nine = fixed_handicap_points(9, 5).unwrap();
+        assert_eq!(nine.len(), 5);
+        assert!(nine.contains(&Point::new(4, 4).unwrap()));
+        assert!(nine.contains(&Point::new(2, 6).unwrap()));
+        assert_eq!(fixed_handicap_points(13, 9).unwrap().len(), 9);
+        assert!(fixed_handicap_points(13, 10).is_none());
+
+        let err = GameState::try_new(GameConfig {
+            size: 10,
+            handicap: 5,
+            ..Default::default()
+        })
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            RuleViolation::InvalidHandicap {
+                stones: 5,
+                size: 10
+            }
+        ));
+    }
+
+    #[test]
+    fn free_handicap_blocks_play_until_all_stones_are_placed() {
+        let mut game = GameState::new(GameConfig {
+            size: 9,
+            handicap: 2,
+            handicap_placement: HandicapPlacement::Free,
+            ..Default::default()
+        });
+        assert_eq!(game.handicap_pending(), 2);
+        assert_eq!(game.to_move(), Color::Black);
+        let err = game
+            .play(Move {
+                color: Color::Black,
+                point: Some(Point::new(4, 4).unwrap()),
+            })
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            RuleViolation::HandicapPending { remaining: 2 }
+        ));
+
+        game.place_handicap_stone(Point::new(2, 2).unwrap())
+            .unwrap();
+        let err = game
+            .place_handicap_stone(Point::new(2, 2).unwrap())
+            .unwrap_err();
+        assert!(matches!(err, RuleViolation::Occupied { .. }));
+        game.place_handicap_stone(Point::new(6, 6).unwrap())
+            .unwrap();
+
+        assert_eq!(game.handicap_pending(), 0);
+        assert_eq!(game.to_move(), Color::White);
+        assert!(matches!(
+            game.place_handicap_stone(Point::new(4, 4).unwrap()),
+            Err(RuleViolation::NoHandicapPending)
+        ));
+        play_at(&mut game, 4, 4);
+        assert_eq!(game.to_move(), Color::Black);
+    }
+
+    #[test]
+    fn handicap_position_seeds_superko_history() {
+        let mut game = GameState::new(GameConfig {
+            size: 9,
+            handicap: 2,
+            ..Default::default()
+        });
+        let start = game.board_snapshot().intersections;
+        game.play(Move {
+            color: Color::White,
+            point: None,
+        })
+        .unwrap();
+        game.play(Move {
+            color: Color::Black,
+            point: None,
+        })
+        .unwrap();
+        game.goto_move(0).unwrap();
+        assert_eq!(game.board_snapshot().intersections, start);
+        assert_eq!(game.to_move(), Color::White);
+        assert_eq!(game.handicap_stones().len(), 2);
+    }
+
+    #[test]
+    fn multi_stone_suicide_follows_ruleset() {
+        let idx = |x: usize, y: usize| Point::new(x, y).unwrap().to_index(5);
+        let mut board = Board::new(5);
+        // Black pair at (0,0)-(1,0) with a last liberty at (2,0); white walls it in.
+        board.set(idx(0, 0), Some(Color::Black));
+        board.set(idx(0, 1), Some(Color::White));
+        board.set(idx(1, 1), Some(Color::White));
+        board.set(idx(3, 0), Some(Color::White));
+        board.set(idx(2, 1), Some(Color::White));
+        board.set(idx(1, 0), Some(Color::Black));
+        let target = Point::new(2, 0).unwrap();
+
+        for ruleset in Ruleset::ALL {
+            let mut game = GameState::new(GameConfig {
+                size: 5,
+                ruleset,
+                ..Default::default()
+            });
+            let mut history = HashSet::new();
+            history.insert(board.position_key(Color::Black));
+            game.set_internal_state(board.clone(), history, Color::Black);
+
+            let result = game.play(Move {
+                color: Color::Black,
+                point: Some(target),
+            });
+            if ruleset.allows_suicide() {
+                let outcome = result.unwrap();
+                assert_eq!(outcome.last_move.captured.len(), 3);
+                assert_eq!(game.captures().white, 3);
+                assert!(outcome
+                    .board
+                    .intersections
+                    .iter()
+                    .take(3)
+                    .all(|c| c.is_none()));
+
+                game.undo().unwrap();
+                assert_eq!(game.board_snapshot().intersections, board.intersections());
+                assert_eq!(game.captures().white, 0);
+            } else {
+                assert!(matches!(result, Err(RuleViolation::Suicide { .. })));
+            }
+        }
+    }
+
+    #[test]
+    fn aga_pass_stones_and_white_passes_last() {
+        let mut game = GameState::new(GameConfig {
+            size: 9,
+            ruleset: Ruleset::Aga,
+            ..Default::default()
+        });
+        play_at(&mut game, 4, 4);
+        let pass = |color| Move { color, point: None };
+
+        let outcome = game.play(pass(Color::White)).unwrap();
+        assert!(!outcome.game_over);
+        assert_eq!(game.captures().black, 1);
+        let outcome = game.play(pass(Color::Black)).unwrap();
+        assert!(!outcome.game_over, "white must make the final pass");
+        assert_eq!(game.captures().white, 1);
+        let outcome = game.play(pass(Color::White)).unwrap();
+        assert!(outcome.game_over);
+        assert_eq!(game.captures().black, 2);
+
+        game.undo().unwrap();
+        assert_eq!(game.captures().black, 1);
+        assert!(!game.is_over());
+    }
+
+    #[test]
+    fn scoring_method_and_handicap_compensation_follow_ruleset() {
+        let stones = |ruleset| {
+            let mut game = GameState::new(GameConfig {
+                size: 9,
+                komi: 0.5,
+                handicap: 4,
+                ruleset,
+                ..Default::default()
+            });
+            game.play(Move {
+                color: Color::White,
+                point: Some(Point::new(0, 0).unwrap()),
+            })
+            .unwrap();
+            game.score()
+        };
+
+        // Every empty point touches both colours, so only stones (area) or nothing count.
+        let chinese = stones(Ruleset::Chinese);
+        assert_eq!(chinese.handicap_compensation, 4.0);
+        assert_eq!(chinese.black_score, 4.0);
+        assert_eq!(chinese.white_score, 1.0 + 0.5 + 4.0);
+
+        let aga = stones(Ruleset::Aga);
+        assert_eq!(aga.handicap_compensation, 3.0);
+
+        let japanese = stones(Ruleset::Japanese);
+        assert_eq!(japanese.handicap_compensation, 0.0);
+        assert_eq!(japanese.black_score, 0.0);
+        assert_eq!(japanese.white_score, 0.5);
+
+        assert_eq!("aga".parse::<Ruleset>().unwrap(), Ruleset::Aga);
+        assert_eq!(
+            "New Zealand".parse::<Ruleset>().unwrap(),
+            Ruleset::NewZealand
+        );
+        assert_eq!(
+            "tromp-taylor".parse::<Ruleset>().unwrap(),
+            Ruleset::TrompTaylor
+        );
+        assert!("klingon".parse::<Ruleset>().is_err());
+    }
+
+    /// Play into a ko on a 5x5 board and let Black take it at (2,1).
+    fn game_after_ko_capture(ko_rule: KoRule) -> GameState {
+        let mut game = GameState::new(GameConfig {
+            size: 5,
+            ko_rule: Some(ko_rule),
+            ..Default::default()
+        });
+        for (x, y) in [
+            (1, 0),
+            (2, 0),
+            (0, 1),
+            (3, 1),
+            (1, 2),
+            (2, 2),
+            (4, 4),
+            (1, 1),
+        ] {
+            play_at(&mut game, x, y);
+        }
+        let outcome = play_at(&mut game, 2, 1);
+        assert_eq!(outcome.last_move.captured, vec![Point::new(1, 1).unwrap()]);
+        game
+    }
+
+    fn retake_after_both_pass(game: &mut GameState) -> Result<MoveOutcome, RuleViolation> {
+        game.play(Move {
+            color: Color::White,
+            point: None,
+        })?;
+        game.play(Move {
+            color: Color::Black,
+            point: None,
+        })?;
+        if game.phase() == GamePhase::Scoring {
+            game.resume_play()?;
+        }
+        game.play(Move {
+            color: Color::White,
+            point: Some(Point::new(1, 1).unwrap()),
+        })
+    }
+
+    #[test]
+    fn every_ko_rule_bans_the_immediate_recapture() {
+        for ko_rule in [
+            KoRule::Simple,
+            KoRule::PositionalSuperko,
+            KoRule::SituationalSuperko,
+            KoRule::NoResult,
+        ] {
+            let mut game = game_after_ko_capture(ko_rule);
+            let err = game
+                .play(Move {
+                    color: Color::White,
+                    point: Some(Point::new(1, 1).unwrap()),
+                })
+                .unwrap_err();
+            match ko_rule {
+                KoRule::Simple | KoRule::NoResult => {
+                    assert!(matches!(err, RuleViolation::Ko { .. }))
+                }
+                _ => assert!(matches!(err, RuleViolation::SuperKo)),
+            }
+
+            // After a ko threat exchange the retake is fine under every rule.
+            play_at(&mut game, 0, 4);
+            play_at(&mut game, 4, 0);
+            let outcome = play_at(&mut game, 1, 1);
+            assert_eq!(outcome.last_move.captured, vec![Point::new(2, 1).unwrap()]);
+            assert!(!outcome.no_result);
+        }
+    }
+
+    #[test]
+    fn simple_ko_allows_longer_repetitions() {
+        let mut game = game_after_ko_capture(KoRule::Simple);
+        let outcome = retake_after_both_pass(&mut game).unwrap();
+        assert_eq!(outcome.last_move.captured, vec![Point::new(2, 1).unwrap()]);
+        assert!(!outcome.no_result);
+    }
+
+    #[test]
+    fn configs_with_the_old_superko_flag_keep_their_ko_rule() {
+        let parse = |json: &str| serde_json::from_str::<GameConfig>(json).unwrap();
+        let config = parse(r#"{"size":9,"superko":false}"#);
+        assert_eq!((config.size, config.ko_rule), (9, Some(KoRule::Simple)));
+        assert_eq!(parse(r#"{"superko":true}"#).ko_rule, None);
+        let explicit = parse(r#"{"superko":false,"ko_rule":"positional_superko"}"#);
+        assert_eq!(explicit.ko_rule, Some(KoRule::PositionalSuperko));
+        assert_eq!(parse("{}").komi, GameConfig::default().komi);
+    }
+
+    #[test]
+    fn superko_forbids_recreating_an_earlier_position() {
+        for ko_rule in [KoRule::PositionalSuperko, KoRule::SituationalSuperko] {
+            let mut game = game_after_ko_capture(ko_rule);
+            let err = retake_after_both_pass(&mut game).unwrap_err();
+            assert!(matches!(err, RuleViolation::SuperKo));
+        }
+    }
+
+    #[test]
+    fn positional_superko_ignores_who_is_to_move() {
+        let idx = |x: usize, y: usize| Point::new(x, y).unwrap().to_index(5);
+        let mut board = Board::new(5);
+        for (x, y) in [(1, 0), (0, 1), (1, 2)] {
+            board.set(idx(x, y), Some(Color::Black));
+        }
+        for (x, y) in [(2, 0), (3, 1), (2, 2), (1, 1)] {
+            board.set(idx(x, y), Some(Color::White));
+        }
+        let capture = Point::new(2, 1).unwrap();
+        let (after_capture, _) = board.after_play(Color::Black, capture).unwrap();
+
+        // The post-capture board has been seen before, but with Black to move.
+        let positional = {
+            let mut game = GameState::new(GameConfig {
+                size: 5,
+                ko_rule: Some(KoRule::PositionalSuperko),
+                ..Default::default()
+            });
+            let history = HashSet::from([board.hash(), after_capture.hash()]);
+            game.set_internal_state(board.clone(), history, Color::Black);
+            game.play(Move {
+                color: Color::Black,
+                point: Some(capture),
+            })
+        };
+        assert!(matches!(positional, Err(RuleViolation::SuperKo)));
+
+        let situational = {
+            let mut game = GameState::new(GameConfig {
+                size: 5,
+                ko_rule: Some(KoRule::SituationalSuperko),
+                ..Default::default()
+            });
+            let history = HashSet::from([
+                board.position_key(Color::Black),
+                after_capture.position_key(Color::Black),
+            ]);
+            game.set_internal_state(board.clone(), history, Color::Black);
+            game.play(Move {
+                color: Color::Black,
+                point: Some(capture),
+            })
+        };
+        assert!(situational.is_ok());
+    }
+
+    #[test]
+    fn repeated_position_ends_japanese_game_without_result() {
+        let mut game = GameState::new(GameConfig {
+            size: 5,
+            ruleset: Ruleset::Japanese,
+            ..Default::default()
+        });
+        assert_eq!(game.config().effective_ko_rule(), KoRule::NoResult);
+        for (x, y) in [
+            (1, 0),
+            (2, 0),
+            (0, 1),
+            (3, 1),
+            (1, 2),
+            (2, 2),
+            (4, 4),
+            (1, 1),
+            (2, 1),
+        ] {
+            play_at(&mut game, x, y);
+        }
+
+        let outcome = retake_after_both_pass(&mut game).unwrap();
+        assert!(outcome.no_result);
+        assert!(outcome.game_over);
+        let err = game
+            .play(Move {
+                color: Color::Black,
+                point: Some(Point::new(0, 4).unwrap()),
+            })
+            .unwrap_err();
+        assert!(matches!(err, RuleViolation::NoResult));
+
+        game.undo().unwrap();
+        assert!(!game.no_result());
+    }
+
+    fn game_with_board(size: usize, ruleset: Ruleset, rows: &[&str]) -> GameState {
+        let mut game = GameState::new(GameConfig {
+            size,
+            komi: 0.5,
+            ruleset,
+            ..Default::default()
+        });
+        let mut board = Board::new(size);
+        for (y, row) in rows.iter().enumerate() {
+            for (x, cell) in row.chars().enumerate() {
+                let color = match cell {
+                    'X' => Some(Color::Black),
+                    'O' => Some(Color::White),
+                    _ => None,
+                };
+                board.set(Point::new(x, y).unwrap().to_index(size), color);
+            }
+        }
+        let history = HashSet::from([board.position_key(Color::Black)]);
+        game.set_internal_state(board, history, Color::Black);
+        game
+    }
+
+    #[test]
+    fn area_scoring_counts_stones_and_territory_scoring_counts_prisoners() {
+        let rows = [".X.O.", ".X.O.", ".XOO.", ".X.O.", ".X.O."];
+
+        let area = game_with_board(5, Ruleset::Chinese, &rows).score();
+        assert_eq!(area.method, ScoringMethod::Area);
+        assert_eq!((area.stones_black, area.stones_white), (5, 6));
+        assert_eq!((area.territory_black, area.territory_white), (5, 5));
+        assert_eq!(area.black_score, 10.0);
+        assert_eq!(area.white_score, 11.5);
+        assert_eq!(area.points[0], PointScore::BlackTerritory);
+        assert_eq!(area.points[2], PointScore::Dame);
+        assert_eq!(area.points[3], PointScore::WhiteStone);
+
+        let territory = game_with_board(5, Ruleset::Japanese, &rows).score();
+        assert_eq!(territory.method, ScoringMethod::Territory);
+        assert_eq!(territory.black_score, 5.0);
+        assert_eq!(territory.white_score, 5.5);
+    }
+
+    #[test]
+    fn dead_stones_count_for_the_opponent() {
+        let rows = [".X.O.", "OX.O.", ".X.O.", ".X.O.", ".X.O."];
+        let dead = [Point::new(0, 1).unwrap()];
+
+        let area = game_with_board(5, Ruleset::Chinese, &rows).score_with_dead(&dead);
+        assert_eq!(area.dead_white, 1);
+        assert_eq!(area.territory_black, 5);
+        assert_eq!(area.black_score, 10.0);
+        assert_eq!(area.points[5], PointScore::DeadWhite);
+
+        let territory = game_with_board(5, Ruleset::Japanese, &rows).score_with_dead(&dead);
+        assert_eq!(territory.black_score, 5.0 + 1.0);
+        assert_eq!(territory.white_score, 5.5);
+
+        // Left alive, the white stone turns black's side into dame.
+        let alive = game_with_board(5, Ruleset::Japanese, &rows).score();
+        assert_eq!(alive.territory_black, 0);
+    }
+
+    #[test]
+    fn eyes_in_seki_are_not_territory_under_japanese_rules() {
+        let rows = [".X.O.", "XXOOO", "OOOXX", "XXXXX", "....."];
+        let idx = |x: usize, y: usize| Point::new(x, y).unwrap().to_index(5);
+
+        let territory = game_with_board(5, Ruleset::Japanese, &rows).score();
+        assert_eq!(territory.points[idx(0, 0)], PointScore::Seki);
+        assert_eq!(territory.points[idx(2, 0)], PointScore::Dame);
+        assert_eq!(territory.points[idx(4, 0)], PointScore::Seki);
+        assert_eq!(territory.points[idx(0, 4)], PointScore::BlackTerritory);
+        assert_eq!(territory.black_score, 5.0);
+        assert_eq!(territory.white_score, 0.5);
+
+        let area = game_with_board(5, Ruleset::Chinese, &rows).score();
+        assert_eq!(area.points[idx(0, 0)], PointScore::BlackTerritory);
+        assert_eq!(area.points[idx(4, 0)], PointScore::WhiteTerritory);
+        assert_eq!(area.black_score, 10.0 + 6.0);
+        assert_eq!(area.white_score, 7.0 + 1.0 + 0.5);
+    }
+
+    #[test]
+    fn dead_stone_marking_requires_both_players_to_accept() {
+        let rows = [".X.O.", "OX.O.", ".X.O.", ".X.O.", ".X.O."];
+        let mut game = game_with_board(5, Ruleset::Japanese, &rows);
+        let pass = |color| Move { color, point: None };
+        let white_stone = Point::new(0, 1).unwrap();
+
+        assert!(matches!(
+            game.toggle_dead(white_stone),
+            Err(RuleViolation::NotScoring {
+                phase: GamePhase::Playing
+            })
+        ));
+        game.play(pass(Color::Black)).unwrap();
+        game.play(pass(Color::White)).unwrap();
+        assert_eq!(game.phase(), GamePhase::Scoring);
+        assert!(game.legal_moves().is_empty());
+        assert!(matches!(
+            game.play(Move {
+                color: Color::Black,
+                point: Some(Point::new(2, 2).unwrap()),
+            }),
+            Err(RuleViolation::NotPlaying { .. })
+        ));
+
+        // The estimate already marks the lone white stone dead.
+        assert_eq!(game.dead_stones(), vec![white_stone]);
+        let revived = game.toggle_dead(white_stone).unwrap();
+        assert!(revived.dead_stones.is_empty());
+        let marked = game.toggle_dead(white_stone).unwrap();
+        assert_eq!(marked.dead_stones, vec![white_stone]);
+        assert_eq!(marked.score.dead_white, 1);
+        assert_eq!(marked.score.black_score, 6.0);
+
+        game.accept_score(Color::Black).unwrap();
+        // Changing the marks withdraws acceptance.
+        game.toggle_dead(Point::new(3, 0).unwrap()).unwrap();
+        let unmarked = game.toggle_dead(Point::new(3, 0).unwrap()).unwrap();
+        assert!(!unmarked.black_accepted);
+        assert_eq!(unmarked.dead_stones, vec![white_stone]);
+
+        game.accept_score(Color::Black).unwrap();
+        let done = game.accept_score(Color::White).unwrap();
+        assert_eq!(done.phase, GamePhase::Finished);
+        assert_eq!(game.final_score().unwrap().black_score, 6.0);
+        let result = game.result().unwrap();
+        assert_eq!(result.winner(), Some(Color::Black));
+        assert!(result.to_string().starts_with("B+"));
+        assert!(matches!(
+            game.toggle_dead(white_stone),
+            Err(RuleViolation::NotScoring {
+                phase: GamePhase::Finished
+            })
+        ));
+    }
+
+    #[test]
+    fn disagreement_resumes_play() {
+        let rows = [".X.O.", "OX.O.", ".X.O.", ".X.O.", ".X.O."];
+        let mut game = game_with_board(5, Ruleset::Japanese, &rows);
+        game.play(Move {
+            color: Color::Black,
+            point: None,
+        })
+        .unwrap();
+        game.play(Move {
+            color: Color::White,
+            point: None,
+        })
+        .unwrap();
+        assert_eq!(game.dead_stones(), vec![Point::new(0, 1).unwrap()]);
+
+        game.resume_play().unwrap();
+        assert_eq!(game.phase(), GamePhase::Playing);
+        assert_eq!(game.consecutive_passes(), 0);
+        assert!(game.dead_stones().is_empty());
+        play_at(&mut game, 0, 0);
+        game.play(Move {
+            color: Color::White,
+            point: None,
+        })
+        .unwrap();
+        let outcome = play_at(&mut game, 0, 2);
+        assert_eq!(outcome.last_move.captured, vec![Point::new(0, 1).unwrap()]);
+
+        // Undoing the final pass also leaves the scoring phase.
+        game.play(Move {
+            color: Color::White,
+            point: None,
+        })
+        .unwrap();
+        game.play(Move {
+            color: Color::Black,
+            point: None,
+        })
+        .unwrap();
+        assert_eq!(game.phase(), GamePhase::Scoring);
+        game.undo().unwrap();
+        assert_eq!(game.phase(), GamePhase::Playing);
+    }
+
+    #[test]
+    fn resignation_ends_the_game_until_undone() {
+        let mut game = GameState::new(GameConfig {
+            size: 9,
+            ..Default::default()
+        });
+        play_at(&mut game, 4, 4);
+        assert_eq!(game.result(), None);
+        game.resign(Color::White).unwrap();
+
+        assert_eq!(game.phase(), GamePhase::Finished);
+        assert!(game.is_over());
+        assert_eq!(
+            game.result(),
+            Some(GameResult::Resignation {
+                winner: Color::Black
+            })
+        );
+        assert_eq!(game.result().unwrap().to_string(), "B+R");
+        let forfeit = GameResult::Forfeit {
+            winner: Color::White,
+        };
+        assert_eq!(forfeit.to_string(), "W+F");
+        assert_eq!(forfeit.winner(), Some(Color::White));
+        assert!(matches!(
+            game.play(Move {
+                color: Color::White,
+                point: None,
+            }),
+            Err(RuleViolation::NotPlaying { .. })
+        ));
+        assert!(matches!(
+            game.resign(Color::Black),
+            Err(RuleViolation::NotPlaying { .. })
+        ));
+
+        game.undo().unwrap();
+        assert_eq!(game.phase(), GamePhase::Playing);
+        assert_eq!(game.resigned(), None);
+    }
+
+    #[test]
+    fn declared_results_and_resumed_play_are_kept_until_undone() {
+        let mut game = GameState::new(GameConfig {
+            size: 9,
+            ..Default::default()
+        });
+        play_at(&mut game, 4, 4);
+        while game.phase() == GamePhase::Playing {
+            let color = game.to_move();
+            game.play(Move { color, point: None }).unwrap();
+        }
+        game.resume_play().unwrap();
+        let resumed = game.move_count();
+        assert_eq!(game.events(), [(resumed, GameEvent::Resume)]);
+        play_at(&mut game, 2, 2);
+
+        let time = GameResult::Time {
+            winner: Color::White,
+        };
+        game.declare_result(time.clone()).unwrap();
+        assert_eq!(game.phase(), GamePhase::Finished);
+        assert_eq!(game.result(), Some(time.clone()));
+        assert_eq!(game.resigned(), None);
+        assert!(matches!(
+            game.declare_result(time),
+            Err(RuleViolation::NotPlaying { .. })
+        ));
+
+        game.undo().unwrap();
+        assert_eq!((game.result(), game.events().len()), (None, 1));
+        game.undo().unwrap();
+        assert!(game.events().is_empty());
+    }
+
+    fn board_from_rows(rows: &[&str]) -> Board {
+        let mut board = Board::new(rows.len());
+        for (y, row) in rows.iter().enumerate() {
+            for (x, cell) in row.chars().enumerate() {
+                let color = match cell {
+                    'X' => Color::Black,
+                    'O' => Color::White,
+                    _ => continue,
+                };
+                board.set(y * rows.len() + x, Some(color));
+            }
+        }
+        board
+    }
+
+    #[test]
+    fn benson_requires_two_vital_regions() {
+        let two_eyes = board_from_rows(&[".X.X.", "XXXXX", ".....", ".....", "....."]);
+        assert!(two_eyes
+            .unconditionally_alive(Color::Black)
+            .iter()
+            .zip(two_eyes.intersections())
+            .all(|(&alive, cell)| alive == cell.is_some()));
+
+        let one_eye = board_from_rows(&[".XXXX", "XXXXX", ".....", ".....", "....."]);
+        assert!(one_eye
+            .unconditionally_alive(Color::Black)
+            .iter()
+            .all(|&alive| !alive));
+
+        // A white stone inside an eye of an unconditionally alive group is dead.
+        let invaded = board_from_rows(&[".XOX.", "XXXXX", "OOOOO", ".....", "....."]);
+        let estimate = invaded.estimate_ownership();
+        assert_eq!(estimate.dead_stones, vec![Point::new(2, 0).unwrap()]);
+        assert_eq!(estimate.unconditionally_alive.len(), 7);
+    }
+
+    #[test]
+    fn estimate_marks_invaders_dead_and_assigns_territory() {
+        let board = board_from_rows(&[
+            "..X.O....",
+            "..X.O....",
+            "..X.O....",
+            "..X.O....",
+            "O.X.O.X..",
+            "..X.O....",
+            "..X.O....",
+            "..X.O....",
+            "..X.O....",
+        ]);
+        let estimate = board.estimate_ownership();
+        assert_eq!(
+            estimate.dead_stones,
+            vec![Point::new(0, 4).unwrap(), Point::new(6, 4).unwrap()]
+        );
+        assert!(estimate.unconditionally_alive.is_empty());
+
+        let owner = |x: usize, y: usize| estimate.owner[y * 9 + x];
+        assert_eq!(owner(0, 4), Some(Color::Black));
+        assert_eq!(owner(1, 0), Some(Color::Black));
+        assert_eq!(owner(6, 4), Some(Color::White));
+        assert_eq!(owner(8, 8), Some(Color::White));
+        assert_eq!(owner(3, 3), None);
+    }
+
+    #[test]
+    fn estimate_leaves_seki_and_open_positions_alone() {
+        let seki = board_from_rows(&[".X.O.", "XXOOO", "OOOXX", "XXXXX", "....."]);
+        assert!(seki.estimate_ownership().dead_stones.is_empty());
+
+        let opening = board_from_rows(&[
+            ".........",
+            ".........",
+            "..X...O..",
+            ".........",
+            "......O..",
+            ".........",
+            "..X...O..",
+            ".........",
+            ".........",
+        ]);
+        let estimate = opening.estimate_ownership();
+        assert!(estimate.dead_stones.is_empty());
+        assert_eq!(estimate.owner[4 * 9 + 4], None);
+    }
+
+    #[test]
+    fn incremental_chains_match_a_fresh_board() {
+        use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut game = GameState::new(GameConfig {
+            size: 9,
+            ruleset: Ruleset::TrompTaylor,
+            ..Default::default()
+        });
+        for _ in 0..300 {
+            if game.move_count() > 0 && rng.gen_ratio(1, 8) {
+                game.undo();
+            } else {
+                let color = game.to_move();
+                let Some(&point) = game.legal_moves().choose(&mut rng) else {
+                    break;
+                };
+                game.play(Move {
+                    color,
+                    point: Some(point),
+                })
+                .unwrap();
+            }
+
+            let board = game.board();
+            let mut fresh = Board::new(board.size());
+            for (index, &cell) in board.intersections().iter().enumerate() {
+                fresh.set(index, cell);
+            }
+            assert_eq!(fresh.hash(), board.hash());
+            for index in 0..board.intersections().len() {
+                let mut tracked = board.group_at(index);
+                let mut rebuilt = fresh.group_at(index);
+                for group in [&mut tracked, &mut rebuilt] {
+                    group.stones.sort_unstable();
+                    group.liberties.sort_unstable();
+                }
+                assert_eq!(tracked.stones, rebuilt.stones);
+                assert_eq!(tracked.liberties, rebuilt.liberties);
+
+                let point = Point::from_index(index, board.size());
+                for color in [Color::Black, Color::White] {
+                    let predicted = board.hash_after_play(color, point, true);
+                    let played = board.after_play_with(color, point, true);
+                    assert_eq!(
+                        predicted.ok(),
+                        played.ok().map(|(next, _)| next.hash()),
+                        "{color:?} at {point:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    fn stone(x: usize, y: usize, color: Color) -> Move {
+        Move {
+            color,
+            point: Some(Point::new(x, y).unwrap()),
+        }
+    }
+
+    #[test]
+    fn game_tree_recomputes_the_board_between_branches() {
+        let mut tree = GameTree::new(GameConfig {
+            size: 5,
+            ruleset: Ruleset::Chinese,
+            ..Default::default()
+        })
+        .unwrap();
+        let corner = Point::new(0, 0).unwrap();
+
+        let first = tree.play(stone(0, 1, Color::Black)).unwrap();
+        let fork = tree.play(stone(0, 0, Color::White)).unwrap();
+        let capture = tree.play(stone(1, 0, Color::Black)).unwrap();
+        assert!(tree.state().board().is_empty(corner));
+        assert_eq!(tree.state().captures().black, 1);
+
+        tree.back().unwrap();
+        assert_eq!(tree.current(), fork);
+        let tenuki = tree.play(stone(3, 3, Color::Black)).unwrap();
+        assert_eq!(tree.children(fork), &[capture, tenuki]);
+        assert_eq!(tree.state().board().get(corner), Some(Color::White));
+        assert_eq!(tree.state().captures().black, 0);
+
+        tree.goto(capture).unwrap();
+        assert!(tree.state().board().is_empty(corner));
+        assert!(tree.state().board().is_empty(Point::new(3, 3).unwrap()));
+        assert_eq!(tree.main_line(), vec![tree.root(), first, fork, capture]);
+        assert_eq!(tree.path_to(tenuki).unwrap(), vec![0, first, fork, tenuki]);
+
+        // Playing a move that already exists follows the existing branch.
+        let bookmark = tree.bookmark();
+        tree.goto(fork).unwrap();
+        assert_eq!(tree.play(stone(3, 3, Color::Black)).unwrap(), tenuki);
+
+        // A node that also adds stones is not what the player played.
+        tree.goto(tenuki).unwrap();
+        let extra = Setup {
+            white: vec![Point::new(4, 4).unwrap()],
+            ..Setup::default()
+        };
+        let with_setup = tree
+            .add_node(extra, Some(stone(2, 2, Color::White)))
+            .unwrap();
+        tree.goto(tenuki).unwrap();
+        let played = tree.play(stone(2, 2, Color::White)).unwrap();
+        assert_ne!(played, with_setup);
+        assert!(tree.state().board().is_empty(Point::new(4, 4).unwrap()));
+        tree.delete_variation(with_setup).unwrap();
+        tree.delete_variation(played).unwrap();
+        tree.goto(fork).unwrap();
+
+        tree.promote_variation(tenuki).unwrap();
+        assert_eq!(tree.main_line(), vec![tree.root(), first, fork, tenuki]);
+
+        tree.delete_variation(tenuki).unwrap();
+        assert_eq!(tree.current(), fork);
+        assert!(tree.node(tenuki).is_none());
+        assert_eq!(tree.state().board().get(corner), Some(Color::White));
+        assert!(tree.forward().unwrap());
+        assert_eq!(tree.current(), capture);
+        assert!(!tree.forward().unwrap());
+        assert!(matches!(
+            tree.delete_variation(tree.root()),
+            Err(RuleViolation::DeleteRoot)
+        ));
+        assert!(matches!(
+            tree.goto(tenuki),
+            Err(RuleViolation::UnknownNode { .. })
+        ));
+
+        // A bookmark brings back its node and position as they were.
+        tree.goto(first).unwrap();
+        tree.restore(bookmark).unwrap();
+        assert_eq!(tree.current(), capture);
+        assert!(tree.state().board().is_empty(corner));
+        assert_eq!(tree.state().captures().black, 1);
+    }
+
+    #[test]
+    fn game_tree_nodes_hold_setup_comments_and_markup() {
+        let mut tree = GameTree::new(GameConfig {
+            size: 9,
+            ..Default::default()
+        })
+        .unwrap();
+        let setup = tree
+            .add_setup(Setup {
+                black: vec![Point::new(2, 2).unwrap(), Point::new(6, 6).unwrap()],
+                to_move: Some(Color::White),
+                ..Setup::default()
+            })
+            .unwrap();
+        assert_eq!(tree.state().to_move(), Color::White);
+        assert_eq!(
+            tree.state().board().get(Point::new(6, 6).unwrap()),
+            Some(Color::Black)
+        );
+
+        let reply = tree.play(stone(4, 4, Color::White)).unwrap();
+        tree.add_setup(Setup {
+            empty: vec![Point::new(2, 2).unwrap()],
+            ..Setup::default()
+        })
+        .unwrap();
+        assert!(tree.state().board().is_empty(Point::new(2, 2).unwrap()));
+        tree.goto(reply).unwrap();
+        assert_eq!(
+            tree.state().board().get(Point::new(2, 2).unwrap()),
+            Some(Color::Black)
+        );
+        assert!(matches!(
+            tree.add_setup(Setup {
+                white: vec![Point::new(9, 0).unwrap()],
+                ..Setup::default()
+            }),
+            Err(RuleViolation::OutOfBounds { .. })
+        ));
+
+        tree.set_comment(setup, Some("Two stones".into())).unwrap();
+        let triangle = Markup {
+            point: Point::new(4, 4).unwrap(),
+            kind: MarkupKind::Triangle,
+        };
+        tree.add_markup(reply, triangle).unwrap();
+        tree.add_markup(
+            reply,
+            Markup {
+                point: Point::new(4, 4).unwrap(),
+                kind: MarkupKind::Label("A".into()),
+            },
+        )
+        .unwrap();
+        let node = tree.node(reply).unwrap();
+        assert_eq!(node.markup.len(), 1);
+        assert_eq!(node.markup[0].kind, MarkupKind::Label("A".into()));
+        assert_eq!(
+            tree.node(setup).unwrap().comment.as_deref(),
+            Some("Two stones")
+        );
+        tree.remove_markup(reply, Point::new(4, 4).unwrap())
+            .unwrap();
+        assert!(tree.node(reply).unwrap().markup.is_empty());
+    }
 }
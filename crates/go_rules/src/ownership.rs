@@ -0,0 +1,373 @@
+use serde::Serialize;
+
+use crate::{board::Board, color::Color, point::Point};
+
+/// Stones further than this (Manhattan distance) exert no influence.
+const INFLUENCE_RADIUS: usize = 4;
+
+/// Offline guess at who owns each intersection, used to prefill dead-stone marking.
+#[derive(Clone, Debug, Serialize)]
+pub struct OwnershipEstimate {
+    /// Owner of each intersection in board index order; `None` for dame or unsettled points.
+    pub owner: Vec<Option<Color>>,
+    /// Stones judged dead; their points count for the opponent.
+    pub dead_stones: Vec<Point>,
+    /// Stones that cannot be captured even if their owner keeps passing (Benson's algorithm).
+    pub unconditionally_alive: Vec<Point>,
+}
+
+/// Outcome of Benson's algorithm for one colour.
+struct Benson {
+    /// Stones of chains that are unconditionally alive.
+    alive: Vec<bool>,
+    /// Points inside regions that are vital to an unconditionally alive chain.
+    enclosed: Vec<bool>,
+}
+
+impl Board {
+    /// Stones of `color` that are unconditionally alive, in board index order.
+    pub fn unconditionally_alive(&self, color: Color) -> Vec<bool> {
+        benson(self, color).alive
+    }
+
+    /// Estimate dead stones and ownership of every point.
+    ///
+    /// Benson's algorithm settles unconditionally alive chains and the eye space they enclose.
+    /// The remaining chains are judged dead when they sit in a small area walled off by the
+    /// opponent, have fewer than two eyes, lose every capturing race against the wall and the
+    /// influence over their area favours the opponent. With the dead stones removed, empty
+    /// regions are assigned as in [`Board::territory_map`].
+    pub fn estimate_ownership(&self) -> OwnershipEstimate {
+        let cells = self.intersections();
+        let len = cells.len();
+        let mut dead = vec![false; len];
+        let mut unconditional = vec![false; len];
+
+        for color in [Color::Black, Color::White] {
+            let settled = benson(self, color);
+            for index in 0..len {
+                if settled.alive[index] {
+                    unconditional[index] = true;
+                }
+                if settled.enclosed[index] && cells[index] == Some(color.opponent()) {
+                    dead[index] = true;
+                }
+            }
+        }
+
+        // Each pass can only add dead stones, so this settles within a few iterations.
+        loop {
+            let influence = influence_map(self, &dead);
+            let mut changed = false;
+            for color in [Color::Black, Color::White] {
+                for area in walled_areas(self, color, &dead) {
+                    if area.stones.iter().any(|&s| unconditional[s] || dead[s]) {
+                        continue;
+                    }
+                    if clearly_dead(self, color, &area, &dead, &unconditional, &influence) {
+                        for &stone in &area.stones {
+                            dead[stone] = true;
+                        }
+                        changed = true;
+                    }
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+
+        let mut alive_board = self.clone();
+        for (index, &is_dead) in dead.iter().enumerate() {
+            if is_dead {
+                alive_board.set(index, None);
+            }
+        }
+        let territory = alive_board.territory_map();
+        let owner = (0..len)
+            .map(|index| match cells[index] {
+                Some(color) if dead[index] => Some(color.opponent()),
+                Some(color) => Some(color),
+                None => territory[index],
+            })
+            .collect();
+
+        let size = self.size();
+        let points_where = |flags: &[bool]| {
+            flags
+                .iter()
+                .enumerate()
+                .filter(|(_, &flag)| flag)
+                .map(|(index, _)| Point::from_index(index, size))
+                .collect()
+        };
+        OwnershipEstimate {
+            owner,
+            dead_stones: points_where(&dead),
+            unconditionally_alive: points_where(&unconditional),
+        }
+    }
+}
+
+fn benson(board: &Board, color: Color) -> Benson {
+    let cells = board.intersections();
+    let len = cells.len();
+
+    // Chains of `color`.
+    let mut chain_of = vec![usize::MAX; len];
+    let mut chain_liberties: Vec<Vec<bool>> = Vec::new();
+    for index in 0..len {
+        if cells[index] != Some(color) || chain_of[index] != usize::MAX {
+            continue;
+        }
+        let group = board.group_at(index);
+        let id = chain_liberties.len();
+        for &stone in &group.stones {
+            chain_of[stone] = id;
+        }
+        let mut liberties = vec![false; len];
+        for liberty in group.liberties {
+            liberties[liberty] = true;
+        }
+        chain_liberties.push(liberties);
+    }
+    let chain_count = chain_liberties.len();
+
+    // Regions: maximal connected sets of points not occupied by `color`.
+    let mut region_of = vec![usize::MAX; len];
+    let mut regions: Vec<Vec<usize>> = Vec::new();
+    for start in 0..len {
+        if cells[start] == Some(color) || region_of[start] != usize::MAX {
+            continue;
+        }
+        let id = regions.len();
+        region_of[start] = id;
+        let mut stack = vec![start];
+        let mut points = Vec::new();
+        while let Some(index) = stack.pop() {
+            points.push(index);
+            for neighbor in board.neighbors(index) {
+                if cells[neighbor] != Some(color) && region_of[neighbor] == usize::MAX {
+                    region_of[neighbor] = id;
+                    stack.push(neighbor);
+                }
+            }
+        }
+        regions.push(points);
+    }
+
+    // Chains bordering each region, and the chains each region is vital to: every empty point
+    // of the region is a liberty of the chain.
+    let mut bordering: Vec<Vec<usize>> = Vec::with_capacity(regions.len());
+    let mut vital: Vec<Vec<usize>> = Vec::with_capacity(regions.len());
+    for points in &regions {
+        let mut chains = Vec::new();
+        for &index in points {
+            for neighbor in board.neighbors(index) {
+                let id = chain_of[neighbor];
+                if id != usize::MAX && !chains.contains(&id) {
+                    chains.push(id);
+                }
+            }
+        }
+        let vital_to = chains
+            .iter()
+            .copied()
+            .filter(|&id| {
+                points
+                    .iter()
+                    .all(|&index| cells[index].is_some() || chain_liberties[id][index])
+            })
+            .collect();
+        bordering.push(chains);
+        vital.push(vital_to);
+    }
+
+    let mut chain_alive = vec![true; chain_count];
+    let mut region_alive = vec![true; regions.len()];
+    loop {
+        let mut changed = false;
+        for (chain, alive) in chain_alive.iter_mut().enumerate() {
+            if !*alive {
+                continue;
+            }
+            let vital_regions = (0..regions.len())
+                .filter(|&region| region_alive[region] && vital[region].contains(&chain))
+                .count();
+            if vital_regions < 2 {
+                *alive = false;
+                changed = true;
+            }
+        }
+        for region in 0..regions.len() {
+            if region_alive[region] && bordering[region].iter().any(|&c| !chain_alive[c]) {
+                region_alive[region] = false;
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    let alive = (0..len)
+        .map(|index| chain_of[index] != usize::MAX && chain_alive[chain_of[index]])
+        .collect();
+    let mut enclosed = vec![false; len];
+    for (region, points) in regions.iter().enumerate() {
+        if region_alive[region] && !vital[region].is_empty() {
+            for &index in points {
+                enclosed[index] = true;
+            }
+        }
+    }
+    Benson { alive, enclosed }
+}
+
+/// Stones of one colour together with the empty points they can reach without crossing the
+/// opponent's live stones.
+struct WalledArea {
+    stones: Vec<usize>,
+    empties: Vec<usize>,
+}
+
+fn walled_areas(board: &Board, color: Color, dead: &[bool]) -> Vec<WalledArea> {
+    let cells = board.intersections();
+    let len = cells.len();
+    let is_wall = |index: usize| cells[index] == Some(color.opponent()) && !dead[index];
+    let mut visited = vec![false; len];
+    let mut areas = Vec::new();
+    for start in 0..len {
+        if cells[start] != Some(color) || dead[start] || visited[start] {
+            continue;
+        }
+        visited[start] = true;
+        let mut stack = vec![start];
+        let mut area = WalledArea {
+            stones: Vec::new(),
+            empties: Vec::new(),
+        };
+        while let Some(index) = stack.pop() {
+            if cells[index] == Some(color) && !dead[index] {
+                area.stones.push(index);
+            } else {
+                area.empties.push(index);
+            }
+            for neighbor in board.neighbors(index) {
+                if !visited[neighbor] && !is_wall(neighbor) {
+                    visited[neighbor] = true;
+                    stack.push(neighbor);
+                }
+            }
+        }
+        areas.push(area);
+    }
+    areas
+}
+
+fn clearly_dead(
+    board: &Board,
+    color: Color,
+    area: &WalledArea,
+    dead: &[bool],
+    unconditional: &[bool],
+    influence: &[i32],
+) -> bool {
+    let cells = board.intersections();
+    let is_own = |index: usize| cells[index] == Some(color) && !dead[index];
+
+    // An area spanning most of the board is open, not walled off.
+    if 2 * (area.stones.len() + area.empties.len()) > cells.len() {
+        return false;
+    }
+
+    // Eyes: empty pockets of the area bordered only by the area's own stones.
+    let mut seen = vec![false; board.intersections().len()];
+    let mut eyes = 0;
+    for &start in &area.empties {
+        if seen[start] {
+            continue;
+        }
+        seen[start] = true;
+        let mut stack = vec![start];
+        let mut enclosed = true;
+        while let Some(index) = stack.pop() {
+            for neighbor in board.neighbors(index) {
+                if is_own(neighbor) {
+                    continue;
+                }
+                if cells[neighbor].is_some() && !dead[neighbor] {
+                    enclosed = false;
+                } else if !seen[neighbor] {
+                    seen[neighbor] = true;
+                    stack.push(neighbor);
+                }
+            }
+        }
+        if enclosed {
+            eyes += 1;
+        }
+    }
+    if eyes >= 2 {
+        return false;
+    }
+
+    // Capturing races: the wall must have more liberties than any chain inside it.
+    let mut inside_liberties = 0;
+    let mut wall = Vec::new();
+    for &stone in &area.stones {
+        let group = board.group_at(stone);
+        inside_liberties = inside_liberties.max(group.liberties.len());
+        for neighbor in group.stones.iter().flat_map(|&s| board.neighbors(s)) {
+            if cells[neighbor] == Some(color.opponent()) && !dead[neighbor] {
+                wall.push(neighbor);
+            }
+        }
+    }
+    let wall_holds = wall.iter().all(|&stone| {
+        unconditional[stone] || board.group_at(stone).liberties.len() > inside_liberties
+    });
+    if !wall_holds {
+        return false;
+    }
+
+    // Influence: summed over the area, it has to favour the opponent.
+    let sign = match color {
+        Color::Black => 1,
+        Color::White => -1,
+    };
+    let balance: i32 = area
+        .stones
+        .iter()
+        .chain(&area.empties)
+        .map(|&index| influence[index] * sign)
+        .sum();
+    balance < 0
+}
+
+/// Positive where Black's live stones dominate, negative where White's do.
+fn influence_map(board: &Board, dead: &[bool]) -> Vec<i32> {
+    let size = board.size();
+    let cells = board.intersections();
+    let mut influence = vec![0i32; board.intersections().len()];
+    for (index, cell) in cells.iter().enumerate() {
+        let Some(color) = cell else { continue };
+        if dead[index] {
+            continue;
+        }
+        let sign = match color {
+            Color::Black => 1,
+            Color::White => -1,
+        };
+        let (x, y) = (index % size, index / size);
+        for ty in y.saturating_sub(INFLUENCE_RADIUS)..(y + INFLUENCE_RADIUS + 1).min(size) {
+            for tx in x.saturating_sub(INFLUENCE_RADIUS)..(x + INFLUENCE_RADIUS + 1).min(size) {
+                let distance = x.abs_diff(tx) + y.abs_diff(ty);
+                if distance <= INFLUENCE_RADIUS {
+                    influence[ty * size + tx] += sign * (1 << (INFLUENCE_RADIUS - distance));
+                }
+            }
+        }
+    }
+    influence
+}
@@ -0,0 +1,131 @@
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Ruleset {
+    Japanese,
+    Chinese,
+    #[default]
+    Aga,
+    NewZealand,
+    Ing,
+    TrompTaylor,
+}
+
+/// Which earlier positions a move is forbidden to recreate.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KoRule {
+    /// Only the immediate recapture of a single-stone ko is forbidden.
+    Simple,
+    /// No move may recreate any earlier board position.
+    PositionalSuperko,
+    /// No move may recreate an earlier board position with the same player to move.
+    SituationalSuperko,
+    /// Japanese rules: the immediate recapture is forbidden, and any other repetition of an
+    /// earlier position (triple ko, eternal life, ...) ends the game without a result.
+    NoResult,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScoringMethod {
+    /// Stones on the board plus surrounded empty points.
+    Area,
+    /// Surrounded empty points plus prisoners.
+    Territory,
+}
+
+impl Ruleset {
+    pub const ALL: [Ruleset; 6] = [
+        Ruleset::Japanese,
+        Ruleset::Chinese,
+        Ruleset::Aga,
+        Ruleset::NewZealand,
+        Ruleset::Ing,
+        Ruleset::TrompTaylor,
+    ];
+
+    /// Label used in the `tournaments.ruleset` column.
+    pub fn name(self) -> &'static str {
+        match self {
+            Ruleset::Japanese => "Japanese",
+            Ruleset::Chinese => "Chinese",
+            Ruleset::Aga => "AGA",
+            Ruleset::NewZealand => "New Zealand",
+            Ruleset::Ing => "Ing",
+            Ruleset::TrompTaylor => "Tromp-Taylor",
+        }
+    }
+
+    /// Whether a move that leaves its own group without liberties removes that group instead of
+    /// being rejected. Single-stone suicide never changes the board and stays illegal.
+    pub fn allows_suicide(self) -> bool {
+        matches!(
+            self,
+            Ruleset::NewZealand | Ruleset::Ing | Ruleset::TrompTaylor
+        )
+    }
+
+    pub fn ko_rule(self) -> KoRule {
+        match self {
+            Ruleset::Japanese => KoRule::NoResult,
+            Ruleset::Chinese | Ruleset::TrompTaylor => KoRule::PositionalSuperko,
+            Ruleset::Aga | Ruleset::NewZealand | Ruleset::Ing => KoRule::SituationalSuperko,
+        }
+    }
+
+    pub fn scoring(self) -> ScoringMethod {
+        match self {
+            Ruleset::Japanese => ScoringMethod::Territory,
+            _ => ScoringMethod::Area,
+        }
+    }
+
+    /// AGA pass stones: passing hands a prisoner to the opponent and White must pass last, so
+    /// area and territory counting agree.
+    pub fn pass_stones(self) -> bool {
+        matches!(self, Ruleset::Aga)
+    }
+
+    /// Points added to White's score in a game with `handicap` stones.
+    pub fn handicap_compensation(self, handicap: u8) -> f64 {
+        if handicap < 2 {
+            return 0.0;
+        }
+        match self {
+            Ruleset::Chinese | Ruleset::Ing => handicap as f64,
+            Ruleset::Aga => (handicap - 1) as f64,
+            Ruleset::Japanese | Ruleset::NewZealand | Ruleset::TrompTaylor => 0.0,
+        }
+    }
+}
+
+impl fmt::Display for Ruleset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Ruleset {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let normalized: String = value
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .map(|c| c.to_ascii_lowercase())
+            .collect();
+        match normalized.as_str() {
+            "japanese" | "jp" => Ok(Ruleset::Japanese),
+            "chinese" | "cn" => Ok(Ruleset::Chinese),
+            "aga" => Ok(Ruleset::Aga),
+            "newzealand" | "nz" => Ok(Ruleset::NewZealand),
+            "ing" | "goe" => Ok(Ruleset::Ing),
+            "tromptaylor" | "tt" => Ok(Ruleset::TrompTaylor),
+            _ => Err(format!("unknown ruleset: {value}")),
+        }
+    }
+}
@@ -0,0 +1,230 @@
+use serde::Serialize;
+
+use crate::{
+    board::Board,
+    color::Color,
+    game::{Captures, ScoreSummary},
+    point::Point,
+    ruleset::ScoringMethod,
+};
+
+/// How a single intersection contributed to the final score.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PointScore {
+    BlackStone,
+    WhiteStone,
+    BlackTerritory,
+    WhiteTerritory,
+    /// A black stone marked dead; its point counts for White.
+    DeadBlack,
+    /// A white stone marked dead; its point counts for Black.
+    DeadWhite,
+    /// Neutral point, including liberties shared by groups in seki.
+    Dame,
+    /// Eye of a group in seki, which is not territory under territory scoring.
+    Seki,
+}
+
+pub(crate) struct ScoreInput<'a> {
+    pub board: &'a Board,
+    pub dead: &'a [Point],
+    pub captures: &'a Captures,
+    pub method: ScoringMethod,
+    pub komi: f64,
+    pub handicap_compensation: f64,
+}
+
+pub(crate) fn score_position(input: ScoreInput<'_>) -> ScoreSummary {
+    let size = input.board.size();
+    let original = input.board.intersections();
+
+    // Score on the board as it would look with the dead stones taken off.
+    let mut alive = input.board.clone();
+    let mut dead_black = 0u32;
+    let mut dead_white = 0u32;
+    let mut is_dead = vec![false; original.len()];
+    for point in input.dead {
+        let index = point.to_index(size);
+        if index >= original.len() || is_dead[index] {
+            continue;
+        }
+        match original[index] {
+            Some(Color::Black) => dead_black += 1,
+            Some(Color::White) => dead_white += 1,
+            None => continue,
+        }
+        is_dead[index] = true;
+        alive.set(index, None);
+    }
+
+    let chain_ids = chain_ids(&alive);
+    let seki_chains = if input.method == ScoringMethod::Territory {
+        seki_chains(&alive, &chain_ids)
+    } else {
+        Vec::new()
+    };
+
+    let mut points: Vec<PointScore> = alive
+        .intersections()
+        .iter()
+        .map(|cell| match cell {
+            Some(Color::Black) => PointScore::BlackStone,
+            Some(Color::White) => PointScore::WhiteStone,
+            None => PointScore::Dame,
+        })
+        .collect();
+
+    for region in empty_regions(&alive) {
+        let mut colors = Vec::new();
+        let mut touches_seki = false;
+        for &index in &region {
+            for neighbor in alive.neighbors(index) {
+                if let Some(color) = alive.intersections()[neighbor] {
+                    if !colors.contains(&color) {
+                        colors.push(color);
+                    }
+                    touches_seki |= seki_chains.contains(&chain_ids[neighbor]);
+                }
+            }
+        }
+        let status = match colors.as_slice() {
+            [_] if touches_seki => PointScore::Seki,
+            [Color::Black] => PointScore::BlackTerritory,
+            [Color::White] => PointScore::WhiteTerritory,
+            _ => PointScore::Dame,
+        };
+        for index in region {
+            points[index] = status;
+        }
+    }
+
+    let mut territory_black = 0u32;
+    let mut territory_white = 0u32;
+    let mut stones_black = 0u32;
+    let mut stones_white = 0u32;
+    for (index, status) in points.iter_mut().enumerate() {
+        match *status {
+            PointScore::BlackStone => stones_black += 1,
+            PointScore::WhiteStone => stones_white += 1,
+            PointScore::BlackTerritory => territory_black += 1,
+            PointScore::WhiteTerritory => territory_white += 1,
+            _ => {}
+        }
+        if is_dead[index] {
+            *status = match original[index] {
+                Some(Color::Black) => PointScore::DeadBlack,
+                _ => PointScore::DeadWhite,
+            };
+        }
+    }
+
+    let (black_points, white_points) = match input.method {
+        ScoringMethod::Area => (
+            stones_black + territory_black,
+            stones_white + territory_white,
+        ),
+        ScoringMethod::Territory => (
+            territory_black + input.captures.black + dead_white,
+            territory_white + input.captures.white + dead_black,
+        ),
+    };
+
+    ScoreSummary {
+        method: input.method,
+        black_score: black_points as f64,
+        white_score: white_points as f64 + input.komi + input.handicap_compensation,
+        territory_black,
+        territory_white,
+        stones_black,
+        stones_white,
+        dead_black,
+        dead_white,
+        captures: input.captures.clone(),
+        komi: input.komi,
+        handicap_compensation: input.handicap_compensation,
+        points,
+    }
+}
+
+/// Label every stone with the index of the chain it belongs to (`usize::MAX` for empty points).
+fn chain_ids(board: &Board) -> Vec<usize> {
+    let cells = board.intersections();
+    let mut ids = vec![usize::MAX; cells.len()];
+    let mut next = 0;
+    for index in 0..cells.len() {
+        if cells[index].is_none() || ids[index] != usize::MAX {
+            continue;
+        }
+        for stone in board.group_at(index).stones {
+            ids[stone] = next;
+        }
+        next += 1;
+    }
+    ids
+}
+
+/// Empty regions, each a list of intersection indices.
+fn empty_regions(board: &Board) -> Vec<Vec<usize>> {
+    let cells = board.intersections();
+    let mut visited = vec![false; cells.len()];
+    let mut regions = Vec::new();
+    for start in 0..cells.len() {
+        if cells[start].is_some() || visited[start] {
+            continue;
+        }
+        visited[start] = true;
+        let mut stack = vec![start];
+        let mut region = Vec::new();
+        while let Some(index) = stack.pop() {
+            region.push(index);
+            for neighbor in board.neighbors(index) {
+                if cells[neighbor].is_none() && !visited[neighbor] {
+                    visited[neighbor] = true;
+                    stack.push(neighbor);
+                }
+            }
+        }
+        regions.push(region);
+    }
+    regions
+}
+
+/// Chains in seki: they share a liberty with an opposing chain that neither side can fill
+/// without putting itself in atari and without capturing anything.
+fn seki_chains(board: &Board, chain_ids: &[usize]) -> Vec<usize> {
+    let size = board.size();
+    let cells = board.intersections();
+    let mut chains = Vec::new();
+    for index in 0..cells.len() {
+        if cells[index].is_some() {
+            continue;
+        }
+        let neighbors: Vec<usize> = board.neighbors(index).collect();
+        let touches = |color: Color| neighbors.iter().any(|&n| cells[n] == Some(color));
+        if !touches(Color::Black) || !touches(Color::White) {
+            continue;
+        }
+        let point = Point::from_index(index, size);
+        if !fills_into_atari(board, Color::Black, point)
+            || !fills_into_atari(board, Color::White, point)
+        {
+            continue;
+        }
+        for &neighbor in &neighbors {
+            let id = chain_ids[neighbor];
+            if id != usize::MAX && !chains.contains(&id) {
+                chains.push(id);
+            }
+        }
+    }
+    chains
+}
+
+fn fills_into_atari(board: &Board, color: Color, point: Point) -> bool {
+    match board.after_play(color, point) {
+        Err(_) => true,
+        Ok((_, captured)) if !captured.is_empty() => false,
+        Ok((next, _)) => next.group_at(point.to_index(board.size())).liberties.len() <= 1,
+    }
+}
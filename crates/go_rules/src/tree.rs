@@ -0,0 +1,360 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    color::Color,
+    error::RuleViolation,
+    game::{GameConfig, GameState, Move},
+    point::Point,
+};
+
+/// Index of a node in a [`GameTree`]. Ids stay valid until the node is deleted.
+pub type NodeId = usize;
+
+/// Stones added or removed by a node, as in SGF `AB`/`AW`/`AE`, and an optional `PL`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Setup {
+    pub black: Vec<Point>,
+    pub white: Vec<Point>,
+    pub empty: Vec<Point>,
+    pub to_move: Option<Color>,
+}
+
+impl Setup {
+    pub fn is_empty(&self) -> bool {
+        self.black.is_empty()
+            && self.white.is_empty()
+            && self.empty.is_empty()
+            && self.to_move.is_none()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MarkupKind {
+    Circle,
+    Square,
+    Triangle,
+    Cross,
+    Label(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Markup {
+    pub point: Point,
+    pub kind: MarkupKind,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Node {
+    pub parent: Option<NodeId>,
+    /// Child variations; the first child continues the main line.
+    pub children: Vec<NodeId>,
+    pub mv: Option<Move>,
+    pub setup: Setup,
+    pub comment: Option<String>,
+    pub markup: Vec<Markup>,
+}
+
+/// A node of a [`GameTree`] with the position after it, to return to without replaying the
+/// path from the root.
+#[derive(Clone, Debug)]
+pub struct Bookmark {
+    node: NodeId,
+    state: GameState,
+}
+
+/// Game record with variations. The tree keeps the position at its current node and
+/// recomputes it whenever navigation leaves the current line.
+#[derive(Clone, Debug)]
+pub struct GameTree {
+    config: GameConfig,
+    nodes: Vec<Option<Node>>,
+    current: NodeId,
+    state: GameState,
+}
+
+impl GameTree {
+    pub fn new(config: GameConfig) -> Result<Self, RuleViolation> {
+        let state = GameState::try_new(config.clone())?;
+        Ok(Self {
+            config,
+            nodes: vec![Some(Node::default())],
+            current: 0,
+            state,
+        })
+    }
+
+    pub fn config(&self) -> &GameConfig {
+        &self.config
+    }
+
+    pub fn root(&self) -> NodeId {
+        0
+    }
+
+    pub fn current(&self) -> NodeId {
+        self.current
+    }
+
+    /// Position after the current node.
+    pub fn state(&self) -> &GameState {
+        &self.state
+    }
+
+    pub fn node(&self, id: NodeId) -> Option<&Node> {
+        self.nodes.get(id)?.as_ref()
+    }
+
+    fn node_mut(&mut self, id: NodeId) -> Result<&mut Node, RuleViolation> {
+        self.nodes
+            .get_mut(id)
+            .and_then(Option::as_mut)
+            .ok_or(RuleViolation::UnknownNode { node: id })
+    }
+
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.node(id)?.parent
+    }
+
+    pub fn children(&self, id: NodeId) -> &[NodeId] {
+        self.node(id).map_or(&[], |node| &node.children)
+    }
+
+    /// Nodes from the root to `id`, inclusive.
+    pub fn path_to(&self, id: NodeId) -> Result<Vec<NodeId>, RuleViolation> {
+        self.node(id)
+            .ok_or(RuleViolation::UnknownNode { node: id })?;
+        let mut path = vec![id];
+        let mut node = id;
+        while let Some(parent) = self.parent(node) {
+            path.push(parent);
+            node = parent;
+        }
+        path.reverse();
+        Ok(path)
+    }
+
+    /// The root followed by the first child of every node.
+    pub fn main_line(&self) -> Vec<NodeId> {
+        let mut line = vec![self.root()];
+        while let Some(&next) = self
+            .children(*line.last().expect("line starts at root"))
+            .first()
+        {
+            line.push(next);
+        }
+        line
+    }
+
+    /// Play `mv` after the current node and move there. An existing child with the same move
+    /// and no setup stones is reused; otherwise the move starts a new variation.
+    pub fn play(&mut self, mv: Move) -> Result<NodeId, RuleViolation> {
+        let existing = self.children(self.current).iter().copied().find(|&child| {
+            self.nodes[child]
+                .as_ref()
+                .is_some_and(|node| node.setup.is_empty() && node.mv.as_ref() == Some(&mv))
+        });
+        if let Some(child) = existing {
+            self.goto(child)?;
+            return Ok(child);
+        }
+
+        self.add_node(Setup::default(), Some(mv))
+    }
+
+    /// Add a child of the current node that places or removes stones, and move there.
+    pub fn add_setup(&mut self, setup: Setup) -> Result<NodeId, RuleViolation> {
+        self.add_node(setup, None)
+    }
+
+    /// Add a child of the current node with setup stones applied before an optional move, as
+    /// a game record node may hold both, and move there.
+    pub fn add_node(&mut self, setup: Setup, mv: Option<Move>) -> Result<NodeId, RuleViolation> {
+        let node = Node {
+            mv,
+            setup,
+            ..Node::default()
+        };
+        let mut state = self.state.clone();
+        apply_node(&mut state, &node)?;
+        let id = self.push_child(node);
+        self.current = id;
+        self.state = state;
+        Ok(id)
+    }
+
+    /// Add setup stones to an existing node, such as handicap stones on the root, and
+    /// recompute the current position.
+    pub fn extend_setup(&mut self, id: NodeId, setup: Setup) -> Result<(), RuleViolation> {
+        let node = self.node_mut(id)?;
+        let previous = node.setup.clone();
+        node.setup.black.extend(setup.black);
+        node.setup.white.extend(setup.white);
+        node.setup.empty.extend(setup.empty);
+        if setup.to_move.is_some() {
+            node.setup.to_move = setup.to_move;
+        }
+        match self.replay(self.current) {
+            Ok(state) => {
+                self.state = state;
+                Ok(())
+            }
+            Err(err) => {
+                self.node_mut(id)?.setup = previous;
+                Err(err)
+            }
+        }
+    }
+
+    fn push_child(&mut self, mut node: Node) -> NodeId {
+        let id = self.nodes.len();
+        node.parent = Some(self.current);
+        self.nodes.push(Some(node));
+        self.nodes[self.current]
+            .as_mut()
+            .expect("current node exists")
+            .children
+            .push(id);
+        id
+    }
+
+    pub fn set_comment(
+        &mut self,
+        id: NodeId,
+        comment: Option<String>,
+    ) -> Result<(), RuleViolation> {
+        self.node_mut(id)?.comment = comment.filter(|text| !text.is_empty());
+        Ok(())
+    }
+
+    /// Add markup to a node, replacing any markup already on that point.
+    pub fn add_markup(&mut self, id: NodeId, markup: Markup) -> Result<(), RuleViolation> {
+        let node = self.node_mut(id)?;
+        node.markup
+            .retain(|existing| existing.point != markup.point);
+        node.markup.push(markup);
+        Ok(())
+    }
+
+    pub fn remove_markup(&mut self, id: NodeId, point: Point) -> Result<(), RuleViolation> {
+        self.node_mut(id)?
+            .markup
+            .retain(|existing| existing.point != point);
+        Ok(())
+    }
+
+    /// The current node and its position, for [`GameTree::restore`].
+    pub fn bookmark(&self) -> Bookmark {
+        Bookmark {
+            node: self.current,
+            state: self.state.clone(),
+        }
+    }
+
+    /// Return to a bookmarked node of this tree without replaying its path. Nodes added since
+    /// do not matter, but the bookmark is stale once a node on its path is changed.
+    pub fn restore(&mut self, bookmark: Bookmark) -> Result<(), RuleViolation> {
+        self.node(bookmark.node).ok_or(RuleViolation::UnknownNode {
+            node: bookmark.node,
+        })?;
+        self.current = bookmark.node;
+        self.state = bookmark.state;
+        Ok(())
+    }
+
+    /// Jump to any node, recomputing the position along its path.
+    pub fn goto(&mut self, id: NodeId) -> Result<(), RuleViolation> {
+        if id == self.current {
+            return Ok(());
+        }
+        // Stepping forward into a child only needs that child applied.
+        let node = self
+            .node(id)
+            .ok_or(RuleViolation::UnknownNode { node: id })?;
+        self.state = if node.parent == Some(self.current) {
+            let mut state = self.state.clone();
+            apply_node(&mut state, node)?;
+            state
+        } else {
+            self.replay(id)?
+        };
+        self.current = id;
+        Ok(())
+    }
+
+    /// Move to the parent of the current node. Returns `false` at the root.
+    pub fn back(&mut self) -> Result<bool, RuleViolation> {
+        match self.parent(self.current) {
+            Some(parent) => self.goto(parent).map(|_| true),
+            None => Ok(false),
+        }
+    }
+
+    /// Move to the first child of the current node. Returns `false` at the end of a line.
+    pub fn forward(&mut self) -> Result<bool, RuleViolation> {
+        match self.children(self.current).first() {
+            Some(&child) => self.goto(child).map(|_| true),
+            None => Ok(false),
+        }
+    }
+
+    /// Delete `id` and every node below it. If the current node was inside the deleted
+    /// branch, the tree moves to the branch's parent.
+    pub fn delete_variation(&mut self, id: NodeId) -> Result<(), RuleViolation> {
+        let parent = self
+            .node(id)
+            .ok_or(RuleViolation::UnknownNode { node: id })?
+            .parent
+            .ok_or(RuleViolation::DeleteRoot)?;
+        let current_deleted = self.path_to(self.current)?.contains(&id);
+
+        self.node_mut(parent)?.children.retain(|&child| child != id);
+        let mut stack = vec![id];
+        while let Some(node) = stack.pop() {
+            if let Some(removed) = self.nodes[node].take() {
+                stack.extend(removed.children);
+            }
+        }
+        if current_deleted {
+            self.current = parent;
+            self.state = self.replay(parent)?;
+        }
+        Ok(())
+    }
+
+    /// Make `id` the first child of its parent, so its branch becomes the main line there.
+    pub fn promote_variation(&mut self, id: NodeId) -> Result<(), RuleViolation> {
+        let node = self
+            .node(id)
+            .ok_or(RuleViolation::UnknownNode { node: id })?;
+        let Some(parent) = node.parent else {
+            return Ok(());
+        };
+        let siblings = &mut self.node_mut(parent)?.children;
+        siblings.retain(|&child| child != id);
+        siblings.insert(0, id);
+        Ok(())
+    }
+
+    fn replay(&self, id: NodeId) -> Result<GameState, RuleViolation> {
+        let mut state = GameState::try_new(self.config.clone())?;
+        for node in self.path_to(id)? {
+            apply_node(
+                &mut state,
+                self.nodes[node].as_ref().expect("path nodes exist"),
+            )?;
+        }
+        Ok(state)
+    }
+}
+
+fn apply_node(state: &mut GameState, node: &Node) -> Result<(), RuleViolation> {
+    if !node.setup.is_empty() {
+        state.apply_setup(&node.setup)?;
+    }
+    if let Some(mv) = &node.mv {
+        state.play_recorded(mv.clone())?;
+    }
+    Ok(())
+}
@@ -0,0 +1,134 @@
+use go_rules::{Color, Point};
+
+use crate::{
+    error::GtpError,
+    vertex::{color_name, parse_vertex},
+};
+
+/// The two analysis command families: KataGo's `kata-analyze` and Leela Zero's
+/// `lz-analyze`, which Leela-derived engines also speak.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnalyzeDialect {
+    KataGo,
+    Leela,
+}
+
+impl AnalyzeDialect {
+    pub fn command_name(self) -> &'static str {
+        match self {
+            AnalyzeDialect::KataGo => "kata-analyze",
+            AnalyzeDialect::Leela => "lz-analyze",
+        }
+    }
+
+    /// The command analysing `color`'s move, reporting every `interval_centis` hundredths of
+    /// a second. Only KataGo reports ownership.
+    pub fn command(self, color: Color, interval_centis: u32, ownership: bool) -> String {
+        let mut command = format!(
+            "{} {} {interval_centis}",
+            self.command_name(),
+            color_name(color)
+        );
+        if ownership && self == AnalyzeDialect::KataGo {
+            command.push_str(" ownership true");
+        }
+        command
+    }
+}
+
+/// One candidate move. Rates are fractions from the point of view of the player to move, or
+/// of Black in KataGo JSON analysis; Leela's scaled integers are converted.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MoveInfo {
+    /// `None` for a pass.
+    pub point: Option<Point>,
+    pub visits: u64,
+    pub winrate: f64,
+    pub prior: f64,
+    pub lcb: f64,
+    /// KataGo's expected score lead, in points.
+    pub score_lead: Option<f64>,
+    pub order: u32,
+    /// Principal variation starting with this move; `None` entries are passes.
+    pub pv: Vec<Option<Point>>,
+}
+
+/// One line of analysis output.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AnalysisUpdate {
+    pub moves: Vec<MoveInfo>,
+    /// Ownership of every point in board index order, from -1 to 1 for the player to move.
+    pub ownership: Option<Vec<f64>>,
+}
+
+/// Parse one streamed analysis line, such as
+/// `info move D4 visits 120 winrate 0.54 prior 0.2 lcb 0.5 order 0 pv D4 Q16`.
+/// Unknown fields are skipped together with their numeric or vertex values.
+pub fn parse_analysis(
+    line: &str,
+    size: usize,
+    dialect: AnalyzeDialect,
+) -> Result<AnalysisUpdate, GtpError> {
+    let invalid = |what: &str| GtpError::Protocol(format!("invalid analysis {what}: {line}"));
+    let scale = match dialect {
+        AnalyzeDialect::KataGo => 1.0,
+        AnalyzeDialect::Leela => 10_000.0,
+    };
+    let mut update = AnalysisUpdate::default();
+    let mut tokens = line.split_whitespace().peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            "info" => update.moves.push(MoveInfo::default()),
+            "ownership" => {
+                let mut values = Vec::with_capacity(size * size);
+                while let Some(value) = tokens.peek().and_then(|value| value.parse().ok()) {
+                    values.push(value);
+                    tokens.next();
+                }
+                if values.len() != size * size {
+                    return Err(invalid("ownership"));
+                }
+                update.ownership = Some(values);
+            }
+            key => {
+                let Some(info) = update.moves.last_mut() else {
+                    return Err(invalid("line"));
+                };
+                match key {
+                    "move" => {
+                        let vertex = tokens.next().ok_or_else(|| invalid("move"))?;
+                        info.point = parse_vertex(vertex, size)?;
+                    }
+                    "pv" => {
+                        while let Some(Ok(point)) = tokens.peek().map(|v| parse_vertex(v, size)) {
+                            info.pv.push(point);
+                            tokens.next();
+                        }
+                    }
+                    "visits" | "winrate" | "prior" | "lcb" | "scoreLead" | "order" => {
+                        let value: f64 = tokens
+                            .next()
+                            .and_then(|value| value.parse().ok())
+                            .ok_or_else(|| invalid(key))?;
+                        match key {
+                            "visits" => info.visits = value as u64,
+                            "winrate" => info.winrate = value / scale,
+                            "prior" => info.prior = value / scale,
+                            "lcb" => info.lcb = value / scale,
+                            "scoreLead" => info.score_lead = Some(value),
+                            _ => info.order = value as u32,
+                        }
+                    }
+                    _ => {
+                        while tokens.peek().is_some_and(|value| {
+                            value.parse::<f64>().is_ok() || parse_vertex(value, size).is_ok()
+                        }) {
+                            tokens.next();
+                        }
+                    }
+                }
+            }
+        }
+    }
+    Ok(update)
+}
@@ -0,0 +1,270 @@
+//! A minimal GTP engine for tests. It keeps a board without captures and answers `genmove`
+//! with the first empty vertex, noting its thinking on stderr. `kata-analyze` streams that
+//! vertex as its only candidate until the next command. Extra commands: `stones` lists the
+//! occupied vertices, `cwd` reports its working directory, `args` echoes its arguments and
+//! `crash` exits without answering.
+//!
+//! Flags: `--resign` resigns every `genmove`, `--pass` passes every time, `--illegal` always
+//! answers `A1`, `--ignore-quit` acknowledges `quit` but keeps running, and `--not-gtp`
+//! prints a usage message and exits like a program that is not an engine. `--katago-json`
+//! turns it into a KataGo analysis engine that answers `query_version` and each turn of a
+//! query, last turn first, with a pass and a score lead equal to the turn number.
+
+use std::{
+    collections::BTreeSet,
+    io::{BufRead, Write},
+    sync::mpsc::{self, RecvTimeoutError},
+    time::Duration,
+};
+
+const COMMANDS: &[&str] = &[
+    "protocol_version",
+    "name",
+    "version",
+    "known_command",
+    "list_commands",
+    "quit",
+    "boardsize",
+    "clear_board",
+    "komi",
+    "play",
+    "genmove",
+    "set_free_handicap",
+    "place_free_handicap",
+    "kata-analyze",
+];
+
+const COLUMNS: &str = "ABCDEFGHJKLMNOPQRSTUVWXYZ";
+
+struct Board {
+    size: usize,
+    stones: BTreeSet<String>,
+}
+
+impl Board {
+    fn empty_vertices(&self) -> impl Iterator<Item = String> + '_ {
+        (1..=self.size)
+            .rev()
+            .flat_map(move |row| {
+                COLUMNS[..self.size]
+                    .chars()
+                    .map(move |column| format!("{column}{row}"))
+            })
+            .filter(|vertex| !self.stones.contains(vertex))
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let flag = |name: &str| args.iter().any(|arg| arg == name);
+    let (ignore_quit, resign, pass, illegal) = (
+        flag("--ignore-quit"),
+        flag("--resign"),
+        flag("--pass"),
+        flag("--illegal"),
+    );
+    if flag("--not-gtp") {
+        println!("usage: mock_gtp_engine [--resign] [--pass] [--illegal]");
+        std::process::exit(2);
+    }
+    if flag("--katago-json") {
+        katago_json();
+        return;
+    }
+    let mut board = Board {
+        size: 19,
+        stones: BTreeSet::new(),
+    };
+
+    // Input is read on its own thread so that streamed analysis can stop at the next command.
+    let (sender, lines) = mpsc::channel();
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lock().lines() {
+            let Ok(line) = line else { break };
+            if sender.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    let mut stdout = std::io::stdout();
+    let mut pending = None;
+    while let Some(line) = pending.take().or_else(|| lines.recv().ok()) {
+        let mut words = line.split_whitespace();
+        let Some(first) = words.next() else { continue };
+        let (id, command) = match first.parse::<u32>() {
+            Ok(id) => (id.to_string(), words.next().unwrap_or_default()),
+            Err(_) => (String::new(), first),
+        };
+        let rest: Vec<String> = words.map(str::to_uppercase).collect();
+
+        if command == "kata-analyze" {
+            let centis = rest.get(1).and_then(|centis| centis.parse().ok());
+            let interval = Duration::from_millis(10 * centis.unwrap_or(10).max(1));
+            let ownership = rest.ends_with(&["OWNERSHIP".to_string(), "TRUE".to_string()]);
+            let _ = writeln!(stdout, "={id}");
+            let _ = stdout.flush();
+            for visits in 1.. {
+                match lines.recv_timeout(interval) {
+                    Ok(next) => {
+                        pending = Some(next);
+                        break;
+                    }
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => return,
+                }
+                let vertex = board.empty_vertices().next().unwrap_or("pass".to_string());
+                let mut info = format!(
+                    "info move {vertex} visits {visits} winrate 0.5 prior 1 lcb 0.4 order 0 pv {vertex}"
+                );
+                if ownership {
+                    info.push_str(" ownership");
+                    info.push_str(&" 0".repeat(board.size * board.size));
+                }
+                let _ = writeln!(stdout, "{info}");
+                let _ = stdout.flush();
+            }
+            let _ = writeln!(stdout);
+            continue;
+        }
+
+        let reply = match command {
+            "protocol_version" => Ok("2".to_string()),
+            "name" => Ok("Mock Engine".to_string()),
+            "version" => Ok("0.1".to_string()),
+            "known_command" => Ok(rest
+                .first()
+                .is_some_and(|name| COMMANDS.contains(&name.to_lowercase().as_str()))
+                .to_string()),
+            "list_commands" => Ok(COMMANDS.join("\n")),
+            "boardsize" => match rest.first().and_then(|size| size.parse::<usize>().ok()) {
+                Some(size @ 2..=25) => {
+                    board.size = size;
+                    board.stones.clear();
+                    Ok(String::new())
+                }
+                _ => Err("unacceptable size"),
+            },
+            "clear_board" => {
+                board.stones.clear();
+                Ok(String::new())
+            }
+            "komi" | "quit" => Ok(String::new()),
+            "play" => match rest.get(1) {
+                Some(vertex) if vertex == "PASS" => Ok(String::new()),
+                Some(vertex) if board.stones.insert(vertex.clone()) => Ok(String::new()),
+                _ => Err("illegal move"),
+            },
+            "set_free_handicap" => {
+                board.stones.extend(rest.iter().cloned());
+                Ok(String::new())
+            }
+            "place_free_handicap" => {
+                let count = rest
+                    .first()
+                    .and_then(|count| count.parse().ok())
+                    .unwrap_or(0);
+                let chosen: Vec<String> = board.empty_vertices().take(count).collect();
+                board.stones.extend(chosen.iter().cloned());
+                Ok(chosen.join(" "))
+            }
+            "genmove" => {
+                let color = rest
+                    .first()
+                    .map_or("?".to_string(), |color| color.to_lowercase());
+                eprintln!("thinking about {color}");
+                if resign {
+                    Ok("resign".to_string())
+                } else if pass {
+                    Ok("pass".to_string())
+                } else if illegal {
+                    Ok("A1".to_string())
+                } else {
+                    let vertex = board.empty_vertices().next();
+                    if let Some(vertex) = &vertex {
+                        board.stones.insert(vertex.clone());
+                    }
+                    Ok(vertex.unwrap_or_else(|| "pass".to_string()))
+                }
+            }
+            "stones" => Ok(board.stones.iter().cloned().collect::<Vec<_>>().join(" ")),
+            "cwd" => Ok(std::env::current_dir()
+                .map(|dir| dir.display().to_string())
+                .unwrap_or_default()),
+            "args" => Ok(args.join(" ")),
+            "crash" => std::process::exit(3),
+            _ => Err("unknown command"),
+        };
+        let written = match reply {
+            Ok(text) => write!(stdout, "={id} {text}\n\n"),
+            Err(message) => write!(stdout, "?{id} {message}\n\n"),
+        };
+        if written.and_then(|()| stdout.flush()).is_err() {
+            break;
+        }
+        if command == "quit" && !ignore_quit {
+            return;
+        }
+    }
+    if ignore_quit {
+        loop {
+            std::thread::sleep(std::time::Duration::from_secs(60));
+        }
+    }
+}
+
+fn katago_json() {
+    let mut stdout = std::io::stdout();
+    for line in std::io::stdin().lock().lines() {
+        let Ok(line) = line else { break };
+        let Ok(query) = serde_json::from_str::<serde_json::Value>(&line) else {
+            let _ = writeln!(stdout, r#"{{"error":"Could not parse json"}}"#);
+            continue;
+        };
+        let id = query["id"].clone();
+        if query["action"] == "query_version" {
+            let reply = serde_json::json!({
+                "id": id,
+                "action": "query_version",
+                "version": "1.0-mock",
+                "git_hash": "mock",
+            });
+            let _ = writeln!(stdout, "{reply}");
+            let _ = stdout.flush();
+            continue;
+        }
+        let size = query["boardXSize"].as_u64().unwrap_or(19) as usize;
+        let moves = query["moves"].as_array().map_or(0, Vec::len) as u64;
+        let turns: Vec<u64> = query["analyzeTurns"]
+            .as_array()
+            .map(|turns| turns.iter().filter_map(serde_json::Value::as_u64).collect())
+            .unwrap_or_default();
+        if turns.iter().any(|turn| *turn > moves) {
+            let error = serde_json::json!({
+                "id": id,
+                "error": "Invalid value for analyzeTurns",
+                "field": "analyzeTurns",
+            });
+            let _ = writeln!(stdout, "{error}");
+            continue;
+        }
+        for turn in turns.into_iter().rev() {
+            let mut reply = serde_json::json!({
+                "id": id,
+                "turnNumber": turn,
+                "moveInfos": [{
+                    "move": "pass", "visits": 1, "winrate": 0.5, "prior": 1.0,
+                    "lcb": 0.5, "order": 0, "pv": ["pass"], "scoreLead": turn,
+                }],
+                "rootInfo": { "visits": 1, "winrate": 0.5, "scoreLead": turn },
+            });
+            if query["includeOwnership"] == true {
+                reply["ownership"] = serde_json::json!(vec![0.0; size * size]);
+            }
+            if query["includePolicy"] == true {
+                reply["policy"] = serde_json::json!(vec![0.0; size * size + 1]);
+            }
+            let _ = writeln!(stdout, "{reply}");
+        }
+        let _ = stdout.flush();
+    }
+}
@@ -0,0 +1,311 @@
+use std::{
+    path::{Path, PathBuf},
+    process::{ExitStatus, Stdio},
+    time::Duration,
+};
+
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    process::{Child, ChildStderr, ChildStdin, ChildStdout, Command},
+    task::JoinHandle,
+    time::timeout,
+};
+
+use crate::{
+    error::GtpError,
+    protocol::{format_command, read_response},
+    transcript::{Direction, Transcript},
+};
+
+/// How to start an engine, as registered in `gtp_engines`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EngineCommand {
+    pub path: PathBuf,
+    pub args: Vec<String>,
+    pub working_directory: Option<PathBuf>,
+}
+
+/// How an engine ended after [`GtpEngine::quit`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Shutdown {
+    /// The engine exited on its own after `quit`.
+    Quit(ExitStatus),
+    /// The engine ignored `quit` and was killed.
+    Killed,
+}
+
+/// A running GTP engine speaking over its stdin and stdout.
+#[derive(Debug)]
+pub struct GtpEngine {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    stdout: BufReader<ChildStdout>,
+    pid: Option<u32>,
+    next_id: u32,
+    /// Whether a streaming response, such as `kata-analyze` output, is still open.
+    streaming: bool,
+    /// Bytes of a streamed line read so far; kept across calls so reads can be cancelled.
+    line: Vec<u8>,
+    transcript: Option<Transcript>,
+    stderr_task: Option<JoinHandle<()>>,
+}
+
+impl GtpEngine {
+    pub fn spawn(command: &EngineCommand) -> Result<Self, GtpError> {
+        Self::start(command, None)
+    }
+
+    /// Spawn an engine that appends every command, response and stderr line to `log_path`.
+    pub fn spawn_logged(command: &EngineCommand, log_path: &Path) -> Result<Self, GtpError> {
+        Self::start(command, Some(Transcript::create(log_path)?))
+    }
+
+    fn start(command: &EngineCommand, transcript: Option<Transcript>) -> Result<Self, GtpError> {
+        let (mut child, stderr_task) = launch(command, transcript.as_ref())?;
+        let stdin = child.stdin.take();
+        let stdout = child.stdout.take().expect("stdout is piped");
+        Ok(Self {
+            pid: child.id(),
+            child,
+            stdin,
+            stdout: BufReader::new(stdout),
+            next_id: 1,
+            streaming: false,
+            line: Vec::new(),
+            transcript,
+            stderr_task,
+        })
+    }
+
+    pub fn pid(&self) -> Option<u32> {
+        self.pid
+    }
+
+    /// Send one command and wait for its response text. Error responses become
+    /// [`GtpError::Failure`]; a closed pipe becomes [`GtpError::Exited`]. An open streaming
+    /// response ends when the command is sent; its remaining lines are discarded.
+    pub async fn send(&mut self, command: &str) -> Result<String, GtpError> {
+        let (id, line) = self.write_command(command).await?;
+        while self.streaming {
+            self.read_stream_line().await?;
+        }
+
+        let Some(response) = read_response(&mut self.stdout).await? else {
+            return Err(self.exited().await);
+        };
+        self.log(Direction::Received, &response.to_string());
+        if response.id.is_some_and(|echoed| echoed != id) {
+            return Err(GtpError::Protocol(format!(
+                "expected a response to command {id}, got one for {}",
+                response.id.unwrap_or_default()
+            )));
+        }
+        if response.success {
+            Ok(response.text)
+        } else {
+            Err(GtpError::Failure {
+                command: line.trim_end().to_string(),
+                message: response.text,
+            })
+        }
+    }
+
+    /// Send a command whose response keeps streaming lines until the next command, such as
+    /// `kata-analyze`. Returns once the engine has accepted it; read the lines with
+    /// [`GtpEngine::read_stream_line`].
+    pub async fn start_stream(&mut self, command: &str) -> Result<(), GtpError> {
+        if self.streaming {
+            self.end_stream().await?;
+        }
+        let (id, line) = self.write_command(command).await?;
+        let status = loop {
+            match self.read_line().await? {
+                Some(status) if status.trim().is_empty() => continue,
+                Some(status) => break status,
+                None => return Err(self.exited().await),
+            }
+        };
+        self.log(Direction::Received, &status);
+        let rest = status.get(1..).unwrap_or_default();
+        let digits = rest.len()
+            - rest
+                .trim_start_matches(|ch: char| ch.is_ascii_digit())
+                .len();
+        if rest[..digits].parse().is_ok_and(|echoed: u32| echoed != id) {
+            return Err(GtpError::Protocol(format!(
+                "expected a response to command {id}, got {status}"
+            )));
+        }
+        match status.as_bytes().first() {
+            Some(b'=') => {
+                self.streaming = true;
+                Ok(())
+            }
+            Some(b'?') => {
+                // The rest of an error response is its message, up to the blank line.
+                self.streaming = true;
+                let mut message = rest[digits..].trim().to_string();
+                while let Some(more) = self.read_stream_line().await? {
+                    message.push('\n');
+                    message.push_str(&more);
+                }
+                Err(GtpError::Failure {
+                    command: line.trim_end().to_string(),
+                    message,
+                })
+            }
+            _ => Err(GtpError::Protocol(status)),
+        }
+    }
+
+    /// The next line of a streaming response, or `None` once it has ended. Cancelling the
+    /// returned future loses nothing; the next call continues the same line.
+    pub async fn read_stream_line(&mut self) -> Result<Option<String>, GtpError> {
+        if !self.streaming {
+            return Ok(None);
+        }
+        let Some(line) = self.read_line().await? else {
+            return Err(self.exited().await);
+        };
+        if line.trim().is_empty() {
+            self.streaming = false;
+            return Ok(None);
+        }
+        self.log(Direction::Received, &line);
+        Ok(Some(line))
+    }
+
+    /// End a streaming response. Any command does that; `protocol_version` is one every
+    /// engine answers.
+    pub async fn end_stream(&mut self) -> Result<(), GtpError> {
+        if self.streaming {
+            self.send("protocol_version").await?;
+        }
+        Ok(())
+    }
+
+    pub fn is_streaming(&self) -> bool {
+        self.streaming
+    }
+
+    async fn read_line(&mut self) -> Result<Option<String>, GtpError> {
+        if self.stdout.read_until(b'\n', &mut self.line).await? == 0 && self.line.is_empty() {
+            return Ok(None);
+        }
+        let line = String::from_utf8_lossy(&self.line)
+            .trim_end_matches(['\n', '\r'])
+            .to_string();
+        self.line.clear();
+        Ok(Some(line))
+    }
+
+    async fn write_command(&mut self, command: &str) -> Result<(u32, String), GtpError> {
+        let id = self.next_id;
+        let line = format_command(id, command)?;
+        self.next_id = self.next_id.wrapping_add(1);
+
+        let Some(stdin) = self.stdin.as_mut() else {
+            return Err(self.exited().await);
+        };
+        if let Some(transcript) = &self.transcript {
+            transcript.record(Direction::Sent, line.trim_end());
+        }
+        let written = match stdin.write_all(line.as_bytes()).await {
+            Ok(()) => stdin.flush().await,
+            Err(err) => Err(err),
+        };
+        match written {
+            Ok(()) => {}
+            Err(err) if err.kind() == std::io::ErrorKind::BrokenPipe => {
+                return Err(self.exited().await)
+            }
+            Err(err) => return Err(err.into()),
+        }
+        Ok((id, line))
+    }
+
+    /// The exit status if the process has already ended.
+    pub fn try_exit_status(&mut self) -> Option<ExitStatus> {
+        self.child.try_wait().ok().flatten()
+    }
+
+    /// Ask the engine to `quit`, closing its input, and kill it if it has not exited within
+    /// `grace`.
+    pub async fn quit(&mut self, grace: Duration) -> Result<Shutdown, GtpError> {
+        if self.try_exit_status().is_none() {
+            let _ = timeout(grace, self.send("quit")).await;
+        }
+        drop(self.stdin.take());
+        let shutdown = match timeout(grace, self.child.wait()).await {
+            Ok(status) => Shutdown::Quit(status?),
+            Err(_) => {
+                self.child.kill().await?;
+                Shutdown::Killed
+            }
+        };
+        // Let the last stderr lines reach the log before reporting the engine as stopped.
+        if let Some(task) = self.stderr_task.take() {
+            let _ = timeout(grace, task).await;
+        }
+        Ok(shutdown)
+    }
+
+    fn log(&self, direction: Direction, text: &str) {
+        if let Some(transcript) = &self.transcript {
+            transcript.record(direction, text);
+        }
+    }
+
+    async fn exited(&mut self) -> GtpError {
+        self.stdin = None;
+        // The pipe can close slightly before the process is reaped.
+        match timeout(Duration::from_millis(500), self.child.wait()).await {
+            Ok(Ok(status)) => GtpError::Exited(status.code()),
+            _ => GtpError::Exited(None),
+        }
+    }
+}
+
+/// Start an engine process with piped stdin and stdout. Its stderr goes to the transcript
+/// when there is one.
+pub(crate) fn launch(
+    command: &EngineCommand,
+    transcript: Option<&Transcript>,
+) -> Result<(Child, Option<JoinHandle<()>>), GtpError> {
+    let mut process = Command::new(&command.path);
+    process
+        .args(&command.args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(if transcript.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .kill_on_drop(true);
+    if let Some(dir) = &command.working_directory {
+        process.current_dir(dir);
+    }
+    let mut child = process.spawn().map_err(|source| GtpError::Spawn {
+        path: command.path.display().to_string(),
+        source,
+    })?;
+    let stderr_task = child
+        .stderr
+        .take()
+        .zip(transcript.cloned())
+        .map(|(stderr, transcript)| tokio::spawn(log_stderr(stderr, transcript)));
+    Ok((child, stderr_task))
+}
+
+async fn log_stderr(stderr: ChildStderr, transcript: Transcript) {
+    let mut reader = BufReader::new(stderr);
+    let mut line = Vec::new();
+    while let Ok(read) = reader.read_until(b'\n', &mut line).await {
+        if read == 0 {
+            break;
+        }
+        transcript.record(Direction::Stderr, String::from_utf8_lossy(&line).trim_end());
+        line.clear();
+    }
+}
@@ -0,0 +1,27 @@
+use std::time::Duration;
+
+#[derive(Debug, thiserror::Error)]
+pub enum GtpError {
+    #[error("failed to start {path}: {source}")]
+    Spawn {
+        path: String,
+        source: std::io::Error,
+    },
+    #[error("engine i/o error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("engine exited{}", exit_code_suffix(.0))]
+    Exited(Option<i32>),
+    #[error("engine did not respond within {0:?}")]
+    Timeout(Duration),
+    #[error("invalid command {0:?}")]
+    InvalidCommand(String),
+    #[error("malformed response: {0}")]
+    Protocol(String),
+    #[error("`{command}` failed: {message}")]
+    Failure { command: String, message: String },
+}
+
+fn exit_code_suffix(code: &Option<i32>) -> String {
+    code.map(|code| format!(" with code {code}"))
+        .unwrap_or_default()
+}
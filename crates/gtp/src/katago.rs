@@ -0,0 +1,498 @@
+use std::{collections::HashMap, path::Path, process::ExitStatus, time::Duration};
+
+use go_rules::{Color, GameState, Point, Ruleset};
+use serde::Deserialize;
+use serde_json::json;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    process::{Child, ChildStdin, ChildStdout},
+    task::JoinHandle,
+    time::timeout,
+};
+
+use crate::{
+    analysis::MoveInfo,
+    engine::{launch, EngineCommand, Shutdown},
+    error::GtpError,
+    transcript::{Direction, Transcript},
+    vertex::{format_vertex, parse_vertex},
+};
+
+/// A position for KataGo's JSON analysis engine: the game's setup and moves, and which
+/// turns to analyse. Turn `n` is the position after the first `n` moves.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KataGoQuery {
+    pub size: usize,
+    pub komi: f64,
+    pub rules: Ruleset,
+    pub initial_stones: Vec<(Color, Point)>,
+    pub initial_player: Color,
+    pub moves: Vec<(Color, Option<Point>)>,
+    pub analyze_turns: Vec<usize>,
+    pub max_visits: Option<u32>,
+    pub include_ownership: bool,
+    pub include_policy: bool,
+}
+
+impl KataGoQuery {
+    /// A query for the moves played so far in `game`, analysing only its current position.
+    /// The initial stones are the handicap and setup stones on the board before the first
+    /// move; stones set up between moves cannot be expressed and are left out.
+    pub fn from_game(game: &GameState) -> Self {
+        let config = game.config();
+        let moves: Vec<_> = game
+            .move_history()
+            .into_iter()
+            .map(|record| (record.mv.color, record.mv.point))
+            .collect();
+        let mut start = game.clone();
+        while start.undo().is_some() {}
+        let initial_stones = start
+            .board()
+            .intersections()
+            .iter()
+            .enumerate()
+            .filter_map(|(index, color)| Some(((*color)?, Point::from_index(index, config.size))))
+            .collect();
+        Self {
+            size: config.size,
+            komi: config.komi,
+            rules: config.ruleset,
+            initial_stones,
+            initial_player: start.to_move(),
+            analyze_turns: vec![moves.len()],
+            moves,
+            max_visits: None,
+            include_ownership: true,
+            include_policy: true,
+        }
+    }
+
+    /// Analyse every position of the game, from the setup to the last move.
+    pub fn all_turns(mut self) -> Self {
+        self.analyze_turns = (0..=self.moves.len()).collect();
+        self
+    }
+
+    /// The query as one JSON line. Rates are always reported from Black's point of view.
+    pub fn to_json(&self, id: &str) -> String {
+        let stone = |color: Color, point: Option<Point>| {
+            let vertex = point
+                .map(|point| format_vertex(point, self.size))
+                .unwrap_or_else(|| "pass".to_string());
+            json!([player(color), vertex])
+        };
+        let mut query = json!({
+            "id": id,
+            "boardXSize": self.size,
+            "boardYSize": self.size,
+            "komi": self.komi,
+            "rules": rules_name(self.rules),
+            "initialStones": self
+                .initial_stones
+                .iter()
+                .map(|(color, point)| stone(*color, Some(*point)))
+                .collect::<Vec<_>>(),
+            "initialPlayer": player(self.initial_player),
+            "moves": self
+                .moves
+                .iter()
+                .map(|(color, point)| stone(*color, *point))
+                .collect::<Vec<_>>(),
+            "analyzeTurns": self.analyze_turns,
+            "includeOwnership": self.include_ownership,
+            "includePolicy": self.include_policy,
+            "overrideSettings": { "reportAnalysisWinratesAs": "BLACK" },
+        });
+        if let Some(visits) = self.max_visits {
+            query["maxVisits"] = json!(visits);
+        }
+        query.to_string()
+    }
+}
+
+fn player(color: Color) -> &'static str {
+    match color {
+        Color::Black => "B",
+        Color::White => "W",
+    }
+}
+
+/// KataGo's name for a ruleset. It has no Ing rules; Chinese area scoring is the closest.
+fn rules_name(rules: Ruleset) -> &'static str {
+    match rules {
+        Ruleset::Japanese => "japanese",
+        Ruleset::Chinese | Ruleset::Ing => "chinese",
+        Ruleset::Aga => "aga",
+        Ruleset::NewZealand => "new-zealand",
+        Ruleset::TrompTaylor => "tromp-taylor",
+    }
+}
+
+/// KataGo's analysis of one turn. Rates, score lead and ownership are from Black's point of
+/// view.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PositionAnalysis {
+    pub turn: usize,
+    pub visits: u64,
+    pub winrate: f64,
+    pub score_lead: f64,
+    pub moves: Vec<MoveInfo>,
+    /// Prior of every point in board index order, then of passing; negative when illegal.
+    pub policy: Option<Vec<f64>>,
+    /// Ownership of every point in board index order, from -1 (White) to 1 (Black).
+    pub ownership: Option<Vec<f64>>,
+}
+
+/// One line from the analysis engine.
+#[derive(Clone, Debug, PartialEq)]
+pub enum KataGoReply {
+    Analysis {
+        id: String,
+        analysis: PositionAnalysis,
+    },
+    /// A rejected query; `id` is missing when KataGo could not read it at all.
+    Error {
+        id: Option<String>,
+        message: String,
+    },
+    Warning {
+        id: Option<String>,
+        message: String,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawReply {
+    id: Option<String>,
+    error: Option<String>,
+    warning: Option<String>,
+    turn_number: Option<usize>,
+    #[serde(default)]
+    move_infos: Vec<RawMoveInfo>,
+    root_info: Option<RawRootInfo>,
+    ownership: Option<Vec<f64>>,
+    policy: Option<Vec<f64>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawMoveInfo {
+    #[serde(rename = "move")]
+    vertex: String,
+    visits: u64,
+    winrate: f64,
+    prior: f64,
+    lcb: f64,
+    score_lead: Option<f64>,
+    order: u32,
+    #[serde(default)]
+    pv: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawRootInfo {
+    visits: u64,
+    winrate: f64,
+    score_lead: f64,
+}
+
+/// Parse one line of analysis engine output for a `size` board.
+pub fn parse_reply(line: &str, size: usize) -> Result<KataGoReply, GtpError> {
+    let invalid = |what: &str| GtpError::Protocol(format!("invalid analysis {what}: {line}"));
+    let raw: RawReply = serde_json::from_str(line).map_err(|_| invalid("response"))?;
+    if let Some(message) = raw.error {
+        return Ok(KataGoReply::Error {
+            id: raw.id,
+            message,
+        });
+    }
+    if let Some(message) = raw.warning {
+        return Ok(KataGoReply::Warning {
+            id: raw.id,
+            message,
+        });
+    }
+    let (Some(id), Some(turn), Some(root)) = (raw.id, raw.turn_number, raw.root_info) else {
+        return Err(invalid("response"));
+    };
+    let moves = raw
+        .move_infos
+        .into_iter()
+        .map(|info| {
+            Ok(MoveInfo {
+                point: parse_vertex(&info.vertex, size)?,
+                visits: info.visits,
+                winrate: info.winrate,
+                prior: info.prior,
+                lcb: info.lcb,
+                score_lead: info.score_lead,
+                order: info.order,
+                pv: info
+                    .pv
+                    .iter()
+                    .map(|vertex| parse_vertex(vertex, size))
+                    .collect::<Result<_, _>>()?,
+            })
+        })
+        .collect::<Result<_, GtpError>>()?;
+    if raw
+        .ownership
+        .as_ref()
+        .is_some_and(|values| values.len() != size * size)
+    {
+        return Err(invalid("ownership"));
+    }
+    if raw
+        .policy
+        .as_ref()
+        .is_some_and(|values| values.len() != size * size + 1)
+    {
+        return Err(invalid("policy"));
+    }
+    Ok(KataGoReply::Analysis {
+        id,
+        analysis: PositionAnalysis {
+            turn,
+            visits: root.visits,
+            winrate: root.winrate,
+            score_lead: root.score_lead,
+            moves,
+            policy: raw.policy,
+            ownership: raw.ownership,
+        },
+    })
+}
+
+/// A query still waiting for some of its turns.
+#[derive(Debug)]
+struct Pending {
+    size: usize,
+    remaining: usize,
+    results: Vec<PositionAnalysis>,
+    error: Option<String>,
+}
+
+/// A running KataGo analysis engine (`katago analysis -config ... -model ...`). Queries are
+/// answered out of order; replies are matched to them by id.
+#[derive(Debug)]
+pub struct KataGoAnalysis {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    stdout: BufReader<ChildStdout>,
+    next_id: u64,
+    pending: HashMap<String, Pending>,
+    transcript: Option<Transcript>,
+    stderr_task: Option<JoinHandle<()>>,
+}
+
+impl KataGoAnalysis {
+    pub fn spawn(command: &EngineCommand) -> Result<Self, GtpError> {
+        Self::start(command, None)
+    }
+
+    /// Spawn an engine that appends every query, reply and stderr line to `log_path`.
+    pub fn spawn_logged(command: &EngineCommand, log_path: &Path) -> Result<Self, GtpError> {
+        Self::start(command, Some(Transcript::create(log_path)?))
+    }
+
+    fn start(command: &EngineCommand, transcript: Option<Transcript>) -> Result<Self, GtpError> {
+        let (mut child, stderr_task) = launch(command, transcript.as_ref())?;
+        let stdin = child.stdin.take();
+        let stdout = child.stdout.take().expect("stdout is piped");
+        Ok(Self {
+            child,
+            stdin,
+            stdout: BufReader::new(stdout),
+            next_id: 1,
+            pending: HashMap::new(),
+            transcript,
+            stderr_task,
+        })
+    }
+
+    /// Send a query without waiting for it, returning the id to [`KataGoAnalysis::collect`]
+    /// its results with.
+    pub async fn submit(&mut self, query: &KataGoQuery) -> Result<String, GtpError> {
+        let id = format!("q{}", self.next_id);
+        self.next_id += 1;
+        self.write_line(&query.to_json(&id)).await?;
+        self.pending.insert(
+            id.clone(),
+            Pending {
+                size: query.size,
+                remaining: query.analyze_turns.len(),
+                results: Vec::new(),
+                error: None,
+            },
+        );
+        Ok(id)
+    }
+
+    /// Wait for every turn of a submitted query, in turn order. Replies to other queries
+    /// that arrive meanwhile are kept for their own `collect`.
+    pub async fn collect(&mut self, id: &str) -> Result<Vec<PositionAnalysis>, GtpError> {
+        loop {
+            let Some(pending) = self.pending.get(id) else {
+                return Err(GtpError::Protocol(format!("unknown analysis query {id}")));
+            };
+            if pending.remaining == 0 || pending.error.is_some() {
+                let pending = self.pending.remove(id).expect("query is pending");
+                if let Some(message) = pending.error {
+                    return Err(GtpError::Failure {
+                        command: format!("analysis query {id}"),
+                        message,
+                    });
+                }
+                let mut results = pending.results;
+                results.sort_by_key(|analysis| analysis.turn);
+                return Ok(results);
+            }
+            self.read_reply().await?;
+        }
+    }
+
+    /// Submit a query and wait for its results.
+    pub async fn analyze(
+        &mut self,
+        query: &KataGoQuery,
+    ) -> Result<Vec<PositionAnalysis>, GtpError> {
+        let id = self.submit(query).await?;
+        self.collect(&id).await
+    }
+
+    /// Ask for the engine's version, which is answered before any network is needed. The
+    /// reply must arrive within `limit`.
+    pub async fn query_version(&mut self, limit: Duration) -> Result<String, GtpError> {
+        let id = format!("q{}", self.next_id);
+        self.next_id += 1;
+        let query = json!({ "id": id, "action": "query_version" });
+        let answer = async {
+            self.write_line(&query.to_string()).await?;
+            loop {
+                let line = self.read_line().await?;
+                let value: serde_json::Value = serde_json::from_str(&line).map_err(|_| {
+                    GtpError::Protocol(format!("invalid analysis response: {line}"))
+                })?;
+                if value.get("id").and_then(serde_json::Value::as_str) != Some(id.as_str()) {
+                    self.handle_reply(&line)?;
+                    continue;
+                }
+                if let Some(message) = value.get("error").and_then(serde_json::Value::as_str) {
+                    return Err(GtpError::Failure {
+                        command: "query_version".to_string(),
+                        message: message.to_string(),
+                    });
+                }
+                return value
+                    .get("version")
+                    .and_then(serde_json::Value::as_str)
+                    .map(str::to_string)
+                    .ok_or_else(|| {
+                        GtpError::Protocol(format!("invalid version response: {line}"))
+                    });
+            }
+        };
+        timeout(limit, answer)
+            .await
+            .map_err(|_| GtpError::Timeout(limit))?
+    }
+
+    async fn write_line(&mut self, line: &str) -> Result<(), GtpError> {
+        if let Some(transcript) = &self.transcript {
+            transcript.record(Direction::Sent, line);
+        }
+        let Some(stdin) = self.stdin.as_mut() else {
+            return Err(self.exited().await);
+        };
+        let written = match stdin.write_all(format!("{line}\n").as_bytes()).await {
+            Ok(()) => stdin.flush().await,
+            Err(err) => Err(err),
+        };
+        match written {
+            Ok(()) => Ok(()),
+            Err(err) if err.kind() == std::io::ErrorKind::BrokenPipe => Err(self.exited().await),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// The next non-blank line of output.
+    async fn read_line(&mut self) -> Result<String, GtpError> {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if self.stdout.read_line(&mut line).await? == 0 {
+                return Err(self.exited().await);
+            }
+            if !line.trim().is_empty() {
+                break;
+            }
+        }
+        let line = line.trim_end().to_string();
+        if let Some(transcript) = &self.transcript {
+            transcript.record(Direction::Received, &line);
+        }
+        Ok(line)
+    }
+
+    async fn read_reply(&mut self) -> Result<(), GtpError> {
+        let line = self.read_line().await?;
+        self.handle_reply(&line)
+    }
+
+    fn handle_reply(&mut self, line: &str) -> Result<(), GtpError> {
+        // Replies are parsed with the board size of the query they answer.
+        let id = serde_json::from_str::<serde_json::Value>(line)
+            .ok()
+            .and_then(|value| value.get("id")?.as_str().map(str::to_string));
+        let Some(pending) = id.and_then(|id| self.pending.get_mut(&id)) else {
+            let reply: RawReply = serde_json::from_str(line)
+                .map_err(|_| GtpError::Protocol(format!("invalid analysis response: {line}")))?;
+            return match reply.error {
+                Some(message) if reply.id.is_none() => Err(GtpError::Protocol(message)),
+                _ => Ok(()),
+            };
+        };
+        match parse_reply(line, pending.size)? {
+            KataGoReply::Analysis { analysis, .. } => {
+                pending.remaining = pending.remaining.saturating_sub(1);
+                pending.results.push(analysis);
+            }
+            KataGoReply::Error { message, .. } => pending.error = Some(message),
+            KataGoReply::Warning { .. } => {}
+        }
+        Ok(())
+    }
+
+    /// The exit status if the process has already ended.
+    pub fn try_exit_status(&mut self) -> Option<ExitStatus> {
+        self.child.try_wait().ok().flatten()
+    }
+
+    /// Close the engine's input, which makes it finish outstanding queries and exit, and
+    /// kill it if it is still running after `grace`.
+    pub async fn quit(&mut self, grace: Duration) -> Result<Shutdown, GtpError> {
+        drop(self.stdin.take());
+        let shutdown = match timeout(grace, self.child.wait()).await {
+            Ok(status) => Shutdown::Quit(status?),
+            Err(_) => {
+                self.child.kill().await?;
+                Shutdown::Killed
+            }
+        };
+        if let Some(task) = self.stderr_task.take() {
+            let _ = timeout(grace, task).await;
+        }
+        Ok(shutdown)
+    }
+
+    async fn exited(&mut self) -> GtpError {
+        self.stdin = None;
+        match timeout(Duration::from_millis(500), self.child.wait()).await {
+            Ok(Ok(status)) => GtpError::Exited(status.code()),
+            _ => GtpError::Exited(None),
+        }
+    }
+}
@@ -0,0 +1,267 @@
+mod analysis;
+mod engine;
+mod error;
+mod katago;
+mod match_play;
+mod probe;
+mod protocol;
+mod sync;
+mod transcript;
+mod vertex;
+
+pub use analysis::{parse_analysis, AnalysisUpdate, AnalyzeDialect, MoveInfo};
+pub use engine::{EngineCommand, GtpEngine, Shutdown};
+pub use error::GtpError;
+pub use katago::{parse_reply, KataGoAnalysis, KataGoQuery, KataGoReply, PositionAnalysis};
+pub use match_play::{play_game, Clock, MatchSettings, PlayedGame, TimeSettings, WinRate};
+pub use probe::{probe, Capabilities, EngineInfo};
+pub use protocol::{format_command, read_response, Response};
+pub use sync::BoardSync;
+pub use transcript::{read_page, read_tail, Direction, LogPage, Transcript};
+pub use vertex::{
+    color_name, format_vertex, parse_engine_move, parse_vertex, EngineMove, MAX_BOARD_SIZE,
+};
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use go_rules::Point;
+
+    use super::*;
+
+    #[test]
+    fn vertices_skip_i_and_count_rows_from_the_bottom() {
+        let point = |x, y| Point::new(x, y).unwrap();
+        assert_eq!(format_vertex(point(0, 0), 19), "A19");
+        assert_eq!(format_vertex(point(8, 18), 19), "J1");
+        assert_eq!(format_vertex(point(3, 5), 9), "D4");
+        assert_eq!(parse_vertex("d4", 9).unwrap(), Some(point(3, 5)));
+        assert_eq!(parse_vertex("J1", 19).unwrap(), Some(point(8, 18)));
+        assert_eq!(parse_vertex("PASS", 19).unwrap(), None);
+        for invalid in ["I5", "K1", "A0", "A10", "", "5A"] {
+            assert!(parse_vertex(invalid, 9).is_err(), "{invalid}");
+        }
+        assert_eq!(parse_engine_move("resign", 9).unwrap(), EngineMove::Resign);
+        assert_eq!(parse_engine_move("pass", 9).unwrap(), EngineMove::Pass);
+    }
+
+    #[test]
+    fn commands_are_cleaned_before_sending() {
+        assert_eq!(format_command(7, "genmove b").unwrap(), "7 genmove b\n");
+        assert_eq!(
+            format_command(8, "\tplay\u{7} b d4  # reply").unwrap(),
+            "8 play b d4\n"
+        );
+        assert!(matches!(
+            format_command(9, "# only a comment"),
+            Err(GtpError::InvalidCommand(_))
+        ));
+        assert!(matches!(
+            format_command(10, "name\nquit"),
+            Err(GtpError::InvalidCommand(_))
+        ));
+    }
+
+    async fn next(stream: &mut &[u8]) -> Option<Response> {
+        read_response(stream).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn responses_are_framed_by_blank_lines() {
+        let mut stream: &[u8] =
+            b"\n=1 2\n\n?2 unknown command\r\n\r\n=3 play\ngenmove\nquit\n\n= \n\n=5";
+
+        assert_eq!(
+            next(&mut stream).await,
+            Some(Response {
+                id: Some(1),
+                success: true,
+                text: "2".into()
+            })
+        );
+        let failure = next(&mut stream).await.unwrap();
+        assert_eq!((failure.id, failure.success), (Some(2), false));
+        assert_eq!(failure.text, "unknown command");
+        assert_eq!(next(&mut stream).await.unwrap().text, "play\ngenmove\nquit");
+        let empty = next(&mut stream).await.unwrap();
+        assert_eq!((empty.id, empty.text.as_str()), (None, ""));
+        // A truncated final response still yields what arrived.
+        assert_eq!(next(&mut stream).await.unwrap().id, Some(5));
+        assert_eq!(next(&mut stream).await, None);
+
+        let mut garbage: &[u8] = b"hello\n\n";
+        assert!(matches!(
+            read_response(&mut garbage).await,
+            Err(GtpError::Protocol(_))
+        ));
+    }
+
+    #[test]
+    fn canadian_byo_yomi_resets_after_each_period() {
+        let secs = Duration::from_secs;
+        let mut clock = Clock::new(TimeSettings {
+            main_time: secs(10),
+            byo_yomi_time: secs(30),
+            byo_yomi_stones: 2,
+        });
+        assert!(clock.spend(secs(4)));
+        assert_eq!(clock.time_left(), (6, 0));
+        assert!(clock.spend(secs(16)));
+        assert_eq!(clock.time_left(), (20, 1));
+        assert!(clock.spend(secs(20)));
+        assert_eq!(clock.time_left(), (30, 2));
+        assert!(!clock.spend(secs(31)));
+
+        let mut sudden_death = Clock::new(TimeSettings {
+            main_time: secs(5),
+            ..TimeSettings::default()
+        });
+        assert!(sudden_death.spend(secs(5)));
+        assert!(!sudden_death.spend(Duration::from_millis(1)));
+    }
+
+    #[test]
+    fn win_rates_carry_wilson_intervals() {
+        let even = WinRate::new(5, 0, 10);
+        assert_eq!(even.rate, 0.5);
+        assert!((even.lower - 0.2366).abs() < 1e-4, "{even:?}");
+        assert!((even.upper - 0.7634).abs() < 1e-4, "{even:?}");
+
+        let sweep = WinRate::new(20, 0, 20);
+        assert_eq!((sweep.rate, sweep.upper), (1.0, 1.0));
+        assert!((sweep.lower - 0.8389).abs() < 1e-4, "{sweep:?}");
+        assert_eq!(WinRate::new(3, 2, 8).rate, 0.5);
+        assert_eq!(WinRate::new(0, 0, 0).upper, 1.0);
+    }
+
+    #[test]
+    fn parses_recorded_kata_analyze_output() {
+        let point = |x, y| Some(Point::new(x, y).unwrap());
+        let recorded = include_str!("../testdata/kata-analyze.txt");
+        let lines: Vec<AnalysisUpdate> = recorded
+            .lines()
+            .map(|line| parse_analysis(line, 9, AnalyzeDialect::KataGo).unwrap())
+            .collect();
+
+        let first = &lines[0];
+        assert_eq!(first.moves.len(), 2);
+        assert_eq!(first.ownership, None);
+        let best = &first.moves[0];
+        assert_eq!((best.point, best.visits, best.order), (point(4, 4), 38, 0));
+        assert_eq!(
+            (best.winrate, best.lcb, best.prior),
+            (0.52744, 0.49012, 0.61838)
+        );
+        assert_eq!(best.score_lead, Some(0.61));
+        assert_eq!(
+            best.pv,
+            [point(4, 4), point(4, 6), point(3, 6), point(4, 5)]
+        );
+        assert_eq!(first.moves[1].pv.last(), Some(&None));
+
+        let second = &lines[1];
+        assert_eq!(second.moves[0].pv.len(), 4);
+        assert_eq!(second.moves[0].visits, 121);
+        assert_eq!(second.moves[1].point, point(2, 6));
+        let ownership = second.ownership.as_ref().unwrap();
+        assert_eq!(ownership.len(), 81);
+        assert_eq!((ownership[0], ownership[8]), (0.9, -0.85));
+
+        assert!(parse_analysis("info move E5 visits many", 9, AnalyzeDialect::KataGo).is_err());
+        assert!(parse_analysis("ownership 0.5 0.5", 9, AnalyzeDialect::KataGo).is_err());
+        assert_eq!(
+            AnalyzeDialect::KataGo.command(go_rules::Color::White, 50, true),
+            "kata-analyze w 50 ownership true"
+        );
+    }
+
+    #[test]
+    fn parses_recorded_lz_analyze_output() {
+        let recorded = include_str!("../testdata/lz-analyze.txt");
+        let lines: Vec<AnalysisUpdate> = recorded
+            .lines()
+            .map(|line| parse_analysis(line, 19, AnalyzeDialect::Leela).unwrap())
+            .collect();
+
+        let best = &lines[0].moves[0];
+        assert_eq!(best.point, Some(Point::new(3, 3).unwrap()));
+        assert_eq!(
+            (best.winrate, best.prior, best.lcb),
+            (0.4584, 0.1811, 0.4521)
+        );
+        assert_eq!(best.score_lead, None);
+        assert_eq!(lines[0].moves[1].pv.len(), 2);
+        let pass = &lines[1].moves[1];
+        assert_eq!((pass.point, pass.pv.as_slice()), (None, &[None][..]));
+        assert_eq!(
+            AnalyzeDialect::Leela.command(go_rules::Color::Black, 100, true),
+            "lz-analyze b 100"
+        );
+    }
+
+    #[test]
+    fn builds_katago_queries_and_parses_recorded_replies() {
+        use go_rules::{Color, GameConfig, GameState, Move, Setup};
+
+        let mut game = GameState::new(GameConfig {
+            size: 9,
+            komi: 0.5,
+            handicap: 2,
+            ..Default::default()
+        });
+        let setup = Setup {
+            white: vec![Point::new(4, 4).unwrap()],
+            ..Setup::default()
+        };
+        game.apply_setup(&setup).unwrap();
+        let d5 = Point::new(3, 4).unwrap();
+        for (color, point) in [(Color::White, Some(d5)), (Color::Black, None)] {
+            game.play(Move { color, point }).unwrap();
+        }
+        let query = KataGoQuery::from_game(&game).all_turns();
+        let json: serde_json::Value = serde_json::from_str(&query.to_json("q7")).unwrap();
+        assert_eq!(json["id"], "q7");
+        assert_eq!(json["rules"], "aga");
+        assert_eq!(
+            json["initialStones"],
+            serde_json::json!([["B", "G7"], ["W", "E5"], ["B", "C3"]])
+        );
+        assert_eq!(json["initialPlayer"], "W");
+        assert_eq!(
+            json["moves"],
+            serde_json::json!([["W", "D5"], ["B", "pass"]])
+        );
+        assert_eq!(json["analyzeTurns"], serde_json::json!([0, 1, 2]));
+        assert_eq!(json.get("maxVisits"), None);
+
+        let recorded: Vec<&str> = include_str!("../testdata/katago-analysis.jsonl")
+            .lines()
+            .collect();
+        assert!(matches!(
+            parse_reply(recorded[0], 5).unwrap(),
+            KataGoReply::Warning { id: Some(id), .. } if id == "q1"
+        ));
+        let KataGoReply::Analysis { id, analysis } = parse_reply(recorded[1], 5).unwrap() else {
+            panic!("expected an analysis");
+        };
+        assert_eq!((id.as_str(), analysis.turn, analysis.visits), ("q1", 2, 65));
+        assert_eq!((analysis.winrate, analysis.score_lead), (0.4318, -1.42));
+        let best = &analysis.moves[0];
+        assert_eq!(best.point, Some(Point::new(2, 3).unwrap()));
+        assert_eq!((best.prior, best.score_lead), (0.3152, Some(-1.37)));
+        assert_eq!(best.pv.last(), Some(&None));
+        assert_eq!(analysis.moves[1].point, None);
+        assert_eq!(analysis.ownership.as_ref().map(Vec::len), Some(25));
+        let policy = analysis.policy.unwrap();
+        assert_eq!((policy.len(), policy[12]), (26, -1.0));
+        assert_eq!(
+            parse_reply(recorded[2], 5).unwrap(),
+            KataGoReply::Error {
+                id: Some("q2".into()),
+                message: "Illegal move 2: C3".into()
+            }
+        );
+        assert!(parse_reply(recorded[1], 9).is_err());
+    }
+}
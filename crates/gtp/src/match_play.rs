@@ -0,0 +1,283 @@
+use std::time::{Duration, Instant};
+
+use go_rules::{Color, GameConfig, GamePhase, GameResult, GameState, Move};
+
+use crate::{
+    engine::GtpEngine,
+    error::GtpError,
+    sync::BoardSync,
+    vertex::{color_name, format_vertex, EngineMove},
+};
+
+/// Canadian byo-yomi: `main_time`, then `byo_yomi_stones` moves in every `byo_yomi_time`
+/// period. Without byo-yomi stones the game is sudden death.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TimeSettings {
+    pub main_time: Duration,
+    pub byo_yomi_time: Duration,
+    pub byo_yomi_stones: u32,
+}
+
+/// One player's remaining time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Clock {
+    settings: TimeSettings,
+    main_time: Duration,
+    period: Duration,
+    stones: u32,
+}
+
+impl Clock {
+    pub fn new(settings: TimeSettings) -> Self {
+        Self {
+            settings,
+            main_time: settings.main_time,
+            period: settings.byo_yomi_time,
+            stones: settings.byo_yomi_stones,
+        }
+    }
+
+    /// Charge `elapsed` for one move. Returns `false` if the player ran out of time.
+    pub fn spend(&mut self, mut elapsed: Duration) -> bool {
+        if !self.main_time.is_zero() {
+            let used = elapsed.min(self.main_time);
+            self.main_time -= used;
+            elapsed -= used;
+            if elapsed.is_zero() {
+                return true;
+            }
+        }
+        if self.settings.byo_yomi_stones == 0 || elapsed > self.period {
+            return false;
+        }
+        self.period -= elapsed;
+        self.stones -= 1;
+        if self.stones == 0 {
+            self.period = self.settings.byo_yomi_time;
+            self.stones = self.settings.byo_yomi_stones;
+        }
+        true
+    }
+
+    /// The GTP `time_left` arguments: seconds left and, in byo-yomi, the stones left to
+    /// play in the current period.
+    pub fn time_left(&self) -> (u64, u32) {
+        if self.main_time.is_zero() {
+            (self.period.as_secs(), self.stones)
+        } else {
+            (self.main_time.as_secs(), 0)
+        }
+    }
+}
+
+/// How each game of a match is played.
+#[derive(Clone, Debug)]
+pub struct MatchSettings {
+    pub config: GameConfig,
+    pub time: Option<TimeSettings>,
+    /// Games still running after this many moves end without a result.
+    pub max_moves: usize,
+}
+
+/// A finished match game. `note` explains forfeits and other unusual endings.
+#[derive(Clone, Debug)]
+pub struct PlayedGame {
+    pub state: GameState,
+    pub result: GameResult,
+    pub note: Option<String>,
+}
+
+/// Play one game between two engines through the rules engine. An engine that fails to
+/// answer, crashes or plays an illegal move forfeits; one that overruns its clock loses on
+/// time. Passed-out games are scored with the estimated dead stones removed. `on_move` sees
+/// the game after every turn.
+pub async fn play_game(
+    black: &mut GtpEngine,
+    white: &mut GtpEngine,
+    settings: &MatchSettings,
+    mut on_move: impl FnMut(&GameState),
+) -> Result<PlayedGame, GtpError> {
+    let mut state = GameState::try_new(settings.config.clone())
+        .map_err(|err| GtpError::Protocol(err.to_string()))?;
+    let mut players = [
+        Player::new(black, settings.time),
+        Player::new(white, settings.time),
+    ];
+    for player in &mut players {
+        if let Some(time) = settings.time {
+            player
+                .tolerate(&format!(
+                    "time_settings {} {} {}",
+                    time.main_time.as_secs(),
+                    time.byo_yomi_time.as_secs(),
+                    time.byo_yomi_stones
+                ))
+                .await?;
+        }
+    }
+
+    // The game is still being played, so the result can always be declared.
+    let decided = |mut state: GameState, result: GameResult, note: Option<String>| {
+        let _ = state.declare_result(result.clone());
+        PlayedGame {
+            state,
+            result,
+            note,
+        }
+    };
+    let ended = |state, winner: Color, note: String| {
+        decided(state, GameResult::Forfeit { winner }, Some(note))
+    };
+    while state.phase() == GamePhase::Playing {
+        if state.move_count() >= settings.max_moves {
+            let note = format!("stopped after {} moves", settings.max_moves);
+            return Ok(decided(state, GameResult::NoResult, Some(note)));
+        }
+        let color = state.to_move();
+        let player = &mut players[color.index()];
+        let started = Instant::now();
+        let turn = player.take_turn(&state).await;
+        let in_time = player
+            .clock
+            .as_mut()
+            .is_none_or(|clock| clock.spend(started.elapsed()));
+        let turn = match turn {
+            Ok(turn) => turn,
+            Err(err) => return Ok(ended(state, color.opponent(), err.to_string())),
+        };
+        if !in_time {
+            let result = GameResult::Time {
+                winner: color.opponent(),
+            };
+            return Ok(decided(state, result, None));
+        }
+        match turn {
+            Turn::Handicap(stones) => {
+                for point in stones {
+                    if let Err(err) = state.place_handicap_stone(point) {
+                        let note = format!("invalid handicap stone: {err}");
+                        return Ok(ended(state, Color::White, note));
+                    }
+                }
+            }
+            Turn::Move(EngineMove::Resign) => {
+                state
+                    .resign(color)
+                    .map_err(|err| GtpError::Protocol(err.to_string()))?;
+            }
+            Turn::Move(reply) => {
+                let point = match reply {
+                    EngineMove::Play(point) => Some(point),
+                    _ => None,
+                };
+                if let Err(err) = state.play(Move { color, point }) {
+                    let vertex = point
+                        .map(|point| format_vertex(point, state.config().size))
+                        .unwrap_or_else(|| "pass".to_string());
+                    let note = format!("illegal move {vertex}: {err}");
+                    return Ok(ended(state, color.opponent(), note));
+                }
+            }
+        }
+        on_move(&state);
+    }
+
+    if state.phase() == GamePhase::Scoring {
+        for point in state.ownership_estimate().dead_stones {
+            let marked = state
+                .scoring_snapshot()
+                .is_some_and(|scoring| scoring.dead_stones.contains(&point));
+            if !marked {
+                state
+                    .toggle_dead(point)
+                    .map_err(|err| GtpError::Protocol(err.to_string()))?;
+            }
+        }
+        for color in [Color::Black, Color::White] {
+            state
+                .accept_score(color)
+                .map_err(|err| GtpError::Protocol(err.to_string()))?;
+        }
+    }
+    let result = state.result().unwrap_or(GameResult::NoResult);
+    Ok(PlayedGame {
+        state,
+        result,
+        note: None,
+    })
+}
+
+enum Turn {
+    Handicap(Vec<go_rules::Point>),
+    Move(EngineMove),
+}
+
+struct Player<'a> {
+    engine: &'a mut GtpEngine,
+    board: BoardSync,
+    clock: Option<Clock>,
+}
+
+impl<'a> Player<'a> {
+    fn new(engine: &'a mut GtpEngine, time: Option<TimeSettings>) -> Self {
+        Self {
+            engine,
+            board: BoardSync::new(),
+            clock: time.map(Clock::new),
+        }
+    }
+
+    async fn take_turn(&mut self, state: &GameState) -> Result<Turn, GtpError> {
+        if state.handicap_pending() > 0 {
+            let stones = self.board.place_free_handicap(self.engine, state).await?;
+            return Ok(Turn::Handicap(stones));
+        }
+        if let Some(clock) = self.clock {
+            let (seconds, stones) = clock.time_left();
+            let color = color_name(state.to_move());
+            self.tolerate(&format!("time_left {color} {seconds} {stones}"))
+                .await?;
+        }
+        let reply = self.board.genmove(self.engine, state).await?;
+        Ok(Turn::Move(reply))
+    }
+
+    /// Send an optional command, ignoring engines that do not support it.
+    async fn tolerate(&mut self, command: &str) -> Result<(), GtpError> {
+        match self.engine.send(command).await {
+            Ok(_) | Err(GtpError::Failure { .. }) => Ok(()),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+/// A win rate with its 95% Wilson score interval. Draws count as half a win.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WinRate {
+    pub rate: f64,
+    pub lower: f64,
+    pub upper: f64,
+}
+
+impl WinRate {
+    pub fn new(wins: u32, draws: u32, games: u32) -> Self {
+        if games == 0 {
+            return Self {
+                rate: 0.5,
+                lower: 0.0,
+                upper: 1.0,
+            };
+        }
+        const Z: f64 = 1.959_963_984_540_054;
+        let n = f64::from(games);
+        let rate = (f64::from(wins) + f64::from(draws) / 2.0) / n;
+        let z2 = Z * Z;
+        let centre = (rate + z2 / (2.0 * n)) / (1.0 + z2 / n);
+        let spread = Z / (1.0 + z2 / n) * (rate * (1.0 - rate) / n + z2 / (4.0 * n * n)).sqrt();
+        Self {
+            rate,
+            lower: (centre - spread).max(0.0),
+            upper: (centre + spread).min(1.0),
+        }
+    }
+}
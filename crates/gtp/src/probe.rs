@@ -0,0 +1,85 @@
+use std::time::Duration;
+
+use tokio::time::timeout;
+
+use crate::{analysis::AnalyzeDialect, engine::GtpEngine, error::GtpError};
+
+/// What an engine reports about itself, and the optional features it supports.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EngineInfo {
+    pub protocol_version: u32,
+    pub name: String,
+    pub version: String,
+    pub commands: Vec<String>,
+    pub capabilities: Capabilities,
+}
+
+/// Optional GTP features, read from `list_commands`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Capabilities {
+    /// The streamed analysis command, preferring `kata-analyze`.
+    pub analysis: Option<AnalyzeDialect>,
+    pub final_status_list: bool,
+    /// `time_settings` together with `time_left`.
+    pub time_settings: bool,
+    pub kgs_time_settings: bool,
+    /// KataGo's `rectangular_boardsize`.
+    pub rectangular_boards: bool,
+    pub free_handicap: bool,
+    pub undo: bool,
+}
+
+impl Capabilities {
+    pub fn from_commands(commands: &[String]) -> Self {
+        let has = |name: &str| commands.iter().any(|command| command == name);
+        let analysis = [AnalyzeDialect::KataGo, AnalyzeDialect::Leela]
+            .into_iter()
+            .find(|dialect| has(dialect.command_name()));
+        Self {
+            analysis,
+            final_status_list: has("final_status_list"),
+            time_settings: has("time_settings") && has("time_left"),
+            kgs_time_settings: has("kgs-time_settings"),
+            rectangular_boards: has("rectangular_boardsize"),
+            free_handicap: has("place_free_handicap") && has("set_free_handicap"),
+            undo: has("undo"),
+        }
+    }
+}
+
+/// Check that `engine` speaks GTP version 2 and collect its identity and capabilities. Every
+/// answer must arrive within `limit`, which has to allow for engines that load a network
+/// before replying.
+pub async fn probe(engine: &mut GtpEngine, limit: Duration) -> Result<EngineInfo, GtpError> {
+    let mut ask = async |command: &str| {
+        timeout(limit, engine.send(command))
+            .await
+            .map_err(|_| GtpError::Timeout(limit))?
+    };
+    let version = ask("protocol_version").await?;
+    let protocol_version = version
+        .trim()
+        .parse()
+        .map_err(|_| GtpError::Protocol(format!("unexpected protocol version {version:?}")))?;
+    if protocol_version != 2 {
+        return Err(GtpError::Protocol(format!(
+            "unsupported GTP version {protocol_version}"
+        )));
+    }
+    let name = ask("name").await?.trim().to_string();
+    let version = ask("version").await?.trim().to_string();
+    let commands: Vec<String> = ask("list_commands")
+        .await?
+        .lines()
+        .map(str::trim)
+        .filter(|command| !command.is_empty())
+        .map(str::to_string)
+        .collect();
+    Ok(EngineInfo {
+        protocol_version,
+        name,
+        version,
+        capabilities: Capabilities::from_commands(&commands),
+        commands,
+    })
+}
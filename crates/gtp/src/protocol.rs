@@ -0,0 +1,93 @@
+use tokio::io::{AsyncBufRead, AsyncBufReadExt};
+
+use crate::error::GtpError;
+
+/// A framed GTP response: `=[id] text` or `?[id] message`, terminated by an empty line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Response {
+    pub id: Option<u32>,
+    pub success: bool,
+    pub text: String,
+}
+
+/// Normalise a command for the wire as described in GTP 2 section 3.1: control characters
+/// are dropped, tabs become spaces and comments are removed. Commands that span lines or are
+/// empty once cleaned are rejected, as they would desynchronise the response stream.
+pub fn format_command(id: u32, command: &str) -> Result<String, GtpError> {
+    if command.contains(['\n', '\r']) {
+        return Err(GtpError::InvalidCommand(command.to_string()));
+    }
+    let cleaned: String = command
+        .split('#')
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .filter_map(|ch| match ch {
+            '\t' => Some(' '),
+            ch if ch.is_control() => None,
+            ch => Some(ch),
+        })
+        .collect();
+    let cleaned = cleaned.trim();
+    if cleaned.is_empty() {
+        return Err(GtpError::InvalidCommand(command.to_string()));
+    }
+    Ok(format!("{id} {cleaned}\n"))
+}
+
+/// Read one response. Blank lines before the status character are skipped; `Ok(None)` means
+/// the engine closed its output.
+pub async fn read_response<R>(reader: &mut R) -> Result<Option<Response>, GtpError>
+where
+    R: AsyncBufRead + Unpin,
+{
+    let mut line = String::new();
+    let first = loop {
+        line.clear();
+        if reader.read_line(&mut line).await? == 0 {
+            return Ok(None);
+        }
+        let trimmed = line.trim_end_matches(['\n', '\r']);
+        if !trimmed.trim().is_empty() {
+            break trimmed.to_string();
+        }
+    };
+
+    let success = match first.as_bytes()[0] {
+        b'=' => true,
+        b'?' => false,
+        _ => return Err(GtpError::Protocol(first)),
+    };
+    let rest = &first[1..];
+    let digits = rest.len()
+        - rest
+            .trim_start_matches(|ch: char| ch.is_ascii_digit())
+            .len();
+    let id = rest[..digits].parse().ok();
+    let mut text = rest[digits..].trim().to_string();
+
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).await? == 0 {
+            break;
+        }
+        let trimmed = line.trim_end_matches(['\n', '\r']);
+        if trimmed.trim().is_empty() {
+            break;
+        }
+        text.push('\n');
+        text.push_str(trimmed);
+    }
+    Ok(Some(Response { id, success, text }))
+}
+
+/// The response as the engine framed it, without the terminating blank line.
+impl std::fmt::Display for Response {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let status = if self.success { '=' } else { '?' };
+        match self.id {
+            Some(id) => write!(f, "{status}{id} {}", self.text),
+            None => write!(f, "{status} {}", self.text),
+        }
+    }
+}
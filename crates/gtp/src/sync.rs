@@ -0,0 +1,149 @@
+use go_rules::{GameState, Move, Point};
+
+use crate::{
+    engine::GtpEngine,
+    error::GtpError,
+    vertex::{color_name, format_vertex, parse_engine_move, parse_vertex, EngineMove},
+};
+
+/// Keeps an engine's board in step with a [`GameState`].
+///
+/// Only the moves the engine has not seen are sent with `play`. When the game no longer
+/// extends what the engine knows (after an undo, or when the engine switches games) the
+/// engine is reset with `boardsize`, `clear_board`, `komi` and `set_free_handicap` and the
+/// whole game is replayed.
+#[derive(Clone, Debug, Default)]
+pub struct BoardSync {
+    synced: Option<Synced>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Synced {
+    size: usize,
+    komi: f64,
+    handicap: Vec<Point>,
+    moves: Vec<Move>,
+}
+
+impl BoardSync {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forget what the engine knows, forcing a reset on the next sync. Use this after
+    /// sending commands that change the engine's board behind the sync's back.
+    pub fn invalidate(&mut self) {
+        self.synced = None;
+    }
+
+    pub async fn sync(
+        &mut self,
+        engine: &mut GtpEngine,
+        state: &GameState,
+    ) -> Result<(), GtpError> {
+        let moves: Vec<Move> = state
+            .move_history()
+            .into_iter()
+            .map(|record| record.mv)
+            .collect();
+        let handicap = state.handicap_stones();
+        let reusable = self.synced.as_ref().is_some_and(|synced| {
+            synced.size == state.config().size
+                && synced.komi == state.config().komi
+                && synced.handicap == handicap
+                && moves.starts_with(&synced.moves)
+        });
+        if !reusable {
+            self.reset(engine, state).await?;
+            if handicap.len() >= 2 {
+                let vertices: Vec<String> = handicap
+                    .iter()
+                    .map(|&point| format_vertex(point, state.config().size))
+                    .collect();
+                engine
+                    .send(&format!("set_free_handicap {}", vertices.join(" ")))
+                    .await?;
+            }
+            if let Some(synced) = self.synced.as_mut() {
+                synced.handicap = handicap.to_vec();
+            }
+        }
+
+        let synced = self.synced.as_mut().expect("reset records the position");
+        for mv in &moves[synced.moves.len()..] {
+            let vertex = match mv.point {
+                Some(point) => format_vertex(point, synced.size),
+                None => "pass".to_string(),
+            };
+            engine
+                .send(&format!("play {} {vertex}", color_name(mv.color)))
+                .await?;
+            synced.moves.push(mv.clone());
+        }
+        Ok(())
+    }
+
+    /// Ask the engine for the next move of the game. The engine has played the move on its
+    /// own board, so if the game rejects it, call [`BoardSync::invalidate`].
+    pub async fn genmove(
+        &mut self,
+        engine: &mut GtpEngine,
+        state: &GameState,
+    ) -> Result<EngineMove, GtpError> {
+        self.sync(engine, state).await?;
+        let color = state.to_move();
+        let reply = engine
+            .send(&format!("genmove {}", color_name(color)))
+            .await?;
+        let engine_move = parse_engine_move(&reply, state.config().size)?;
+        let point = match engine_move {
+            EngineMove::Play(point) => Some(point),
+            EngineMove::Pass => None,
+            EngineMove::Resign => return Ok(engine_move),
+        };
+        if let Some(synced) = self.synced.as_mut() {
+            synced.moves.push(Move { color, point });
+        }
+        Ok(engine_move)
+    }
+
+    /// Let the engine choose Black's free handicap stones with `place_free_handicap`.
+    pub async fn place_free_handicap(
+        &mut self,
+        engine: &mut GtpEngine,
+        state: &GameState,
+    ) -> Result<Vec<Point>, GtpError> {
+        self.reset(engine, state).await?;
+        let stones = state.handicap_pending() as usize + state.handicap_stones().len();
+        let reply = engine
+            .send(&format!("place_free_handicap {stones}"))
+            .await?;
+        let size = state.config().size;
+        let points = reply
+            .split_whitespace()
+            .map(|vertex| {
+                parse_vertex(vertex, size)?
+                    .ok_or_else(|| GtpError::Protocol("pass is not a handicap stone".into()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if let Some(synced) = self.synced.as_mut() {
+            synced.handicap = points.clone();
+        }
+        Ok(points)
+    }
+
+    async fn reset(&mut self, engine: &mut GtpEngine, state: &GameState) -> Result<(), GtpError> {
+        self.synced = None;
+        let config = state.config();
+        engine.send(&format!("boardsize {}", config.size)).await?;
+        engine.send("clear_board").await?;
+        engine.send(&format!("komi {}", config.komi)).await?;
+        self.synced = Some(Synced {
+            size: config.size,
+            komi: config.komi,
+            handicap: Vec::new(),
+            moves: Vec::new(),
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,98 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::Write,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+/// Which way a logged line travelled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Sent,
+    Received,
+    Stderr,
+}
+
+impl Direction {
+    fn marker(self) -> char {
+        match self {
+            Direction::Sent => '>',
+            Direction::Received => '<',
+            Direction::Stderr => '!',
+        }
+    }
+}
+
+/// An append-only log of everything exchanged with an engine, one timestamped line per
+/// protocol line. Writes are best effort: a full disk must not stop a game.
+#[derive(Clone, Debug)]
+pub struct Transcript {
+    file: Arc<Mutex<File>>,
+}
+
+impl Transcript {
+    pub fn create(path: &Path) -> std::io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            file: Arc::new(Mutex::new(file)),
+        })
+    }
+
+    pub fn record(&self, direction: Direction, text: &str) {
+        let timestamp = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        let mut file = self
+            .file
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        for line in text.lines() {
+            let _ = writeln!(file, "{timestamp} {} {line}", direction.marker());
+        }
+        let _ = file.flush();
+    }
+}
+
+/// A window of lines from a transcript.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LogPage {
+    /// Index of the first returned line.
+    pub start: usize,
+    /// Number of lines in the whole log.
+    pub total: usize,
+    pub lines: Vec<String>,
+}
+
+/// Read up to `limit` lines starting at line `start`.
+pub fn read_page(path: &Path, start: usize, limit: usize) -> std::io::Result<LogPage> {
+    let lines = read_lines(path)?;
+    let total = lines.len();
+    let start = start.min(total);
+    Ok(LogPage {
+        start,
+        total,
+        lines: lines.into_iter().skip(start).take(limit).collect(),
+    })
+}
+
+/// Read the last `limit` lines.
+pub fn read_tail(path: &Path, limit: usize) -> std::io::Result<LogPage> {
+    let mut lines = read_lines(path)?;
+    let total = lines.len();
+    let start = total.saturating_sub(limit);
+    Ok(LogPage {
+        start,
+        total,
+        lines: lines.split_off(start),
+    })
+}
+
+fn read_lines(path: &Path) -> std::io::Result<Vec<String>> {
+    // Engines are not always careful about the encoding of their diagnostics.
+    let bytes = std::fs::read(path)?;
+    Ok(String::from_utf8_lossy(&bytes)
+        .lines()
+        .map(str::to_string)
+        .collect())
+}
@@ -0,0 +1,60 @@
+use go_rules::{Color, Point};
+
+use crate::error::GtpError;
+
+/// GTP column letters; `I` is skipped.
+const COLUMNS: &[u8] = b"ABCDEFGHJKLMNOPQRSTUVWXYZ";
+
+/// The largest board GTP vertices can describe.
+pub const MAX_BOARD_SIZE: usize = COLUMNS.len();
+
+/// An engine's answer to `genmove`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EngineMove {
+    Play(Point),
+    Pass,
+    Resign,
+}
+
+pub fn color_name(color: Color) -> &'static str {
+    match color {
+        Color::Black => "b",
+        Color::White => "w",
+    }
+}
+
+/// Format a point such as `D4`. GTP counts rows from the bottom of the board.
+pub fn format_vertex(point: Point, size: usize) -> String {
+    let column = COLUMNS[point.x as usize] as char;
+    format!("{column}{}", size - point.y as usize)
+}
+
+/// Parse a vertex; `pass` yields `None`.
+pub fn parse_vertex(text: &str, size: usize) -> Result<Option<Point>, GtpError> {
+    let invalid = || GtpError::Protocol(format!("invalid vertex {text:?}"));
+    let text = text.trim();
+    if text.eq_ignore_ascii_case("pass") {
+        return Ok(None);
+    }
+    let mut chars = text.chars();
+    let letter = chars.next().ok_or_else(invalid)?.to_ascii_uppercase();
+    let x = COLUMNS
+        .iter()
+        .position(|&column| column as char == letter)
+        .ok_or_else(invalid)?;
+    let row: usize = chars.as_str().parse().map_err(|_| invalid())?;
+    if x >= size || row == 0 || row > size {
+        return Err(invalid());
+    }
+    Point::new(x, size - row).map(Some).map_err(|_| invalid())
+}
+
+pub fn parse_engine_move(text: &str, size: usize) -> Result<EngineMove, GtpError> {
+    if text.trim().eq_ignore_ascii_case("resign") {
+        return Ok(EngineMove::Resign);
+    }
+    Ok(match parse_vertex(text, size)? {
+        Some(point) => EngineMove::Play(point),
+        None => EngineMove::Pass,
+    })
+}
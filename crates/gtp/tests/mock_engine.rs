@@ -0,0 +1,419 @@
+use std::{path::PathBuf, time::Duration};
+
+use go_rules::{
+    Color, GameConfig, GameResult, GameState, HandicapPlacement, Move, Point, RuleViolation,
+};
+use gtp::{
+    parse_analysis, play_game, read_page, read_tail, AnalyzeDialect, BoardSync, EngineCommand,
+    EngineMove, GtpEngine, GtpError, KataGoAnalysis, KataGoQuery, MatchSettings, Shutdown,
+};
+
+fn mock(args: &[&str]) -> EngineCommand {
+    EngineCommand {
+        path: PathBuf::from(env!("CARGO_BIN_EXE_mock_gtp_engine")),
+        args: args.iter().map(|arg| arg.to_string()).collect(),
+        working_directory: None,
+    }
+}
+
+#[tokio::test]
+async fn exchanges_commands_and_quits() {
+    let mut engine = GtpEngine::spawn(&mock(&["--level", "3"])).unwrap();
+    assert!(engine.pid().is_some());
+
+    assert_eq!(engine.send("protocol_version").await.unwrap(), "2");
+    assert_eq!(
+        engine.send("name  # with a comment").await.unwrap(),
+        "Mock Engine"
+    );
+    assert_eq!(engine.send("args").await.unwrap(), "--level 3");
+    let commands = engine.send("list_commands").await.unwrap();
+    assert!(commands.lines().any(|line| line == "genmove"));
+    assert!(matches!(
+        engine.send("boardsize 40").await,
+        Err(GtpError::Failure { message, .. }) if message == "unacceptable size"
+    ));
+    assert_eq!(engine.send("genmove b").await.unwrap(), "A19");
+
+    match engine.quit(Duration::from_secs(5)).await.unwrap() {
+        Shutdown::Quit(status) => assert!(status.success()),
+        Shutdown::Killed => panic!("the mock engine honours quit"),
+    }
+}
+
+#[tokio::test]
+async fn runs_in_the_working_directory() {
+    let dir = std::env::temp_dir().canonicalize().unwrap();
+    let mut engine = GtpEngine::spawn(&EngineCommand {
+        working_directory: Some(dir.clone()),
+        ..mock(&[])
+    })
+    .unwrap();
+    let cwd = engine.send("cwd").await.unwrap();
+    assert_eq!(PathBuf::from(cwd).canonicalize().unwrap(), dir);
+    engine.quit(Duration::from_secs(5)).await.unwrap();
+}
+
+#[tokio::test]
+async fn reports_crashes_and_kills_stuck_engines() {
+    let mut engine = GtpEngine::spawn(&mock(&[])).unwrap();
+    assert!(matches!(
+        engine.send("crash").await,
+        Err(GtpError::Exited(Some(3)))
+    ));
+    assert!(engine.try_exit_status().is_some());
+    assert!(matches!(
+        engine.send("name").await,
+        Err(GtpError::Exited(_))
+    ));
+
+    let mut stuck = GtpEngine::spawn(&mock(&["--ignore-quit"])).unwrap();
+    assert_eq!(
+        stuck.quit(Duration::from_millis(200)).await.unwrap(),
+        Shutdown::Killed
+    );
+
+    let missing = GtpEngine::spawn(&EngineCommand {
+        path: PathBuf::from("/nonexistent/engine"),
+        ..EngineCommand::default()
+    });
+    assert!(matches!(missing, Err(GtpError::Spawn { .. })));
+}
+
+#[tokio::test]
+async fn transcripts_record_both_directions_and_stderr() {
+    let path = std::env::temp_dir().join(format!("gtp-transcript-{}.log", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let mut engine = GtpEngine::spawn_logged(&mock(&[]), &path).unwrap();
+    engine.send("list_commands").await.unwrap();
+    engine.send("genmove w").await.unwrap();
+    assert!(engine.send("frobnicate").await.is_err());
+    engine.quit(Duration::from_secs(5)).await.unwrap();
+
+    let log = read_page(&path, 0, usize::MAX).unwrap();
+    let entries: Vec<&str> = log
+        .lines
+        .iter()
+        .map(|line| line.split_once(' ').unwrap().1)
+        .collect();
+    let commands = entries
+        .iter()
+        .position(|entry| *entry == "> 1 list_commands");
+    assert_eq!(
+        entries[commands.unwrap() + 1..commands.unwrap() + 3],
+        ["< =1 protocol_version", "< name"]
+    );
+    assert!(entries.contains(&"! thinking about w"));
+    assert!(entries.contains(&"< ?3 unknown command"));
+    assert_eq!(entries.last(), Some(&"< =4 "));
+
+    let tail = read_tail(&path, 2).unwrap();
+    assert_eq!((tail.start, tail.total), (log.total - 2, log.total));
+    assert_eq!(tail.lines, log.lines[log.total - 2..]);
+    let page = read_page(&path, 1, 3).unwrap();
+    assert_eq!(page.lines, log.lines[1..4]);
+    std::fs::remove_file(&path).unwrap();
+}
+
+fn point(x: usize, y: usize) -> Point {
+    Point::new(x, y).unwrap()
+}
+
+async fn stones(engine: &mut GtpEngine) -> Vec<String> {
+    let reply = engine.send("stones").await.unwrap();
+    reply.split_whitespace().map(str::to_string).collect()
+}
+
+#[tokio::test]
+async fn board_sync_replays_the_game_and_resets_after_undo() {
+    let mut engine = GtpEngine::spawn(&mock(&[])).unwrap();
+    let mut sync = BoardSync::new();
+    let mut state = GameState::new(GameConfig {
+        size: 9,
+        komi: 0.5,
+        handicap: 2,
+        ..Default::default()
+    });
+    state
+        .play(Move {
+            color: Color::White,
+            point: Some(point(4, 4)),
+        })
+        .unwrap();
+
+    let reply = sync.genmove(&mut engine, &state).await.unwrap();
+    assert_eq!(reply, EngineMove::Play(point(0, 0)));
+    state
+        .play(Move {
+            color: Color::Black,
+            point: Some(point(0, 0)),
+        })
+        .unwrap();
+    assert_eq!(stones(&mut engine).await, ["A9", "C3", "E5", "G7"]);
+
+    // Only the new move is sent while the engine's game is a prefix of ours.
+    state
+        .play(Move {
+            color: Color::White,
+            point: None,
+        })
+        .unwrap();
+    sync.genmove(&mut engine, &state).await.unwrap();
+    assert_eq!(stones(&mut engine).await, ["A9", "B9", "C3", "E5", "G7"]);
+
+    state.undo().unwrap();
+    state.undo().unwrap();
+    state.undo().unwrap();
+    state
+        .play(Move {
+            color: Color::White,
+            point: Some(point(0, 8)),
+        })
+        .unwrap();
+    sync.sync(&mut engine, &state).await.unwrap();
+    assert_eq!(stones(&mut engine).await, ["A1", "C3", "G7"]);
+}
+
+#[tokio::test]
+async fn engines_resign_place_handicap_and_make_illegal_moves() {
+    let mut state = GameState::new(GameConfig {
+        size: 9,
+        ..Default::default()
+    });
+    let mut resigner = GtpEngine::spawn(&mock(&["--resign"])).unwrap();
+    let reply = BoardSync::new().genmove(&mut resigner, &state).await;
+    assert_eq!(reply.unwrap(), EngineMove::Resign);
+
+    let mut engine = GtpEngine::spawn(&mock(&["--illegal"])).unwrap();
+    let mut sync = BoardSync::new();
+    state
+        .play(Move {
+            color: Color::Black,
+            point: Some(point(0, 8)),
+        })
+        .unwrap();
+    let Ok(EngineMove::Play(reply)) = sync.genmove(&mut engine, &state).await else {
+        panic!("the mock always answers A1");
+    };
+    let rejected = state.play(Move {
+        color: Color::White,
+        point: Some(reply),
+    });
+    assert!(matches!(rejected, Err(RuleViolation::Occupied { .. })));
+    sync.invalidate();
+    sync.sync(&mut engine, &state).await.unwrap();
+    assert_eq!(stones(&mut engine).await, ["A1"]);
+
+    let mut state = GameState::new(GameConfig {
+        size: 9,
+        handicap: 3,
+        handicap_placement: HandicapPlacement::Free,
+        ..Default::default()
+    });
+    let mut engine = GtpEngine::spawn(&mock(&[])).unwrap();
+    let mut sync = BoardSync::new();
+    let placed = sync.place_free_handicap(&mut engine, &state).await.unwrap();
+    assert_eq!(placed, [point(0, 0), point(1, 0), point(2, 0)]);
+    for stone in placed {
+        state.place_handicap_stone(stone).unwrap();
+    }
+    state
+        .play(Move {
+            color: Color::White,
+            point: Some(point(8, 8)),
+        })
+        .unwrap();
+    sync.genmove(&mut engine, &state).await.unwrap();
+    assert_eq!(stones(&mut engine).await, ["A9", "B9", "C9", "D9", "J1"]);
+}
+
+async fn match_game(black: &[&str], white: &[&str], config: GameConfig) -> gtp::PlayedGame {
+    let mut black = GtpEngine::spawn(&mock(black)).unwrap();
+    let mut white = GtpEngine::spawn(&mock(white)).unwrap();
+    let settings = MatchSettings {
+        config,
+        time: None,
+        max_moves: 40,
+    };
+    let mut seen = 0;
+    let game = play_game(&mut black, &mut white, &settings, |state| {
+        seen = state.move_count()
+    })
+    .await
+    .unwrap();
+    assert_eq!(seen, game.state.move_count());
+    game
+}
+
+#[tokio::test]
+async fn matches_end_by_score_resignation_or_forfeit() {
+    let small = GameConfig {
+        size: 5,
+        komi: 0.5,
+        ..Default::default()
+    };
+    let passed = match_game(&["--pass"], &["--pass"], small.clone()).await;
+    assert_eq!(passed.result.to_string(), "W+0.5");
+    assert_eq!(passed.state.move_count(), 2);
+
+    let resigned = match_game(&[], &["--resign"], small.clone()).await;
+    assert_eq!(
+        resigned.result,
+        GameResult::Resignation {
+            winner: Color::Black
+        }
+    );
+
+    let illegal = match_game(&["--illegal"], &[], small.clone()).await;
+    assert_eq!(illegal.result.to_string(), "W+F");
+    assert_eq!(illegal.state.move_count(), 2);
+    assert!(illegal.note.unwrap().contains("illegal move A1"));
+
+    let endless = match_game(&[], &["--pass"], small).await;
+    assert_eq!(
+        (endless.result, endless.state.move_count()),
+        (GameResult::NoResult, 40)
+    );
+
+    let handicap = match_game(
+        &["--pass"],
+        &["--pass"],
+        GameConfig {
+            size: 9,
+            handicap: 2,
+            handicap_placement: HandicapPlacement::Free,
+            ..Default::default()
+        },
+    )
+    .await;
+    assert_eq!(handicap.state.handicap_stones().len(), 2);
+    assert_eq!(handicap.result.winner(), Some(Color::Black));
+}
+
+#[tokio::test]
+async fn analysis_streams_until_the_next_command() {
+    let mut engine = GtpEngine::spawn(&mock(&[])).unwrap();
+    engine.send("boardsize 5").await.unwrap();
+    engine.send("play b A5").await.unwrap();
+    let command = AnalyzeDialect::KataGo.command(Color::White, 1, true);
+    engine.start_stream(&command).await.unwrap();
+    assert!(engine.is_streaming());
+
+    let mut visits = Vec::new();
+    for _ in 0..3 {
+        let line = engine.read_stream_line().await.unwrap().unwrap();
+        let update = parse_analysis(&line, 5, AnalyzeDialect::KataGo).unwrap();
+        assert_eq!(update.moves[0].point, Some(point(1, 0)));
+        assert_eq!(update.ownership.map(|values| values.len()), Some(25));
+        visits.push(update.moves[0].visits);
+    }
+    assert_eq!(visits, [1, 2, 3]);
+
+    // A cancelled read keeps its place in the stream.
+    let cancelled = tokio::time::timeout(Duration::from_micros(1), engine.read_stream_line()).await;
+    drop(cancelled);
+    assert_eq!(engine.send("name").await.unwrap(), "Mock Engine");
+    assert!(!engine.is_streaming());
+    assert_eq!(engine.read_stream_line().await.unwrap(), None);
+
+    engine.start_stream("kata-analyze b 1").await.unwrap();
+    engine.end_stream().await.unwrap();
+    assert!(matches!(
+        engine.start_stream("lz-analyze b 1").await,
+        Err(GtpError::Failure { message, .. }) if message == "unknown command"
+    ));
+    assert_eq!(engine.send("protocol_version").await.unwrap(), "2");
+}
+
+#[tokio::test]
+async fn katago_json_replies_are_matched_to_their_queries() {
+    let mut engine = KataGoAnalysis::spawn(&mock(&["--katago-json"])).unwrap();
+    let mut game = GameState::new(GameConfig {
+        size: 9,
+        ..Default::default()
+    });
+    for point in [point(2, 2), point(6, 6)] {
+        let color = game.to_move();
+        game.play(Move {
+            color,
+            point: Some(point),
+        })
+        .unwrap();
+    }
+
+    let limit = Duration::from_secs(5);
+    assert_eq!(engine.query_version(limit).await.unwrap(), "1.0-mock");
+    let mut impostor = KataGoAnalysis::spawn(&mock(&[])).unwrap();
+    assert!(impostor.query_version(limit).await.is_err());
+    let _ = impostor.quit(limit).await;
+
+    let review = KataGoQuery::from_game(&game).all_turns();
+    let mut current = KataGoQuery::from_game(&game);
+    current.include_ownership = false;
+    let review_id = engine.submit(&review).await.unwrap();
+    let current_id = engine.submit(&current).await.unwrap();
+    assert_ne!(review_id, current_id);
+
+    // Collecting the later query first leaves the earlier one's replies waiting for it.
+    let latest = engine.collect(&current_id).await.unwrap();
+    assert_eq!(latest.len(), 1);
+    assert_eq!((latest[0].turn, latest[0].score_lead), (2, 2.0));
+    assert_eq!(latest[0].ownership, None);
+    assert_eq!(latest[0].policy.as_ref().map(Vec::len), Some(82));
+    let turns = engine.collect(&review_id).await.unwrap();
+    assert_eq!(
+        turns
+            .iter()
+            .map(|analysis| analysis.turn)
+            .collect::<Vec<_>>(),
+        [0, 1, 2]
+    );
+    assert_eq!(turns[1].ownership.as_ref().map(Vec::len), Some(81));
+    assert_eq!(turns[0].moves[0].point, None);
+
+    let mut invalid = KataGoQuery::from_game(&game);
+    invalid.analyze_turns = vec![5];
+    assert!(matches!(
+        engine.analyze(&invalid).await,
+        Err(GtpError::Failure { message, .. }) if message == "Invalid value for analyzeTurns"
+    ));
+    assert!(matches!(
+        engine.quit(Duration::from_secs(5)).await.unwrap(),
+        Shutdown::Quit(status) if status.success()
+    ));
+}
+
+#[tokio::test]
+async fn probing_reports_capabilities_and_rejects_other_programs() {
+    let limit = Duration::from_secs(5);
+    let mut engine = GtpEngine::spawn(&mock(&[])).unwrap();
+    let info = gtp::probe(&mut engine, limit).await.unwrap();
+    assert_eq!(
+        (
+            info.protocol_version,
+            info.name.as_str(),
+            info.version.as_str()
+        ),
+        (2, "Mock Engine", "0.1")
+    );
+    assert!(info.commands.iter().any(|command| command == "genmove"));
+    let capabilities = info.capabilities;
+    assert_eq!(capabilities.analysis, Some(AnalyzeDialect::KataGo));
+    assert!(capabilities.free_handicap);
+    assert!(!capabilities.final_status_list && !capabilities.time_settings);
+    assert!(!capabilities.rectangular_boards && !capabilities.undo);
+
+    let mut impostor = GtpEngine::spawn(&mock(&["--not-gtp"])).unwrap();
+    assert!(matches!(
+        gtp::probe(&mut impostor, limit).await,
+        Err(GtpError::Protocol(_) | GtpError::Exited(_))
+    ));
+    let missing = EngineCommand {
+        path: PathBuf::from("/nonexistent/engine"),
+        ..Default::default()
+    };
+    assert!(matches!(
+        GtpEngine::spawn(&missing),
+        Err(GtpError::Spawn { .. })
+    ));
+}
@@ -0,0 +1,348 @@
+use go_rules::{
+    Color, GameConfig, GameState, GameTree, HandicapPlacement, Markup, MarkupKind, Move, Node,
+    NodeId, Ruleset, Setup,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::SgfError,
+    parse::{SgfNode, SgfTree},
+    value::{
+        escape_compose_part, escape_text, format_point, parse_move, parse_point, parse_point_list,
+        split_compose, unescape_text,
+    },
+};
+
+/// Root properties describing the game rather than the position.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameInfo {
+    pub game_name: Option<String>,
+    pub event: Option<String>,
+    pub round: Option<String>,
+    pub date: Option<String>,
+    pub place: Option<String>,
+    pub result: Option<String>,
+    pub black_player: Option<String>,
+    pub white_player: Option<String>,
+    pub black_rank: Option<String>,
+    pub white_rank: Option<String>,
+}
+
+impl GameInfo {
+    fn fields(&self) -> [(&'static str, &Option<String>); 10] {
+        [
+            ("GN", &self.game_name),
+            ("EV", &self.event),
+            ("RO", &self.round),
+            ("DT", &self.date),
+            ("PC", &self.place),
+            ("RE", &self.result),
+            ("PB", &self.black_player),
+            ("PW", &self.white_player),
+            ("BR", &self.black_rank),
+            ("WR", &self.white_rank),
+        ]
+    }
+
+    fn fields_mut(&mut self) -> [(&'static str, &mut Option<String>); 10] {
+        [
+            ("GN", &mut self.game_name),
+            ("EV", &mut self.event),
+            ("RO", &mut self.round),
+            ("DT", &mut self.date),
+            ("PC", &mut self.place),
+            ("RE", &mut self.result),
+            ("PB", &mut self.black_player),
+            ("PW", &mut self.white_player),
+            ("BR", &mut self.black_rank),
+            ("WR", &mut self.white_rank),
+        ]
+    }
+}
+
+/// A game record converted into `go_rules` types.
+#[derive(Clone, Debug)]
+pub struct SgfGame {
+    pub info: GameInfo,
+    pub tree: GameTree,
+}
+
+const MARKUP: [(&str, MarkupKind); 4] = [
+    ("CR", MarkupKind::Circle),
+    ("SQ", MarkupKind::Square),
+    ("TR", MarkupKind::Triangle),
+    ("MA", MarkupKind::Cross),
+];
+
+/// Largest board FF[4] can address.
+pub(crate) const MAX_SIZE: usize = 52;
+
+/// Build a [`GameTree`] from a parsed game, replaying every move so illegal records are
+/// rejected. The tree is left at its root.
+pub fn to_game(sgf: &SgfTree) -> Result<SgfGame, SgfError> {
+    let root = sgf.nodes.first().ok_or(SgfError::Empty)?;
+    if let Some(game) = root.value("GM") {
+        if game.trim() != "1" {
+            return Err(SgfError::UnsupportedGame(game.to_string()));
+        }
+    }
+
+    let defaults = GameConfig::default();
+    let size = match root.value("SZ") {
+        None => 19,
+        Some(raw) => match split_compose(raw) {
+            Some((columns, rows)) if columns.trim() == rows.trim() => number("SZ", columns)?,
+            Some(_) => return Err(invalid("SZ", raw)),
+            None => number("SZ", raw)?,
+        },
+    };
+    if !(1..=MAX_SIZE).contains(&size) {
+        return Err(SgfError::InvalidSize(size));
+    }
+    let handicap: u8 = root
+        .value("HA")
+        .map(|raw| number("HA", raw))
+        .transpose()?
+        .unwrap_or(0);
+    let config = GameConfig {
+        size,
+        komi: root
+            .value("KM")
+            .map(|raw| number("KM", raw))
+            .transpose()?
+            .unwrap_or(defaults.komi),
+        handicap,
+        // Recorded handicap stones are read from AB rather than assumed to be on star points.
+        handicap_placement: if handicap >= 2 && root.get("AB").is_some() {
+            HandicapPlacement::Free
+        } else {
+            HandicapPlacement::Fixed
+        },
+        ruleset: root
+            .value("RU")
+            .and_then(|raw| unescape_text(raw).parse::<Ruleset>().ok())
+            .unwrap_or(defaults.ruleset),
+        ..defaults
+    };
+
+    let mut info = GameInfo::default();
+    for (ident, field) in info.fields_mut() {
+        *field = root.value(ident).map(unescape_text);
+    }
+
+    let mut tree = GameTree::new(config)?;
+    let (setup, mv) = read_node(root, size)?;
+    let root_id = tree.root();
+    tree.extend_setup(root_id, setup)?;
+    annotate(&mut tree, root_id, root, size)?;
+    if let Some(mv) = mv {
+        tree.add_node(Setup::default(), Some(mv))?;
+    }
+    add_branch(&mut tree, &sgf.nodes[1..], &sgf.variations, size)?;
+    tree.goto(root_id)?;
+    Ok(SgfGame { info, tree })
+}
+
+fn add_branch(
+    tree: &mut GameTree,
+    nodes: &[SgfNode],
+    variations: &[SgfTree],
+    size: usize,
+) -> Result<(), SgfError> {
+    for node in nodes {
+        let (setup, mv) = read_node(node, size)?;
+        let id = tree.add_node(setup, mv)?;
+        annotate(tree, id, node, size)?;
+    }
+    // Come back to the fork from its saved position rather than replaying it from the root.
+    let fork = tree.bookmark();
+    for variation in variations {
+        tree.restore(fork.clone())?;
+        add_branch(tree, &variation.nodes, &variation.variations, size)?;
+    }
+    Ok(())
+}
+
+fn read_node(node: &SgfNode, size: usize) -> Result<(Setup, Option<Move>), SgfError> {
+    let setup = Setup {
+        black: parse_point_list(node.values("AB"), size)?,
+        white: parse_point_list(node.values("AW"), size)?,
+        empty: parse_point_list(node.values("AE"), size)?,
+        to_move: node.value("PL").map(|raw| color("PL", raw)).transpose()?,
+    };
+    let mv = [("B", Color::Black), ("W", Color::White)]
+        .into_iter()
+        .find_map(|(ident, color)| Some((node.value(ident)?, color)))
+        .map(|(raw, color)| parse_move(raw.trim(), size).map(|point| Move { color, point }))
+        .transpose()?;
+    Ok((setup, mv))
+}
+
+fn annotate(tree: &mut GameTree, id: NodeId, node: &SgfNode, size: usize) -> Result<(), SgfError> {
+    if let Some(comment) = node.value("C") {
+        tree.set_comment(id, Some(unescape_text(comment)))?;
+    }
+    for (ident, kind) in MARKUP {
+        for point in parse_point_list(node.values(ident), size)? {
+            tree.add_markup(
+                id,
+                Markup {
+                    point,
+                    kind: kind.clone(),
+                },
+            )?;
+        }
+    }
+    for raw in node.values("LB") {
+        let (point, text) = split_compose(raw).ok_or_else(|| invalid("LB", raw))?;
+        let markup = Markup {
+            point: parse_point(point, size)?,
+            kind: MarkupKind::Label(unescape_text(text)),
+        };
+        tree.add_markup(id, markup)?;
+    }
+    Ok(())
+}
+
+/// Write a [`GameTree`] with all of its variations.
+pub fn from_game(tree: &GameTree, info: &GameInfo) -> SgfTree {
+    let config = tree.config();
+    let mut root = SgfNode::default();
+    for (ident, value) in [
+        ("FF", "4".to_string()),
+        ("GM", "1".to_string()),
+        ("CA", "UTF-8".to_string()),
+        ("AP", "offline-go".to_string()),
+        ("SZ", config.size.to_string()),
+        ("KM", config.komi.to_string()),
+    ] {
+        root.push(ident, [value]);
+    }
+    if config.handicap >= 2 {
+        root.push("HA", [config.handicap.to_string()]);
+    }
+    root.push("RU", [escape_text(ruleset_name(config.ruleset))]);
+    for (ident, value) in info.fields() {
+        root.push(ident, value.as_deref().map(escape_text));
+    }
+    // Fixed handicap stones are implied by the config; write them out for other programs.
+    let fixed = GameState::try_new(config.clone())
+        .map(|state| state.handicap_stones().to_vec())
+        .unwrap_or_default();
+    root.push("AB", fixed.into_iter().map(format_point));
+
+    let mut sgf = branch(tree, tree.root());
+    let written = std::mem::take(&mut sgf.nodes[0].properties);
+    for property in written {
+        root.push(&property.ident, property.values);
+    }
+    sgf.nodes[0] = root;
+    sgf
+}
+
+/// Write a played game as a single line, with its handicap stones in the root.
+pub fn from_state(state: &GameState, info: &GameInfo) -> Result<SgfTree, SgfError> {
+    let mut tree = GameTree::new(state.config().clone())?;
+    if state.config().handicap_placement == HandicapPlacement::Free {
+        let handicap = Setup {
+            black: state.handicap_stones().to_vec(),
+            ..Setup::default()
+        };
+        let root = tree.root();
+        tree.extend_setup(root, handicap)?;
+    }
+    for record in state.move_history() {
+        tree.add_node(Setup::default(), Some(record.mv))?;
+    }
+    Ok(from_game(&tree, info))
+}
+
+fn branch(tree: &GameTree, start: NodeId) -> SgfTree {
+    let mut sgf = SgfTree::default();
+    let mut id = start;
+    loop {
+        if let Some(node) = tree.node(id) {
+            sgf.nodes.push(write_node(node));
+        }
+        match tree.children(id) {
+            [only] => id = *only,
+            children => {
+                sgf.variations = children.iter().map(|&child| branch(tree, child)).collect();
+                return sgf;
+            }
+        }
+    }
+}
+
+fn write_node(node: &Node) -> SgfNode {
+    let mut sgf = SgfNode::default();
+    if let Some(mv) = &node.mv {
+        let ident = match mv.color {
+            Color::Black => "B",
+            Color::White => "W",
+        };
+        sgf.push(ident, [mv.point.map(format_point).unwrap_or_default()]);
+    }
+    let setup = &node.setup;
+    sgf.push("AB", setup.black.iter().copied().map(format_point));
+    sgf.push("AW", setup.white.iter().copied().map(format_point));
+    sgf.push("AE", setup.empty.iter().copied().map(format_point));
+    if let Some(color) = setup.to_move {
+        let value = match color {
+            Color::Black => "B",
+            Color::White => "W",
+        };
+        sgf.push("PL", [value.to_string()]);
+    }
+    if let Some(comment) = &node.comment {
+        sgf.push("C", [escape_text(comment)]);
+    }
+    for markup in &node.markup {
+        let point = format_point(markup.point);
+        match &markup.kind {
+            MarkupKind::Label(text) => {
+                sgf.push("LB", [format!("{point}:{}", escape_compose_part(text))])
+            }
+            kind => {
+                let (ident, _) = MARKUP
+                    .iter()
+                    .find(|(_, candidate)| candidate == kind)
+                    .expect("every shape has a property");
+                sgf.push(ident, [point]);
+            }
+        }
+    }
+    sgf
+}
+
+/// `RU` values understood by other SGF programs.
+fn ruleset_name(ruleset: Ruleset) -> &'static str {
+    match ruleset {
+        Ruleset::Japanese => "Japanese",
+        Ruleset::Chinese => "Chinese",
+        Ruleset::Aga => "AGA",
+        Ruleset::NewZealand => "NZ",
+        Ruleset::Ing => "GOE",
+        Ruleset::TrompTaylor => "Tromp-Taylor",
+    }
+}
+
+fn color(property: &str, raw: &str) -> Result<Color, SgfError> {
+    match raw.trim() {
+        "B" | "b" => Ok(Color::Black),
+        "W" | "w" => Ok(Color::White),
+        _ => Err(invalid(property, raw)),
+    }
+}
+
+fn number<T: std::str::FromStr>(property: &str, raw: &str) -> Result<T, SgfError> {
+    raw.trim().parse().map_err(|_| invalid(property, raw))
+}
+
+fn invalid(property: &str, raw: &str) -> SgfError {
+    SgfError::InvalidValue {
+        property: property.to_string(),
+        value: raw.to_string(),
+    }
+}
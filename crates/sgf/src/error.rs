@@ -0,0 +1,27 @@
+use go_rules::RuleViolation;
+
+#[derive(Debug, thiserror::Error)]
+pub enum SgfError {
+    #[error("unexpected end of input")]
+    UnexpectedEnd,
+    #[error("expected {expected} at offset {offset}, found {found:?}")]
+    Unexpected {
+        expected: &'static str,
+        found: char,
+        offset: usize,
+    },
+    #[error("variations are nested more than {0} deep")]
+    TooDeep(usize),
+    #[error("no game trees found")]
+    Empty,
+    #[error("only Go game records are supported (GM[{0}])")]
+    UnsupportedGame(String),
+    #[error("invalid {property} value {value:?}")]
+    InvalidValue { property: String, value: String },
+    #[error("board size {0} is outside 1 to {max}", max = crate::convert::MAX_SIZE)]
+    InvalidSize(usize),
+    #[error("point {value:?} is off a {size}x{size} board")]
+    InvalidPoint { value: String, size: usize },
+    #[error("illegal game record: {0}")]
+    Rule(#[from] RuleViolation),
+}
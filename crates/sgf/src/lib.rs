@@ -0,0 +1,259 @@
+mod convert;
+mod error;
+mod parse;
+mod value;
+mod write;
+
+pub use convert::{from_game, from_state, to_game, GameInfo, SgfGame};
+pub use error::SgfError;
+pub use parse::{parse, Property, SgfNode, SgfTree};
+pub use value::{
+    escape_compose_part, escape_text, format_point, parse_move, parse_point, parse_point_list,
+    split_compose, unescape_text,
+};
+pub use write::write;
+
+/// Parse an SGF collection and convert every game in it.
+pub fn read_games(input: &str) -> Result<Vec<SgfGame>, SgfError> {
+    let trees = parse(input)?;
+    if trees.is_empty() {
+        return Err(SgfError::Empty);
+    }
+    trees.iter().map(to_game).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use go_rules::{
+        Color, GameConfig, GameState, GameTree, HandicapPlacement, MarkupKind, Move, Point,
+        Ruleset, Setup,
+    };
+
+    use super::*;
+
+    fn point(x: usize, y: usize) -> Point {
+        Point::new(x, y).unwrap()
+    }
+
+    #[test]
+    fn parses_escapes_and_soft_line_breaks() {
+        let input = "(;GM[1]FF[4]C[a \\] bracket, a \\\\ backslash\\\nand a soft break]\n\
+                     PB[Black\\:Player];B[pd])";
+        let trees = parse(input).unwrap();
+        let root = &trees[0].nodes[0];
+        assert_eq!(
+            unescape_text(root.value("C").unwrap()),
+            "a ] bracket, a \\ backslashand a soft break"
+        );
+        assert_eq!(unescape_text(root.value("PB").unwrap()), "Black:Player");
+        assert_eq!(trees[0].nodes[1].value("B"), Some("pd"));
+
+        let (point, text) = split_compose("dd:a\\:b").unwrap();
+        assert_eq!((point, unescape_text(text).as_str()), ("dd", "a:b"));
+    }
+
+    #[test]
+    fn parses_nested_variations_and_long_property_names() {
+        let input =
+            "junk before (;SZ[9]AddBlack[cc];B[ee](;W[ce];B[dc](;W[ec])(;W[gc]))(;W[gg]))\n";
+        let trees = parse(input).unwrap();
+        assert_eq!(trees.len(), 1);
+        let tree = &trees[0];
+        assert_eq!(tree.nodes.len(), 2);
+        assert_eq!(tree.nodes[0].values("AB"), ["cc"]);
+        assert_eq!(tree.variations.len(), 2);
+        assert_eq!(tree.variations[0].nodes.len(), 2);
+        assert_eq!(tree.variations[0].variations.len(), 2);
+        assert_eq!(tree.variations[1].nodes[0].value("W"), Some("gg"));
+
+        assert!(matches!(parse("(;B[aa]"), Err(SgfError::UnexpectedEnd)));
+        assert!(matches!(
+            parse("(;B[aa];W)"),
+            Err(SgfError::Unexpected { offset: 9, .. })
+        ));
+    }
+
+    #[test]
+    fn expands_compressed_point_lists() {
+        let values = vec!["aa:bc".to_string(), "dd".to_string()];
+        let points = parse_point_list(&values, 19).unwrap();
+        assert_eq!(
+            points,
+            vec![
+                point(0, 0),
+                point(1, 0),
+                point(0, 1),
+                point(1, 1),
+                point(0, 2),
+                point(1, 2),
+                point(3, 3),
+            ]
+        );
+        assert_eq!(parse_move("tt", 19).unwrap(), None);
+        assert_eq!(parse_move("", 19).unwrap(), None);
+        assert_eq!(parse_move("tt", 21).unwrap(), Some(point(19, 19)));
+        assert!(matches!(
+            parse_point("jj", 9),
+            Err(SgfError::InvalidPoint { .. })
+        ));
+    }
+
+    #[test]
+    fn converts_a_record_into_a_game_tree() {
+        let input = "(;GM[1]FF[4]SZ[9]KM[5.5]RU[Japanese]HA[2]AB[cc][gg]PB[Shusaku]PW[Gennan]\
+                     RE[B+2]C[Ear-reddening game]\
+                     ;W[ee]LB[ee:A]TR[cg]\
+                     (;B[ge];W[]C[Tenuki])\
+                     (;B[eg]AW[aa]))";
+        let game = read_games(input).unwrap().remove(0);
+        assert_eq!(game.info.black_player.as_deref(), Some("Shusaku"));
+        assert_eq!(game.info.result.as_deref(), Some("B+2"));
+
+        let mut tree = game.tree;
+        let config = tree.config().clone();
+        assert_eq!(
+            (config.size, config.komi, config.handicap, config.ruleset),
+            (9, 5.5, 2, Ruleset::Japanese)
+        );
+        assert_eq!(config.handicap_placement, HandicapPlacement::Free);
+        assert_eq!(tree.state().handicap_stones(), &[point(2, 2), point(6, 6)]);
+        assert_eq!(tree.state().to_move(), Color::White);
+        let root = tree.node(tree.root()).unwrap();
+        assert_eq!(root.comment.as_deref(), Some("Ear-reddening game"));
+
+        let main = tree.main_line();
+        assert_eq!(main.len(), 4);
+        let reply = tree.node(main[1]).unwrap();
+        assert_eq!(reply.markup.len(), 2);
+        assert_eq!(reply.markup[0].kind, MarkupKind::Triangle);
+        assert_eq!(reply.markup[1].kind, MarkupKind::Label("A".into()));
+        assert_eq!(tree.children(main[1]).len(), 2);
+
+        tree.goto(main[3]).unwrap();
+        assert_eq!(tree.state().consecutive_passes(), 1);
+        let side = tree.children(main[1])[1];
+        tree.goto(side).unwrap();
+        assert_eq!(tree.state().board().get(point(0, 0)), Some(Color::White));
+        assert_eq!(tree.state().board().get(point(4, 6)), Some(Color::Black));
+    }
+
+    #[test]
+    fn rejects_illegal_records() {
+        assert!(matches!(
+            read_games("(;GM[3];B[aa])"),
+            Err(SgfError::UnsupportedGame(_))
+        ));
+        assert!(matches!(
+            read_games("(;SZ[9];B[ee];W[ee])"),
+            Err(SgfError::Rule(_))
+        ));
+        assert!(matches!(
+            read_games("(;SZ[abc])"),
+            Err(SgfError::InvalidValue { .. })
+        ));
+        assert!(matches!(
+            read_games("(;SZ[100000])"),
+            Err(SgfError::InvalidSize(100000))
+        ));
+        assert!(matches!(
+            read_games("(;SZ[0])"),
+            Err(SgfError::InvalidSize(0))
+        ));
+
+        let nested = format!("{}{}", "(;".repeat(5000), ")".repeat(5000));
+        assert!(matches!(parse(&nested), Err(SgfError::TooDeep(_))));
+    }
+
+    #[test]
+    fn written_records_read_back_identically() {
+        let mut tree = GameTree::new(GameConfig {
+            size: 13,
+            komi: 0.5,
+            handicap: 3,
+            ruleset: Ruleset::Chinese,
+            ..Default::default()
+        })
+        .unwrap();
+        let first = tree
+            .play(Move {
+                color: Color::White,
+                point: Some(point(6, 2)),
+            })
+            .unwrap();
+        tree.set_comment(
+            first,
+            Some("Approach ] with [brackets] \\ and: colons".into()),
+        )
+        .unwrap();
+        tree.add_markup(
+            first,
+            go_rules::Markup {
+                point: point(6, 2),
+                kind: MarkupKind::Label("1:a]".into()),
+            },
+        )
+        .unwrap();
+        tree.play(Move {
+            color: Color::Black,
+            point: None,
+        })
+        .unwrap();
+        tree.goto(first).unwrap();
+        tree.add_node(
+            Setup {
+                white: vec![point(0, 0)],
+                to_move: Some(Color::White),
+                ..Setup::default()
+            },
+            None,
+        )
+        .unwrap();
+
+        let info = GameInfo {
+            black_player: Some("Honinbo [Dosaku]".into()),
+            ..GameInfo::default()
+        };
+        let text = write(&[from_game(&tree, &info)]);
+        let game = read_games(&text).unwrap().remove(0);
+        assert_eq!(game.info, info);
+        assert_eq!(game.tree.state().handicap_stones().len(), 3);
+        assert_eq!(write(&[from_game(&game.tree, &game.info)]), text);
+
+        let reread = game.tree.node(game.tree.main_line()[1]).unwrap();
+        assert_eq!(
+            reread.comment.as_deref(),
+            Some("Approach ] with [brackets] \\ and: colons")
+        );
+        assert_eq!(reread.markup[0].kind, MarkupKind::Label("1:a]".into()));
+        assert_eq!(game.tree.children(game.tree.main_line()[1]).len(), 2);
+    }
+
+    #[test]
+    fn played_games_export_as_a_single_line() {
+        let mut state = GameState::new(GameConfig {
+            size: 9,
+            handicap: 2,
+            handicap_placement: HandicapPlacement::Free,
+            ..Default::default()
+        });
+        state.place_handicap_stone(point(4, 4)).unwrap();
+        state.place_handicap_stone(point(2, 6)).unwrap();
+        state
+            .play(Move {
+                color: Color::White,
+                point: Some(point(6, 2)),
+            })
+            .unwrap();
+
+        let sgf = from_state(&state, &GameInfo::default()).unwrap();
+        assert_eq!(sgf.nodes[0].values("AB"), ["ee", "cg"]);
+        assert_eq!(sgf.nodes[1].value("W"), Some("gc"));
+        let game = to_game(&sgf).unwrap();
+        let mut tree = game.tree;
+        tree.goto(tree.main_line()[1]).unwrap();
+        assert_eq!(
+            tree.state().board_snapshot().intersections,
+            state.board_snapshot().intersections
+        );
+    }
+}
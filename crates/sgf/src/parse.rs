@@ -0,0 +1,217 @@
+use crate::error::SgfError;
+
+/// A property with its values exactly as written, escapes included. Decode them with the
+/// helpers in [`crate::value`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Property {
+    pub ident: String,
+    pub values: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SgfNode {
+    pub properties: Vec<Property>,
+}
+
+impl SgfNode {
+    pub fn get(&self, ident: &str) -> Option<&Property> {
+        self.properties
+            .iter()
+            .find(|property| property.ident == ident)
+    }
+
+    /// First raw value of a property.
+    pub fn value(&self, ident: &str) -> Option<&str> {
+        self.get(ident)?.values.first().map(String::as_str)
+    }
+
+    /// All raw values of a property, or an empty slice.
+    pub fn values(&self, ident: &str) -> &[String] {
+        self.get(ident).map_or(&[], |property| &property.values)
+    }
+
+    /// Append values to a property, creating it if needed. Values must already be escaped.
+    pub fn push(&mut self, ident: &str, values: impl IntoIterator<Item = String>) {
+        let values: Vec<String> = values.into_iter().collect();
+        if values.is_empty() {
+            return;
+        }
+        match self
+            .properties
+            .iter_mut()
+            .find(|property| property.ident == ident)
+        {
+            Some(property) => property.values.extend(values),
+            None => self.properties.push(Property {
+                ident: ident.to_string(),
+                values,
+            }),
+        }
+    }
+}
+
+/// A game tree: a sequence of nodes followed by its variations. The first variation
+/// continues the main line.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SgfTree {
+    pub nodes: Vec<SgfNode>,
+    pub variations: Vec<SgfTree>,
+}
+
+/// Deepest nesting of variations accepted, so a hostile file cannot overflow the stack.
+const MAX_DEPTH: usize = 1000;
+
+/// Parse an SGF collection. Text before the first game tree and after the last one is
+/// ignored, as many files carry headers or trailing newlines.
+pub fn parse(input: &str) -> Result<Vec<SgfTree>, SgfError> {
+    let mut parser = Parser {
+        input,
+        pos: 0,
+        depth: 0,
+    };
+    let mut trees = Vec::new();
+    match input.find('(') {
+        Some(start) => parser.pos = start,
+        None => return Err(SgfError::Empty),
+    }
+    loop {
+        parser.skip_whitespace();
+        if parser.peek() != Some(b'(') {
+            break;
+        }
+        trees.push(parser.game_tree()?);
+    }
+    Ok(trees)
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.input.as_bytes().get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|byte| byte.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, byte: u8, expected: &'static str) -> Result<(), SgfError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(found) if found == byte => {
+                self.pos += 1;
+                Ok(())
+            }
+            Some(_) => Err(self.unexpected(expected)),
+            None => Err(SgfError::UnexpectedEnd),
+        }
+    }
+
+    fn unexpected(&self, expected: &'static str) -> SgfError {
+        let found = self.input[self.pos..].chars().next().unwrap_or('\0');
+        SgfError::Unexpected {
+            expected,
+            found,
+            offset: self.pos,
+        }
+    }
+
+    fn game_tree(&mut self) -> Result<SgfTree, SgfError> {
+        if self.depth >= MAX_DEPTH {
+            return Err(SgfError::TooDeep(MAX_DEPTH));
+        }
+        self.expect(b'(', "'('")?;
+        let mut tree = SgfTree::default();
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some(b';') {
+                break;
+            }
+            tree.nodes.push(self.node()?);
+        }
+        if tree.nodes.is_empty() {
+            return match self.peek() {
+                Some(_) => Err(self.unexpected("';'")),
+                None => Err(SgfError::UnexpectedEnd),
+            };
+        }
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some(b'(') {
+                break;
+            }
+            self.depth += 1;
+            let variation = self.game_tree();
+            self.depth -= 1;
+            tree.variations.push(variation?);
+        }
+        self.expect(b')', "')'")?;
+        Ok(tree)
+    }
+
+    fn node(&mut self) -> Result<SgfNode, SgfError> {
+        self.expect(b';', "';'")?;
+        let mut node = SgfNode::default();
+        loop {
+            self.skip_whitespace();
+            if !self.peek().is_some_and(|byte| byte.is_ascii_alphabetic()) {
+                return Ok(node);
+            }
+            let property = self.property()?;
+            // FF[3] long names such as `AddBlack` reduce to their capitals.
+            if !property.ident.is_empty() {
+                node.push(&property.ident, property.values);
+            }
+        }
+    }
+
+    fn property(&mut self) -> Result<Property, SgfError> {
+        let mut ident = String::new();
+        while let Some(byte) = self.peek().filter(u8::is_ascii_alphabetic) {
+            if byte.is_ascii_uppercase() {
+                ident.push(byte as char);
+            }
+            self.pos += 1;
+        }
+        let mut values = Vec::new();
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some(b'[') {
+                break;
+            }
+            values.push(self.value()?);
+        }
+        if values.is_empty() {
+            return match self.peek() {
+                Some(_) => Err(self.unexpected("'['")),
+                None => Err(SgfError::UnexpectedEnd),
+            };
+        }
+        Ok(Property { ident, values })
+    }
+
+    fn value(&mut self) -> Result<String, SgfError> {
+        self.pos += 1;
+        let start = self.pos;
+        loop {
+            match self.peek() {
+                None => return Err(SgfError::UnexpectedEnd),
+                Some(b'\\') => self.pos += 2,
+                Some(b']') => break,
+                Some(_) => self.pos += 1,
+            }
+        }
+        let raw = self
+            .input
+            .get(start..self.pos)
+            .ok_or(SgfError::UnexpectedEnd)?;
+        self.pos += 1;
+        Ok(raw.to_string())
+    }
+}
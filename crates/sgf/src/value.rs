@@ -0,0 +1,120 @@
+use go_rules::Point;
+
+use crate::error::SgfError;
+
+/// Decode a Text or SimpleText value: `\` escapes the next character and a backslash before a
+/// line break removes the break (a soft line break).
+pub fn unescape_text(raw: &str) -> String {
+    let mut text = String::with_capacity(raw.len());
+    let mut chars = raw.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            text.push(ch);
+            continue;
+        }
+        match chars.next() {
+            Some(first @ ('\n' | '\r')) => {
+                let pair = if first == '\n' { '\r' } else { '\n' };
+                chars.next_if_eq(&pair);
+            }
+            Some(escaped) => text.push(escaped),
+            None => {}
+        }
+    }
+    text
+}
+
+/// Escape text for a property value.
+pub fn escape_text(text: &str) -> String {
+    escape(text, &['\\', ']'])
+}
+
+/// Escape one half of a composed value such as `LB[aa:label]`.
+pub fn escape_compose_part(text: &str) -> String {
+    escape(text, &['\\', ']', ':'])
+}
+
+fn escape(text: &str, special: &[char]) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        if special.contains(&ch) {
+            escaped.push('\\');
+        }
+        escaped.push(ch);
+    }
+    escaped
+}
+
+/// Split a composed value at its first unescaped `:`.
+pub fn split_compose(raw: &str) -> Option<(&str, &str)> {
+    let bytes = raw.as_bytes();
+    let mut index = 0;
+    while index < bytes.len() {
+        match bytes[index] {
+            b'\\' => index += 2,
+            b':' => return Some((&raw[..index], &raw[index + 1..])),
+            _ => index += 1,
+        }
+    }
+    None
+}
+
+fn coordinate(byte: u8) -> Option<usize> {
+    match byte {
+        b'a'..=b'z' => Some((byte - b'a') as usize),
+        b'A'..=b'Z' => Some((byte - b'A') as usize + 26),
+        _ => None,
+    }
+}
+
+/// Decode a move value. An empty value, or `tt` on boards up to 19x19, is a pass.
+pub fn parse_move(raw: &str, size: usize) -> Result<Option<Point>, SgfError> {
+    if raw.is_empty() || (raw == "tt" && size <= 19) {
+        return Ok(None);
+    }
+    parse_point(raw, size).map(Some)
+}
+
+/// Decode a single point such as `pd`.
+pub fn parse_point(raw: &str, size: usize) -> Result<Point, SgfError> {
+    let invalid = || SgfError::InvalidPoint {
+        value: raw.to_string(),
+        size,
+    };
+    let [x, y] = raw.as_bytes() else {
+        return Err(invalid());
+    };
+    match (coordinate(*x), coordinate(*y)) {
+        (Some(x), Some(y)) if x < size && y < size => Point::new(x, y).map_err(|_| invalid()),
+        _ => Err(invalid()),
+    }
+}
+
+/// Decode a list of points, expanding compressed rectangles such as `aa:cc`.
+pub fn parse_point_list(values: &[String], size: usize) -> Result<Vec<Point>, SgfError> {
+    let mut points = Vec::new();
+    for raw in values {
+        match split_compose(raw) {
+            None => points.push(parse_point(raw, size)?),
+            Some((from, to)) => {
+                let (from, to) = (parse_point(from, size)?, parse_point(to, size)?);
+                let (left, right) = (from.x.min(to.x), from.x.max(to.x));
+                let (top, bottom) = (from.y.min(to.y), from.y.max(to.y));
+                for y in top..=bottom {
+                    for x in left..=right {
+                        points.push(Point { x, y });
+                    }
+                }
+            }
+        }
+    }
+    Ok(points)
+}
+
+pub fn format_point(point: Point) -> String {
+    let letter = |value: u8| match value {
+        0..=25 => (b'a' + value) as char,
+        _ => (b'A' + value - 26) as char,
+    };
+    [letter(point.x), letter(point.y)].iter().collect()
+}
@@ -0,0 +1,39 @@
+use crate::parse::{SgfNode, SgfTree};
+
+/// Serialize a collection of game trees. Values are written as stored, so they must already
+/// be escaped.
+pub fn write(trees: &[SgfTree]) -> String {
+    let mut out = String::new();
+    for tree in trees {
+        write_tree(&mut out, tree);
+        out.push('\n');
+    }
+    out
+}
+
+fn write_tree(out: &mut String, tree: &SgfTree) {
+    out.push('(');
+    for (index, node) in tree.nodes.iter().enumerate() {
+        if index > 0 {
+            out.push('\n');
+        }
+        write_node(out, node);
+    }
+    for variation in &tree.variations {
+        out.push('\n');
+        write_tree(out, variation);
+    }
+    out.push(')');
+}
+
+fn write_node(out: &mut String, node: &SgfNode) {
+    out.push(';');
+    for property in &node.properties {
+        out.push_str(&property.ident);
+        for value in &property.values {
+            out.push('[');
+            out.push_str(value);
+            out.push(']');
+        }
+    }
+}
@@ -0,0 +1,218 @@
+use std::collections::HashMap;
+
+use go_rules::Color;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::TournamentError,
+    pairing::{a_takes_black, match_seats, seeded, Bye, Entrant, Pairing, Round, Seat},
+    record::{records, PlayerRecord, RoundGame, RoundResult},
+    swiss::check_entrants,
+};
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct EliminationSettings {
+    /// Players are out after their second loss rather than their first.
+    pub double: bool,
+}
+
+/// The order seeds are placed in a bracket of `size` slots, a power of two, so that the
+/// top seeds meet as late as possible: 1, 8, 4, 5, 2, 7, 3, 6 for eight.
+fn bracket_order(size: usize) -> Vec<usize> {
+    let mut order = vec![1];
+    while order.len() < size {
+        let mirror = order.len() * 2 + 1;
+        order = order
+            .iter()
+            .flat_map(|&seed| [seed, mirror - seed])
+            .collect();
+    }
+    order
+}
+
+/// Every player's losses in the rounds before `round`. A double forfeit is a loss for
+/// both players.
+fn losses(games: &[RoundGame], round: u32) -> Result<HashMap<&str, u32>, TournamentError> {
+    let mut losses = HashMap::new();
+    for game in games.iter().filter(|game| game.round < round) {
+        let Some(white) = &game.white else {
+            continue;
+        };
+        let result = game
+            .result
+            .ok_or(TournamentError::PendingResults(game.round))?;
+        let losers = match result.winner() {
+            Some(Color::Black) => vec![white.as_str()],
+            Some(_) => vec![game.black.as_str()],
+            None if result == RoundResult::Forfeit(None) => {
+                vec![game.black.as_str(), white.as_str()]
+            }
+            None => return Err(TournamentError::NoWinner(game.black.clone(), white.clone())),
+        };
+        for loser in losers {
+            *losses.entry(loser).or_default() += 1;
+        }
+    }
+    Ok(losses)
+}
+
+/// Pair round `round` of a knockout between `entrants`, seeded by rating.
+///
+/// The first round fills a bracket whose size is a power of two, giving the top seeds a
+/// bye when the field is short. After that, the winners of neighbouring tables meet, so a
+/// bye keeps its table to hold its place in the bracket. In a double elimination players
+/// who have lost once play on among themselves, avoiding rematches, at the tables after
+/// the winners' bracket. Its survivor meets the winners' champion in the final, which is
+/// replayed if the champion loses it.
+pub fn pair_elimination(
+    entrants: &[Entrant],
+    games: &[RoundGame],
+    round: u32,
+    settings: &EliminationSettings,
+) -> Result<Round, TournamentError> {
+    check_entrants(entrants)?;
+    let lives = if settings.double { 2 } else { 1 };
+    let lost = losses(games, round)?;
+    let records = records(games, round, 0.0, |_| 0.0);
+    let empty = PlayerRecord::default();
+    let seats: Vec<Seat<'_>> = seeded(entrants)
+        .into_iter()
+        .map(|entrant| Seat {
+            id: &entrant.id,
+            score: 0.0,
+            record: records.get(&entrant.id).unwrap_or(&empty),
+        })
+        .collect();
+    let losses_of = |seat: usize| lost.get(seats[seat].id).copied().unwrap_or(0);
+    let (unbeaten, beaten): (Vec<usize>, Vec<usize>) = (0..seats.len())
+        .filter(|&seat| losses_of(seat) < lives)
+        .partition(|&seat| losses_of(seat) == 0);
+    if unbeaten.len() + beaten.len() < 2 {
+        return Err(TournamentError::Finished);
+    }
+
+    let game = |a: usize, b: usize, table: u32| {
+        let (upper, lower) = (a.min(b), a.max(b));
+        let (black, white) = if a_takes_black(&seats[upper], &seats[lower], table) {
+            (upper, lower)
+        } else {
+            (lower, upper)
+        };
+        Pairing {
+            table,
+            black: seats[black].id.to_string(),
+            white: seats[white].id.to_string(),
+            handicap: 0,
+        }
+    };
+    let mut paired = Round {
+        round,
+        ..Round::default()
+    };
+    if let ([champion], [challenger]) = (unbeaten.as_slice(), beaten.as_slice()) {
+        paired.pairings.push(game(*champion, *challenger, 1));
+        return Ok(paired);
+    }
+
+    let mut winners_tables = 0;
+    if unbeaten.len() >= 2 {
+        let slots = bracket_slots(games, round, &seats, &unbeaten)?;
+        winners_tables = slots.len() as u32;
+        for (index, slot) in slots.into_iter().enumerate() {
+            let table = index as u32 + 1;
+            match slot {
+                [Some(a), Some(b)] => paired.pairings.push(game(a, b, table)),
+                [Some(player), None] | [None, Some(player)] => paired.byes.push(Bye {
+                    table,
+                    player: seats[player].id.to_string(),
+                }),
+                [None, None] => {}
+            }
+        }
+    }
+
+    if beaten.len() >= 2 {
+        let group: Vec<Seat<'_>> = beaten
+            .iter()
+            .map(|&seat| Seat {
+                id: seats[seat].id,
+                score: 0.0,
+                record: seats[seat].record,
+            })
+            .collect();
+        let matching = match_seats(&group);
+        let mut pairs: Vec<(usize, usize)> = matching
+            .pairs
+            .iter()
+            .map(|&(a, b)| (beaten[a.min(b)], beaten[a.max(b)]))
+            .collect();
+        pairs.sort();
+        let mut table = winners_tables;
+        for (a, b) in pairs {
+            table += 1;
+            paired.pairings.push(game(a, b, table));
+        }
+        if let Some(bye) = matching.bye {
+            paired.byes.push(Bye {
+                table: table + 1,
+                player: seats[beaten[bye]].id.to_string(),
+            });
+        }
+        paired.rematches = matching.rematches;
+    }
+    Ok(paired)
+}
+
+/// The winners' bracket of round `round` as pairs of seats per table: the seeded bracket
+/// in the first round, and then the unbeaten players of neighbouring tables.
+fn bracket_slots(
+    games: &[RoundGame],
+    round: u32,
+    seats: &[Seat<'_>],
+    unbeaten: &[usize],
+) -> Result<Vec<[Option<usize>; 2]>, TournamentError> {
+    let previous: Vec<&RoundGame> = games
+        .iter()
+        .filter(|game| game.round + 1 == round)
+        .collect();
+    if previous.is_empty() {
+        let size = unbeaten.len().next_power_of_two();
+        let order = bracket_order(size);
+        return Ok(order
+            .chunks(2)
+            .map(|pair| {
+                [
+                    unbeaten.get(pair[0] - 1).copied(),
+                    unbeaten.get(pair[1] - 1).copied(),
+                ]
+            })
+            .collect());
+    }
+    let before = losses(games, round - 1)?;
+    let seat_of: HashMap<&str, usize> = unbeaten
+        .iter()
+        .map(|&seat| (seats[seat].id, seat))
+        .collect();
+    let mut slots: Vec<[Option<usize>; 2]> = Vec::new();
+    for game in previous {
+        let players = [Some(game.black.as_str()), game.white.as_deref()];
+        // Only games of the winners' bracket lead on in it.
+        if players
+            .iter()
+            .flatten()
+            .any(|id| before.get(id).is_some_and(|&losses| losses > 0))
+        {
+            continue;
+        }
+        let Some(&advancing) = players.iter().flatten().find_map(|id| seat_of.get(id)) else {
+            continue;
+        };
+        let index = game.table.saturating_sub(1) as usize;
+        if slots.len() <= index / 2 {
+            slots.resize(index / 2 + 1, [None, None]);
+        }
+        slots[index / 2][index % 2] = Some(advancing);
+    }
+    Ok(slots)
+}
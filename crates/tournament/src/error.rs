@@ -0,0 +1,21 @@
+#[derive(Debug, thiserror::Error)]
+pub enum TournamentError {
+    #[error("invalid rank {0:?}")]
+    InvalidRank(String),
+    #[error("rank code {0} is out of range")]
+    InvalidRankCode(i64),
+    #[error("invalid result {0:?}")]
+    InvalidResult(String),
+    #[error("a round needs at least two players, not {0}")]
+    NotEnoughPlayers(usize),
+    #[error("player {0} is listed twice")]
+    DuplicatePlayer(String),
+    #[error("invalid pairing settings: {0}")]
+    InvalidSettings(String),
+    #[error("round {0} still has games without a result")]
+    PendingResults(u32),
+    #[error("a knockout game between {0} and {1} needs a winner")]
+    NoWinner(String, String),
+    #[error("every round of the tournament has been paired")]
+    Finished,
+}
@@ -0,0 +1,301 @@
+use std::collections::HashMap;
+
+use go_rules::Color;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    rank::Rank,
+    record::RoundResult,
+    standings::{Standing, StandingRound, TieBreaker},
+};
+
+/// A results file format accepted by a rating database.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResultsFormat {
+    /// The `.h` tournament file of the European Go Database.
+    Egd,
+    /// The results text file of the AGA ratings.
+    Aga,
+}
+
+/// The tournament a results file describes.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ResultsInfo {
+    pub name: String,
+    pub location: Option<String>,
+    pub start_date: Option<String>,
+    pub end_date: Option<String>,
+    pub komi: f64,
+    pub ruleset: String,
+}
+
+/// A player as a rating database knows them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResultsPlayer {
+    pub id: String,
+    pub name: String,
+    pub rank: Rank,
+    /// Two-letter country code.
+    pub country: Option<String>,
+    /// Club abbreviation, at most four letters in the EGD.
+    pub club: Option<String>,
+    /// The player's membership number with the federation, such as their AGA ID.
+    pub federation_id: Option<String>,
+}
+
+/// Surname and given name from a full name written given name first, each without spaces.
+fn split_name(name: &str) -> (String, String) {
+    let words: Vec<&str> = name.split_whitespace().collect();
+    match words.split_last() {
+        Some((surname, [])) => (surname.to_string(), "-".to_string()),
+        Some((surname, given)) => (surname.to_string(), given.join("_")),
+        None => ("-".to_string(), "-".to_string()),
+    }
+}
+
+/// A score without a trailing `.0`.
+fn number(value: f64) -> String {
+    if value.fract() == 0.0 {
+        format!("{value:.0}")
+    } else {
+        format!("{value}")
+    }
+}
+
+fn column(criterion: TieBreaker) -> &'static str {
+    match criterion {
+        TieBreaker::Points => "Pt",
+        TieBreaker::Mms => "MMS",
+        TieBreaker::Sos => "SOS",
+        TieBreaker::Sosos => "SOSOS",
+        TieBreaker::Sodos => "SODOS",
+        TieBreaker::Cumulative => "CUSS",
+        TieBreaker::DirectEncounter => "DE",
+    }
+}
+
+/// The results of a tournament as an EGD `.h` file, from standings with `tie_breakers`
+/// through `rounds` rounds.
+///
+/// Players are numbered by their line, and each round reads as the opponent's number, the
+/// outcome (`+`, `-` or `=`), the player's color (`w` or `b`) and the handicap when there
+/// was one: `7+b2` is a win with Black and two stones against player 7. Forfeits are
+/// marked `!` instead of a color, a bye is `0+` when it earned points and a round
+/// without a game is `0=`.
+pub fn egd_results(
+    info: &ResultsInfo,
+    players: &[ResultsPlayer],
+    standings: &[Standing],
+    tie_breakers: &[TieBreaker],
+    rounds: u32,
+) -> String {
+    let details: HashMap<&str, &ResultsPlayer> = players
+        .iter()
+        .map(|player| (player.id.as_str(), player))
+        .collect();
+    let numbers: HashMap<&str, usize> = standings
+        .iter()
+        .enumerate()
+        .map(|(index, standing)| (standing.player.as_str(), index + 1))
+        .collect();
+
+    let mut lines = Vec::new();
+    lines.push(format!("; EV[{}]", info.name));
+    if let Some(location) = &info.location {
+        lines.push(format!("; PC[{location}]"));
+    }
+    let dates: Vec<&str> = [&info.start_date, &info.end_date]
+        .into_iter()
+        .flatten()
+        .map(String::as_str)
+        .collect();
+    if !dates.is_empty() {
+        lines.push(format!("; DT[{}]", dates.join(",")));
+    }
+    lines.push(format!("; KM[{}]", number(info.komi)));
+    lines.push(format!("; RU[{}]", info.ruleset));
+    let mut header = format!(
+        "; {:>3} {:<32} {:>3} {:<2} {:<4}",
+        "Num", "Name", "Rk", "Co", "Club"
+    );
+    for &criterion in tie_breakers {
+        header.push_str(&format!(" {:>5}", column(criterion)));
+    }
+    lines.push(header.trim_end().to_string());
+
+    for (index, standing) in standings.iter().enumerate() {
+        let player = details.get(standing.player.as_str());
+        let (surname, given) = split_name(player.map_or(&standing.player, |p| &p.name));
+        let rank = player.map_or(String::from("30k"), |p| p.rank.to_string());
+        let country = player.and_then(|p| p.country.as_deref()).unwrap_or("-");
+        let club: String = player
+            .and_then(|p| p.club.as_deref())
+            .unwrap_or("-")
+            .chars()
+            .take(4)
+            .collect();
+        let mut line = format!(
+            "{:>5} {:<32} {:>3} {:<2} {:<4}",
+            index + 1,
+            format!("{surname} {given}"),
+            rank,
+            country,
+            club
+        );
+        for value in &standing.tie_breakers {
+            line.push_str(&format!(" {:>5}", number(*value)));
+        }
+        for round in 1..=rounds {
+            let entry = standing.rounds.iter().find(|entry| entry.round == round);
+            line.push_str(&format!(" {:>6}", egd_round(entry, &numbers)));
+        }
+        lines.push(line);
+    }
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+fn egd_round(entry: Option<&StandingRound>, numbers: &HashMap<&str, usize>) -> String {
+    let Some(entry) = entry else {
+        return "0=".to_string();
+    };
+    if entry.bye {
+        return if entry.points.is_some_and(|points| points > 0.0) {
+            "0+".to_string()
+        } else {
+            "0=".to_string()
+        };
+    }
+    let opponent = entry.opponent.as_deref().and_then(|id| numbers.get(id));
+    let (Some(opponent), Some(result), Some(points)) = (opponent, entry.result, entry.points)
+    else {
+        return "0=".to_string();
+    };
+    let outcome = if points == 1.0 {
+        '+'
+    } else if points == 0.0 {
+        '-'
+    } else {
+        '='
+    };
+    if !result.was_played() {
+        return format!("{opponent}{outcome}!");
+    }
+    let color = match entry.color {
+        Some(Color::White) => 'w',
+        _ => 'b',
+    };
+    if entry.handicap > 0 {
+        format!("{opponent}{outcome}{color}{}", entry.handicap)
+    } else {
+        format!("{opponent}{outcome}{color}")
+    }
+}
+
+/// The results of a tournament in the AGA results format: the players with their AGA ID,
+/// name and rank, then every game of every round as White's ID, Black's ID, the winner,
+/// the handicap and the komi. Players without an AGA ID get temporary `TMP` IDs.
+///
+/// Only games won on the board are rated, so forfeits, jigo and unfinished games are left
+/// as comments. Handicap games are played without komi.
+pub fn aga_results(
+    info: &ResultsInfo,
+    players: &[ResultsPlayer],
+    standings: &[Standing],
+) -> String {
+    let mut temporary = 0;
+    let ids: HashMap<&str, String> = players
+        .iter()
+        .map(|player| {
+            let id = player.federation_id.clone().unwrap_or_else(|| {
+                temporary += 1;
+                format!("TMP{temporary}")
+            });
+            (player.id.as_str(), id)
+        })
+        .collect();
+
+    let mut lines = Vec::new();
+    let mut tourney = format!("TOURNEY {}", info.name);
+    if let Some(location) = &info.location {
+        tourney.push_str(&format!(", {location}"));
+    }
+    lines.push(tourney);
+    if let Some(start) = &info.start_date {
+        lines.push(format!("     start={start}"));
+    }
+    if let Some(finish) = info.end_date.as_ref().or(info.start_date.as_ref()) {
+        lines.push(format!("     finish={finish}"));
+    }
+    lines.push(format!("     rules={}", info.ruleset));
+
+    lines.push(String::new());
+    lines.push("PLAYERS".to_string());
+    for player in players {
+        let (surname, given) = split_name(&player.name);
+        let mut line = format!(
+            "{:>8} {}, {} {}",
+            ids[player.id.as_str()],
+            surname,
+            given.replace('_', " "),
+            player.rank
+        );
+        if let Some(club) = &player.club {
+            line.push_str(&format!(" CLUB={club}"));
+        }
+        if let Some(country) = &player.country {
+            line.push_str(&format!(" COUNTRY={country}"));
+        }
+        lines.push(line);
+    }
+
+    lines.push(String::new());
+    lines.push("GAMES".to_string());
+    let mut games: Vec<(u32, &str, &StandingRound)> = standings
+        .iter()
+        .flat_map(|standing| {
+            standing
+                .rounds
+                .iter()
+                .filter(|entry| entry.color == Some(Color::White))
+                .map(move |entry| (entry.round, standing.player.as_str(), entry))
+        })
+        .collect();
+    games.sort_by_key(|&(round, white, _)| (round, ids.get(white).cloned()));
+    let mut current = 0;
+    for (round, white, entry) in games {
+        if round != current {
+            current = round;
+            lines.push(format!("# Round {round}"));
+        }
+        let (Some(white), Some(black)) = (
+            ids.get(white),
+            entry.opponent.as_deref().and_then(|id| ids.get(id)),
+        ) else {
+            continue;
+        };
+        match entry.result {
+            Some(RoundResult::Win(winner)) => {
+                let komi = if entry.handicap > 0 { 0.0 } else { info.komi };
+                let winner = match winner {
+                    Color::White => 'W',
+                    Color::Black => 'B',
+                };
+                lines.push(format!(
+                    "{white} {black} {winner} {} {}",
+                    entry.handicap,
+                    number(komi)
+                ));
+            }
+            Some(result) => {
+                lines.push(format!("# {white} {black} {result} not rated"));
+            }
+            None => {
+                lines.push(format!("# {white} {black} unfinished"));
+            }
+        }
+    }
+    lines.push(String::new());
+    lines.join("\n")
+}
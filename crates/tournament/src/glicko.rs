@@ -0,0 +1,246 @@
+use std::{collections::HashMap, f64::consts::PI, hash::Hash};
+
+use serde::{Deserialize, Serialize};
+
+use crate::rank;
+
+/// Factor between the Glicko and Glicko-2 scales.
+const SCALE: f64 = 173.7178;
+/// Convergence tolerance of the volatility iteration.
+const EPSILON: f64 = 1e-6;
+/// Komi that makes an even game fair, and twice the value of a move.
+const FAIR_KOMI: f64 = 7.0;
+
+/// A Glicko-2 rating on the familiar Glicko scale.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Rating {
+    pub rating: f64,
+    pub deviation: f64,
+    pub volatility: f64,
+}
+
+impl Default for Rating {
+    fn default() -> Self {
+        Self {
+            rating: 1500.0,
+            deviation: 350.0,
+            volatility: 0.06,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct GlickoSettings {
+    /// Constrains how much the volatility changes in a period; Glickman suggests 0.3–1.2.
+    pub tau: f64,
+    /// Deviations never grow past this, the deviation of an unrated player.
+    pub max_deviation: f64,
+}
+
+impl Default for GlickoSettings {
+    fn default() -> Self {
+        Self {
+            tau: 0.5,
+            max_deviation: 350.0,
+        }
+    }
+}
+
+/// One game against an opponent: `score` is 1 for a win, 0.5 for jigo and 0 for a loss.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Outcome {
+    pub opponent: Rating,
+    pub score: f64,
+}
+
+/// The rating after a period in which `player` had `outcomes`, following Glickman's
+/// "Example of the Glicko-2 system". A player without games only grows less certain.
+pub fn rate(player: Rating, outcomes: &[Outcome], settings: &GlickoSettings) -> Rating {
+    let mu = (player.rating - 1500.0) / SCALE;
+    let phi = player.deviation / SCALE;
+    let sigma = player.volatility;
+    if outcomes.is_empty() {
+        let deviation = (phi.powi(2) + sigma.powi(2)).sqrt() * SCALE;
+        return Rating {
+            deviation: deviation.min(settings.max_deviation),
+            ..player
+        };
+    }
+
+    let games: Vec<(f64, f64, f64)> = outcomes
+        .iter()
+        .map(|outcome| {
+            let mu_j = (outcome.opponent.rating - 1500.0) / SCALE;
+            let g_j = g(outcome.opponent.deviation / SCALE);
+            (g_j, expectation(mu, mu_j, g_j), outcome.score)
+        })
+        .collect();
+    let v = 1.0
+        / games
+            .iter()
+            .map(|(g_j, e_j, _)| g_j.powi(2) * e_j * (1.0 - e_j))
+            .sum::<f64>();
+    let improvement: f64 = games
+        .iter()
+        .map(|(g_j, e_j, score)| g_j * (score - e_j))
+        .sum();
+    let delta = v * improvement;
+
+    let sigma = new_volatility(phi, sigma, v, delta, settings.tau);
+    let phi_star = (phi.powi(2) + sigma.powi(2)).sqrt();
+    let phi = 1.0 / (1.0 / phi_star.powi(2) + 1.0 / v).sqrt();
+    let mu = mu + phi.powi(2) * improvement;
+    Rating {
+        rating: mu * SCALE + 1500.0,
+        deviation: (phi * SCALE).min(settings.max_deviation),
+        volatility: sigma,
+    }
+}
+
+/// The chance that `player` beats `opponent`.
+pub fn expected_score(player: Rating, opponent: Rating) -> f64 {
+    let g_j = g(opponent.deviation / SCALE);
+    expectation(
+        (player.rating - 1500.0) / SCALE,
+        (opponent.rating - 1500.0) / SCALE,
+        g_j,
+    )
+}
+
+fn g(phi: f64) -> f64 {
+    1.0 / (1.0 + 3.0 * phi.powi(2) / PI.powi(2)).sqrt()
+}
+
+fn expectation(mu: f64, mu_j: f64, g_j: f64) -> f64 {
+    1.0 / (1.0 + (-g_j * (mu - mu_j)).exp())
+}
+
+/// Step 5 of the paper: solve for the new volatility with the Illinois algorithm.
+fn new_volatility(phi: f64, sigma: f64, v: f64, delta: f64, tau: f64) -> f64 {
+    let a = sigma.powi(2).ln();
+    let f = |x: f64| {
+        let ex = x.exp();
+        ex * (delta.powi(2) - phi.powi(2) - v - ex) / (2.0 * (phi.powi(2) + v + ex).powi(2))
+            - (x - a) / tau.powi(2)
+    };
+    let mut big_a = a;
+    let mut big_b = if delta.powi(2) > phi.powi(2) + v {
+        (delta.powi(2) - phi.powi(2) - v).ln()
+    } else {
+        let mut k = 1.0;
+        while f(a - k * tau) < 0.0 {
+            k += 1.0;
+        }
+        a - k * tau
+    };
+    let (mut f_a, mut f_b) = (f(big_a), f(big_b));
+    while (big_b - big_a).abs() > EPSILON {
+        let big_c = big_a + (big_a - big_b) * f_a / (f_b - f_a);
+        let f_c = f(big_c);
+        if f_c * f_b <= 0.0 {
+            big_a = big_b;
+            f_a = f_b;
+        } else {
+            f_a /= 2.0;
+        }
+        big_b = big_c;
+        f_b = f_c;
+    }
+    (big_a / 2.0).exp()
+}
+
+/// How many grades of advantage Black has from `handicap` stones and `komi`: every move is
+/// worth about a grade, and Black's first move is made fair by a komi of 7.
+pub fn handicap_grades(handicap: u8, komi: f64) -> f64 {
+    let extra_moves = handicap.saturating_sub(1);
+    f64::from(extra_moves) + (FAIR_KOMI - komi) / (2.0 * FAIR_KOMI)
+}
+
+/// A finished game between two players, for a rating period.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RatedGame<Id> {
+    pub black: Id,
+    pub white: Id,
+    /// 1 when Black won, 0.5 for jigo and 0 when White won.
+    pub black_score: f64,
+    pub handicap: u8,
+    pub komi: f64,
+}
+
+/// Games collected over a period and rated together, so that every game is judged by the
+/// ratings the players had when the period began.
+#[derive(Clone, Debug)]
+pub struct RatingPeriod<Id> {
+    games: Vec<RatedGame<Id>>,
+}
+
+impl<Id> Default for RatingPeriod<Id> {
+    fn default() -> Self {
+        Self { games: Vec::new() }
+    }
+}
+
+impl<Id: Clone + Eq + Hash> RatingPeriod<Id> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_game(&mut self, game: RatedGame<Id>) {
+        self.games.push(game);
+    }
+
+    pub fn games(&self) -> &[RatedGame<Id>] {
+        &self.games
+    }
+
+    /// The ratings at the end of the period for every player in `ratings` and every player
+    /// in a game. Players missing from `ratings` start from the default rating.
+    ///
+    /// In a handicap game Black plays with an effective rating raised by the grades the
+    /// handicap is worth, so that winning as expected with stones gains nothing.
+    pub fn rate(
+        &self,
+        ratings: &HashMap<Id, Rating>,
+        settings: &GlickoSettings,
+    ) -> HashMap<Id, Rating> {
+        let current = |id: &Id| ratings.get(id).copied().unwrap_or_default();
+        let mut outcomes: HashMap<Id, Vec<Outcome>> =
+            ratings.keys().map(|id| (id.clone(), Vec::new())).collect();
+        for game in &self.games {
+            let (black, white) = (current(&game.black), current(&game.white));
+            let advantage = handicap_grades(game.handicap, game.komi);
+            let effective_black = Rating {
+                rating: rank::adjust_rating(black.rating, advantage),
+                ..black
+            };
+            // Black's result is judged against a White weakened by the same margin.
+            let effective_white = Rating {
+                rating: white.rating - (effective_black.rating - black.rating),
+                ..white
+            };
+            outcomes
+                .entry(game.black.clone())
+                .or_default()
+                .push(Outcome {
+                    opponent: effective_white,
+                    score: game.black_score,
+                });
+            outcomes
+                .entry(game.white.clone())
+                .or_default()
+                .push(Outcome {
+                    opponent: effective_black,
+                    score: 1.0 - game.black_score,
+                });
+        }
+        outcomes
+            .into_iter()
+            .map(|(id, outcomes)| {
+                let rating = rate(current(&id), &outcomes, settings);
+                (id, rating)
+            })
+            .collect()
+    }
+}
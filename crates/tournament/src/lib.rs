@@ -0,0 +1,1065 @@
+mod elimination;
+mod error;
+mod export;
+mod glicko;
+mod mcmahon;
+mod pairing;
+mod rank;
+mod record;
+mod round_robin;
+mod standings;
+mod swiss;
+
+pub use elimination::{pair_elimination, EliminationSettings};
+pub use error::TournamentError;
+pub use export::{aga_results, egd_results, ResultsFormat, ResultsInfo, ResultsPlayer};
+pub use glicko::{
+    expected_score, handicap_grades, rate, GlickoSettings, Outcome, RatedGame, Rating, RatingPeriod,
+};
+pub use mcmahon::{pair_mcmahon, HandicapRules, McMahonSettings};
+pub use pairing::{Bye, Entrant, Pairing, PairingSettings, PlayerFloat, Round};
+pub use rank::Rank;
+pub use record::{records, Float, PlayerRecord, RoundGame, RoundResult};
+pub use round_robin::{berger_tables, schedule_round_robin, RoundRobinSettings};
+pub use standings::{standings, Standing, StandingRound, TieBreaker};
+pub use swiss::{pair_swiss, SwissSettings};
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use go_rules::Color;
+
+    use super::*;
+
+    #[test]
+    fn ranks_parse_display_and_order() {
+        assert_eq!("5k".parse::<Rank>().unwrap(), Rank::Kyu(5));
+        assert_eq!(" 3 Dan ".parse::<Rank>().unwrap(), Rank::Dan(3));
+        assert_eq!("9P".parse::<Rank>().unwrap(), Rank::Pro(9));
+        assert_eq!("30 kyu".parse::<Rank>().unwrap(), Rank::WEAKEST);
+        for invalid in ["31k", "0k", "10d", "0p", "k", "5", "5x", "-3k", ""] {
+            assert!(invalid.parse::<Rank>().is_err(), "{invalid}");
+        }
+        assert_eq!(Rank::Kyu(12).to_string(), "12k");
+        assert_eq!(Rank::Pro(1).to_string(), "1p");
+
+        let mut ranks = vec![Rank::Pro(1), Rank::Dan(1), Rank::Kyu(1), Rank::Kyu(30)];
+        ranks.sort();
+        assert_eq!(
+            ranks,
+            [Rank::Kyu(30), Rank::Kyu(1), Rank::Dan(1), Rank::Pro(1)]
+        );
+        assert!(Rank::Dan(9) < Rank::Pro(1));
+    }
+
+    #[test]
+    fn rank_codes_round_trip() {
+        let every = (1..=30)
+            .map(Rank::Kyu)
+            .chain((1..=9).map(Rank::Dan))
+            .chain((1..=9).map(Rank::Pro));
+        for rank in every {
+            assert_eq!(Rank::from_code(rank.code()).unwrap(), rank);
+        }
+        assert_eq!(Rank::Kyu(30).code(), -30);
+        assert_eq!(Rank::Dan(1).code(), 1);
+        assert_eq!(Rank::Pro(9).code(), 19);
+        for invalid in [0, 10, 20, -31] {
+            assert!(Rank::from_code(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn ranks_convert_to_and_from_ratings() {
+        assert!((Rank::Kyu(30).to_rating() - 525.0).abs() < 1e-9);
+        assert!((1900.0..1910.0).contains(&Rank::Dan(1).to_rating()));
+        assert_eq!(Rank::from_rating(1500.0), Rank::Kyu(6));
+        for amateur in (1..=30).map(Rank::Kyu).chain((1..=9).map(Rank::Dan)) {
+            assert_eq!(Rank::from_rating(amateur.to_rating()), amateur);
+        }
+        // Each grade is worth more rating points than the one below it.
+        assert!(
+            Rank::Dan(2).to_rating() - Rank::Dan(1).to_rating()
+                > Rank::Kyu(20).to_rating() - Rank::Kyu(21).to_rating()
+        );
+        assert!(Rank::Pro(1).to_rating() > Rank::Dan(9).to_rating());
+        assert_eq!(Rank::from_rating(Rank::Pro(9).to_rating()), Rank::Dan(9));
+        assert_eq!(Rank::from_rating(0.0), Rank::WEAKEST);
+        assert_eq!(Rank::from_rating(f64::NAN), Rank::WEAKEST);
+    }
+
+    fn rating(rating: f64, deviation: f64) -> Rating {
+        Rating {
+            rating,
+            deviation,
+            volatility: 0.06,
+        }
+    }
+
+    #[test]
+    fn glicko2_matches_glickmans_example() {
+        // "Example of the Glicko-2 system", section 3: a 1500 player rated after beating
+        // a 1400 and losing to a 1550 and a 1700, with tau 0.5.
+        let outcomes = [
+            Outcome {
+                opponent: rating(1400.0, 30.0),
+                score: 1.0,
+            },
+            Outcome {
+                opponent: rating(1550.0, 100.0),
+                score: 0.0,
+            },
+            Outcome {
+                opponent: rating(1700.0, 300.0),
+                score: 0.0,
+            },
+        ];
+        let updated = rate(rating(1500.0, 200.0), &outcomes, &GlickoSettings::default());
+        assert!((updated.rating - 1464.06).abs() < 0.01, "{updated:?}");
+        assert!((updated.deviation - 151.52).abs() < 0.01, "{updated:?}");
+        assert!((updated.volatility - 0.05999).abs() < 1e-5, "{updated:?}");
+
+        let expected = [0.639, 0.432, 0.303];
+        for (outcome, expected) in outcomes.iter().zip(expected) {
+            let score = expected_score(rating(1500.0, 200.0), outcome.opponent);
+            assert!((score - expected).abs() < 0.001, "{score}");
+        }
+    }
+
+    #[test]
+    fn idle_players_only_lose_certainty() {
+        let settings = GlickoSettings::default();
+        let idle = rate(rating(1500.0, 200.0), &[], &settings);
+        assert_eq!(idle.rating, 1500.0);
+        assert!(
+            (idle.deviation - (200.0f64.powi(2) + (0.06 * 173.7178f64).powi(2)).sqrt()).abs()
+                < 1e-6
+        );
+        assert_eq!(rate(rating(1500.0, 349.9), &[], &settings).deviation, 350.0);
+    }
+
+    #[test]
+    fn rating_periods_use_the_ratings_from_the_start_of_the_period() {
+        let mut period = RatingPeriod::new();
+        for (black, white, black_score) in [("a", "b", 1.0), ("a", "c", 0.0), ("a", "d", 0.0)] {
+            period.add_game(RatedGame {
+                black,
+                white,
+                black_score,
+                handicap: 0,
+                komi: 7.0,
+            });
+        }
+        let ratings = HashMap::from([
+            ("a", rating(1500.0, 200.0)),
+            ("b", rating(1400.0, 30.0)),
+            ("c", rating(1550.0, 100.0)),
+            ("d", rating(1700.0, 300.0)),
+            ("idle", rating(1600.0, 100.0)),
+        ]);
+        let rated = period.rate(&ratings, &GlickoSettings::default());
+        assert_eq!(rated.len(), 5);
+        // Komi 7 makes the games even, so player "a" matches Glickman's example.
+        assert!((rated["a"].rating - 1464.06).abs() < 0.01);
+        assert!(rated["b"].rating < 1400.0);
+        assert!(rated["c"].rating > 1550.0 && rated["d"].rating > 1700.0);
+        assert_eq!(rated["idle"].rating, 1600.0);
+        assert!(rated["idle"].deviation > 100.0);
+    }
+
+    #[test]
+    fn handicap_games_are_rated_with_an_effective_rating() {
+        assert_eq!(handicap_grades(0, 7.0), 0.0);
+        assert!((handicap_grades(0, 0.5) - 0.464).abs() < 0.001);
+        assert!((handicap_grades(4, 0.5) - 3.464).abs() < 0.001);
+
+        // A 5k taking four stones from a 1k is an even game.
+        let (kyu5, kyu1) = (Rank::Kyu(5).to_rating(), Rank::Kyu(1).to_rating());
+        let rate_game = |handicap, black_score| {
+            let mut period = RatingPeriod::new();
+            period.add_game(RatedGame {
+                black: "black",
+                white: "white",
+                black_score,
+                handicap,
+                komi: 0.5,
+            });
+            let ratings = HashMap::from([
+                ("black", rating(kyu5, 100.0)),
+                ("white", rating(kyu1, 100.0)),
+            ]);
+            period.rate(&ratings, &GlickoSettings::default())
+        };
+        let even = rate_game(4, 1.0);
+        let gain = even["black"].rating - kyu5;
+        let loss = kyu1 - even["white"].rating;
+        assert!(gain > 0.0 && loss > 0.0);
+        let lost = rate_game(4, 0.0);
+        // With the stones it is a fair game, so a win and a loss move the rating alike.
+        assert!((gain - (kyu5 - lost["black"].rating)).abs() < 10.0);
+        // Without stones Black was expected to lose, so winning gains far more.
+        let upset = rate_game(0, 1.0);
+        assert!(upset["black"].rating - kyu5 > 1.25 * gain);
+    }
+
+    fn entrants(ids: &[&str]) -> Vec<Entrant> {
+        // Listed strongest first.
+        ids.iter()
+            .enumerate()
+            .map(|(index, id)| Entrant {
+                id: id.to_string(),
+                rating: 2000.0 - 50.0 * index as f64,
+                rank: None,
+            })
+            .collect()
+    }
+
+    fn game(round: u32, black: &str, white: &str, result: &str) -> RoundGame {
+        RoundGame {
+            round,
+            table: 1,
+            black: black.to_string(),
+            white: Some(white.to_string()),
+            handicap: 0,
+            result: Some(result.parse().unwrap()),
+        }
+    }
+
+    /// The games of a paired round, with the player listed first in `winners` winning.
+    fn play(paired: &Round, winners: &[&str]) -> Vec<RoundGame> {
+        let mut games: Vec<RoundGame> = paired
+            .pairings
+            .iter()
+            .map(|pairing| {
+                let result = if winners.contains(&pairing.black.as_str()) {
+                    "B+R"
+                } else {
+                    "W+R"
+                };
+                RoundGame {
+                    table: pairing.table,
+                    handicap: pairing.handicap,
+                    ..game(paired.round, &pairing.black, &pairing.white, result)
+                }
+            })
+            .collect();
+        games.extend(paired.byes.iter().map(|bye| RoundGame {
+            round: paired.round,
+            table: bye.table,
+            black: bye.player.clone(),
+            white: None,
+            handicap: 0,
+            result: None,
+        }));
+        games
+    }
+
+    fn tables(paired: &Round) -> Vec<(&str, &str)> {
+        paired
+            .pairings
+            .iter()
+            .map(|pairing| (pairing.black.as_str(), pairing.white.as_str()))
+            .collect()
+    }
+
+    fn bye(paired: &Round) -> Option<&str> {
+        assert!(paired.byes.len() <= 1);
+        paired.byes.first().map(|bye| bye.player.as_str())
+    }
+
+    fn met(games: &[RoundGame], a: &str, b: &str) -> bool {
+        games.iter().any(|game| {
+            let white = game.white.as_deref();
+            (game.black == a && white == Some(b)) || (game.black == b && white == Some(a))
+        })
+    }
+
+    #[test]
+    fn round_results_parse_and_score() {
+        assert_eq!(
+            "B+R".parse::<RoundResult>().unwrap(),
+            RoundResult::Win(Color::Black)
+        );
+        assert_eq!(
+            "w+6.5".parse::<RoundResult>().unwrap(),
+            RoundResult::Win(Color::White)
+        );
+        assert_eq!("0".parse::<RoundResult>().unwrap(), RoundResult::Jigo);
+        assert_eq!("Jigo".parse::<RoundResult>().unwrap(), RoundResult::Jigo);
+        assert_eq!(
+            "W+F".parse::<RoundResult>().unwrap(),
+            RoundResult::Forfeit(Some(Color::White))
+        );
+        assert_eq!(
+            "0+F".parse::<RoundResult>().unwrap(),
+            RoundResult::Forfeit(None)
+        );
+        for invalid in ["", "X+R", "Void", "B"] {
+            assert!(invalid.parse::<RoundResult>().is_err(), "{invalid}");
+        }
+        assert_eq!(RoundResult::Jigo.points(Color::White), 0.5);
+        assert_eq!(
+            RoundResult::Forfeit(Some(Color::Black)).points(Color::Black),
+            1.0
+        );
+        assert_eq!(RoundResult::Forfeit(None).points(Color::Black), 0.0);
+
+        let games = [
+            game(1, "a", "b", "B+R"),
+            game(1, "c", "d", "W+F"),
+            game(2, "b", "a", "0"),
+        ];
+        let records = records(&games, 3, 1.0, |_| 0.0);
+        assert_eq!(records["a"].points, 1.5);
+        assert_eq!(records["a"].colors, [Color::Black, Color::White]);
+        assert_eq!(records["d"].points, 1.0);
+        // The forfeited game was never played, so c and d may still meet.
+        assert!(records["d"].opponents.is_empty() && records["d"].colors.is_empty());
+        assert_eq!(records["a"].last_float, Some(Float::Down));
+        assert_eq!(records["b"].last_float, Some(Float::Up));
+    }
+
+    #[test]
+    fn first_round_folds_the_field() {
+        let players = entrants(&["p1", "p2", "p3", "p4", "p5", "p6", "p7", "p8"]);
+        let round = pair_swiss(&players, &[], 1, &SwissSettings::default()).unwrap();
+        // With no colors to make up, Black alternates starting with the lower seat.
+        assert_eq!(
+            tables(&round),
+            [("p5", "p1"), ("p2", "p6"), ("p7", "p3"), ("p4", "p8")]
+        );
+        assert!(round.byes.is_empty());
+        assert!(round.floats.is_empty() && !round.rematches);
+    }
+
+    #[test]
+    fn score_groups_are_paired_without_rematches_and_colors_alternate() {
+        let players = entrants(&["p1", "p2", "p3", "p4", "p5", "p6", "p7", "p8"]);
+        let settings = SwissSettings::default();
+        let first = pair_swiss(&players, &[], 1, &settings).unwrap();
+        let mut games = play(&first, &["p1", "p2", "p3", "p4"]);
+        let second = pair_swiss(&players, &games, 2, &settings).unwrap();
+        // Winners meet winners and losers meet losers, top half against bottom half, with
+        // the bottom half swapped so that everyone gets the other color.
+        assert_eq!(
+            tables(&second),
+            [("p1", "p4"), ("p3", "p2"), ("p8", "p5"), ("p6", "p7")]
+        );
+        games.extend(play(&second, &["p1", "p2", "p5", "p6"]));
+
+        let third = pair_swiss(&players, &games, 3, &settings).unwrap();
+        for pairing in &third.pairings {
+            assert!(!met(&games, &pairing.black, &pairing.white), "{pairing:?}");
+        }
+        // p1 and p2 lead on 2 points and have not met.
+        assert_eq!(third.pairings[0].black, "p2");
+        assert_eq!(third.pairings[0].white, "p1");
+        // Nobody has the same color three times or is two games out of balance.
+        games.extend(play(&third, &[]));
+        let records = records(&games, 4, 1.0, |_| 0.0);
+        for record in records.values() {
+            assert!(record.color_balance().abs() <= 1, "{record:?}");
+        }
+    }
+
+    #[test]
+    fn odd_score_groups_float_their_lowest_player() {
+        let players = entrants(&["p1", "p2", "p3", "p4", "p5", "p6"]);
+        let settings = SwissSettings::default();
+        let games = vec![
+            game(1, "p4", "p1", "W+R"),
+            game(1, "p5", "p2", "W+R"),
+            game(1, "p6", "p3", "W+R"),
+        ];
+        let second = pair_swiss(&players, &games, 2, &settings).unwrap();
+        // p3 is the lowest of the winners and floats down to the top loser.
+        assert_eq!(
+            second.floats,
+            [
+                PlayerFloat {
+                    player: "p3".into(),
+                    direction: Float::Down
+                },
+                PlayerFloat {
+                    player: "p4".into(),
+                    direction: Float::Up
+                },
+            ]
+        );
+        assert!(tables(&second).contains(&("p3", "p4")) || tables(&second).contains(&("p4", "p3")));
+
+        // p3 floated down in round 2 and is the lowest of the odd top group in round 3, so
+        // another player floats down this time.
+        let players = entrants(&["p1", "p2", "p3", "p4", "p5", "p6", "p7", "p8", "p9", "p10"]);
+        let games = vec![
+            game(1, "p6", "p1", "W+R"),
+            game(1, "p7", "p2", "W+R"),
+            game(1, "p8", "p3", "W+R"),
+            game(1, "p9", "p4", "W+R"),
+            game(1, "p10", "p5", "W+R"),
+            game(2, "p4", "p1", "W+R"),
+            game(2, "p5", "p2", "W+R"),
+            game(2, "p6", "p3", "W+R"),
+            game(2, "p8", "p7", "W+R"),
+            game(2, "p10", "p9", "W+R"),
+        ];
+        assert_eq!(
+            records(&games, 3, 1.0, |_| 0.0)["p3"].last_float,
+            Some(Float::Down)
+        );
+        let third = pair_swiss(&players, &games, 3, &settings).unwrap();
+        let floated_down: Vec<&str> = third
+            .floats
+            .iter()
+            .filter(|float| float.direction == Float::Down)
+            .map(|float| float.player.as_str())
+            .collect();
+        assert!(floated_down.contains(&"p2"), "{:?}", third.floats);
+        assert!(!floated_down.contains(&"p3"), "{:?}", third.floats);
+        assert!(tables(&third).contains(&("p1", "p3")) || tables(&third).contains(&("p3", "p1")));
+    }
+
+    #[test]
+    fn rematches_are_avoided_by_floating() {
+        let players = entrants(&["p1", "p2", "p3", "p4"]);
+        // p1 and p2 lead but have met; so have p3 and p4.
+        let games = vec![
+            game(1, "p2", "p1", "B+R"),
+            game(1, "p4", "p3", "B+R"),
+            game(2, "p1", "p4", "B+R"),
+            game(2, "p2", "p3", "B+R"),
+        ];
+        let third = pair_swiss(&players, &games, 3, &SwissSettings::default()).unwrap();
+        for pairing in &third.pairings {
+            assert!(!met(&games, &pairing.black, &pairing.white), "{pairing:?}");
+        }
+        assert!(!third.rematches);
+
+        // Once everyone has met, rematches are unavoidable and reported.
+        let mut games = games;
+        games.extend(play(&third, &[]));
+        let fourth = pair_swiss(&players, &games, 4, &SwissSettings::default()).unwrap();
+        assert!(fourth.rematches);
+        assert_eq!(fourth.pairings.len(), 2);
+    }
+
+    #[test]
+    fn byes_go_to_the_weakest_player_without_one() {
+        let players = entrants(&["p1", "p2", "p3", "p4", "p5"]);
+        let settings = SwissSettings::default();
+        let first = pair_swiss(&players, &[], 1, &settings).unwrap();
+        assert_eq!(bye(&first), Some("p5"));
+        assert_eq!(first.pairings.len(), 2);
+        let mut games = play(&first, &["p1", "p2"]);
+
+        let second = pair_swiss(&players, &games, 2, &settings).unwrap();
+        let second_bye = bye(&second).unwrap();
+        assert_ne!(second_bye, "p5");
+        // The bye counts as a win.
+        assert_eq!(records(&games, 2, 1.0, |_| 0.0)["p5"].points, 1.0);
+        games.extend(play(&second, &[]));
+        let third = pair_swiss(&players, &games, 3, &settings).unwrap();
+        let third_bye = bye(&third).unwrap();
+        assert!(third_bye != "p5" && third_bye != second_bye);
+    }
+
+    #[test]
+    fn withdrawn_players_are_not_paired_but_their_games_count() {
+        let mut players = entrants(&["p1", "p2", "p3", "p4", "p5", "p6"]);
+        let settings = SwissSettings::default();
+        let first = pair_swiss(&players, &[], 1, &settings).unwrap();
+        let games = play(&first, &["p1", "p2", "p3"]);
+        // p6 withdraws after losing to p3.
+        players.retain(|player| player.id != "p6");
+        let second = pair_swiss(&players, &games, 2, &settings).unwrap();
+        let seated: Vec<&str> = second
+            .pairings
+            .iter()
+            .flat_map(|pairing| [pairing.black.as_str(), pairing.white.as_str()])
+            .chain(bye(&second))
+            .collect();
+        assert_eq!(seated.len(), 5);
+        assert!(!seated.contains(&"p6"));
+        assert_eq!(bye(&second), Some("p5"));
+        for pairing in &second.pairings {
+            assert!(!met(&games, &pairing.black, &pairing.white), "{pairing:?}");
+        }
+
+        assert!(matches!(
+            pair_swiss(&players[..1], &games, 2, &settings),
+            Err(TournamentError::NotEnoughPlayers(1))
+        ));
+        let mut twice = players.clone();
+        twice.push(players[0].clone());
+        assert!(matches!(
+            pair_swiss(&twice, &games, 2, &settings),
+            Err(TournamentError::DuplicatePlayer(_))
+        ));
+    }
+
+    #[test]
+    fn pairing_does_not_depend_on_the_order_of_the_input() {
+        let players = entrants(&["p1", "p2", "p3", "p4", "p5", "p6", "p7"]);
+        let settings = SwissSettings::default();
+        let first = pair_swiss(&players, &[], 1, &settings).unwrap();
+        let mut games = play(&first, &["p1", "p2", "p3"]);
+        let second = pair_swiss(&players, &games, 2, &settings).unwrap();
+
+        let mut shuffled = players.clone();
+        shuffled.reverse();
+        shuffled.swap(1, 4);
+        games.reverse();
+        assert_eq!(pair_swiss(&shuffled, &games, 2, &settings).unwrap(), second);
+        // Equal ratings fall back to the id.
+        let even: Vec<Entrant> = ["b", "a", "d", "c"]
+            .iter()
+            .map(|id| Entrant {
+                id: id.to_string(),
+                rating: 1500.0,
+                rank: None,
+            })
+            .collect();
+        let round = pair_swiss(&even, &[], 1, &settings).unwrap();
+        assert_eq!(tables(&round), [("c", "a"), ("b", "d")]);
+    }
+
+    fn ranked(players: &[(&str, &str)]) -> Vec<Entrant> {
+        players
+            .iter()
+            .map(|(id, rank)| {
+                let rank: Rank = rank.parse().unwrap();
+                Entrant {
+                    id: id.to_string(),
+                    rating: rank.to_rating(),
+                    rank: Some(rank),
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn mcmahon_scores_start_from_the_rank_between_floor_and_bar() {
+        let settings = McMahonSettings {
+            bar: Rank::Dan(2),
+            floor: Rank::Kyu(10),
+            ..McMahonSettings::default()
+        };
+        assert_eq!(settings.initial_score(Rank::Pro(1)), 11.0);
+        assert_eq!(settings.initial_score(Rank::Dan(5)), 11.0);
+        assert_eq!(settings.initial_score(Rank::Dan(2)), 11.0);
+        assert_eq!(settings.initial_score(Rank::Dan(1)), 10.0);
+        assert_eq!(settings.initial_score(Rank::Kyu(5)), 5.0);
+        assert_eq!(settings.initial_score(Rank::Kyu(10)), 0.0);
+        assert_eq!(settings.initial_score(Rank::Kyu(25)), 0.0);
+
+        let backwards = McMahonSettings {
+            bar: Rank::Kyu(10),
+            floor: Rank::Dan(2),
+            ..settings
+        };
+        let players = ranked(&[("a", "1d"), ("b", "1k")]);
+        assert!(matches!(
+            pair_mcmahon(&players, &[], 1, &backwards),
+            Err(TournamentError::InvalidSettings(_))
+        ));
+    }
+
+    #[test]
+    fn mcmahon_pairs_within_score_groups_with_handicaps() {
+        let players = ranked(&[
+            ("a", "3d"),
+            ("b", "2d"),
+            ("c", "5k"),
+            ("d", "5k"),
+            ("e", "6k"),
+            ("f", "12k"),
+        ]);
+        let mut settings = McMahonSettings {
+            bar: Rank::Dan(2),
+            floor: Rank::Kyu(10),
+            handicap: Some(HandicapRules::default()),
+            ..McMahonSettings::default()
+        };
+        let first = pair_mcmahon(&players, &[], 1, &settings).unwrap();
+        let games: Vec<(&str, &str, u8)> = first
+            .pairings
+            .iter()
+            .map(|p| (p.black.as_str(), p.white.as_str(), p.handicap))
+            .collect();
+        // 3d and 2d are both at the bar, so their game is even. 6k against 12k counts as
+        // 6k against the 10k floor: four grades minus one.
+        assert_eq!(games[1..], [("c", "d", 0), ("f", "e", 3)]);
+        assert!(matches!(games[0], ("a", "b", 0) | ("b", "a", 0)));
+        assert_eq!(first.floats.len(), 2);
+
+        settings.handicap = Some(HandicapRules {
+            reduction: 0,
+            max_handicap: 2,
+        });
+        let capped = pair_mcmahon(&players, &[], 1, &settings).unwrap();
+        assert_eq!(capped.pairings[2].handicap, 2);
+        assert_eq!(capped.pairings[1].handicap, 0);
+
+        // The 12k wins and catches up with the 5k group, which the 6k left behind.
+        settings.handicap = Some(HandicapRules::default());
+        let games = play(&first, &["a", "c", "f"]);
+        let second = pair_mcmahon(&players, &games, 2, &settings).unwrap();
+        for pairing in &second.pairings {
+            assert!(!met(&games, &pairing.black, &pairing.white));
+        }
+        assert!(!second.rematches);
+        let opponent = |id: &str| {
+            second
+                .pairings
+                .iter()
+                .find_map(|p| {
+                    if p.black == id {
+                        Some(p.white.as_str())
+                    } else if p.white == id {
+                        Some(p.black.as_str())
+                    } else {
+                        None
+                    }
+                })
+                .unwrap()
+        };
+        assert_eq!(opponent("a"), "c");
+        assert_eq!(opponent("d"), "f");
+    }
+
+    #[test]
+    fn pairing_settings_are_tagged_by_system() {
+        let settings: PairingSettings = serde_json::from_str(
+            r#"{"system":"mcmahon","bar":"3d","floor":"20k","handicap":{"reduction":2}}"#,
+        )
+        .unwrap();
+        let PairingSettings::McMahon(mcmahon) = &settings else {
+            panic!("expected McMahon settings, got {settings:?}");
+        };
+        assert_eq!(mcmahon.bar, Rank::Dan(3));
+        assert_eq!(mcmahon.bye_points, 1.0);
+        assert_eq!(
+            mcmahon.handicap,
+            Some(HandicapRules {
+                reduction: 2,
+                max_handicap: 9
+            })
+        );
+        let json = serde_json::to_string(&settings).unwrap();
+        assert_eq!(
+            serde_json::from_str::<PairingSettings>(&json).unwrap(),
+            settings
+        );
+        assert_eq!(
+            serde_json::from_str::<PairingSettings>(r#"{"system":"swiss"}"#).unwrap(),
+            PairingSettings::default()
+        );
+    }
+
+    #[test]
+    fn berger_tables_meet_everyone_once() {
+        assert_eq!(
+            berger_tables(4),
+            [
+                vec![(0, 3), (1, 2)],
+                vec![(3, 2), (0, 1)],
+                vec![(1, 3), (2, 0)]
+            ]
+        );
+        assert_eq!(berger_tables(6)[1], [(5, 3), (4, 2), (0, 1)]);
+        for players in 2..=11 {
+            let tables = berger_tables(players);
+            let seats = players + players % 2;
+            assert_eq!(tables.len(), seats - 1);
+            let mut met = HashMap::new();
+            for round in &tables {
+                for &(a, b) in round {
+                    *met.entry((a.min(b), a.max(b))).or_insert(0) += 1;
+                }
+            }
+            assert_eq!(met.len(), seats * (seats - 1) / 2);
+            assert!(met.values().all(|&count| count == 1));
+        }
+    }
+
+    #[test]
+    fn double_round_robins_swap_colors() {
+        let players = entrants(&["p1", "p2", "p3"]);
+        let single = schedule_round_robin(&players, &RoundRobinSettings::default()).unwrap();
+        assert_eq!(single.len(), 3);
+        let byes: Vec<&str> = single.iter().filter_map(|round| bye(round)).collect();
+        assert_eq!(byes.len(), 3);
+        assert!(["p1", "p2", "p3"].iter().all(|id| byes.contains(id)));
+
+        let double = schedule_round_robin(&players, &RoundRobinSettings { double: true }).unwrap();
+        assert_eq!(double.len(), 6);
+        for (first, second) in double[..3].iter().zip(&double[3..]) {
+            assert_eq!(second.round, first.round + 3);
+            assert_eq!(second.byes, first.byes);
+            let swapped: Vec<(&str, &str)> =
+                tables(first).into_iter().map(|(b, w)| (w, b)).collect();
+            assert_eq!(tables(second), swapped);
+        }
+    }
+
+    #[test]
+    fn single_elimination_seeds_byes_and_advances_winners() {
+        let mut players = entrants(&["p1", "p2", "p3", "p4", "p5", "p6"]);
+        let settings = EliminationSettings::default();
+        let first = pair_elimination(&players, &[], 1, &settings).unwrap();
+        // Seeds 1, 8, 4, 5, 2, 7, 3, 6 with 7 and 8 missing.
+        let byes: Vec<(u32, &str)> = first
+            .byes
+            .iter()
+            .map(|bye| (bye.table, bye.player.as_str()))
+            .collect();
+        assert_eq!(byes, [(1, "p1"), (3, "p2")]);
+        let seated: Vec<(u32, [&str; 2])> = first
+            .pairings
+            .iter()
+            .map(|p| {
+                let mut pair = [p.black.as_str(), p.white.as_str()];
+                pair.sort();
+                (p.table, pair)
+            })
+            .collect();
+        assert_eq!(seated, [(2, ["p4", "p5"]), (4, ["p3", "p6"])]);
+
+        let mut games = play(&first, &["p5", "p3"]);
+        let second = pair_elimination(&players, &games, 2, &settings).unwrap();
+        assert!(second.byes.is_empty());
+        assert_eq!(second.pairings[1].table, 2);
+        let second_games = play(&second, &["p5", "p2"]);
+        assert!(met(&second_games, "p1", "p5") && met(&second_games, "p2", "p3"));
+
+        // Had p3 withdrawn after winning their first game, p2 would go through.
+        players.retain(|player| player.id != "p3");
+        let without_p3 = pair_elimination(&players, &games, 2, &settings).unwrap();
+        assert_eq!(
+            without_p3.byes,
+            [Bye {
+                table: 2,
+                player: "p2".to_string()
+            }]
+        );
+        assert_eq!(without_p3.pairings.len(), 1);
+
+        games.extend(second_games);
+        let final_round = pair_elimination(&players, &games, 3, &settings).unwrap();
+        assert!(met(&play(&final_round, &[]), "p2", "p5"));
+        games.extend(play(&final_round, &["p2"]));
+        assert!(matches!(
+            pair_elimination(&players, &games, 4, &settings),
+            Err(TournamentError::Finished)
+        ));
+    }
+
+    #[test]
+    fn double_elimination_replays_a_lost_final() {
+        let players = entrants(&["p1", "p2", "p3", "p4"]);
+        let settings = EliminationSettings { double: true };
+        let mut games = Vec::new();
+        let mut pair_and_play = |round: u32, winners: &[&str]| {
+            let paired = pair_elimination(&players, &games, round, &settings).unwrap();
+            let mut round_games = play(&paired, winners);
+            round_games.sort_by_key(|game| game.table);
+            games.extend(round_games.clone());
+            round_games
+                .iter()
+                .map(|game| (game.black.clone(), game.white.clone()))
+                .collect::<Vec<_>>()
+        };
+        let pairs = |round: Vec<(String, Option<String>)>| {
+            round
+                .into_iter()
+                .map(|(black, white)| {
+                    let mut pair = [Some(black), white];
+                    pair.sort();
+                    pair.map(Option::unwrap_or_default)
+                })
+                .collect::<Vec<_>>()
+        };
+        let names = |list: &[[&str; 2]]| {
+            list.iter()
+                .map(|pair| pair.map(str::to_string))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            pairs(pair_and_play(1, &["p1", "p2"])),
+            names(&[["p1", "p4"], ["p2", "p3"]])
+        );
+        // The winners meet, and so do the players who lost once.
+        assert_eq!(
+            pairs(pair_and_play(2, &["p1", "p3"])),
+            names(&[["p1", "p2"], ["p3", "p4"]])
+        );
+        // The unbeaten p1 waits for the losers' bracket.
+        assert_eq!(pairs(pair_and_play(3, &["p3"])), names(&[["p2", "p3"]]));
+        // p3 wins the final, so it is replayed with one loss each.
+        assert_eq!(pairs(pair_and_play(4, &["p3"])), names(&[["p1", "p3"]]));
+        assert_eq!(pairs(pair_and_play(5, &["p1"])), names(&[["p1", "p3"]]));
+        assert!(matches!(
+            pair_elimination(&players, &games, 6, &settings),
+            Err(TournamentError::Finished)
+        ));
+
+        let drawn = [game(1, "p1", "p4", "0"), game(1, "p2", "p3", "B+R")];
+        assert!(matches!(
+            pair_elimination(&players, &drawn, 2, &settings),
+            Err(TournamentError::NoWinner(..))
+        ));
+    }
+
+    fn places(table: &[Standing]) -> Vec<(u32, &str)> {
+        table
+            .iter()
+            .map(|standing| (standing.place, standing.player.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn standings_count_forfeits_and_jigo_but_only_games_for_sos() {
+        let players = entrants(&["a", "b", "c", "d"]);
+        let games = [
+            game(1, "a", "b", "B+R"),
+            game(1, "c", "d", "B+F"),
+            game(2, "a", "c", "0"),
+            game(2, "d", "b", "W+3.5"),
+        ];
+        let settings = PairingSettings::default();
+        let all = [
+            TieBreaker::Points,
+            TieBreaker::Sos,
+            TieBreaker::Sosos,
+            TieBreaker::Sodos,
+            TieBreaker::Cumulative,
+        ];
+        let table = standings(&players, &games, 2, &settings, &all);
+        assert_eq!(places(&table), [(1, "a"), (2, "c"), (3, "b"), (4, "d")]);
+        // c's forfeit win counts, but d is not one of c's opponents.
+        assert_eq!(table[0].tie_breakers, [1.5, 2.5, 3.0, 1.75, 2.5]);
+        assert_eq!(table[1].tie_breakers, [1.5, 1.5, 2.5, 0.75, 2.5]);
+        assert_eq!(table[3].tie_breakers[1], 1.0);
+        let forfeit = &table[1].rounds[0];
+        assert_eq!(forfeit.opponent.as_deref(), Some("d"));
+        assert_eq!(forfeit.color, Some(Color::Black));
+        assert_eq!(
+            forfeit.result,
+            Some(RoundResult::Forfeit(Some(Color::Black)))
+        );
+        assert_eq!(forfeit.points, Some(1.0));
+
+        // c is not paired in round 3 and keeps their score for it.
+        let mut later = games.to_vec();
+        later.push(game(3, "b", "a", "B+R"));
+        let table = standings(&players, &later, 3, &settings, &[TieBreaker::Cumulative]);
+        let c = table
+            .iter()
+            .find(|standing| standing.player == "c")
+            .unwrap();
+        assert_eq!(c.tie_breakers, [4.0]);
+
+        // The jigo between a and c leaves them tied.
+        let direct = [TieBreaker::Points, TieBreaker::DirectEncounter];
+        let table = standings(&players, &games, 2, &settings, &direct);
+        assert_eq!(places(&table), [(1, "a"), (1, "c"), (3, "b"), (4, "d")]);
+        let after_first = standings(&players, &games, 1, &settings, &direct);
+        assert_eq!(after_first[0].points, 1.0);
+        assert!(after_first
+            .iter()
+            .all(|standing| standing.rounds.len() == 1));
+    }
+
+    #[test]
+    fn standings_break_ties_by_direct_encounter() {
+        let players = entrants(&["p", "q", "r", "s"]);
+        let games = [
+            game(1, "p", "q", "B+R"),
+            game(1, "r", "s", "B+R"),
+            game(2, "r", "p", "B+R"),
+            game(2, "q", "s", "B+R"),
+        ];
+        let criteria = [TieBreaker::Points, TieBreaker::DirectEncounter];
+        let table = standings(&players, &games, 2, &PairingSettings::default(), &criteria);
+        assert_eq!(places(&table), [(1, "r"), (2, "p"), (3, "q"), (4, "s")]);
+        assert_eq!(table[1].tie_breakers, [1.0, 1.0]);
+        assert_eq!(table[2].tie_breakers, [1.0, 0.0]);
+    }
+
+    #[test]
+    fn mcmahon_standings_start_from_the_rank_and_score_byes() {
+        let players = ranked(&[("x", "1d"), ("y", "5k"), ("z", "5k")]);
+        let settings = PairingSettings::McMahon(McMahonSettings {
+            bar: Rank::Dan(1),
+            floor: Rank::Kyu(10),
+            ..McMahonSettings::default()
+        });
+        let games = [
+            RoundGame {
+                round: 1,
+                table: 2,
+                black: "x".to_string(),
+                white: None,
+                handicap: 0,
+                result: None,
+            },
+            game(1, "z", "y", "W+R"),
+        ];
+        let table = standings(
+            &players,
+            &games,
+            1,
+            &settings,
+            &settings.default_tie_breakers(),
+        );
+        assert_eq!(places(&table), [(1, "x"), (2, "y"), (3, "z")]);
+        assert_eq!((table[0].points, table[0].mms), (1.0, 11.0));
+        assert!(table[0].rounds[0].bye);
+        // The bye counts as a game against x's own starting score.
+        assert_eq!(table[0].tie_breakers[1], 10.0);
+        assert_eq!(table[1].tie_breakers[..2], [6.0, 5.0]);
+    }
+
+    fn results_player(id: &str, name: &str, rank: &str) -> ResultsPlayer {
+        ResultsPlayer {
+            id: id.to_string(),
+            name: name.to_string(),
+            rank: rank.parse().unwrap(),
+            country: Some("NL".to_string()),
+            club: Some("Amsterdam".to_string()),
+            federation_id: None,
+        }
+    }
+
+    fn exported_event() -> (ResultsInfo, Vec<ResultsPlayer>, Vec<Standing>) {
+        let players = entrants(&["a", "b", "c", "d", "e"]);
+        let games = [
+            game(1, "a", "b", "B+R"),
+            RoundGame {
+                handicap: 2,
+                ..game(1, "d", "c", "W+3.5")
+            },
+            RoundGame {
+                round: 1,
+                table: 3,
+                black: "e".to_string(),
+                white: None,
+                handicap: 0,
+                result: None,
+            },
+            game(2, "c", "a", "0"),
+            game(2, "b", "e", "W+F"),
+        ];
+        let settings = PairingSettings::default();
+        let criteria = [TieBreaker::Points, TieBreaker::Sos];
+        let table = standings(&players, &games, 2, &settings, &criteria);
+        let details = vec![
+            results_player("a", "Ann Smith", "2d"),
+            results_player("b", "Bo Lee", "1k"),
+            ResultsPlayer {
+                federation_id: Some("12345".to_string()),
+                ..results_player("c", "Carl van Dam", "3k")
+            },
+            results_player("d", "Dee", "5k"),
+            ResultsPlayer {
+                country: None,
+                club: None,
+                ..results_player("e", "Eve Stone", "10k")
+            },
+        ];
+        let info = ResultsInfo {
+            name: "Club Cup".to_string(),
+            location: Some("Amsterdam".to_string()),
+            start_date: Some("2024-03-02".to_string()),
+            end_date: None,
+            komi: 6.5,
+            ruleset: "Japanese".to_string(),
+        };
+        (info, details, table)
+    }
+
+    #[test]
+    fn egd_results_list_rounds_with_colors_and_handicaps() {
+        let (info, players, table) = exported_event();
+        let criteria = [TieBreaker::Points, TieBreaker::Sos];
+        let egd = egd_results(&info, &players, &table, &criteria, 2);
+        let lines: Vec<&str> = egd.lines().collect();
+        assert_eq!(
+            lines[..4],
+            [
+                "; EV[Club Cup]",
+                "; PC[Amsterdam]",
+                "; DT[2024-03-02]",
+                "; KM[6.5]"
+            ]
+        );
+        let rows: Vec<Vec<&str>> = lines
+            .iter()
+            .filter(|line| !line.starts_with(';'))
+            .map(|line| line.split_whitespace().collect())
+            .collect();
+        let order: Vec<&str> = rows.iter().map(|row| row[1]).collect();
+        assert_eq!(order, ["Stone", "Smith", "Dam", "Lee", "Dee"]);
+        // e's bye and forfeit win earn points but no SOS, and are not colored.
+        assert_eq!(
+            rows[0],
+            ["1", "Stone", "Eve", "10k", "-", "-", "2", "0", "0+", "4+!"]
+        );
+        assert_eq!(
+            rows[1][2..],
+            ["Ann", "2d", "NL", "Amst", "1.5", "1.5", "4+b", "3=w"]
+        );
+        // c played White against a 5k with two stones.
+        assert_eq!(
+            rows[2][2..],
+            ["Carl_van", "3k", "NL", "Amst", "1.5", "1.5", "5+w2", "2=b"]
+        );
+        assert_eq!(rows[3][8..], ["2-w", "1-!"]);
+    }
+
+    #[test]
+    fn aga_results_rate_only_games_won_on_the_board() {
+        let (info, players, table) = exported_event();
+        let aga = aga_results(&info, &players, &table);
+        let lines: Vec<&str> = aga.lines().map(str::trim).collect();
+        assert_eq!(
+            lines[..4],
+            [
+                "TOURNEY Club Cup, Amsterdam",
+                "start=2024-03-02",
+                "finish=2024-03-02",
+                "rules=Japanese"
+            ]
+        );
+        assert!(lines.contains(&"TMP1 Smith, Ann 2d CLUB=Amsterdam COUNTRY=NL"));
+        assert!(lines.contains(&"12345 Dam, Carl van 3k CLUB=Amsterdam COUNTRY=NL"));
+        assert!(lines.contains(&"TMP4 Stone, Eve 10k"));
+        let games = &lines[lines.iter().position(|line| *line == "GAMES").unwrap() + 1..];
+        assert_eq!(
+            games,
+            [
+                "# Round 1",
+                "12345 TMP3 W 2 0",
+                "TMP2 TMP1 B 0 6.5",
+                "# Round 2",
+                "# TMP1 12345 0 not rated",
+                "# TMP4 TMP2 W+F not rated",
+            ]
+        );
+    }
+
+    #[test]
+    fn ranks_serialize_as_strings() {
+        assert_eq!(
+            serde_json::to_string(&Rank::Dan(4)).unwrap(),
+            "\"4d\"".to_string()
+        );
+        assert_eq!(
+            serde_json::from_str::<Rank>("\"15k\"").unwrap(),
+            Rank::Kyu(15)
+        );
+        assert!(serde_json::from_str::<Rank>("\"40k\"").is_err());
+    }
+}
@@ -0,0 +1,159 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::TournamentError,
+    pairing::{a_takes_black, build_round, match_seats, Entrant, Round, Seat},
+    rank::Rank,
+    record::{records, PlayerRecord, RoundGame},
+    swiss::check_entrants,
+};
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct McMahonSettings {
+    /// Players of this rank and above all start in the top group.
+    pub bar: Rank,
+    /// Players of this rank and below all start in the bottom group, with a McMahon score
+    /// of 0.
+    pub floor: Rank,
+    /// Points for sitting out a round.
+    pub bye_points: f64,
+    /// How games between players of different ranks are handicapped; even games when
+    /// `None`.
+    pub handicap: Option<HandicapRules>,
+}
+
+impl Default for McMahonSettings {
+    fn default() -> Self {
+        Self {
+            bar: Rank::Dan(1),
+            floor: Rank::Kyu(20),
+            bye_points: 1.0,
+            handicap: None,
+        }
+    }
+}
+
+/// Handicap from the rank difference, as in "rank difference minus one": a difference of
+/// `reduction` grades or less is an even game. Ranks above the bar and below the floor
+/// count as the bar and the floor.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HandicapRules {
+    pub reduction: u8,
+    pub max_handicap: u8,
+}
+
+impl Default for HandicapRules {
+    fn default() -> Self {
+        Self {
+            reduction: 1,
+            max_handicap: 9,
+        }
+    }
+}
+
+impl HandicapRules {
+    /// Stones for a game between players of these grades. A handicap of 1 is a game
+    /// without komi.
+    pub fn handicap(&self, a_grade: f64, b_grade: f64) -> u8 {
+        let difference = (a_grade - b_grade).abs() - f64::from(self.reduction);
+        if difference < 1.0 {
+            return 0;
+        }
+        difference.min(f64::from(self.max_handicap)) as u8
+    }
+}
+
+impl McMahonSettings {
+    /// The grade a rank counts as, between the floor and the bar.
+    fn grade(&self, rank: Rank) -> f64 {
+        let (floor, bar) = (self.floor.grade(), self.bar.grade().max(self.floor.grade()));
+        rank.grade().clamp(floor, bar).floor()
+    }
+
+    /// McMahon score a player of `rank` starts with: grades above the floor, up to the bar.
+    pub fn initial_score(&self, rank: Rank) -> f64 {
+        self.grade(rank) - self.floor.grade().floor()
+    }
+}
+
+/// The rank an entrant is paired by: their rank, or the one their rating suggests.
+pub(crate) fn entrant_rank(entrant: &Entrant) -> Rank {
+    entrant
+        .rank
+        .unwrap_or_else(|| Rank::from_rating(entrant.rating))
+}
+
+/// Pair round `round` of a McMahon tournament between `entrants`. Players start with a
+/// McMahon score from their rank and are paired within groups of equal McMahon score
+/// (initial score plus points) like in Swiss, floating between neighbouring groups.
+///
+/// In a handicap game the weaker player takes Black; even games follow the Swiss color
+/// rules.
+pub fn pair_mcmahon(
+    entrants: &[Entrant],
+    games: &[RoundGame],
+    round: u32,
+    settings: &McMahonSettings,
+) -> Result<Round, TournamentError> {
+    check_entrants(entrants)?;
+    if settings.bar < settings.floor {
+        return Err(TournamentError::InvalidSettings(format!(
+            "the McMahon bar {} is below the floor {}",
+            settings.bar, settings.floor
+        )));
+    }
+    // Withdrawn players are not entrants; for the floats of old rounds they count as
+    // starting from 0.
+    let start = |id: &str| {
+        entrants
+            .iter()
+            .find(|entrant| entrant.id == id)
+            .map_or(0.0, |entrant| settings.initial_score(entrant_rank(entrant)))
+    };
+    let records = records(games, round, settings.bye_points, start);
+    let empty = PlayerRecord::default();
+    let mms = |entrant: &Entrant| {
+        let points = records.get(&entrant.id).map_or(0.0, |r| r.points);
+        settings.initial_score(entrant_rank(entrant)) + points
+    };
+    let mut ranked: Vec<&Entrant> = entrants.iter().collect();
+    ranked.sort_by(|a, b| {
+        mms(b)
+            .total_cmp(&mms(a))
+            .then(entrant_rank(b).cmp(&entrant_rank(a)))
+            .then(b.rating.total_cmp(&a.rating))
+            .then(a.id.cmp(&b.id))
+    });
+    let seats: Vec<Seat<'_>> = ranked
+        .iter()
+        .map(|entrant| Seat {
+            id: &entrant.id,
+            score: mms(entrant),
+            record: records.get(&entrant.id).unwrap_or(&empty),
+        })
+        .collect();
+    let matching = match_seats(&seats);
+    let paired = build_round(round, &seats, matching, |upper, lower, table| {
+        let (upper_grade, lower_grade) = (
+            settings.grade(entrant_rank(ranked[upper])),
+            settings.grade(entrant_rank(ranked[lower])),
+        );
+        let handicap = settings
+            .handicap
+            .map_or(0, |rules| rules.handicap(upper_grade, lower_grade));
+        if handicap > 0 {
+            if upper_grade > lower_grade {
+                (lower, upper, handicap)
+            } else {
+                (upper, lower, handicap)
+            }
+        } else if a_takes_black(&seats[upper], &seats[lower], table) {
+            (upper, lower, 0)
+        } else {
+            (lower, upper, 0)
+        }
+    });
+    Ok(paired)
+}
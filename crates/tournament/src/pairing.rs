@@ -0,0 +1,423 @@
+use go_rules::Color;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    elimination::EliminationSettings,
+    mcmahon::{entrant_rank, McMahonSettings},
+    rank::Rank,
+    record::{Float, PlayerRecord},
+    round_robin::RoundRobinSettings,
+    standings::TieBreaker,
+    swiss::SwissSettings,
+};
+
+/// Search steps allowed before settling for the best pairing found so far.
+const SEARCH_BUDGET: u32 = 200_000;
+/// Cost of pairing across one point of score difference.
+const SCORE_GAP_COST: f64 = 1000.0;
+/// Cost of floating a player who floated the same way last round.
+const REPEAT_FLOAT_COST: f64 = 300.0;
+/// Cost per place that a floater is from the edge of their score group.
+const FLOAT_PLACE_COST: f64 = 20.0;
+/// Cost per place that an opponent is from the fold of the score group.
+const FOLD_COST: f64 = 10.0;
+/// Cost of pairing two players from the same half of their score group.
+const HALF_EXCHANGE_COST: f64 = 20.0;
+/// Cost per step of color preference two players share.
+const COLOR_COST: f64 = 15.0;
+/// Cost of a rematch, once pairing without one has failed.
+const REMATCH_COST: f64 = 100_000.0;
+
+/// How a tournament pairs its rounds, stored in `tournaments.pairing_settings`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "system", rename_all = "snake_case")]
+pub enum PairingSettings {
+    Swiss(SwissSettings),
+    #[serde(rename = "mcmahon")]
+    McMahon(McMahonSettings),
+    RoundRobin(RoundRobinSettings),
+    Elimination(EliminationSettings),
+}
+
+impl Default for PairingSettings {
+    fn default() -> Self {
+        PairingSettings::Swiss(SwissSettings::default())
+    }
+}
+
+impl PairingSettings {
+    /// Points for sitting out a round. Byes of round robins and knockouts earn nothing.
+    pub fn bye_points(&self) -> f64 {
+        match self {
+            PairingSettings::Swiss(settings) => settings.bye_points,
+            PairingSettings::McMahon(settings) => settings.bye_points,
+            PairingSettings::RoundRobin(_) | PairingSettings::Elimination(_) => 0.0,
+        }
+    }
+
+    /// The score a player starts the tournament with, which is only above 0 in McMahon.
+    pub fn initial_score(&self, entrant: &Entrant) -> f64 {
+        match self {
+            PairingSettings::McMahon(settings) => settings.initial_score(entrant_rank(entrant)),
+            _ => 0.0,
+        }
+    }
+
+    /// The usual order of standings for the system.
+    pub fn default_tie_breakers(&self) -> Vec<TieBreaker> {
+        match self {
+            PairingSettings::Swiss(_) => vec![
+                TieBreaker::Points,
+                TieBreaker::Sos,
+                TieBreaker::Sosos,
+                TieBreaker::DirectEncounter,
+            ],
+            PairingSettings::McMahon(_) => vec![
+                TieBreaker::Mms,
+                TieBreaker::Sos,
+                TieBreaker::Sosos,
+                TieBreaker::DirectEncounter,
+            ],
+            PairingSettings::RoundRobin(_) => vec![
+                TieBreaker::Points,
+                TieBreaker::DirectEncounter,
+                TieBreaker::Sodos,
+            ],
+            PairingSettings::Elimination(_) => vec![TieBreaker::Points, TieBreaker::Sos],
+        }
+    }
+}
+
+/// A player taking part in the round being paired.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Entrant {
+    pub id: String,
+    pub rating: f64,
+    /// The rank McMahon starts the player from; their rating's rank when `None`.
+    #[serde(default)]
+    pub rank: Option<Rank>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Pairing {
+    /// Tables are numbered from 1, strongest pairing first.
+    pub table: u32,
+    pub black: String,
+    pub white: String,
+    pub handicap: u8,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerFloat {
+    pub player: String,
+    pub direction: Float,
+}
+
+/// A player who sits out a round, or advances without a game in a knockout.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Bye {
+    /// The table the player would have played at; it keeps their place in a bracket.
+    pub table: u32,
+    pub player: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Round {
+    pub round: u32,
+    pub pairings: Vec<Pairing>,
+    pub byes: Vec<Bye>,
+    pub floats: Vec<PlayerFloat>,
+    /// Whether some players had to meet again because no pairing avoided it.
+    pub rematches: bool,
+}
+
+/// Entrants strongest first by rating, then by id, for systems that seed players.
+pub(crate) fn seeded(entrants: &[Entrant]) -> Vec<&Entrant> {
+    let mut seeds: Vec<&Entrant> = entrants.iter().collect();
+    seeds.sort_by(|a, b| b.rating.total_cmp(&a.rating).then(a.id.cmp(&b.id)));
+    seeds
+}
+
+/// A player ready to be paired, ordered strongest first by the pairing system.
+pub(crate) struct Seat<'a> {
+    pub id: &'a str,
+    /// The score players are grouped by: points in Swiss, McMahon score in McMahon.
+    pub score: f64,
+    pub record: &'a PlayerRecord,
+}
+
+/// Pairs of seat indexes, and the seat given a bye when the count is odd.
+pub(crate) struct Matching {
+    pub pairs: Vec<(usize, usize)>,
+    pub bye: Option<usize>,
+    pub rematches: bool,
+}
+
+/// Pair `seats`, which must be sorted strongest first. Players are paired within their score
+/// group where they can be, folding the top half of the group against the bottom half, and
+/// otherwise float to the neighbouring group. Rematches are only allowed when there is no
+/// other way. With an odd count the weakest player with the fewest byes sits out.
+pub(crate) fn match_seats(seats: &[Seat<'_>]) -> Matching {
+    let mut budget = SEARCH_BUDGET;
+    let bye_candidates: Vec<Option<usize>> = if seats.len().is_multiple_of(2) {
+        vec![None]
+    } else {
+        let mut order: Vec<usize> = (0..seats.len()).rev().collect();
+        order.sort_by_key(|&index| seats[index].record.byes);
+        order.into_iter().map(Some).collect()
+    };
+    for &bye in &bye_candidates {
+        let active: Vec<usize> = (0..seats.len()).filter(|&i| Some(i) != bye).collect();
+        if let Some(pairs) = Search::new(seats, &active, false).run(&mut budget) {
+            return Matching {
+                pairs,
+                bye,
+                rematches: false,
+            };
+        }
+        if budget == 0 {
+            break;
+        }
+    }
+    let bye = bye_candidates[0];
+    let active: Vec<usize> = (0..seats.len()).filter(|&i| Some(i) != bye).collect();
+    let mut budget = SEARCH_BUDGET;
+    let pairs = Search::new(seats, &active, true)
+        .run(&mut budget)
+        .expect("any even number of players can be paired when rematches are allowed");
+    Matching {
+        pairs,
+        bye,
+        rematches: true,
+    }
+}
+
+/// Branch and bound over the pairings of `active`, which pairs the strongest unpaired player
+/// first and tries opponents cheapest first, so that the first complete pairing is already a
+/// good one.
+struct Search {
+    active: Vec<usize>,
+    /// Cost of pairing two positions of `active`, or `None` when they may not meet.
+    costs: Vec<Vec<Option<f64>>>,
+    best: Option<(f64, Vec<(usize, usize)>)>,
+}
+
+impl Search {
+    fn new(seats: &[Seat<'_>], active: &[usize], allow_rematches: bool) -> Self {
+        let places = group_places(seats, active);
+        let costs = (0..active.len())
+            .map(|a| {
+                (0..active.len())
+                    .map(|b| {
+                        if a == b {
+                            return None;
+                        }
+                        let (upper, lower) = (a.min(b), a.max(b));
+                        pair_cost(seats, active, &places, upper, lower, allow_rematches)
+                    })
+                    .collect()
+            })
+            .collect();
+        Self {
+            active: active.to_vec(),
+            costs,
+            best: None,
+        }
+    }
+
+    fn run(mut self, budget: &mut u32) -> Option<Vec<(usize, usize)>> {
+        let mut paired = vec![false; self.active.len()];
+        let mut pairs = Vec::with_capacity(self.active.len() / 2);
+        self.search(&mut paired, &mut pairs, 0.0, budget);
+        let active = self.active;
+        self.best.map(|(_, pairs)| {
+            pairs
+                .into_iter()
+                .map(|(a, b)| (active[a], active[b]))
+                .collect()
+        })
+    }
+
+    fn search(
+        &mut self,
+        paired: &mut [bool],
+        pairs: &mut Vec<(usize, usize)>,
+        cost: f64,
+        budget: &mut u32,
+    ) {
+        let Some(lead) = paired.iter().position(|done| !done) else {
+            if self.best.as_ref().is_none_or(|(best, _)| cost < *best) {
+                self.best = Some((cost, pairs.clone()));
+            }
+            return;
+        };
+        let mut options: Vec<(f64, usize)> = (lead + 1..paired.len())
+            .filter(|&other| !paired[other])
+            .filter_map(|other| self.costs[lead][other].map(|cost| (cost, other)))
+            .collect();
+        options.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+        paired[lead] = true;
+        for (step, other) in options {
+            if *budget == 0 {
+                break;
+            }
+            *budget -= 1;
+            let total = cost + step;
+            if self.best.as_ref().is_some_and(|(best, _)| total >= *best) {
+                break;
+            }
+            paired[other] = true;
+            pairs.push((lead, other));
+            self.search(paired, pairs, total, budget);
+            pairs.pop();
+            paired[other] = false;
+        }
+        paired[lead] = false;
+    }
+}
+
+/// For each position of `active`: its place in its score group from the top, and the size
+/// of the group.
+fn group_places(seats: &[Seat<'_>], active: &[usize]) -> Vec<(usize, usize)> {
+    let mut places = Vec::with_capacity(active.len());
+    for group in active.chunk_by(|&a, &b| seats[a].score == seats[b].score) {
+        places.extend((0..group.len()).map(|place| (place, group.len())));
+    }
+    places
+}
+
+/// Cost of pairing the positions `upper` and `lower` of `active`.
+fn pair_cost(
+    seats: &[Seat<'_>],
+    active: &[usize],
+    places: &[(usize, usize)],
+    upper: usize,
+    lower: usize,
+    allow_rematches: bool,
+) -> Option<f64> {
+    let (a, b) = (&seats[active[upper]], &seats[active[lower]]);
+    let mut cost = 0.0;
+    if a.record.has_played(b.id) {
+        if !allow_rematches {
+            return None;
+        }
+        cost += REMATCH_COST;
+    }
+    let (a_place, a_size) = places[upper];
+    let b_place = places[lower].0;
+    let gap = a.score - b.score;
+    if gap > 0.0 {
+        // The lowest of the upper group floats down to meet the top of the lower group.
+        cost += SCORE_GAP_COST * gap;
+        cost += FLOAT_PLACE_COST * (a_size - 1 - a_place + b_place) as f64;
+        if a.record.last_float == Some(Float::Down) {
+            cost += REPEAT_FLOAT_COST;
+        }
+        if b.record.last_float == Some(Float::Up) {
+            cost += REPEAT_FLOAT_COST;
+        }
+    } else {
+        let fold = a_size / 2;
+        cost += FOLD_COST * (b_place - a_place).abs_diff(fold) as f64;
+        // Moving an opponent within the bottom half is better than pairing inside a half.
+        if (a_place < fold) == (b_place < fold) {
+            cost += HALF_EXCHANGE_COST;
+        }
+    }
+    if let (Some((a_color, a_strength)), Some((b_color, b_strength))) =
+        (preference(a.record), preference(b.record))
+    {
+        if a_color == b_color {
+            cost += COLOR_COST * f64::from(a_strength.min(b_strength));
+        }
+    }
+    Some(cost)
+}
+
+/// The color a player should have next and how much it matters: playing the same color
+/// three times or being two games out of balance is worse than a mild wish to alternate.
+fn preference(record: &PlayerRecord) -> Option<(Color, u8)> {
+    let balance = record.color_balance();
+    if balance != 0 {
+        let color = if balance > 0 {
+            Color::White
+        } else {
+            Color::Black
+        };
+        let strength = if balance.abs() >= 2 { 4 } else { 2 };
+        return Some((color, strength));
+    }
+    let last = *record.colors.last()?;
+    Some((last.opponent(), 1))
+}
+
+/// Whether the upper seat `a` takes Black against `b` in an even game at `table`: the player
+/// who has had Black less often takes it, then the one who had White last. Otherwise Black
+/// alternates between the lower seat on odd tables and the upper seat on even ones.
+pub(crate) fn a_takes_black(a: &Seat<'_>, b: &Seat<'_>, table: u32) -> bool {
+    let (a_balance, b_balance) = (a.record.color_balance(), b.record.color_balance());
+    if a_balance != b_balance {
+        return a_balance < b_balance;
+    }
+    let (a_last, b_last) = (a.record.colors.last(), b.record.colors.last());
+    if a_last != b_last {
+        return a_last == Some(&Color::White) || b_last == Some(&Color::Black);
+    }
+    table.is_multiple_of(2)
+}
+
+/// Turn a matching into a round, with tables in order of the upper seat. `seat_game` gives
+/// Black, White and the handicap for an upper and a lower seat at a table.
+pub(crate) fn build_round(
+    round: u32,
+    seats: &[Seat<'_>],
+    matching: Matching,
+    mut seat_game: impl FnMut(usize, usize, u32) -> (usize, usize, u8),
+) -> Round {
+    let mut pairs: Vec<(usize, usize)> = matching
+        .pairs
+        .into_iter()
+        .map(|(a, b)| (a.min(b), a.max(b)))
+        .collect();
+    pairs.sort();
+    let mut floats = Vec::new();
+    let mut pairings = Vec::with_capacity(pairs.len());
+    for (index, &(upper, lower)) in pairs.iter().enumerate() {
+        let table = index as u32 + 1;
+        if seats[upper].score > seats[lower].score {
+            for (seat, direction) in [(upper, Float::Down), (lower, Float::Up)] {
+                floats.push(PlayerFloat {
+                    player: seats[seat].id.to_string(),
+                    direction,
+                });
+            }
+        }
+        let (black, white, handicap) = seat_game(upper, lower, table);
+        pairings.push(Pairing {
+            table,
+            black: seats[black].id.to_string(),
+            white: seats[white].id.to_string(),
+            handicap,
+        });
+    }
+    let byes = matching
+        .bye
+        .map(|seat| Bye {
+            table: pairings.len() as u32 + 1,
+            player: seats[seat].id.to_string(),
+        })
+        .into_iter()
+        .collect();
+    Round {
+        round,
+        pairings,
+        byes,
+        floats,
+        rematches: matching.rematches,
+    }
+}
@@ -0,0 +1,163 @@
+use std::{cmp::Ordering, fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::TournamentError;
+
+/// Rating of a 30 kyu player, the bottom of the scale.
+const RATING_AT_30K: f64 = 525.0;
+/// Growth of the rating per grade: ratings are exponential in rank, as on OGS, so that the
+/// Glicko default of 1500 sits around 6 kyu and 1 dan around 1900.
+const RATING_PER_GRADE: f64 = 0.043;
+/// Professional ranks are a third of an amateur grade apart, starting just above 9 dan.
+const PRO_GRADE_STEP: f64 = 1.0 / 3.0;
+
+/// A player's rank: amateur kyu (30k–1k) and dan (1d–9d) grades, or a professional dan rank
+/// (1p–9p). Ranks order from 30k up to 9p and serialize as strings such as `"5k"`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Rank {
+    Kyu(u8),
+    Dan(u8),
+    Pro(u8),
+}
+
+impl Rank {
+    pub const WEAKEST: Rank = Rank::Kyu(30);
+    pub const STRONGEST_AMATEUR: Rank = Rank::Dan(9);
+
+    /// Check the number against the range of its kind.
+    pub fn new(rank: Rank) -> Result<Rank, TournamentError> {
+        let valid = match rank {
+            Rank::Kyu(kyu) => (1..=30).contains(&kyu),
+            Rank::Dan(dan) | Rank::Pro(dan) => (1..=9).contains(&dan),
+        };
+        if valid {
+            Ok(rank)
+        } else {
+            Err(TournamentError::InvalidRank(rank.to_string()))
+        }
+    }
+
+    /// Value stored in `players.rank`: kyu ranks are negative and dan ranks positive, as in
+    /// AGA ratings, with professional ranks from 11 (1p) to 19 (9p).
+    pub fn code(self) -> i64 {
+        match self {
+            Rank::Kyu(kyu) => -i64::from(kyu),
+            Rank::Dan(dan) => i64::from(dan),
+            Rank::Pro(dan) => 10 + i64::from(dan),
+        }
+    }
+
+    pub fn from_code(code: i64) -> Result<Rank, TournamentError> {
+        let rank = match code {
+            -30..=-1 => Rank::Kyu(code.unsigned_abs() as u8),
+            1..=9 => Rank::Dan(code as u8),
+            11..=19 => Rank::Pro((code - 10) as u8),
+            _ => return Err(TournamentError::InvalidRankCode(code)),
+        };
+        Ok(rank)
+    }
+
+    /// Position on a continuous scale where 30k is 0, 1k is 29, 1d is 30 and 9d is 38.
+    pub fn grade(self) -> f64 {
+        match self {
+            Rank::Kyu(kyu) => f64::from(30 - i32::from(kyu)),
+            Rank::Dan(dan) => f64::from(29 + i32::from(dan)),
+            Rank::Pro(dan) => 38.0 + f64::from(dan) * PRO_GRADE_STEP,
+        }
+    }
+
+    /// The amateur rank nearest to `grade`, between 30k and 9d.
+    pub fn from_grade(grade: f64) -> Rank {
+        if grade.is_nan() {
+            return Rank::WEAKEST;
+        }
+        let grade = grade.round().clamp(0.0, 38.0) as u8;
+        if grade < 30 {
+            Rank::Kyu(30 - grade)
+        } else {
+            Rank::Dan(grade - 29)
+        }
+    }
+
+    /// The rating a player of this rank is expected to have.
+    pub fn to_rating(self) -> f64 {
+        RATING_AT_30K * (RATING_PER_GRADE * self.grade()).exp()
+    }
+
+    /// The amateur rank matching `rating`. Professional ranks are awarded rather than
+    /// earned by rating, so strong ratings stop at 9d.
+    pub fn from_rating(rating: f64) -> Rank {
+        if rating <= 0.0 {
+            return Rank::WEAKEST;
+        }
+        Rank::from_grade((rating / RATING_AT_30K).ln() / RATING_PER_GRADE)
+    }
+}
+
+impl Ord for Rank {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.code().cmp(&other.code())
+    }
+}
+
+impl PartialOrd for Rank {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Rank {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rank::Kyu(kyu) => write!(f, "{kyu}k"),
+            Rank::Dan(dan) => write!(f, "{dan}d"),
+            Rank::Pro(dan) => write!(f, "{dan}p"),
+        }
+    }
+}
+
+impl FromStr for Rank {
+    type Err = TournamentError;
+
+    /// Accepts `5k`, `5 kyu`, `3D`, `3 dan`, `2p` and `2 pro`.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || TournamentError::InvalidRank(value.to_string());
+        let trimmed = value.trim().to_ascii_lowercase();
+        let digits = trimmed.chars().take_while(char::is_ascii_digit).count();
+        let number: u8 = trimmed[..digits].parse().map_err(|_| invalid())?;
+        let rank = match trimmed[digits..].trim() {
+            "k" | "kyu" => Rank::Kyu(number),
+            "d" | "dan" => Rank::Dan(number),
+            "p" | "pro" => Rank::Pro(number),
+            _ => return Err(invalid()),
+        };
+        Rank::new(rank).map_err(|_| invalid())
+    }
+}
+
+impl TryFrom<String> for Rank {
+    type Error = TournamentError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Rank> for String {
+    fn from(rank: Rank) -> Self {
+        rank.to_string()
+    }
+}
+
+/// `rating` moved up the rank scale by `grades`, which may be fractional or negative. Below
+/// 30k the scale is continued with the width of its bottom grade.
+pub(crate) fn adjust_rating(rating: f64, grades: f64) -> f64 {
+    let factor = (RATING_PER_GRADE * grades).exp();
+    if rating >= RATING_AT_30K {
+        rating * factor
+    } else {
+        rating + RATING_AT_30K * (factor - 1.0)
+    }
+}
@@ -0,0 +1,212 @@
+use std::{cmp::Ordering, collections::HashMap, fmt, str::FromStr};
+
+use go_rules::Color;
+use serde::{Deserialize, Serialize};
+
+use crate::error::TournamentError;
+
+/// The result of a tournament game, as stored in `pairings.result`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum RoundResult {
+    /// A game won on the board, by resignation, on time or by score.
+    Win(Color),
+    Jigo,
+    /// A game that was not played. `None` when both players lose it.
+    Forfeit(Option<Color>),
+}
+
+impl RoundResult {
+    /// Points the player of `color` earns from the game.
+    pub fn points(self, color: Color) -> f64 {
+        match self {
+            RoundResult::Win(winner) | RoundResult::Forfeit(Some(winner)) if winner == color => 1.0,
+            RoundResult::Jigo => 0.5,
+            _ => 0.0,
+        }
+    }
+
+    /// The color that won the game, on the board or by forfeit.
+    pub fn winner(self) -> Option<Color> {
+        match self {
+            RoundResult::Win(winner) | RoundResult::Forfeit(Some(winner)) => Some(winner),
+            RoundResult::Jigo | RoundResult::Forfeit(None) => None,
+        }
+    }
+
+    /// Whether the game was actually played. Forfeited games neither count as a meeting
+    /// of the two players nor as a color they played.
+    pub fn was_played(self) -> bool {
+        !matches!(self, RoundResult::Forfeit(_))
+    }
+}
+
+impl fmt::Display for RoundResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RoundResult::Win(Color::Black) => f.write_str("B+"),
+            RoundResult::Win(Color::White) => f.write_str("W+"),
+            RoundResult::Jigo => f.write_str("0"),
+            RoundResult::Forfeit(Some(Color::Black)) => f.write_str("B+F"),
+            RoundResult::Forfeit(Some(Color::White)) => f.write_str("W+F"),
+            RoundResult::Forfeit(None) => f.write_str("0+F"),
+        }
+    }
+}
+
+impl FromStr for RoundResult {
+    type Err = TournamentError;
+
+    /// Accepts SGF results such as `B+R`, `W+6.5` and `0`, forfeits `B+F`, `W+F` and
+    /// `0+F` for a double forfeit, and `Jigo` or `Draw`.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let result = value.trim().to_ascii_uppercase();
+        let (winner, reason) = match result.split_once('+') {
+            Some(("B", reason)) => (Color::Black, reason),
+            Some(("W", reason)) => (Color::White, reason),
+            Some(("0", "F")) => return Ok(RoundResult::Forfeit(None)),
+            None if matches!(result.as_str(), "0" | "JIGO" | "DRAW") => {
+                return Ok(RoundResult::Jigo)
+            }
+            _ => return Err(TournamentError::InvalidResult(value.to_string())),
+        };
+        if reason == "F" {
+            Ok(RoundResult::Forfeit(Some(winner)))
+        } else {
+            Ok(RoundResult::Win(winner))
+        }
+    }
+}
+
+impl TryFrom<String> for RoundResult {
+    type Error = TournamentError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<RoundResult> for String {
+    fn from(result: RoundResult) -> Self {
+        result.to_string()
+    }
+}
+
+/// A game of a tournament round, or a bye when there is no White player.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoundGame {
+    pub round: u32,
+    pub table: u32,
+    pub black: String,
+    pub white: Option<String>,
+    pub handicap: u8,
+    /// `None` while the game is being played.
+    pub result: Option<RoundResult>,
+}
+
+/// A player moved out of their score group to be paired.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Float {
+    /// Paired against a lower score.
+    Down,
+    /// Paired against a higher score.
+    Up,
+}
+
+/// A player's tournament so far, as pairing sees it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PlayerRecord {
+    pub points: f64,
+    /// Opponents actually played, in round order.
+    pub opponents: Vec<String>,
+    /// Colors actually played, in round order.
+    pub colors: Vec<Color>,
+    pub byes: u32,
+    /// The float of the player's most recent round, if they floated.
+    pub last_float: Option<Float>,
+}
+
+impl PlayerRecord {
+    pub fn has_played(&self, opponent: &str) -> bool {
+        self.opponents.iter().any(|id| id == opponent)
+    }
+
+    /// Games played with Black minus games played with White.
+    pub fn color_balance(&self) -> i32 {
+        self.colors
+            .iter()
+            .map(|color| match color {
+                Color::Black => 1,
+                Color::White => -1,
+            })
+            .sum()
+    }
+}
+
+/// Every player's record from the games of rounds before `round`. A bye is worth
+/// `bye_points`, and floats compare `start` plus the points before each round.
+pub fn records(
+    games: &[RoundGame],
+    round: u32,
+    bye_points: f64,
+    start: impl Fn(&str) -> f64,
+) -> HashMap<String, PlayerRecord> {
+    let mut earlier: Vec<&RoundGame> = games.iter().filter(|game| game.round < round).collect();
+    earlier.sort_by_key(|game| game.round);
+    let mut records: HashMap<String, PlayerRecord> = HashMap::new();
+    for round_games in earlier.chunk_by(|a, b| a.round == b.round) {
+        let before: HashMap<&str, f64> = round_games
+            .iter()
+            .flat_map(|game| [Some(&game.black), game.white.as_ref()])
+            .flatten()
+            .map(|id| {
+                let points = records.get(id).map_or(0.0, |record| record.points);
+                (id.as_str(), start(id) + points)
+            })
+            .collect();
+        for game in round_games {
+            let Some(white) = &game.white else {
+                let record = records.entry(game.black.clone()).or_default();
+                record.points += bye_points;
+                record.byes += 1;
+                record.last_float = None;
+                continue;
+            };
+            let played = game.result.is_none_or(RoundResult::was_played);
+            let (black_before, white_before) =
+                (before[game.black.as_str()], before[white.as_str()]);
+            for (player, opponent, color, float) in [
+                (
+                    &game.black,
+                    white,
+                    Color::Black,
+                    black_before.total_cmp(&white_before),
+                ),
+                (
+                    white,
+                    &game.black,
+                    Color::White,
+                    white_before.total_cmp(&black_before),
+                ),
+            ] {
+                let record = records.entry(player.clone()).or_default();
+                if let Some(result) = game.result {
+                    record.points += result.points(color);
+                }
+                record.last_float = None;
+                if played {
+                    record.opponents.push(opponent.clone());
+                    record.colors.push(color);
+                    record.last_float = match float {
+                        Ordering::Greater => Some(Float::Down),
+                        Ordering::Less => Some(Float::Up),
+                        Ordering::Equal => None,
+                    };
+                }
+            }
+        }
+    }
+    records
+}
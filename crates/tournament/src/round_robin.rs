@@ -0,0 +1,87 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::TournamentError,
+    pairing::{seeded, Bye, Entrant, Pairing, Round},
+    swiss::check_entrants,
+};
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RoundRobinSettings {
+    /// Play everyone twice, with colors swapped in the second cycle.
+    pub double: bool,
+}
+
+/// The rounds of a Berger table for `players` seats, each a list of seat pairs with the
+/// seat that moves first listed first. With an odd count, the seat paired with the extra
+/// seat `players` has a bye.
+pub fn berger_tables(players: usize) -> Vec<Vec<(usize, usize)>> {
+    let seats = players + players % 2;
+    let last = seats.saturating_sub(1);
+    (0..last)
+        .map(|index| {
+            // Successive Berger rounds turn the circle by half the table.
+            let turn = index * seats / 2 % last;
+            let mut pairs = Vec::with_capacity(seats / 2);
+            pairs.push(if index.is_multiple_of(2) {
+                (turn, last)
+            } else {
+                (last, turn)
+            });
+            pairs.extend(
+                (1..seats / 2)
+                    .map(|offset| ((turn + offset) % last, (turn + last - offset) % last)),
+            );
+            pairs
+        })
+        .collect()
+}
+
+/// Every round of a round robin between `entrants`, seeded by rating. The player listed
+/// first in the Berger table takes Black. A double round robin repeats the schedule with
+/// colors swapped. All games are even.
+pub fn schedule_round_robin(
+    entrants: &[Entrant],
+    settings: &RoundRobinSettings,
+) -> Result<Vec<Round>, TournamentError> {
+    check_entrants(entrants)?;
+    let seeds = seeded(entrants);
+    let tables = berger_tables(seeds.len());
+    let cycles = if settings.double { 2 } else { 1 };
+    let mut rounds = Vec::with_capacity(tables.len() * cycles);
+    for cycle in 0..cycles {
+        for pairs in &tables {
+            let mut round = Round {
+                round: rounds.len() as u32 + 1,
+                ..Round::default()
+            };
+            let mut bye = None;
+            for &(first, second) in pairs {
+                match (seeds.get(first), seeds.get(second)) {
+                    (Some(first), Some(second)) => {
+                        let (black, white) = if cycle == 0 {
+                            (first, second)
+                        } else {
+                            (second, first)
+                        };
+                        round.pairings.push(Pairing {
+                            table: round.pairings.len() as u32 + 1,
+                            black: black.id.clone(),
+                            white: white.id.clone(),
+                            handicap: 0,
+                        });
+                    }
+                    (Some(player), None) | (None, Some(player)) => bye = Some(player),
+                    (None, None) => {}
+                }
+            }
+            round.byes.extend(bye.map(|player| Bye {
+                table: round.pairings.len() as u32 + 1,
+                player: player.id.clone(),
+            }));
+            rounds.push(round);
+        }
+    }
+    Ok(rounds)
+}
@@ -0,0 +1,225 @@
+use std::collections::HashMap;
+
+use go_rules::Color;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    pairing::{Entrant, PairingSettings},
+    record::{RoundGame, RoundResult},
+};
+
+/// A criterion players are ordered by, listed from the most important.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TieBreaker {
+    /// Points from wins, jigo and byes.
+    Points,
+    /// McMahon score: the starting score from the rank plus points.
+    Mms,
+    /// Sum of the opponents' scores.
+    Sos,
+    /// Sum of the opponents' SOS.
+    Sosos,
+    /// Sum of the scores of the opponents the player beat, half for jigo.
+    Sodos,
+    /// Sum of the player's score after each round.
+    Cumulative,
+    /// Points from the games between the players still tied.
+    DirectEncounter,
+}
+
+/// What a player did in one round.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StandingRound {
+    pub round: u32,
+    /// `None` for a bye or a round the player was not paired in.
+    pub opponent: Option<String>,
+    pub color: Option<Color>,
+    pub handicap: u8,
+    pub bye: bool,
+    /// `None` while the game is being played.
+    pub result: Option<RoundResult>,
+    /// Points earned in the round, once known.
+    pub points: Option<f64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Standing {
+    /// Places start at 1. Players tied on every criterion share a place.
+    pub place: u32,
+    pub player: String,
+    pub points: f64,
+    pub mms: f64,
+    /// The values of the requested criteria, in order.
+    pub tie_breakers: Vec<f64>,
+    pub rounds: Vec<StandingRound>,
+}
+
+/// Standings of `entrants` after round `round`, counting every game with a result up to
+/// it, ordered by `criteria`. Scores are McMahon scores in a McMahon tournament and points
+/// otherwise.
+///
+/// Byes and forfeits earn their points but are not games on the board: for SOS they count
+/// as a game against an opponent with the player's own starting score, and they add
+/// nothing to SOSOS or SODOS.
+pub fn standings(
+    entrants: &[Entrant],
+    games: &[RoundGame],
+    round: u32,
+    settings: &PairingSettings,
+    criteria: &[TieBreaker],
+) -> Vec<Standing> {
+    let mut entrants: Vec<&Entrant> = entrants.iter().collect();
+    entrants.sort_by(|a, b| a.id.cmp(&b.id));
+    let index: HashMap<&str, usize> = entrants
+        .iter()
+        .enumerate()
+        .map(|(index, entrant)| (entrant.id.as_str(), index))
+        .collect();
+    let start: Vec<f64> = entrants
+        .iter()
+        .map(|entrant| settings.initial_score(entrant))
+        .collect();
+
+    let mut rounds: Vec<Vec<StandingRound>> = vec![Vec::new(); entrants.len()];
+    let mut counted: Vec<&RoundGame> = games.iter().filter(|game| game.round <= round).collect();
+    counted.sort_by_key(|game| (game.round, game.table));
+    let played_rounds = counted.last().map_or(0, |game| game.round);
+    for game in counted {
+        let Some(white) = &game.white else {
+            if let Some(&player) = index.get(game.black.as_str()) {
+                rounds[player].push(StandingRound {
+                    round: game.round,
+                    opponent: None,
+                    color: None,
+                    handicap: 0,
+                    bye: true,
+                    result: None,
+                    points: Some(settings.bye_points()),
+                });
+            }
+            continue;
+        };
+        for (player, opponent, color) in [
+            (&game.black, white, Color::Black),
+            (white, &game.black, Color::White),
+        ] {
+            if let Some(&player) = index.get(player.as_str()) {
+                rounds[player].push(StandingRound {
+                    round: game.round,
+                    opponent: Some(opponent.clone()),
+                    color: Some(color),
+                    handicap: game.handicap,
+                    bye: false,
+                    result: game.result,
+                    points: game.result.map(|result| result.points(color)),
+                });
+            }
+        }
+    }
+
+    let points: Vec<f64> = rounds
+        .iter()
+        .map(|played| played.iter().filter_map(|entry| entry.points).sum())
+        .collect();
+    let score: Vec<f64> = (0..entrants.len()).map(|p| start[p] + points[p]).collect();
+    // The opponents a player met on the board, with the points they earned against them.
+    let met: Vec<Vec<(usize, f64)>> = rounds
+        .iter()
+        .map(|played| {
+            played
+                .iter()
+                .filter(|entry| entry.result.is_some_and(RoundResult::was_played))
+                .filter_map(|entry| {
+                    let opponent = index.get(entry.opponent.as_deref()?)?;
+                    Some((*opponent, entry.points?))
+                })
+                .collect()
+        })
+        .collect();
+    let sos: Vec<f64> = (0..entrants.len())
+        .map(|p| {
+            let unplayed = rounds[p]
+                .iter()
+                .filter(|entry| {
+                    entry.bye || entry.result.is_some_and(|result| !result.was_played())
+                })
+                .count();
+            met[p].iter().map(|&(o, _)| score[o]).sum::<f64>() + start[p] * unplayed as f64
+        })
+        .collect();
+
+    let value = |criterion: TieBreaker, p: usize, group: &[usize]| match criterion {
+        TieBreaker::Points => points[p],
+        TieBreaker::Mms => score[p],
+        TieBreaker::Sos => sos[p],
+        TieBreaker::Sosos => met[p].iter().map(|&(o, _)| sos[o]).sum(),
+        TieBreaker::Sodos => met[p].iter().map(|&(o, earned)| earned * score[o]).sum(),
+        // Rounds the player was not paired in still count, with the score unchanged.
+        TieBreaker::Cumulative => {
+            let mut running = start[p];
+            (1..=played_rounds)
+                .map(|number| {
+                    running += rounds[p]
+                        .iter()
+                        .filter(|entry| entry.round == number)
+                        .filter_map(|entry| entry.points)
+                        .sum::<f64>();
+                    running
+                })
+                .sum()
+        }
+        TieBreaker::DirectEncounter => rounds[p]
+            .iter()
+            .filter(|entry| {
+                entry
+                    .opponent
+                    .as_deref()
+                    .and_then(|id| index.get(id))
+                    .is_some_and(|o| group.contains(o))
+            })
+            .filter_map(|entry| entry.points)
+            .sum(),
+    };
+
+    // Split the field into groups tied on every criterion so far, one criterion at a time.
+    let mut values = vec![Vec::with_capacity(criteria.len()); entrants.len()];
+    let mut groups: Vec<Vec<usize>> = vec![(0..entrants.len()).collect()];
+    for &criterion in criteria {
+        let mut refined = Vec::with_capacity(groups.len());
+        for group in groups {
+            let mut keyed: Vec<(f64, usize)> = group
+                .iter()
+                .map(|&p| (value(criterion, p, &group), p))
+                .collect();
+            keyed.sort_by(|a, b| b.0.total_cmp(&a.0));
+            for &(key, p) in &keyed {
+                values[p].push(key);
+            }
+            refined.extend(
+                keyed
+                    .chunk_by(|a, b| a.0 == b.0)
+                    .map(|tied| tied.iter().map(|&(_, p)| p).collect::<Vec<_>>()),
+            );
+        }
+        groups = refined;
+    }
+
+    let mut table = Vec::with_capacity(entrants.len());
+    for group in groups {
+        let place = table.len() as u32 + 1;
+        for p in group {
+            table.push(Standing {
+                place,
+                player: entrants[p].id.clone(),
+                points: points[p],
+                mms: score[p],
+                tie_breakers: std::mem::take(&mut values[p]),
+                rounds: std::mem::take(&mut rounds[p]),
+            });
+        }
+    }
+    table
+}
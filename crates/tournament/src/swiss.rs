@@ -0,0 +1,81 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::TournamentError,
+    pairing::{a_takes_black, build_round, match_seats, Entrant, Round, Seat},
+    record::{records, PlayerRecord, RoundGame},
+};
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SwissSettings {
+    /// Points for sitting out a round.
+    pub bye_points: f64,
+}
+
+impl Default for SwissSettings {
+    fn default() -> Self {
+        Self { bye_points: 1.0 }
+    }
+}
+
+/// Pair round `round` of a Swiss tournament between `entrants`, the players still taking
+/// part, from the games of earlier rounds. Withdrawn players are simply left out of
+/// `entrants`; their games still count for their opponents.
+///
+/// Players are ranked by points, then rating, then id, so the same input always gives the
+/// same pairing. All games are even.
+pub fn pair_swiss(
+    entrants: &[Entrant],
+    games: &[RoundGame],
+    round: u32,
+    settings: &SwissSettings,
+) -> Result<Round, TournamentError> {
+    check_entrants(entrants)?;
+    let records = records(games, round, settings.bye_points, |_| 0.0);
+    let empty = PlayerRecord::default();
+    let mut ranked: Vec<&Entrant> = entrants.iter().collect();
+    let points = |entrant: &Entrant| records.get(&entrant.id).map_or(0.0, |r| r.points);
+    ranked.sort_by(|a, b| {
+        points(b)
+            .total_cmp(&points(a))
+            .then(b.rating.total_cmp(&a.rating))
+            .then(a.id.cmp(&b.id))
+    });
+    let seats: Vec<Seat<'_>> = ranked
+        .iter()
+        .map(|entrant| {
+            let record = records.get(&entrant.id).unwrap_or(&empty);
+            Seat {
+                id: &entrant.id,
+                score: record.points,
+                record,
+            }
+        })
+        .collect();
+    let matching = match_seats(&seats);
+    let paired = build_round(round, &seats, matching, |upper, lower, table| {
+        if a_takes_black(&seats[upper], &seats[lower], table) {
+            (upper, lower, 0)
+        } else {
+            (lower, upper, 0)
+        }
+    });
+    Ok(paired)
+}
+
+/// A round needs at least two players, each listed once.
+pub(crate) fn check_entrants(entrants: &[Entrant]) -> Result<(), TournamentError> {
+    if entrants.len() < 2 {
+        return Err(TournamentError::NotEnoughPlayers(entrants.len()));
+    }
+    let mut seen = HashSet::with_capacity(entrants.len());
+    for entrant in entrants {
+        if !seen.insert(entrant.id.as_str()) {
+            return Err(TournamentError::DuplicatePlayer(entrant.id.clone()));
+        }
+    }
+    Ok(())
+}
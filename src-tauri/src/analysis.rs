@@ -0,0 +1,102 @@
+use go_rules::{Color, Point};
+use gtp::{AnalysisUpdate, MoveInfo};
+use serde::{Deserialize, Serialize};
+use tauri::Emitter;
+use uuid::Uuid;
+
+use crate::{
+    app_state::AppState,
+    errors::{AppError, AppResult},
+};
+
+/// Emitted for every line an analysing engine reports, and when the stream fails.
+pub const ANALYSIS_UPDATE_EVENT: &str = "analysis-update";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CandidateMove {
+    /// Absent for a pass.
+    pub point: Option<Point>,
+    pub visits: u64,
+    pub winrate: f64,
+    pub prior: f64,
+    pub lcb: f64,
+    pub score_lead: Option<f64>,
+    pub order: u32,
+    pub pv: Vec<Option<Point>>,
+}
+
+impl From<MoveInfo> for CandidateMove {
+    fn from(info: MoveInfo) -> Self {
+        Self {
+            point: info.point,
+            visits: info.visits,
+            winrate: info.winrate,
+            prior: info.prior,
+            lcb: info.lcb,
+            score_lead: info.score_lead,
+            order: info.order,
+            pv: info.pv,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalysisEvent {
+    pub session_id: String,
+    pub game_id: Uuid,
+    /// The player the rates and ownership are reported for.
+    pub to_move: Color,
+    pub move_count: usize,
+    pub moves: Vec<CandidateMove>,
+    pub ownership: Option<Vec<f64>>,
+    pub error: Option<String>,
+}
+
+/// Analyse the current position of a registry game on a session until stopped. Returns the
+/// analysis command the engine understood.
+pub async fn start_analysis(
+    state: &AppState,
+    session_id: &str,
+    game_id: Uuid,
+    interval_centis: u32,
+    ownership: bool,
+) -> AppResult<&'static str> {
+    let game = state
+        .rules()
+        .blocking(move |rules| rules.game(game_id))
+        .await
+        .map_err(AppError::other)?;
+    let (to_move, move_count) = (game.to_move(), game.move_count());
+    let (handle, id) = (state.handle().clone(), session_id.to_string());
+    let emit = move |update: Result<AnalysisUpdate, String>| {
+        let (update, error) = match update {
+            Ok(update) => (update, None),
+            Err(err) => (AnalysisUpdate::default(), Some(err)),
+        };
+        let event = AnalysisEvent {
+            session_id: id.clone(),
+            game_id,
+            to_move,
+            move_count,
+            moves: update.moves.into_iter().map(CandidateMove::from).collect(),
+            ownership: update.ownership,
+            error,
+        };
+        // A missed update is superseded by the next one.
+        let _ = handle.emit(ANALYSIS_UPDATE_EVENT, event);
+    };
+    let dialect = state
+        .gtp()
+        .analyze(
+            state.database(),
+            session_id,
+            &game,
+            interval_centis,
+            ownership,
+            emit,
+        )
+        .await?;
+    Ok(dialect.command_name())
+}
@@ -2,13 +2,26 @@ use std::path::PathBuf;
 
 use tauri::{AppHandle, Manager};
 
-use crate::{db::Database, errors::AppResult, rules_registry::RulesRegistry};
+use crate::{
+    db::Database,
+    engine_matches::EngineMatches,
+    engine_players::EnginePlayers,
+    errors::AppResult,
+    gtp_sessions::{self, GtpSessions},
+    katago_analysis::KataGoEngines,
+    rules_registry::RulesRegistry,
+};
 
 #[derive(Clone)]
 pub struct AppState {
+    handle: AppHandle,
     data_dir: PathBuf,
     database: Database,
     rules: RulesRegistry,
+    gtp: GtpSessions,
+    engines: EnginePlayers,
+    matches: EngineMatches,
+    katago: KataGoEngines,
 }
 
 impl AppState {
@@ -19,15 +32,25 @@ impl AppState {
 
         let db_path = data_dir.join("offline_go.db3");
         let database = Database::connect(db_path)?;
-        let rules = RulesRegistry::new();
+        let rules = RulesRegistry::load(database.clone())?;
+        gtp_sessions::close_stale_sessions(&database)?;
 
         Ok(Self {
+            handle,
             data_dir,
             database,
             rules,
+            gtp: GtpSessions::new(),
+            engines: EnginePlayers::new(),
+            matches: EngineMatches::new(),
+            katago: KataGoEngines::new(),
         })
     }
 
+    pub fn handle(&self) -> &AppHandle {
+        &self.handle
+    }
+
     pub fn database(&self) -> &Database {
         &self.database
     }
@@ -39,4 +62,20 @@ impl AppState {
     pub fn rules(&self) -> &RulesRegistry {
         &self.rules
     }
+
+    pub fn gtp(&self) -> &GtpSessions {
+        &self.gtp
+    }
+
+    pub fn engines(&self) -> &EnginePlayers {
+        &self.engines
+    }
+
+    pub fn matches(&self) -> &EngineMatches {
+        &self.matches
+    }
+
+    pub fn katago(&self) -> &KataGoEngines {
+        &self.katago
+    }
 }
@@ -1,24 +1,58 @@
-use go_rules::{Color, GameConfig, Move, MoveOutcome, Point, ScoreSummary};
-use rusqlite::params;
+use go_rules::{
+    Color, GameConfig, HandicapPlacement, KoRule, Move, MoveOutcome, OwnershipEstimate, Point,
+    Ruleset, ScoreSummary, ScoringSnapshot,
+};
+use gtp::EngineCommand;
+use rusqlite::{params, OptionalExtension};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use sgf::GameInfo;
+use std::path::PathBuf;
 use tauri::State;
+use tournament::{GlickoSettings, ResultsFormat, Round, TieBreaker};
 use uuid::Uuid;
 
 use crate::{
+    analysis,
     app_state::AppState,
+    db::Database,
+    engine_matches::EngineMatchRequest,
+    engine_players::spawn_engine_turns,
     errors::{AppError, AppResult},
+    game_store,
+    gtp_sessions::{self, EngineCapabilities, EngineKind},
+    katago_analysis::{self, PositionReview},
+    players::{self, PlayerInfo, UpsertPlayer},
+    ratings::{self, RatingHistoryEntry, RatingPeriodSummary},
     rules_registry::{GameStateSnapshot, GameSummary, RulesError},
+    tournaments::{
+        self, PairingInfo, StandingsTable, TournamentInfo, TournamentPlayer, UpsertTournament,
+    },
 };
 #[derive(Default, Deserialize)]
 pub struct CreateGameRequest {
     pub size: Option<usize>,
     pub komi: Option<f64>,
-    pub superko: Option<bool>,
+    #[serde(alias = "koRule")]
+    pub ko_rule: Option<KoRule>,
+    pub handicap: Option<u8>,
+    #[serde(alias = "handicapPlacement")]
+    pub handicap_placement: Option<HandicapPlacement>,
+    pub ruleset: Option<Ruleset>,
+    /// GTP session that plays Black.
+    #[serde(alias = "blackEngine")]
+    pub black_engine: Option<String>,
+    #[serde(alias = "whiteEngine")]
+    pub white_engine: Option<String>,
+    /// Player records the game is played between.
+    #[serde(alias = "blackPlayerId")]
+    pub black_player_id: Option<String>,
+    #[serde(alias = "whitePlayerId")]
+    pub white_player_id: Option<String>,
 }
 
 impl CreateGameRequest {
-    fn into_config(self) -> GameConfig {
+    fn to_config(&self) -> GameConfig {
         let mut config = GameConfig::default();
         if let Some(size) = self.size {
             config.size = size;
@@ -26,8 +60,17 @@ impl CreateGameRequest {
         if let Some(komi) = self.komi {
             config.komi = komi;
         }
-        if let Some(superko) = self.superko {
-            config.superko = superko;
+        if let Some(ko_rule) = self.ko_rule {
+            config.ko_rule = Some(ko_rule);
+        }
+        if let Some(handicap) = self.handicap {
+            config.handicap = handicap;
+        }
+        if let Some(placement) = self.handicap_placement {
+            config.handicap_placement = placement;
+        }
+        if let Some(ruleset) = self.ruleset {
+            config.ruleset = ruleset;
         }
         config
     }
@@ -70,6 +113,20 @@ impl MovePayload {
     }
 }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HandicapStonePayload {
+    pub game_id: Uuid,
+    pub point: PointPayload,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeadStonesPayload {
+    pub game_id: Uuid,
+    pub point: PointPayload,
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SyncOperationInput {
@@ -97,6 +154,7 @@ fn map_rules_error(err: RulesError) -> AppError {
     match err {
         RulesError::NotFound(id) => AppError::other(format!("game {id} not found")),
         RulesError::Rule(rule) => AppError::other(rule.to_string()),
+        other => AppError::other(other.to_string()),
     }
 }
 
@@ -142,21 +200,59 @@ pub async fn vacuum_database(state: State<'_, AppState>) -> AppResult<()> {
     Ok(())
 }
 
-/// Create a new in-memory Go game managed by the rules registry.
+/// Create a new in-memory Go game managed by the rules registry, optionally with running
+/// GTP sessions playing either color and linked to the players of each color.
 #[tauri::command]
 pub async fn create_game(
     state: State<'_, AppState>,
     config: Option<CreateGameRequest>,
 ) -> AppResult<GameStateSnapshot> {
-    let config = config.unwrap_or_default().into_config();
-    let (_, snapshot) = state.rules().create_game(config);
+    let request = config.unwrap_or_default();
+    let config = request.to_config();
+    let engines = [&request.black_engine, &request.white_engine];
+    for session_id in engines.into_iter().flatten() {
+        if state.gtp().get(session_id).is_none() {
+            return Err(AppError::Engine(format!(
+                "session {session_id} is not running"
+            )));
+        }
+        if config.size > gtp::MAX_BOARD_SIZE {
+            return Err(AppError::Engine(format!(
+                "GTP engines cannot play on a {0}x{0} board",
+                config.size
+            )));
+        }
+    }
+    let (db, rules) = (state.database().clone(), state.rules().clone());
+    let (black, white) = (
+        request.black_player_id.clone(),
+        request.white_player_id.clone(),
+    );
+    let (game_id, snapshot) = tauri::async_runtime::spawn_blocking(move || {
+        let seated = [&black, &white].into_iter().flatten();
+        db.with_conn(|conn| players::ensure_exist(conn, seated))?;
+        let (game_id, snapshot) = rules.create_game(config).map_err(map_rules_error)?;
+        db.with_conn(|conn| {
+            game_store::set_players(conn, game_id, black.as_deref(), white.as_deref())
+        })?;
+        Ok::<_, AppError>((game_id, snapshot))
+    })
+    .await
+    .map_err(|err| AppError::other(format!("task join error: {err}")))??;
+    state
+        .engines()
+        .seat(game_id, request.black_engine, request.white_engine);
+    spawn_engine_turns(&state, game_id);
     Ok(snapshot)
 }
 
-/// List the active in-memory Go games.
+/// List every stored Go game, most recently changed first, including finished ones.
 #[tauri::command]
 pub async fn list_games(state: State<'_, AppState>) -> AppResult<Vec<GameSummary>> {
-    Ok(state.rules().list_games())
+    let rules = state.rules().clone();
+    tauri::async_runtime::spawn_blocking(move || rules.list_games())
+        .await
+        .map_err(|err| AppError::other(format!("task join error: {err}")))?
 }
 
 /// Fetch the latest snapshot for a specific game.
@@ -165,7 +261,11 @@ pub async fn get_game_state(
     state: State<'_, AppState>,
     game_id: Uuid,
 ) -> AppResult<GameStateSnapshot> {
-    state.rules().snapshot(game_id).map_err(map_rules_error)
+    state
+        .rules()
+        .blocking(move |rules| rules.snapshot(game_id))
+        .await
+        .map_err(map_rules_error)
 }
 
 /// Play a move inside an active game.
@@ -175,16 +275,257 @@ pub async fn play_game_move(
     payload: MovePayload,
 ) -> AppResult<MoveOutcome> {
     let (game_id, mv) = payload.into_parts()?;
+    ensure_human_seat(&state, game_id, mv.color)?;
+    let outcome = state
+        .rules()
+        .blocking(move |rules| rules.play_move(game_id, mv))
+        .await
+        .map_err(map_rules_error)?;
+    spawn_engine_turns(&state, game_id);
+    Ok(outcome)
+}
+
+/// Place one of Black's stones while a free handicap is being set up.
+#[tauri::command]
+pub async fn place_handicap_stone(
+    state: State<'_, AppState>,
+    payload: HandicapStonePayload,
+) -> AppResult<GameStateSnapshot> {
+    let point = payload.point.into_point()?;
+    ensure_human_seat(&state, payload.game_id, Color::Black)?;
+    let snapshot = state
+        .rules()
+        .blocking(move |rules| rules.place_handicap_stone(payload.game_id, point))
+        .await
+        .map_err(map_rules_error)?;
+    spawn_engine_turns(&state, payload.game_id);
+    Ok(snapshot)
+}
+
+/// Take back the last move of an active game.
+#[tauri::command]
+pub async fn undo_game_move(
+    state: State<'_, AppState>,
+    game_id: Uuid,
+) -> AppResult<GameStateSnapshot> {
+    state
+        .rules()
+        .blocking(move |rules| rules.undo_move(game_id))
+        .await
+        .map_err(map_rules_error)
+}
+
+/// Replay the most recently undone move of an active game.
+#[tauri::command]
+pub async fn redo_game_move(
+    state: State<'_, AppState>,
+    game_id: Uuid,
+) -> AppResult<GameStateSnapshot> {
+    state
+        .rules()
+        .blocking(move |rules| rules.redo_move(game_id))
+        .await
+        .map_err(map_rules_error)
+}
+
+/// Seek an active game to the position after `move_number` moves.
+#[tauri::command]
+pub async fn goto_game_move(
+    state: State<'_, AppState>,
+    game_id: Uuid,
+    move_number: usize,
+) -> AppResult<GameStateSnapshot> {
+    state
+        .rules()
+        .blocking(move |rules| rules.goto_move(game_id, move_number))
+        .await
+        .map_err(map_rules_error)
+}
+
+/// Mark the chain at a point dead, or alive again, while the game is being scored.
+#[tauri::command]
+pub async fn toggle_dead_stones(
+    state: State<'_, AppState>,
+    payload: DeadStonesPayload,
+) -> AppResult<ScoringSnapshot> {
+    let point = payload.point.into_point()?;
+    let scoring = state
+        .rules()
+        .blocking(move |rules| rules.toggle_dead_stones(payload.game_id, point))
+        .await
+        .map_err(map_rules_error)?;
+    // Marking stones withdraws acceptance, so engine players accept again.
+    spawn_engine_turns(&state, payload.game_id);
+    Ok(scoring)
+}
+
+/// Accept the current dead stones on behalf of one player.
+#[tauri::command]
+pub async fn accept_score(
+    state: State<'_, AppState>,
+    game_id: Uuid,
+    color: Color,
+) -> AppResult<ScoringSnapshot> {
+    state
+        .rules()
+        .blocking(move |rules| rules.accept_score(game_id, color))
+        .await
+        .map_err(map_rules_error)
+}
+
+/// Leave the scoring phase and continue playing when the players disagree.
+#[tauri::command]
+pub async fn resume_game(
+    state: State<'_, AppState>,
+    game_id: Uuid,
+) -> AppResult<GameStateSnapshot> {
+    let snapshot = state
+        .rules()
+        .blocking(move |rules| rules.resume_game(game_id))
+        .await
+        .map_err(map_rules_error)?;
+    spawn_engine_turns(&state, game_id);
+    Ok(snapshot)
+}
+
+/// Resign an active game on behalf of one player.
+#[tauri::command]
+pub async fn resign_game(
+    state: State<'_, AppState>,
+    game_id: Uuid,
+    color: Color,
+) -> AppResult<GameStateSnapshot> {
+    state
+        .rules()
+        .blocking(move |rules| rules.resign(game_id, color))
+        .await
+        .map_err(map_rules_error)
+}
+
+/// Ask the engine seated for the side to move to play, for instance after an undo or after
+/// an engine's move was rejected. Results arrive as `engine-move` events.
+#[tauri::command]
+pub async fn request_engine_move(state: State<'_, AppState>, game_id: Uuid) -> AppResult<()> {
+    let game = state
+        .rules()
+        .blocking(move |rules| rules.game(game_id))
+        .await
+        .map_err(map_rules_error)?;
+    if state.engines().next_turn(game_id, &game).is_none() {
+        return Err(AppError::Engine(format!(
+            "no engine is due to play in game {game_id}"
+        )));
+    }
+    spawn_engine_turns(&state, game_id);
+    Ok(())
+}
+
+fn ensure_human_seat(state: &AppState, game_id: Uuid, color: Color) -> AppResult<()> {
+    match state.engines().session(game_id, color) {
+        Some(session_id) => Err(AppError::Engine(format!(
+            "{color:?} is played by GTP session {session_id}"
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Estimate dead stones and point ownership of an active game without an engine.
+#[tauri::command]
+pub async fn estimate_ownership(
+    state: State<'_, AppState>,
+    game_id: Uuid,
+) -> AppResult<OwnershipEstimate> {
     state
         .rules()
-        .play_move(game_id, mv)
+        .blocking(move |rules| rules.estimate_ownership(game_id))
+        .await
         .map_err(map_rules_error)
 }
 
 /// Calculate area score + captures for an active game.
 #[tauri::command]
 pub async fn score_game(state: State<'_, AppState>, game_id: Uuid) -> AppResult<ScoreSummary> {
-    state.rules().score(game_id).map_err(map_rules_error)
+    state
+        .rules()
+        .blocking(move |rules| rules.score(game_id))
+        .await
+        .map_err(map_rules_error)
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportedGame {
+    pub game: GameStateSnapshot,
+    pub info: GameInfo,
+}
+
+/// Read every game in an SGF file into the registry, positioned at the end of its main line.
+#[tauri::command]
+pub async fn import_sgf(state: State<'_, AppState>, path: String) -> AppResult<Vec<ImportedGame>> {
+    let text = tauri::async_runtime::spawn_blocking(move || std::fs::read_to_string(path))
+        .await
+        .map_err(|err| AppError::other(format!("task join error: {err}")))??;
+    let mut imported = Vec::new();
+    for sgf::SgfGame { info, mut tree } in sgf::read_games(&text)? {
+        if let Some(&last) = tree.main_line().last() {
+            tree.goto(last)
+                .map_err(|err| AppError::other(err.to_string()))?;
+        }
+        let game = tree.state().clone();
+        let game = state
+            .rules()
+            .blocking(move |rules| rules.insert_game(game, "sgf"))
+            .await
+            .map_err(map_rules_error)?;
+        imported.push(ImportedGame { game, info });
+    }
+
+    // Keep the players, event and recorded result alongside the stored moves.
+    let db = state.database().clone();
+    let rows = imported
+        .iter()
+        .map(|game| {
+            Ok((
+                game.game.game_id.to_string(),
+                serde_json::to_string(&game.info)?,
+            ))
+        })
+        .collect::<AppResult<Vec<_>>>()?;
+    tauri::async_runtime::spawn_blocking(move || {
+        db.with_conn(|conn| {
+            for (game_id, info) in &rows {
+                conn.execute(
+                    "UPDATE games SET metadata = ?2 WHERE id = ?1",
+                    params![game_id, info],
+                )?;
+            }
+            Ok(())
+        })
+    })
+    .await
+    .map_err(|err| AppError::other(format!("task join error: {err}")))??;
+    Ok(imported)
+}
+
+/// Write an active game to an SGF file.
+#[tauri::command]
+pub async fn export_sgf(
+    state: State<'_, AppState>,
+    game_id: Uuid,
+    path: String,
+    info: Option<GameInfo>,
+) -> AppResult<()> {
+    let game = state
+        .rules()
+        .blocking(move |rules| rules.game(game_id))
+        .await
+        .map_err(map_rules_error)?;
+    let record = sgf::from_state(&game, &info.unwrap_or_default())?;
+    let text = sgf::write(&[record]);
+    tauri::async_runtime::spawn_blocking(move || std::fs::write(path, text))
+        .await
+        .map_err(|err| AppError::other(format!("task join error: {err}")))??;
+    Ok(())
 }
 
 /// Persist optimistic updates queued on the client.
@@ -288,6 +629,9 @@ pub struct GtpEngineInfo {
     pub args: Vec<String>,
     pub working_directory: Option<String>,
     pub enabled: bool,
+    pub kind: EngineKind,
+    /// Absent for engines registered before probing existed.
+    pub capabilities: Option<EngineCapabilities>,
     pub created_at: String,
     pub updated_at: String,
 }
@@ -301,6 +645,7 @@ pub struct UpsertGtpEngine {
     pub args: Option<Vec<String>>,
     pub working_directory: Option<String>,
     pub enabled: Option<bool>,
+    pub kind: Option<EngineKind>,
 }
 
 #[derive(Serialize)]
@@ -309,7 +654,19 @@ pub struct GtpSessionInfo {
     pub session_id: String,
     pub engine_id: String,
     pub status: String,
+    pub pid: Option<u32>,
     pub started_at: String,
+    pub stopped_at: Option<String>,
+    pub log_path: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GtpLogPage {
+    pub session_id: String,
+    pub start: usize,
+    pub total: usize,
+    pub lines: Vec<String>,
 }
 
 #[tauri::command]
@@ -318,7 +675,7 @@ pub async fn list_gtp_engines(state: State<'_, AppState>) -> AppResult<Vec<GtpEn
     tauri::async_runtime::spawn_blocking(move || {
         db.with_conn(|conn| {
             let mut stmt = conn.prepare(
-                "SELECT id, name, path, args, working_directory, enabled, created_at, updated_at FROM gtp_engines ORDER BY name",
+                "SELECT id, name, path, args, working_directory, enabled, created_at, updated_at, kind, capabilities FROM gtp_engines ORDER BY name",
             )?;
             let mut rows = stmt.query([])?;
             let mut engines = Vec::new();
@@ -331,6 +688,10 @@ pub async fn list_gtp_engines(state: State<'_, AppState>) -> AppResult<Vec<GtpEn
                     args: serde_json::from_str(&args).unwrap_or_default(),
                     working_directory: row.get(4)?,
                     enabled: row.get::<_, i64>(5)? != 0,
+                    kind: EngineKind::from_db(&row.get::<_, String>(8)?),
+                    capabilities: row
+                        .get::<_, Option<String>>(9)?
+                        .and_then(|json| serde_json::from_str(&json).ok()),
                     created_at: row.get(6)?,
                     updated_at: row.get(7)?,
                 });
@@ -342,13 +703,23 @@ pub async fn list_gtp_engines(state: State<'_, AppState>) -> AppResult<Vec<GtpEn
     .map_err(|err| AppError::other(format!("task join error: {err}")))?
 }
 
+/// Register or update an engine after checking that it starts and speaks its protocol.
 #[tauri::command]
 pub async fn register_gtp_engine(
     state: State<'_, AppState>,
     payload: UpsertGtpEngine,
 ) -> AppResult<GtpEngineInfo> {
+    let kind = payload.kind.unwrap_or_default();
+    let command = EngineCommand {
+        path: PathBuf::from(&payload.path),
+        args: payload.args.clone().unwrap_or_default(),
+        working_directory: payload.working_directory.as_ref().map(PathBuf::from),
+    };
+    let capabilities = gtp_sessions::probe_engine(&command, kind).await?;
+    let capabilities_json = serde_json::to_string(&capabilities)?;
+
     let db = state.database().clone();
-    tauri::async_runtime::spawn_blocking(move || {
+    let engine = tauri::async_runtime::spawn_blocking(move || {
         db.with_conn(|conn| {
             let UpsertGtpEngine {
                 id,
@@ -357,27 +728,31 @@ pub async fn register_gtp_engine(
                 args,
                 working_directory,
                 enabled,
+                ..
             } = payload;
 
             let engine_id = id.unwrap_or_else(|| Uuid::new_v4().to_string());
             let args_json = serde_json::to_string(&args.unwrap_or_default())?;
             let enabled_flag = enabled.unwrap_or(true) as i64;
+            let kind = kind.as_str();
 
             conn.execute(
-                "INSERT INTO gtp_engines (id, name, path, args, working_directory, enabled, created_at, updated_at)
-                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, COALESCE((SELECT created_at FROM gtp_engines WHERE id = ?1), CURRENT_TIMESTAMP), CURRENT_TIMESTAMP)
+                "INSERT INTO gtp_engines (id, name, path, args, working_directory, enabled, kind, capabilities, created_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, COALESCE((SELECT created_at FROM gtp_engines WHERE id = ?1), CURRENT_TIMESTAMP), CURRENT_TIMESTAMP)
                  ON CONFLICT(id) DO UPDATE SET
                     name = excluded.name,
                     path = excluded.path,
                     args = excluded.args,
                     working_directory = excluded.working_directory,
                     enabled = excluded.enabled,
+                    kind = excluded.kind,
+                    capabilities = excluded.capabilities,
                     updated_at = CURRENT_TIMESTAMP",
-                params![engine_id, name, path, args_json, working_directory, enabled_flag],
+                params![engine_id, name, path, args_json, working_directory, enabled_flag, kind, capabilities_json],
             )?;
 
             let mut stmt = conn.prepare(
-                "SELECT id, name, path, args, working_directory, enabled, created_at, updated_at FROM gtp_engines WHERE id = ?1",
+                "SELECT id, name, path, args, working_directory, enabled, created_at, updated_at, kind, capabilities FROM gtp_engines WHERE id = ?1",
             )?;
             let engine = stmt.query_row(params![engine_id.clone()], |row| {
                 let args: String = row.get(3)?;
@@ -388,6 +763,10 @@ pub async fn register_gtp_engine(
                     args: serde_json::from_str(&args).unwrap_or_default(),
                     working_directory: row.get(4)?,
                     enabled: row.get::<_, i64>(5)? != 0,
+                    kind: EngineKind::from_db(&row.get::<_, String>(8)?),
+                    capabilities: row
+                        .get::<_, Option<String>>(9)?
+                        .and_then(|json| serde_json::from_str(&json).ok()),
                     created_at: row.get(6)?,
                     updated_at: row.get(7)?,
                 })
@@ -397,11 +776,15 @@ pub async fn register_gtp_engine(
         })
     })
     .await
-    .map_err(|err| AppError::other(format!("task join error: {err}")))?
+    .map_err(|err| AppError::other(format!("task join error: {err}")))??;
+    // A running analysis process would keep the old settings.
+    state.katago().stop(&engine.id).await;
+    Ok(engine)
 }
 
 #[tauri::command]
 pub async fn remove_gtp_engine(state: State<'_, AppState>, engine_id: String) -> AppResult<()> {
+    state.katago().stop(&engine_id).await;
     let db = state.database().clone();
     tauri::async_runtime::spawn_blocking(move || {
         db.with_conn(|conn| {
@@ -413,42 +796,456 @@ pub async fn remove_gtp_engine(state: State<'_, AppState>, engine_id: String) ->
     .map_err(|err| AppError::other(format!("task join error: {err}")))?
 }
 
+/// Start a registered engine process and open a GTP session with it.
 #[tauri::command]
 pub async fn launch_gtp_engine(
     state: State<'_, AppState>,
     engine_id: String,
 ) -> AppResult<GtpSessionInfo> {
+    let engine = gtp_sessions::load_engine(state.database(), &engine_id, EngineKind::Gtp).await?;
+    let session_id = state
+        .gtp()
+        .launch(
+            state.database(),
+            &engine_id,
+            &engine.command,
+            &state.data_dir().join("gtp-logs"),
+        )
+        .await?;
+    let session = load_gtp_session(state.database().clone(), session_id).await?;
+    session.ok_or_else(|| AppError::other("session vanished after launch"))
+}
+
+/// Send a raw GTP command to a running session and return the response text.
+#[tauri::command]
+pub async fn send_gtp_command(
+    state: State<'_, AppState>,
+    session_id: String,
+    command: String,
+) -> AppResult<String> {
+    state
+        .gtp()
+        .send(state.database(), &session_id, &command)
+        .await
+}
+
+/// Stop a session with `quit`, killing the engine if it does not exit in time.
+#[tauri::command]
+pub async fn stop_gtp_engine(state: State<'_, AppState>, session_id: String) -> AppResult<()> {
+    state.gtp().stop(state.database(), &session_id).await?;
+    Ok(())
+}
+
+/// List GTP sessions, most recent first.
+#[tauri::command]
+pub async fn list_gtp_sessions(state: State<'_, AppState>) -> AppResult<Vec<GtpSessionInfo>> {
     let db = state.database().clone();
     tauri::async_runtime::spawn_blocking(move || {
         db.with_conn(|conn| {
-            let session_id = Uuid::new_v4().to_string();
-            let now = chrono::Utc::now().to_rfc3339();
-            conn.execute(
-                "INSERT INTO gtp_sessions (id, engine_id, status, started_at) VALUES (?1, ?2, ?3, ?4)",
-                params![session_id, engine_id, "pending", now],
-            )?;
-            Ok(GtpSessionInfo {
-                session_id,
-                engine_id,
-                status: "pending".to_string(),
-                started_at: now,
-            })
+            let mut stmt =
+                conn.prepare(&format!("{GTP_SESSION_COLUMNS} ORDER BY started_at DESC"))?;
+            let sessions = stmt
+                .query_map([], gtp_session_from_row)?
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(sessions)
         })
     })
     .await
     .map_err(|err| AppError::other(format!("task join error: {err}")))?
 }
 
+/// Analyse a registry game's current position on a session. Results arrive as
+/// `analysis-update` events until `stop_analysis` or another command for the session.
+/// Returns the analysis command in use.
 #[tauri::command]
-pub async fn stop_gtp_engine(state: State<'_, AppState>, session_id: String) -> AppResult<()> {
+pub async fn start_analysis(
+    state: State<'_, AppState>,
+    session_id: String,
+    game_id: Uuid,
+    interval_centis: Option<u32>,
+    ownership: Option<bool>,
+) -> AppResult<String> {
+    let interval = interval_centis.unwrap_or(50).clamp(10, 1000);
+    let command = analysis::start_analysis(
+        &state,
+        &session_id,
+        game_id,
+        interval,
+        ownership.unwrap_or(true),
+    )
+    .await?;
+    Ok(command.to_string())
+}
+
+/// Stop a session's analysis stream, if it has one.
+#[tauri::command]
+pub async fn stop_analysis(state: State<'_, AppState>, session_id: String) -> AppResult<()> {
+    state.gtp().stop_analysis(&session_id).await;
+    Ok(())
+}
+
+/// Page through a session's GTP log from line `start`, or tail it when `start` is omitted.
+#[tauri::command]
+pub async fn read_gtp_log(
+    state: State<'_, AppState>,
+    session_id: String,
+    start: Option<usize>,
+    limit: Option<u32>,
+) -> AppResult<GtpLogPage> {
+    let session = load_gtp_session(state.database().clone(), session_id.clone())
+        .await?
+        .ok_or_else(|| AppError::Engine(format!("session {session_id} not found")))?;
+    let path = session
+        .log_path
+        .map(PathBuf::from)
+        .ok_or_else(|| AppError::Engine(format!("session {session_id} has no log")))?;
+    let limit = limit.unwrap_or(200).clamp(1, 5000) as usize;
+
+    let page = tauri::async_runtime::spawn_blocking(move || match start {
+        Some(start) => gtp::read_page(&path, start, limit),
+        None => gtp::read_tail(&path, limit),
+    })
+    .await
+    .map_err(|err| AppError::other(format!("task join error: {err}")))??;
+    Ok(GtpLogPage {
+        session_id,
+        start: page.start,
+        total: page.total,
+        lines: page.lines,
+    })
+}
+
+/// Analyse every position of a registry game with a KataGo JSON analysis engine. Positions
+/// already in the analysis cache are not sent to the engine again.
+#[tauri::command]
+pub async fn review_game_analysis(
+    state: State<'_, AppState>,
+    engine_id: String,
+    game_id: Uuid,
+    max_visits: Option<u32>,
+) -> AppResult<Vec<PositionReview>> {
+    katago_analysis::review_game(&state, &engine_id, game_id, max_visits).await
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EngineMatchInfo {
+    pub match_id: String,
+    pub engine_a_id: String,
+    pub engine_b_id: String,
+    pub games: u32,
+    pub settings: Value,
+    pub status: String,
+    pub summary: Option<Value>,
+    pub created_at: String,
+    pub finished_at: Option<String>,
+}
+
+/// Play a series of games between two registered engines in the background. Progress and
+/// the running win rate arrive as `engine-match-progress` events.
+#[tauri::command]
+pub async fn start_engine_match(
+    state: State<'_, AppState>,
+    request: EngineMatchRequest,
+) -> AppResult<String> {
+    state.matches().start(&state, request).await
+}
+
+/// Stop a match after the game in progress. Returns whether the match was running.
+#[tauri::command]
+pub async fn cancel_engine_match(state: State<'_, AppState>, match_id: String) -> AppResult<bool> {
+    Ok(state.matches().cancel(&match_id))
+}
+
+/// List engine matches, most recent first.
+#[tauri::command]
+pub async fn list_engine_matches(state: State<'_, AppState>) -> AppResult<Vec<EngineMatchInfo>> {
     let db = state.database().clone();
     tauri::async_runtime::spawn_blocking(move || {
         db.with_conn(|conn| {
-            conn.execute(
-                "UPDATE gtp_sessions SET status = ?2, stopped_at = CURRENT_TIMESTAMP WHERE id = ?1",
-                params![session_id, "stopped"],
+            let mut stmt = conn.prepare(
+                "SELECT id, engine_a_id, engine_b_id, games, settings, status, summary, created_at, finished_at
+                 FROM engine_matches ORDER BY created_at DESC",
             )?;
-            Ok(())
+            let matches = stmt
+                .query_map([], |row| {
+                    let settings: String = row.get(4)?;
+                    let summary: Option<String> = row.get(6)?;
+                    Ok(EngineMatchInfo {
+                        match_id: row.get(0)?,
+                        engine_a_id: row.get(1)?,
+                        engine_b_id: row.get(2)?,
+                        games: row.get(3)?,
+                        settings: serde_json::from_str(&settings).unwrap_or_default(),
+                        status: row.get(5)?,
+                        summary: summary.and_then(|summary| serde_json::from_str(&summary).ok()),
+                        created_at: row.get(7)?,
+                        finished_at: row.get(8)?,
+                    })
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(matches)
+        })
+    })
+    .await
+    .map_err(|err| AppError::other(format!("task join error: {err}")))?
+}
+
+#[tauri::command]
+pub async fn list_players(state: State<'_, AppState>) -> AppResult<Vec<PlayerInfo>> {
+    let db = state.database().clone();
+    tauri::async_runtime::spawn_blocking(move || db.with_conn(players::list))
+        .await
+        .map_err(|err| AppError::other(format!("task join error: {err}")))?
+}
+
+#[tauri::command]
+pub async fn get_player(
+    state: State<'_, AppState>,
+    player_id: String,
+) -> AppResult<Option<PlayerInfo>> {
+    let db = state.database().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        db.with_conn(|conn| players::get(conn, &player_id))
+    })
+    .await
+    .map_err(|err| AppError::other(format!("task join error: {err}")))?
+}
+
+/// Add a player or update an existing one. New players start at the rating of their rank.
+#[tauri::command]
+pub async fn save_player(
+    state: State<'_, AppState>,
+    payload: UpsertPlayer,
+) -> AppResult<PlayerInfo> {
+    let db = state.database().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        db.with_conn(|conn| players::upsert(conn, payload))
+    })
+    .await
+    .map_err(|err| AppError::other(format!("task join error: {err}")))?
+}
+
+/// Delete a player, returning whether they existed.
+#[tauri::command]
+pub async fn remove_player(state: State<'_, AppState>, player_id: String) -> AppResult<bool> {
+    let db = state.database().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        db.with_conn(|conn| players::remove(conn, &player_id))
+    })
+    .await
+    .map_err(|err| AppError::other(format!("task join error: {err}")))?
+}
+
+/// Close a Glicko-2 rating period: rate every finished game between registered players
+/// that has not been rated yet and update all players. `period` defaults to today's date.
+/// Fails when there are no such games.
+#[tauri::command]
+pub async fn run_rating_period(
+    state: State<'_, AppState>,
+    period: Option<String>,
+    settings: Option<GlickoSettings>,
+) -> AppResult<RatingPeriodSummary> {
+    let period = period.unwrap_or_else(|| chrono::Utc::now().format("%Y-%m-%d").to_string());
+    let settings = settings.unwrap_or_default();
+    let db = state.database().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        db.with_conn(|conn| ratings::run_period(conn, &period, &settings))
+    })
+    .await
+    .map_err(|err| AppError::other(format!("task join error: {err}")))?
+}
+
+#[tauri::command]
+pub async fn get_rating_history(
+    state: State<'_, AppState>,
+    player_id: String,
+) -> AppResult<Vec<RatingHistoryEntry>> {
+    let db = state.database().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        db.with_conn(|conn| ratings::history(conn, &player_id))
+    })
+    .await
+    .map_err(|err| AppError::other(format!("task join error: {err}")))?
+}
+
+#[tauri::command]
+pub async fn list_tournaments(state: State<'_, AppState>) -> AppResult<Vec<TournamentInfo>> {
+    let db = state.database().clone();
+    tauri::async_runtime::spawn_blocking(move || db.with_conn(tournaments::list))
+        .await
+        .map_err(|err| AppError::other(format!("task join error: {err}")))?
+}
+
+/// Create a tournament or update an existing one, including its pairing system.
+#[tauri::command]
+pub async fn save_tournament(
+    state: State<'_, AppState>,
+    payload: UpsertTournament,
+) -> AppResult<TournamentInfo> {
+    let db = state.database().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        db.with_conn(|conn| tournaments::upsert(conn, payload))
+    })
+    .await
+    .map_err(|err| AppError::other(format!("task join error: {err}")))?
+}
+
+/// Delete a tournament with its rounds and pairings, returning whether it existed.
+#[tauri::command]
+pub async fn remove_tournament(
+    state: State<'_, AppState>,
+    tournament_id: String,
+) -> AppResult<bool> {
+    let db = state.database().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        db.with_conn(|conn| tournaments::remove(conn, &tournament_id))
+    })
+    .await
+    .map_err(|err| AppError::other(format!("task join error: {err}")))?
+}
+
+#[tauri::command]
+pub async fn list_tournament_players(
+    state: State<'_, AppState>,
+    tournament_id: String,
+) -> AppResult<Vec<TournamentPlayer>> {
+    let db = state.database().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        db.with_conn(|conn| tournaments::participants(conn, &tournament_id))
+    })
+    .await
+    .map_err(|err| AppError::other(format!("task join error: {err}")))?
+}
+
+/// Enter a player into a tournament, or withdraw them with `active: false`. Withdrawn
+/// players are not paired again but keep their results.
+#[tauri::command]
+pub async fn set_tournament_player(
+    state: State<'_, AppState>,
+    tournament_id: String,
+    player_id: String,
+    active: Option<bool>,
+) -> AppResult<Vec<TournamentPlayer>> {
+    let db = state.database().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        db.with_conn(|conn| {
+            players::ensure_exist(conn, [&player_id])?;
+            tournaments::set_participant(conn, &tournament_id, &player_id, active.unwrap_or(true))?;
+            tournaments::participants(conn, &tournament_id)
+        })
+    })
+    .await
+    .map_err(|err| AppError::other(format!("task join error: {err}")))?
+}
+
+/// Pair the next round of a tournament and store its pairings. A round robin is scheduled
+/// in full, so all of its rounds are returned at once.
+#[tauri::command]
+pub async fn pair_tournament_round(
+    state: State<'_, AppState>,
+    tournament_id: String,
+) -> AppResult<Vec<Round>> {
+    let db = state.database().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        db.with_conn(|conn| tournaments::pair_next_round(conn, &tournament_id))
+    })
+    .await
+    .map_err(|err| AppError::other(format!("task join error: {err}")))?
+}
+
+/// The pairings of one round, or of every round when `round_index` is omitted.
+#[tauri::command]
+pub async fn list_pairings(
+    state: State<'_, AppState>,
+    tournament_id: String,
+    round_index: Option<u32>,
+) -> AppResult<Vec<PairingInfo>> {
+    let db = state.database().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        db.with_conn(|conn| tournaments::pairings(conn, &tournament_id, round_index))
+    })
+    .await
+    .map_err(|err| AppError::other(format!("task join error: {err}")))?
+}
+
+/// Record a pairing's result, such as `B+R`, `W+F` or `0`, or clear it with `None`.
+/// Knockouts pair their next round as soon as the current one is finished.
+#[tauri::command]
+pub async fn record_pairing_result(
+    state: State<'_, AppState>,
+    pairing_id: String,
+    result: Option<String>,
+) -> AppResult<PairingInfo> {
+    let db = state.database().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        db.with_conn(|conn| tournaments::record_result(conn, &pairing_id, result.as_deref()))
+    })
+    .await
+    .map_err(|err| AppError::other(format!("task join error: {err}")))?
+}
+
+/// The standings after a round, the latest one by default. `tie_breakers` overrides the
+/// usual criteria of the tournament's pairing system.
+#[tauri::command]
+pub async fn get_standings(
+    state: State<'_, AppState>,
+    tournament_id: String,
+    round_index: Option<u32>,
+    tie_breakers: Option<Vec<TieBreaker>>,
+) -> AppResult<StandingsTable> {
+    let db = state.database().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        db.with_conn(|conn| tournaments::standings(conn, &tournament_id, round_index, tie_breakers))
+    })
+    .await
+    .map_err(|err| AppError::other(format!("task join error: {err}")))?
+}
+
+/// Write a tournament's results to `path` as an EGD `.h` file or AGA results file.
+#[tauri::command]
+pub async fn export_tournament_results(
+    state: State<'_, AppState>,
+    tournament_id: String,
+    format: ResultsFormat,
+    path: String,
+) -> AppResult<()> {
+    let db = state.database().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let results =
+            db.with_conn(|conn| tournaments::export_results(conn, &tournament_id, format))?;
+        std::fs::write(&path, results)?;
+        Ok(())
+    })
+    .await
+    .map_err(|err| AppError::other(format!("task join error: {err}")))?
+}
+
+const GTP_SESSION_COLUMNS: &str =
+    "SELECT id, engine_id, status, pid, started_at, stopped_at, log_path FROM gtp_sessions";
+
+fn gtp_session_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<GtpSessionInfo> {
+    Ok(GtpSessionInfo {
+        session_id: row.get(0)?,
+        engine_id: row.get(1)?,
+        status: row.get(2)?,
+        pid: row.get(3)?,
+        started_at: row.get(4)?,
+        stopped_at: row.get(5)?,
+        log_path: row.get(6)?,
+    })
+}
+
+async fn load_gtp_session(db: Database, session_id: String) -> AppResult<Option<GtpSessionInfo>> {
+    tauri::async_runtime::spawn_blocking(move || {
+        db.with_conn(|conn| {
+            conn.query_row(
+                &format!("{GTP_SESSION_COLUMNS} WHERE id = ?1"),
+                params![session_id],
+                gtp_session_from_row,
+            )
+            .optional()
+            .map_err(AppError::from)
         })
     })
     .await
@@ -29,6 +29,18 @@ impl Database {
         Ok(db)
     }
 
+    /// A fresh database that lives only as long as the connection, for tests.
+    #[cfg(test)]
+    pub fn in_memory() -> AppResult<Self> {
+        let db = Self {
+            path: PathBuf::from(":memory:"),
+            connection: Arc::new(Mutex::new(Connection::open_in_memory()?)),
+        };
+        db.configure()?;
+        db.apply_migrations()?;
+        Ok(db)
+    }
+
     fn configure(&self) -> AppResult<()> {
         let conn = self.connection.lock();
         conn.execute_batch(
@@ -119,7 +131,15 @@ impl Database {
                 sgf_path TEXT,
                 gtp_log_path TEXT,
                 metadata TEXT,
-                created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+                config TEXT,
+                moves TEXT,
+                move_count INTEGER,
+                to_move TEXT,
+                phase TEXT,
+                consecutive_passes INTEGER,
+                rating_period TEXT,
+                created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                updated_at TEXT
             );
 
             CREATE TABLE IF NOT EXISTS pairings (
@@ -129,6 +149,7 @@ impl Database {
                 table_number INTEGER NOT NULL,
                 black_player_id TEXT REFERENCES players(id) ON DELETE SET NULL,
                 white_player_id TEXT REFERENCES players(id) ON DELETE SET NULL,
+                handicap INTEGER NOT NULL DEFAULT 0,
                 result TEXT,
                 status TEXT NOT NULL DEFAULT 'pending',
                 game_id TEXT REFERENCES games(id) ON DELETE SET NULL,
@@ -136,6 +157,14 @@ impl Database {
                 UNIQUE(tournament_id, round_index, table_number)
             );
 
+            CREATE TABLE IF NOT EXISTS tournament_players (
+                tournament_id TEXT NOT NULL REFERENCES tournaments(id) ON DELETE CASCADE,
+                player_id TEXT NOT NULL REFERENCES players(id) ON DELETE CASCADE,
+                status TEXT NOT NULL DEFAULT 'active',
+                created_at TEXT NOT NULL,
+                PRIMARY KEY(tournament_id, player_id)
+            );
+
             CREATE TABLE IF NOT EXISTS puzzles (
                 id TEXT PRIMARY KEY,
                 name TEXT NOT NULL,
@@ -187,6 +216,8 @@ impl Database {
                 args TEXT NOT NULL,
                 working_directory TEXT,
                 enabled INTEGER NOT NULL DEFAULT 1,
+                kind TEXT NOT NULL DEFAULT 'gtp',
+                capabilities TEXT,
                 created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
                 updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
             );
@@ -201,6 +232,34 @@ impl Database {
                 log_path TEXT
             );
 
+            CREATE TABLE IF NOT EXISTS engine_matches (
+                id TEXT PRIMARY KEY,
+                engine_a_id TEXT NOT NULL REFERENCES gtp_engines(id) ON DELETE CASCADE,
+                engine_b_id TEXT NOT NULL REFERENCES gtp_engines(id) ON DELETE CASCADE,
+                games INTEGER NOT NULL,
+                settings TEXT NOT NULL,
+                status TEXT NOT NULL,
+                summary TEXT,
+                created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                finished_at TEXT
+            );
+
+            CREATE TABLE IF NOT EXISTS analysis_cache (
+                position_hash TEXT NOT NULL,
+                board_size INTEGER NOT NULL,
+                komi REAL NOT NULL,
+                rules TEXT NOT NULL,
+                engine_id TEXT NOT NULL REFERENCES gtp_engines(id) ON DELETE CASCADE,
+                visits INTEGER NOT NULL,
+                winrate REAL NOT NULL,
+                score_lead REAL NOT NULL,
+                moves TEXT NOT NULL,
+                policy TEXT,
+                ownership TEXT,
+                created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                PRIMARY KEY (position_hash, board_size, komi, rules, engine_id)
+            );
+
             CREATE TABLE IF NOT EXISTS sync_peers (
                 id TEXT PRIMARY KEY,
                 name TEXT NOT NULL,
@@ -217,6 +276,18 @@ impl Database {
             CREATE INDEX IF NOT EXISTS idx_gtp_sessions_engine ON gtp_sessions(engine_id);
         "#,
         )?;
+        // Columns added after their table was first released.
+        add_column(&conn, "gtp_engines", "kind", "TEXT NOT NULL DEFAULT 'gtp'")?;
+        add_column(&conn, "gtp_engines", "capabilities", "TEXT")?;
+        add_column(&conn, "games", "config", "TEXT")?;
+        add_column(&conn, "games", "moves", "TEXT")?;
+        add_column(&conn, "games", "updated_at", "TEXT")?;
+        add_column(&conn, "games", "rating_period", "TEXT")?;
+        add_column(&conn, "games", "move_count", "INTEGER")?;
+        add_column(&conn, "games", "to_move", "TEXT")?;
+        add_column(&conn, "games", "phase", "TEXT")?;
+        add_column(&conn, "games", "consecutive_passes", "INTEGER")?;
+        add_column(&conn, "pairings", "handicap", "INTEGER NOT NULL DEFAULT 0")?;
 
         Ok(())
     }
@@ -249,3 +320,17 @@ impl Database {
             .map_err(|_| AppError::other(format!("invalid schema version: {value}")))
     }
 }
+
+fn add_column(conn: &Connection, table: &str, column: &str, definition: &str) -> AppResult<()> {
+    let exists = conn
+        .prepare(&format!(
+            "SELECT 1 FROM pragma_table_info('{table}') WHERE name = ?1"
+        ))?
+        .exists([column])?;
+    if !exists {
+        conn.execute_batch(&format!(
+            "ALTER TABLE {table} ADD COLUMN {column} {definition}"
+        ))?;
+    }
+    Ok(())
+}
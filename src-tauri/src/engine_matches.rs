@@ -0,0 +1,459 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use go_rules::{Color, GameConfig, GameResult, Ruleset};
+use gtp::{MatchSettings, PlayedGame, TimeSettings, WinRate};
+use parking_lot::Mutex;
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use sgf::GameInfo;
+use tauri::Emitter;
+
+use crate::{
+    app_state::AppState,
+    db::Database,
+    errors::{AppError, AppResult},
+    game_store,
+    gtp_sessions::{self, EngineKind, RegisteredEngine},
+};
+
+/// Emitted as a match moves along; see [`MatchProgress`].
+pub const MATCH_PROGRESS_EVENT: &str = "engine-match-progress";
+
+/// Matches currently being played, with a flag that cancels them after the current game.
+#[derive(Clone, Default)]
+pub struct EngineMatches {
+    running: Arc<Mutex<HashMap<String, Arc<AtomicBool>>>>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EngineMatchRequest {
+    /// Engine that takes Black in the first game; colors alternate after that.
+    pub engine_a: String,
+    pub engine_b: String,
+    pub games: u32,
+    pub size: Option<usize>,
+    pub komi: Option<f64>,
+    pub ruleset: Option<Ruleset>,
+    pub main_time_secs: Option<u64>,
+    pub byo_yomi_secs: Option<u64>,
+    pub byo_yomi_stones: Option<u32>,
+    pub max_moves: Option<usize>,
+}
+
+impl EngineMatchRequest {
+    fn settings(&self) -> MatchSettings {
+        let mut config = GameConfig::default();
+        if let Some(size) = self.size {
+            config.size = size;
+        }
+        if let Some(komi) = self.komi {
+            config.komi = komi;
+        }
+        if let Some(ruleset) = self.ruleset {
+            config.ruleset = ruleset;
+        }
+        let time =
+            (self.main_time_secs.is_some() || self.byo_yomi_secs.is_some()).then(|| TimeSettings {
+                main_time: Duration::from_secs(self.main_time_secs.unwrap_or(0)),
+                byo_yomi_time: Duration::from_secs(self.byo_yomi_secs.unwrap_or(0)),
+                byo_yomi_stones: self.byo_yomi_stones.unwrap_or(1),
+            });
+        let max_moves = self.max_moves.unwrap_or(config.size * config.size * 3);
+        MatchSettings {
+            config,
+            time,
+            max_moves,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WinRateInfo {
+    pub rate: f64,
+    pub lower: f64,
+    pub upper: f64,
+}
+
+/// Tallies from engine A's point of view. Jigo counts as a draw; void games, stopped at the
+/// move limit, are left out of the win rate.
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MatchSummary {
+    pub games_played: u32,
+    pub games_total: u32,
+    pub engine_a_wins: u32,
+    pub engine_b_wins: u32,
+    pub draws: u32,
+    pub void_games: u32,
+    pub engine_a_black_wins: u32,
+    pub engine_a_white_wins: u32,
+    pub engine_a_win_rate: Option<WinRateInfo>,
+}
+
+impl MatchSummary {
+    fn record(&mut self, result: &GameResult, engine_a_color: Color) {
+        self.games_played += 1;
+        match result.winner() {
+            Some(winner) if winner == engine_a_color => {
+                self.engine_a_wins += 1;
+                match engine_a_color {
+                    Color::Black => self.engine_a_black_wins += 1,
+                    Color::White => self.engine_a_white_wins += 1,
+                }
+            }
+            Some(_) => self.engine_b_wins += 1,
+            None if matches!(result, GameResult::NoResult) => self.void_games += 1,
+            None => self.draws += 1,
+        }
+        let decided = self.games_played - self.void_games;
+        if decided == 0 {
+            return;
+        }
+        let rate = WinRate::new(self.engine_a_wins, self.draws, decided);
+        self.engine_a_win_rate = Some(WinRateInfo {
+            rate: rate.rate,
+            lower: rate.lower,
+            upper: rate.upper,
+        });
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MatchProgress {
+    pub match_id: String,
+    /// `move`, `game`, `finished`, `cancelled` or `failed`.
+    pub kind: &'static str,
+    /// Zero-based index of the game in progress or just finished.
+    pub game_index: u32,
+    pub black_engine_id: String,
+    pub white_engine_id: String,
+    pub move_count: usize,
+    /// SGF result of the finished game, such as `B+R`.
+    pub result: Option<String>,
+    pub game_id: Option<String>,
+    pub summary: MatchSummary,
+    pub error: Option<String>,
+}
+
+impl EngineMatches {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the match and play it in the background, returning its id.
+    pub async fn start(&self, state: &AppState, request: EngineMatchRequest) -> AppResult<String> {
+        if request.games == 0 {
+            return Err(AppError::other("a match needs at least one game"));
+        }
+        let settings = request.settings();
+        if settings.config.size > gtp::MAX_BOARD_SIZE {
+            return Err(AppError::Engine(format!(
+                "GTP engines cannot play on a {0}x{0} board",
+                settings.config.size
+            )));
+        }
+        let engine_a =
+            gtp_sessions::load_engine(state.database(), &request.engine_a, EngineKind::Gtp).await?;
+        let engine_b =
+            gtp_sessions::load_engine(state.database(), &request.engine_b, EngineKind::Gtp).await?;
+
+        let match_id = uuid::Uuid::new_v4().to_string();
+        let db = state.database().clone();
+        let (id, row) = (match_id.clone(), request.clone());
+        tauri::async_runtime::spawn_blocking(move || {
+            db.with_conn(|conn| {
+                conn.execute(
+                    "INSERT INTO engine_matches (id, engine_a_id, engine_b_id, games, settings, status) VALUES (?1, ?2, ?3, ?4, ?5, 'running')",
+                    params![id, row.engine_a, row.engine_b, row.games, serde_json::to_string(&row)?],
+                )?;
+                Ok(())
+            })
+        })
+        .await
+        .map_err(|err| AppError::other(format!("task join error: {err}")))??;
+
+        let cancel = Arc::new(AtomicBool::new(false));
+        self.running.lock().insert(match_id.clone(), cancel.clone());
+        let runner = MatchRunner {
+            state: state.clone(),
+            match_id: match_id.clone(),
+            request,
+            settings,
+            engines: [engine_a, engine_b],
+            cancel,
+            summary: MatchSummary::default(),
+        };
+        let matches = self.clone();
+        tauri::async_runtime::spawn(async move {
+            let match_id = runner.match_id.clone();
+            runner.run().await;
+            matches.running.lock().remove(&match_id);
+        });
+        Ok(match_id)
+    }
+
+    /// Stop a running match once its current game is over. Returns whether it was running.
+    pub fn cancel(&self, match_id: &str) -> bool {
+        match self.running.lock().get(match_id) {
+            Some(cancel) => {
+                cancel.store(true, Ordering::Relaxed);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+struct MatchRunner {
+    state: AppState,
+    match_id: String,
+    request: EngineMatchRequest,
+    settings: MatchSettings,
+    engines: [RegisteredEngine; 2],
+    cancel: Arc<AtomicBool>,
+    summary: MatchSummary,
+}
+
+impl MatchRunner {
+    async fn run(mut self) {
+        self.summary.games_total = self.request.games;
+        let mut index = 0;
+        let (status, error) = loop {
+            if index == self.request.games {
+                break ("finished", None);
+            }
+            if self.cancel.load(Ordering::Relaxed) {
+                break ("cancelled", None);
+            }
+            if let Err(err) = self.play(index).await {
+                break ("failed", Some(err.to_string()));
+            }
+            index += 1;
+        };
+
+        let summary = serde_json::to_string(&self.summary).ok();
+        let db = self.state.database().clone();
+        let match_id = self.match_id.clone();
+        let stored = tauri::async_runtime::spawn_blocking(move || {
+            db.with_conn(|conn| {
+                conn.execute(
+                    "UPDATE engine_matches SET status = ?2, summary = ?3, finished_at = CURRENT_TIMESTAMP WHERE id = ?1",
+                    params![match_id, status, summary],
+                )?;
+                Ok(())
+            })
+        })
+        .await
+        .map_err(|err| AppError::other(format!("task join error: {err}")))
+        .and_then(|stored| stored);
+        // Nobody awaits the match, so a status that could not be stored is reported with the
+        // final progress event.
+        let error = match (error, stored) {
+            (error, Ok(())) => error,
+            (None, Err(err)) => Some(format!("failed to record the match: {err}")),
+            (Some(error), Err(err)) => Some(format!("{error}; failed to record the match: {err}")),
+        };
+        let mut progress = self.progress(status, index.min(self.request.games.saturating_sub(1)));
+        progress.error = error;
+        self.emit(progress);
+    }
+
+    /// Engine A takes Black in even games.
+    fn engine_a_color(index: u32) -> Color {
+        if index.is_multiple_of(2) {
+            Color::Black
+        } else {
+            Color::White
+        }
+    }
+
+    fn seats(&self, index: u32) -> [(&str, &RegisteredEngine); 2] {
+        let a = (self.request.engine_a.as_str(), &self.engines[0]);
+        let b = (self.request.engine_b.as_str(), &self.engines[1]);
+        match Self::engine_a_color(index) {
+            Color::Black => [a, b],
+            Color::White => [b, a],
+        }
+    }
+
+    async fn play(&mut self, index: u32) -> AppResult<()> {
+        let state = self.state.clone();
+        let game_dir = state
+            .data_dir()
+            .join("matches")
+            .join(&self.match_id)
+            .join(format!("game-{:03}", index + 1));
+        let [(black_id, black), (white_id, white)] = self.seats(index);
+        let (black_id, white_id) = (black_id.to_string(), white_id.to_string());
+        let logs = [game_dir.join("black.log"), game_dir.join("white.log")];
+        let sessions = state.gtp();
+        let black_session = sessions
+            .launch_with_log(state.database(), &black_id, &black.command, &logs[0])
+            .await?;
+        let white_session = match sessions
+            .launch_with_log(state.database(), &white_id, &white.command, &logs[1])
+            .await
+        {
+            Ok(session) => session,
+            Err(err) => {
+                sessions.stop(state.database(), &black_session).await?;
+                return Err(err);
+            }
+        };
+
+        let played = self
+            .play_sessions(index, &black_session, &white_session)
+            .await;
+        sessions.stop(state.database(), &black_session).await?;
+        sessions.stop(state.database(), &white_session).await?;
+        let played = played?;
+
+        let info = GameInfo {
+            event: Some("Engine match".to_string()),
+            round: Some((index + 1).to_string()),
+            date: Some(chrono::Utc::now().format("%Y-%m-%d").to_string()),
+            result: Some(played.result.to_string()),
+            black_player: Some(black.name.clone()),
+            white_player: Some(white.name.clone()),
+            ..GameInfo::default()
+        };
+        let sgf_path = game_dir.with_extension("sgf");
+        let (path, record) = (
+            sgf_path.clone(),
+            sgf::write(&[sgf::from_state(&played.state, &info)?]),
+        );
+        tauri::async_runtime::spawn_blocking(move || std::fs::write(path, record))
+            .await
+            .map_err(|err| AppError::other(format!("task join error: {err}")))??;
+        let game_id = self.store_game(index, &played, &sgf_path, &logs).await?;
+
+        self.summary
+            .record(&played.result, Self::engine_a_color(index));
+        let mut progress = self.progress("game", index);
+        progress.move_count = played.state.move_count();
+        progress.result = Some(played.result.to_string());
+        progress.game_id = Some(game_id);
+        self.emit(progress);
+        Ok(())
+    }
+
+    async fn play_sessions(
+        &self,
+        index: u32,
+        black_session: &str,
+        white_session: &str,
+    ) -> AppResult<PlayedGame> {
+        let sessions = self.state.gtp();
+        let missing = || AppError::Engine("match engine exited before the game".to_string());
+        let black = sessions.get(black_session).ok_or_else(missing)?;
+        let white = sessions.get(white_session).ok_or_else(missing)?;
+        let (mut black, mut white) = (black.lock().await, white.lock().await);
+        let played = gtp::play_game(
+            &mut black.engine,
+            &mut white.engine,
+            &self.settings,
+            |game| {
+                let mut progress = self.progress("move", index);
+                progress.move_count = game.move_count();
+                self.emit(progress);
+            },
+        )
+        .await?;
+        Ok(played)
+    }
+
+    /// Record a finished game. `gtp_log_path` holds Black's engine log; both logs are listed
+    /// in the metadata.
+    async fn store_game(
+        &self,
+        index: u32,
+        played: &PlayedGame,
+        sgf_path: &Path,
+        logs: &[PathBuf; 2],
+    ) -> AppResult<String> {
+        let game_id = uuid::Uuid::new_v4().to_string();
+        let [(black_id, _), (white_id, _)] = self.seats(index);
+        let [black_log, white_log] = logs.clone().map(|path| path.to_string_lossy().into_owned());
+        let metadata = serde_json::json!({
+            "matchId": self.match_id,
+            "gameIndex": index,
+            "blackEngineId": black_id,
+            "whiteEngineId": white_id,
+            "blackLogPath": black_log,
+            "whiteLogPath": white_log,
+            "moves": played.state.move_count(),
+            "note": played.note,
+        });
+        let winner = played.result.winner().map(|color| match color {
+            Color::Black => "black",
+            Color::White => "white",
+        });
+        let config = played.state.config();
+        let (size, komi, handicap) = (config.size as i64, config.komi, config.handicap);
+        let (id, result) = (game_id.clone(), played.result.to_string());
+        let config_json = serde_json::to_string(config)?;
+        let moves = serde_json::to_string(&game_store::actions(&played.state))?;
+        let played_at = chrono::Utc::now().to_rfc3339();
+        let sgf_path = sgf_path.to_string_lossy().into_owned();
+        let db: Database = self.state.database().clone();
+        tauri::async_runtime::spawn_blocking(move || {
+            db.with_conn(|conn| {
+                conn.execute(
+                    "INSERT INTO games (id, source, winner, result, board_size, komi, handicap, played_at, sgf_path, gtp_log_path, metadata, config, moves, updated_at)
+                     VALUES (?1, 'engine_match', ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, CURRENT_TIMESTAMP)",
+                    params![
+                        id,
+                        winner,
+                        result,
+                        size,
+                        komi,
+                        handicap,
+                        played_at,
+                        sgf_path,
+                        black_log,
+                        metadata.to_string(),
+                        config_json,
+                        moves
+                    ],
+                )?;
+                Ok(())
+            })
+        })
+        .await
+        .map_err(|err| AppError::other(format!("task join error: {err}")))??;
+        Ok(game_id)
+    }
+
+    fn progress(&self, kind: &'static str, index: u32) -> MatchProgress {
+        let [(black_id, _), (white_id, _)] = self.seats(index);
+        MatchProgress {
+            match_id: self.match_id.clone(),
+            kind,
+            game_index: index,
+            black_engine_id: black_id.to_string(),
+            white_engine_id: white_id.to_string(),
+            move_count: 0,
+            result: None,
+            game_id: None,
+            summary: self.summary.clone(),
+            error: None,
+        }
+    }
+
+    fn emit(&self, progress: MatchProgress) {
+        // Progress is informational: the match carries on and records its results whether or
+        // not a window is listening.
+        let _ = self.state.handle().emit(MATCH_PROGRESS_EVENT, progress);
+    }
+}
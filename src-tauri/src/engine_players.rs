@@ -0,0 +1,269 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use go_rules::{Color, GamePhase, GameState, Move};
+use gtp::EngineMove;
+use parking_lot::Mutex;
+use serde::Serialize;
+use tauri::Emitter;
+use uuid::Uuid;
+
+use crate::{
+    app_state::AppState,
+    errors::{AppError, AppResult},
+    rules_registry::{GameStateSnapshot, RulesError},
+};
+
+/// Emitted after every engine turn, and when an engine fails to take one.
+pub const ENGINE_MOVE_EVENT: &str = "engine-move";
+
+/// GTP sessions seated at registry games, keyed by game.
+#[derive(Clone, Default)]
+pub struct EnginePlayers {
+    seats: Arc<Mutex<HashMap<Uuid, [Option<String>; 2]>>>,
+    /// Games with a task currently playing engine turns.
+    thinking: Arc<Mutex<HashSet<Uuid>>>,
+}
+
+/// What an engine does on its turn.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EngineTurn {
+    PlaceHandicap,
+    Move,
+    AcceptScore,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EngineMoveEvent {
+    pub game_id: Uuid,
+    pub color: Color,
+    /// `handicap`, `play`, `pass`, `resign` or `accept`; absent when the turn failed.
+    pub action: Option<&'static str>,
+    pub snapshot: Option<GameStateSnapshot>,
+    pub error: Option<String>,
+}
+
+impl EnginePlayers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn seat(&self, game_id: Uuid, black: Option<String>, white: Option<String>) {
+        if black.is_some() || white.is_some() {
+            self.seats.lock().insert(game_id, [black, white]);
+        }
+    }
+
+    /// The session playing `color` in a game, if an engine has that seat.
+    pub fn session(&self, game_id: Uuid, color: Color) -> Option<String> {
+        let seats = self.seats.lock();
+        seats.get(&game_id)?[color.index()].clone()
+    }
+
+    /// The engine that should act next in `game`, if any.
+    pub fn next_turn(
+        &self,
+        game_id: Uuid,
+        game: &GameState,
+    ) -> Option<(Color, String, EngineTurn)> {
+        match game.phase() {
+            GamePhase::Playing => {
+                let color = game.to_move();
+                let session = self.session(game_id, color)?;
+                let turn = if game.handicap_pending() > 0 {
+                    EngineTurn::PlaceHandicap
+                } else {
+                    EngineTurn::Move
+                };
+                Some((color, session, turn))
+            }
+            // Engines agree with whatever dead stones the human marks.
+            GamePhase::Scoring => {
+                let scoring = game.scoring_snapshot()?;
+                [
+                    (Color::Black, scoring.black_accepted),
+                    (Color::White, scoring.white_accepted),
+                ]
+                .into_iter()
+                .filter(|(_, accepted)| !accepted)
+                .find_map(|(color, _)| {
+                    let session = self.session(game_id, color)?;
+                    Some((color, session, EngineTurn::AcceptScore))
+                })
+            }
+            GamePhase::Finished => None,
+        }
+    }
+
+    fn begin(&self, game_id: Uuid) -> bool {
+        self.thinking.lock().insert(game_id)
+    }
+
+    fn finish(&self, game_id: Uuid) {
+        self.thinking.lock().remove(&game_id);
+    }
+}
+
+/// Play engine turns in the background until a human is to move or the game is over. Does
+/// nothing if a task is already playing for this game.
+pub fn spawn_engine_turns(state: &AppState, game_id: Uuid) {
+    let engines = state.engines().clone();
+    if !engines.begin(game_id) {
+        return;
+    }
+    let state = state.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let failed = play_engine_turns(&state, game_id).await.err();
+            engines.finish(game_id);
+            if let Some((color, err)) = failed {
+                emit(&state, game_id, color, Err(err));
+                return;
+            }
+            // A human move made while the task was finishing would otherwise go unanswered.
+            let pending = state
+                .rules()
+                .blocking(move |rules| rules.game(game_id))
+                .await
+                .is_ok_and(|game| engines.next_turn(game_id, &game).is_some());
+            if !pending || !engines.begin(game_id) {
+                return;
+            }
+        }
+    });
+}
+
+async fn play_engine_turns(state: &AppState, game_id: Uuid) -> Result<(), (Color, AppError)> {
+    loop {
+        let Ok(game) = state
+            .rules()
+            .blocking(move |rules| rules.game(game_id))
+            .await
+        else {
+            return Ok(());
+        };
+        let Some((color, session_id, turn)) = state.engines().next_turn(game_id, &game) else {
+            return Ok(());
+        };
+        let played = play_turn(state, game_id, &game, color, &session_id, turn)
+            .await
+            .map_err(|err| (color, err))?;
+        if let Some(action) = played {
+            let snapshot = state
+                .rules()
+                .blocking(move |rules| rules.snapshot(game_id))
+                .await
+                .map_err(AppError::other);
+            emit(
+                state,
+                game_id,
+                color,
+                snapshot.map(|snapshot| (action, snapshot)),
+            );
+        }
+    }
+}
+
+/// Take one turn, returning what the engine did, or `None` if the game moved on while the
+/// engine was thinking.
+async fn play_turn(
+    state: &AppState,
+    game_id: Uuid,
+    game: &GameState,
+    color: Color,
+    session_id: &str,
+    turn: EngineTurn,
+) -> AppResult<Option<&'static str>> {
+    let (rules, db) = (state.rules(), state.database());
+    match turn {
+        EngineTurn::AcceptScore => {
+            rules
+                .blocking(move |rules| rules.accept_score(game_id, color))
+                .await
+                .map_err(AppError::other)?;
+            Ok(Some("accept"))
+        }
+        EngineTurn::PlaceHandicap => {
+            let stones = state
+                .gtp()
+                .place_free_handicap(db, session_id, game)
+                .await?;
+            rules
+                .blocking(move |rules| {
+                    stones
+                        .into_iter()
+                        .try_for_each(|point| rules.place_handicap_stone(game_id, point).map(drop))
+                })
+                .await
+                .map_err(AppError::other)?;
+            Ok(Some("handicap"))
+        }
+        EngineTurn::Move => {
+            let reply = state.gtp().genmove(db, session_id, game).await?;
+            let (point, action) = match reply {
+                EngineMove::Play(point) => (Some(point), "play"),
+                EngineMove::Pass => (None, "pass"),
+                EngineMove::Resign => {
+                    rules
+                        .blocking(move |rules| rules.resign(game_id, color))
+                        .await
+                        .map_err(AppError::other)?;
+                    return Ok(Some("resign"));
+                }
+            };
+            let move_count = game.move_count();
+            let played = rules
+                .blocking(move |rules| {
+                    rules.play_move_at(game_id, move_count, Move { color, point })
+                })
+                .await;
+            match played {
+                Ok(Some(_)) => Ok(Some(action)),
+                Ok(None) => {
+                    state.gtp().invalidate(session_id).await;
+                    Ok(None)
+                }
+                Err(RulesError::Rule(violation)) => {
+                    state.gtp().invalidate(session_id).await;
+                    let vertex = point
+                        .map(|point| gtp::format_vertex(point, game.config().size))
+                        .unwrap_or_else(|| "pass".to_string());
+                    Err(AppError::Engine(format!(
+                        "engine played an illegal move {vertex}: {violation}"
+                    )))
+                }
+                Err(err) => Err(AppError::other(err)),
+            }
+        }
+    }
+}
+
+fn emit(
+    state: &AppState,
+    game_id: Uuid,
+    color: Color,
+    turn: AppResult<(&'static str, GameStateSnapshot)>,
+) {
+    let event = match turn {
+        Ok((action, snapshot)) => EngineMoveEvent {
+            game_id,
+            color,
+            action: Some(action),
+            snapshot: Some(snapshot),
+            error: None,
+        },
+        Err(err) => EngineMoveEvent {
+            game_id,
+            color,
+            action: None,
+            snapshot: None,
+            error: Some(err.to_string()),
+        },
+    };
+    // Nothing waits on the engine's turn, so an event the window can no longer receive is
+    // dropped.
+    let _ = state.handle().emit(ENGINE_MOVE_EVENT, event);
+}
@@ -10,6 +10,10 @@ pub enum AppError {
     Serialization(#[from] serde_json::Error),
     #[error("io error: {0}")]
     Io(#[from] std::io::Error),
+    #[error("sgf error: {0}")]
+    Sgf(#[from] sgf::SgfError),
+    #[error("tournament error: {0}")]
+    Tournament(#[from] tournament::TournamentError),
     #[error("engine error: {0}")]
     Engine(String),
     #[error("other error: {0}")]
@@ -38,3 +42,9 @@ impl From<tauri::Error> for AppError {
         Self::Other(err.to_string())
     }
 }
+
+impl From<gtp::GtpError> for AppError {
+    fn from(err: gtp::GtpError) -> Self {
+        Self::Engine(err.to_string())
+    }
+}
@@ -0,0 +1,574 @@
+use go_rules::{
+    Color, GameConfig, GameEvent, GamePhase, GameResult, GameState, HandicapPlacement, Move, Point,
+    RuleViolation, Setup,
+};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    errors::{AppError, AppResult},
+    rules_registry::GameSummary,
+};
+
+/// One step of rebuilding a game. A game is stored in `games.moves` as the actions that
+/// recreate its current position, in order.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum GameAction {
+    /// A freely placed handicap stone; fixed handicaps follow from the config.
+    Handicap {
+        point: Point,
+    },
+    /// Stones added or removed by a game record.
+    Setup(Setup),
+    /// A move, replayed as recorded: imported records may repeat a color or play on after
+    /// both players passed.
+    Play {
+        color: Color,
+        point: Option<Point>,
+    },
+    /// Play resumed from the scoring phase.
+    Resume,
+    Resign {
+        color: Color,
+    },
+    /// A result decided outside the rules, such as a loss on time or a forfeit.
+    Declare {
+        result: GameResult,
+    },
+    /// Every stone marked dead during scoring.
+    DeadStones {
+        points: Vec<Point>,
+    },
+    Accept {
+        color: Color,
+    },
+}
+
+/// The actions that recreate `game`. Undone moves are not kept.
+pub fn actions(game: &GameState) -> Vec<GameAction> {
+    let mut actions = Vec::new();
+    if game.config().handicap_placement == HandicapPlacement::Free {
+        actions.extend(
+            game.handicap_stones()
+                .iter()
+                .map(|&point| GameAction::Handicap { point }),
+        );
+    }
+    let event = |event: &GameEvent| match event {
+        GameEvent::Setup(setup) => GameAction::Setup(setup.clone()),
+        GameEvent::Resume => GameAction::Resume,
+    };
+    let mut events = game.events().iter().peekable();
+    for (played, record) in game.move_history().into_iter().enumerate() {
+        while let Some((_, before)) = events.next_if(|(count, _)| *count <= played) {
+            actions.push(event(before));
+        }
+        actions.push(GameAction::Play {
+            color: record.mv.color,
+            point: record.mv.point,
+        });
+    }
+    actions.extend(events.map(|(_, after)| event(after)));
+    match game.declared_result() {
+        Some(GameResult::Resignation { winner }) => actions.push(GameAction::Resign {
+            color: winner.opponent(),
+        }),
+        Some(result) => actions.push(GameAction::Declare {
+            result: result.clone(),
+        }),
+        None => {}
+    }
+    if let Some(scoring) = game.scoring_snapshot() {
+        actions.push(GameAction::DeadStones {
+            points: scoring.dead_stones,
+        });
+        for (color, accepted) in [
+            (Color::Black, scoring.black_accepted),
+            (Color::White, scoring.white_accepted),
+        ] {
+            if accepted {
+                actions.push(GameAction::Accept { color });
+            }
+        }
+    }
+    actions
+}
+
+/// Rebuild a game by replaying its actions through the rules engine.
+pub fn replay(config: GameConfig, actions: &[GameAction]) -> Result<GameState, RuleViolation> {
+    let mut game = GameState::try_new(config)?;
+    for action in actions {
+        match action {
+            GameAction::Handicap { point } => game.place_handicap_stone(*point)?,
+            GameAction::Setup(setup) => game.apply_setup(setup)?,
+            GameAction::Play { color, point } => game.play_recorded(Move {
+                color: *color,
+                point: *point,
+            })?,
+            GameAction::Resume => game.resume_play()?,
+            GameAction::Resign { color } => game.resign(*color)?,
+            GameAction::Declare { result } => game.declare_result(result.clone())?,
+            GameAction::DeadStones { points } => {
+                // Scoring starts from an estimate; toggle chains until only `points` are dead.
+                for point in game.dead_stones() {
+                    if !points.contains(&point) && game.dead_stones().contains(&point) {
+                        game.toggle_dead(point)?;
+                    }
+                }
+                for &point in points {
+                    if !game.dead_stones().contains(&point) {
+                        game.toggle_dead(point)?;
+                    }
+                }
+            }
+            GameAction::Accept { color } => {
+                game.accept_score(*color)?;
+            }
+        }
+    }
+    Ok(game)
+}
+
+/// Insert or update the `games` row of a registry game. `source` only applies to new rows;
+/// players, tournament links and paths set elsewhere are left alone.
+pub fn save(conn: &Connection, game_id: Uuid, source: &str, game: &GameState) -> AppResult<()> {
+    let config = game.config();
+    let result = game.result();
+    let winner = result
+        .as_ref()
+        .and_then(|result| result.winner())
+        .map(color_name);
+    let played_at = result.is_some().then(|| chrono::Utc::now().to_rfc3339());
+    let (move_count, to_move, phase, consecutive_passes) = summary_columns(game);
+    conn.execute(
+        "INSERT INTO games (id, source, winner, result, board_size, komi, handicap, played_at, config, moves,
+                            move_count, to_move, phase, consecutive_passes, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, CURRENT_TIMESTAMP)
+         ON CONFLICT(id) DO UPDATE SET
+            winner = excluded.winner,
+            result = excluded.result,
+            played_at = CASE WHEN excluded.result IS NULL THEN NULL
+                             ELSE COALESCE(games.played_at, excluded.played_at) END,
+            config = excluded.config,
+            moves = excluded.moves,
+            move_count = excluded.move_count,
+            to_move = excluded.to_move,
+            phase = excluded.phase,
+            consecutive_passes = excluded.consecutive_passes,
+            updated_at = CURRENT_TIMESTAMP",
+        params![
+            game_id.to_string(),
+            source,
+            winner,
+            result.map(|result| result.to_string()),
+            config.size as i64,
+            config.komi,
+            config.handicap,
+            played_at,
+            serde_json::to_string(config)?,
+            serde_json::to_string(&actions(game))?,
+            move_count,
+            to_move,
+            phase,
+            consecutive_passes,
+        ],
+    )?;
+    Ok(())
+}
+
+/// The `games` columns that let a game be listed without replaying it.
+fn summary_columns(game: &GameState) -> (i64, &'static str, &'static str, i64) {
+    (
+        game.move_count() as i64,
+        color_name(game.to_move()),
+        phase_name(game.phase()),
+        i64::from(game.consecutive_passes()),
+    )
+}
+
+/// Rebuild one stored game, or `None` if there is no such game or it was not stored with
+/// its moves.
+pub fn load(conn: &Connection, game_id: Uuid) -> AppResult<Option<GameState>> {
+    let row = conn
+        .query_row(
+            "SELECT config, moves FROM games
+             WHERE id = ?1 AND config IS NOT NULL AND moves IS NOT NULL",
+            params![game_id.to_string()],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+        )
+        .optional()?;
+    row.map(|(config, moves)| rebuild(&config, &moves))
+        .transpose()
+}
+
+/// Stored games without a result, to reload into the registry on startup.
+pub struct ActiveGames {
+    pub games: Vec<(Uuid, GameState)>,
+    /// Games that no longer replay, with the reason.
+    pub unreadable: Vec<(Uuid, String)>,
+}
+
+/// Rebuild the games without a result, keeping those that fail apart.
+pub fn load_active(conn: &Connection) -> AppResult<ActiveGames> {
+    let mut stmt = conn.prepare(
+        "SELECT id, config, moves FROM games
+         WHERE result IS NULL AND config IS NOT NULL AND moves IS NOT NULL",
+    )?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut active = ActiveGames {
+        games: Vec::new(),
+        unreadable: Vec::new(),
+    };
+    for (id, config, moves) in rows {
+        let game_id = Uuid::parse_str(&id).map_err(AppError::other)?;
+        match rebuild(&config, &moves) {
+            Ok(game) => active.games.push((game_id, game)),
+            Err(err) => active.unreadable.push((game_id, err.to_string())),
+        }
+    }
+    Ok(active)
+}
+
+fn rebuild(config: &str, moves: &str) -> AppResult<GameState> {
+    let config: GameConfig = serde_json::from_str(config)?;
+    let actions: Vec<GameAction> = serde_json::from_str(moves)?;
+    replay(config, &actions).map_err(AppError::other)
+}
+
+/// Summaries of every stored game, most recently changed first, read from the summary
+/// columns written by [`save`]. Games stored before those columns existed are replayed once
+/// to fill them in.
+pub fn list(conn: &Connection) -> AppResult<Vec<GameSummary>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, source, config, moves, result, move_count, to_move, phase, consecutive_passes,
+                black_player_id, white_player_id
+         FROM games
+         WHERE config IS NOT NULL AND moves IS NOT NULL
+         ORDER BY COALESCE(updated_at, created_at) DESC",
+    )?;
+    let rows = stmt
+        .query_map([], |row| {
+            let summary = (
+                row.get::<_, Option<i64>>(5)?,
+                row.get::<_, Option<String>>(6)?,
+                row.get::<_, Option<String>>(7)?,
+                row.get::<_, Option<i64>>(8)?,
+            );
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, Option<String>>(4)?,
+                summary,
+                row.get::<_, Option<String>>(9)?,
+                row.get::<_, Option<String>>(10)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut summaries = Vec::with_capacity(rows.len());
+    for (id, source, config, moves, result, stored, black_player_id, white_player_id) in rows {
+        let game_id = Uuid::parse_str(&id).map_err(AppError::other)?;
+        let mut summary = GameSummary {
+            game_id,
+            config: serde_json::from_str(&config)?,
+            to_move: Color::Black,
+            move_count: 0,
+            consecutive_passes: 0,
+            phase: GamePhase::Playing,
+            result,
+            source,
+            black_player_id,
+            white_player_id,
+            load_error: None,
+        };
+        match stored {
+            (Some(move_count), Some(to_move), Some(phase), Some(consecutive_passes)) => {
+                summary.move_count = move_count as usize;
+                summary.to_move = color_from(&to_move)?;
+                summary.phase = phase_from(&phase)?;
+                summary.consecutive_passes = consecutive_passes as u8;
+            }
+            _ => match rebuild(&config, &moves) {
+                Ok(game) => {
+                    let (move_count, to_move, phase, consecutive_passes) = summary_columns(&game);
+                    conn.execute(
+                        "UPDATE games SET move_count = ?2, to_move = ?3, phase = ?4,
+                                          consecutive_passes = ?5
+                         WHERE id = ?1",
+                        params![id, move_count, to_move, phase, consecutive_passes],
+                    )?;
+                    summary.move_count = game.move_count();
+                    summary.to_move = game.to_move();
+                    summary.phase = game.phase();
+                    summary.consecutive_passes = game.consecutive_passes();
+                }
+                Err(err) => summary.load_error = Some(err.to_string()),
+            },
+        }
+        summaries.push(summary);
+    }
+    Ok(summaries)
+}
+
+/// Record who plays each color in a game.
+pub fn set_players(
+    conn: &Connection,
+    game_id: Uuid,
+    black_player_id: Option<&str>,
+    white_player_id: Option<&str>,
+) -> AppResult<()> {
+    conn.execute(
+        "UPDATE games SET black_player_id = ?2, white_player_id = ?3 WHERE id = ?1",
+        params![game_id.to_string(), black_player_id, white_player_id],
+    )?;
+    Ok(())
+}
+
+fn color_name(color: Color) -> &'static str {
+    match color {
+        Color::Black => "black",
+        Color::White => "white",
+    }
+}
+
+fn color_from(name: &str) -> AppResult<Color> {
+    match name {
+        "black" => Ok(Color::Black),
+        "white" => Ok(Color::White),
+        other => Err(AppError::other(format!("unknown color {other}"))),
+    }
+}
+
+fn phase_name(phase: GamePhase) -> &'static str {
+    match phase {
+        GamePhase::Playing => "playing",
+        GamePhase::Scoring => "scoring",
+        GamePhase::Finished => "finished",
+    }
+}
+
+fn phase_from(name: &str) -> AppResult<GamePhase> {
+    match name {
+        "playing" => Ok(GamePhase::Playing),
+        "scoring" => Ok(GamePhase::Scoring),
+        "finished" => Ok(GamePhase::Finished),
+        other => Err(AppError::other(format!("unknown game phase {other}"))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use go_rules::GameTree;
+
+    use super::*;
+    use crate::db::Database;
+
+    fn point(x: usize, y: usize) -> Point {
+        Point::new(x, y).unwrap()
+    }
+
+    fn play(game: &mut GameState, point: Option<Point>) {
+        let color = game.to_move();
+        game.play(Move { color, point }).unwrap();
+    }
+
+    fn pass_until_scoring(game: &mut GameState) {
+        while game.phase() == GamePhase::Playing {
+            play(game, None);
+        }
+    }
+
+    /// Save `game`, load it back and check that it is the same game.
+    fn round_trip(db: &Database, game: &GameState) -> GameState {
+        let game_id = Uuid::new_v4();
+        let loaded = db
+            .with_conn(|conn| {
+                save(conn, game_id, "local", game)?;
+                load(conn, game_id)
+            })
+            .unwrap()
+            .unwrap();
+        assert_eq!(loaded.board().intersections(), game.board().intersections());
+        assert_eq!(
+            (loaded.to_move(), loaded.phase(), loaded.result()),
+            (game.to_move(), game.phase(), game.result())
+        );
+        assert_eq!(
+            (loaded.move_count(), loaded.consecutive_passes()),
+            (game.move_count(), game.consecutive_passes())
+        );
+        assert_eq!(loaded.dead_stones(), game.dead_stones());
+        assert_eq!(
+            loaded
+                .scoring_snapshot()
+                .map(|s| (s.black_accepted, s.white_accepted)),
+            game.scoring_snapshot()
+                .map(|s| (s.black_accepted, s.white_accepted))
+        );
+        loaded
+    }
+
+    #[test]
+    fn stored_games_replay_to_the_state_they_were_saved_in() {
+        let path = std::env::temp_dir().join(format!("game-store-{}.sqlite", Uuid::new_v4()));
+        let db = Database::connect(path.clone()).unwrap();
+        let config = GameConfig {
+            size: 9,
+            ..Default::default()
+        };
+
+        // Play resumed after both players passed, with and without a move since.
+        let mut resumed = GameState::new(config.clone());
+        play(&mut resumed, Some(point(4, 4)));
+        pass_until_scoring(&mut resumed);
+        resumed.resume_play().unwrap();
+        assert_eq!(round_trip(&db, &resumed).phase(), GamePhase::Playing);
+        play(&mut resumed, Some(point(2, 2)));
+        round_trip(&db, &resumed);
+
+        // A lone White stone in Black's area starts out marked dead and is marked alive.
+        let mut marked = GameState::new(config.clone());
+        for y in 0..9 {
+            play(&mut marked, Some(point(4, y)));
+            play(&mut marked, (y == 0).then(|| point(1, 1)));
+        }
+        pass_until_scoring(&mut marked);
+        assert!(marked.dead_stones().contains(&point(1, 1)));
+        marked.toggle_dead(point(1, 1)).unwrap();
+        marked.accept_score(Color::Black).unwrap();
+        let loaded = round_trip(&db, &marked);
+        assert!(!loaded.dead_stones().contains(&point(1, 1)));
+
+        // Setup stones, a color moving twice and play after passes from a game record.
+        let record = "(;GM[1]SZ[9]AB[aa][bb]AW[cc];B[dd];B[ee];AE[aa]AW[hh];W[];B[];W[ff])";
+        let sgf::SgfGame { mut tree, .. } = sgf::read_games(record).unwrap().remove(0);
+        let last = *tree.main_line().last().unwrap();
+        tree.goto(last).unwrap();
+        round_trip(&db, tree.state());
+        let mut setup_only = GameTree::new(config.clone()).unwrap();
+        setup_only
+            .add_setup(Setup {
+                white: vec![point(3, 3)],
+                ..Setup::default()
+            })
+            .unwrap();
+        round_trip(&db, setup_only.state());
+
+        // Engine matches lost on time or by forfeit.
+        let mut timed_out = GameState::new(config.clone());
+        play(&mut timed_out, Some(point(4, 4)));
+        let time = GameResult::Time {
+            winner: Color::Black,
+        };
+        timed_out.declare_result(time.clone()).unwrap();
+        assert_eq!(round_trip(&db, &timed_out).result(), Some(time));
+
+        // Summaries follow the replayed games.
+        let pending = GameState::new(GameConfig {
+            handicap: 3,
+            handicap_placement: HandicapPlacement::Free,
+            ..config.clone()
+        });
+        let mut just_resumed = GameState::new(config);
+        pass_until_scoring(&mut just_resumed);
+        just_resumed.resume_play().unwrap();
+        let ids = [Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4()];
+        let summaries = db
+            .with_conn(|conn| {
+                save(conn, ids[0], "local", &pending)?;
+                save(conn, ids[1], "local", &just_resumed)?;
+                save(conn, ids[2], "local", &marked)?;
+                list(conn)
+            })
+            .unwrap();
+        let summary = |id| summaries.iter().find(|s| s.game_id == id).unwrap();
+        assert_eq!(summary(ids[0]).to_move, Color::Black);
+        assert_eq!(
+            (summary(ids[1]).consecutive_passes, summary(ids[1]).phase),
+            (0, GamePhase::Playing)
+        );
+        assert_eq!(summary(ids[2]).phase, GamePhase::Scoring);
+
+        // Rows stored before the summary columns are replayed once; broken ones are reported.
+        let (legacy, broken) = (Uuid::new_v4(), Uuid::new_v4());
+        let (active, summaries) = db
+            .with_conn(|conn| {
+                save(conn, legacy, "local", &resumed)?;
+                save(
+                    conn,
+                    broken,
+                    "local",
+                    &GameState::new(GameConfig::default()),
+                )?;
+                conn.execute(
+                    "UPDATE games SET move_count = NULL, to_move = NULL, phase = NULL,
+                                      consecutive_passes = NULL
+                     WHERE id IN (?1, ?2)",
+                    params![legacy.to_string(), broken.to_string()],
+                )?;
+                conn.execute(
+                    "UPDATE games SET moves = '[{\"action\":\"resume\"}]' WHERE id = ?1",
+                    params![broken.to_string()],
+                )?;
+                Ok((load_active(conn)?, list(conn)?))
+            })
+            .unwrap();
+        assert!(active.games.iter().any(|(id, _)| *id == legacy));
+        assert!(active.unreadable.iter().any(|(id, _)| *id == broken));
+        let summary = |id| summaries.iter().find(|s| s.game_id == id).unwrap();
+        assert_eq!(summary(legacy).move_count, resumed.move_count());
+        assert!(summary(legacy).load_error.is_none());
+        assert!(summary(broken).load_error.is_some());
+        let backfilled: Option<i64> = db
+            .with_conn(|conn| {
+                Ok(conn.query_row(
+                    "SELECT move_count FROM games WHERE id = ?1",
+                    params![legacy.to_string()],
+                    |row| row.get(0),
+                )?)
+            })
+            .unwrap();
+        assert_eq!(backfilled, Some(resumed.move_count() as i64));
+
+        drop(db);
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{suffix}", path.display()));
+        }
+    }
+
+    #[test]
+    fn undone_setup_stones_stay_undone_after_reloading() {
+        let db = Database::in_memory().unwrap();
+        let record = "(;GM[1]SZ[9]AB[aa];B[dd];W[ee];AE[aa]AW[hh];B[ff])";
+        let sgf::SgfGame { mut tree, .. } = sgf::read_games(record).unwrap().remove(0);
+        let last = *tree.main_line().last().unwrap();
+        tree.goto(last).unwrap();
+        let mut game = tree.state().clone();
+
+        // Taking back W[ee] also takes back the stones set up after it.
+        game.undo().unwrap();
+        game.undo().unwrap();
+        assert_eq!(game.move_count(), 1);
+        let loaded = round_trip(&db, &game);
+        assert_eq!(loaded.board().get(point(0, 0)), Some(Color::Black));
+        assert_eq!(loaded.board().get(point(7, 7)), None);
+        assert_eq!(loaded.redo_count(), 0);
+
+        // Undoing the first move keeps the stones set up in the root.
+        game.undo().unwrap();
+        let loaded = round_trip(&db, &game);
+        assert_eq!(loaded.board().get(point(0, 0)), Some(Color::Black));
+        assert_eq!(loaded.board().get(point(3, 3)), None);
+    }
+}
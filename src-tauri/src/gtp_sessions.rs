@@ -0,0 +1,518 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use go_rules::{GameState, Point};
+use gtp::{
+    AnalysisUpdate, AnalyzeDialect, BoardSync, EngineCommand, EngineMove, GtpEngine, GtpError,
+    KataGoAnalysis, Shutdown,
+};
+use parking_lot::Mutex;
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
+
+use crate::{
+    db::Database,
+    errors::{AppError, AppResult},
+};
+
+/// How long an engine gets to honour `quit` before it is killed.
+const QUIT_GRACE: Duration = Duration::from_secs(3);
+/// Engines may load a network before answering their first command.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(30);
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A running engine together with what it knows of the game it is playing.
+pub struct Session {
+    pub engine: GtpEngine,
+    pub board: BoardSync,
+}
+
+pub type SharedSession = Arc<tokio::sync::Mutex<Session>>;
+
+/// Live engine processes keyed by their `gtp_sessions` id.
+#[derive(Clone, Default)]
+pub struct GtpSessions {
+    sessions: Arc<Mutex<HashMap<String, SharedSession>>>,
+    analyses: Arc<Mutex<HashMap<String, Analysis>>>,
+}
+
+/// A running analysis stream, which holds its session until stopped.
+struct Analysis {
+    stop: oneshot::Sender<()>,
+    done: oneshot::Receiver<()>,
+}
+
+impl GtpSessions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Spawn an engine and record it as a running session, logging its traffic to a
+    /// timestamped file in `log_dir`.
+    pub async fn launch(
+        &self,
+        db: &Database,
+        engine_id: &str,
+        command: &EngineCommand,
+        log_dir: &Path,
+    ) -> AppResult<String> {
+        let session_id = uuid::Uuid::new_v4().to_string();
+        let log_path = log_dir.join(format!(
+            "{}-{session_id}.log",
+            chrono::Utc::now().format("%Y%m%dT%H%M%SZ")
+        ));
+        self.start(db, engine_id, command, &log_path, session_id)
+            .await
+    }
+
+    /// Spawn an engine and record it as a running session, logging its traffic to
+    /// `log_path`.
+    pub async fn launch_with_log(
+        &self,
+        db: &Database,
+        engine_id: &str,
+        command: &EngineCommand,
+        log_path: &Path,
+    ) -> AppResult<String> {
+        let session_id = uuid::Uuid::new_v4().to_string();
+        self.start(db, engine_id, command, log_path, session_id)
+            .await
+    }
+
+    async fn start(
+        &self,
+        db: &Database,
+        engine_id: &str,
+        command: &EngineCommand,
+        log_path: &Path,
+        session_id: String,
+    ) -> AppResult<String> {
+        let started = chrono::Utc::now();
+        let engine = GtpEngine::spawn_logged(command, log_path)?;
+        let pid = engine.pid();
+        let (id, engine_id) = (session_id.clone(), engine_id.to_string());
+        let log_path = log_path.to_string_lossy().into_owned();
+        let now = started.to_rfc3339();
+        let db_task = db.clone();
+        tauri::async_runtime::spawn_blocking(move || {
+            db_task.with_conn(|conn| {
+                conn.execute(
+                    "INSERT INTO gtp_sessions (id, engine_id, status, pid, started_at, log_path) VALUES (?1, ?2, 'running', ?3, ?4, ?5)",
+                    params![id, engine_id, pid, now, log_path],
+                )?;
+                Ok(())
+            })
+        })
+        .await
+        .map_err(|err| AppError::other(format!("task join error: {err}")))??;
+
+        let session = Arc::new(tokio::sync::Mutex::new(Session {
+            engine,
+            board: BoardSync::new(),
+        }));
+        self.sessions
+            .lock()
+            .insert(session_id.clone(), session.clone());
+        tauri::async_runtime::spawn(Self::watch(
+            self.clone(),
+            db.clone(),
+            session_id.clone(),
+            session,
+        ));
+        Ok(session_id)
+    }
+
+    pub fn get(&self, session_id: &str) -> Option<SharedSession> {
+        self.sessions.lock().get(session_id).cloned()
+    }
+
+    fn running(&self, session_id: &str) -> AppResult<SharedSession> {
+        self.get(session_id)
+            .ok_or_else(|| AppError::Engine(format!("session {session_id} is not running")))
+    }
+
+    /// Send a command to a running session. A session whose engine has gone away is marked
+    /// crashed. Raw commands may change the engine's board, so the next game move resyncs it.
+    pub async fn send(&self, db: &Database, session_id: &str, command: &str) -> AppResult<String> {
+        let session = self.running(session_id)?;
+        self.stop_analysis(session_id).await;
+        let response = {
+            let mut session = session.lock().await;
+            session.board.invalidate();
+            session.engine.send(command).await
+        };
+        self.checked(db, session_id, response).await
+    }
+
+    /// Bring the engine's board up to date with `game` and ask it for the next move.
+    pub async fn genmove(
+        &self,
+        db: &Database,
+        session_id: &str,
+        game: &GameState,
+    ) -> AppResult<EngineMove> {
+        let session = self.running(session_id)?;
+        self.stop_analysis(session_id).await;
+        let response = {
+            let mut session = session.lock().await;
+            let Session { engine, board } = &mut *session;
+            board.genmove(engine, game).await
+        };
+        self.checked(db, session_id, response).await
+    }
+
+    /// Let the engine choose Black's free handicap stones for `game`.
+    pub async fn place_free_handicap(
+        &self,
+        db: &Database,
+        session_id: &str,
+        game: &GameState,
+    ) -> AppResult<Vec<Point>> {
+        let session = self.running(session_id)?;
+        self.stop_analysis(session_id).await;
+        let response = {
+            let mut session = session.lock().await;
+            let Session { engine, board } = &mut *session;
+            board.place_free_handicap(engine, game).await
+        };
+        self.checked(db, session_id, response).await
+    }
+
+    /// Start `kata-analyze`, or `lz-analyze` for engines without it, on the position of
+    /// `game`. Each streamed line is passed to `on_update` until [`GtpSessions::stop_analysis`]
+    /// is called or another command is sent to the session.
+    pub async fn analyze(
+        &self,
+        db: &Database,
+        session_id: &str,
+        game: &GameState,
+        interval_centis: u32,
+        ownership: bool,
+        mut on_update: impl FnMut(Result<AnalysisUpdate, String>) + Send + 'static,
+    ) -> AppResult<AnalyzeDialect> {
+        let session = self.running(session_id)?;
+        self.stop_analysis(session_id).await;
+        let mut session = session.lock_owned().await;
+        let started = async {
+            let Session { engine, board } = &mut *session;
+            let mut dialect = None;
+            for candidate in [AnalyzeDialect::KataGo, AnalyzeDialect::Leela] {
+                let known = engine
+                    .send(&format!("known_command {}", candidate.command_name()))
+                    .await?;
+                if known.trim() == "true" {
+                    dialect = Some(candidate);
+                    break;
+                }
+            }
+            let Some(dialect) = dialect else {
+                return Ok(None);
+            };
+            board.sync(engine, game).await?;
+            let command = dialect.command(game.to_move(), interval_centis, ownership);
+            engine.start_stream(&command).await?;
+            Ok(Some(dialect))
+        }
+        .await;
+        let dialect = self
+            .checked(db, session_id, started)
+            .await?
+            .ok_or_else(|| {
+                AppError::Engine("engine supports neither kata-analyze nor lz-analyze".into())
+            })?;
+
+        let (stop, mut stopped) = oneshot::channel();
+        let (finished, done) = oneshot::channel::<()>();
+        self.analyses
+            .lock()
+            .insert(session_id.to_string(), Analysis { stop, done });
+        let size = game.config().size;
+        let (sessions, db, session_id) = (self.clone(), db.clone(), session_id.to_string());
+        tauri::async_runtime::spawn(async move {
+            let failure = loop {
+                let line = tokio::select! {
+                    _ = &mut stopped => break None,
+                    line = session.engine.read_stream_line() => line,
+                };
+                match line {
+                    Ok(Some(line)) => on_update(
+                        gtp::parse_analysis(&line, size, dialect).map_err(|err| err.to_string()),
+                    ),
+                    Ok(None) => break None,
+                    Err(err) => break Some(err),
+                }
+            };
+            let failure = match failure {
+                Some(err) => Some(err),
+                None => session.engine.end_stream().await.err(),
+            };
+            drop(session);
+            if let Some(err) = failure {
+                on_update(Err(err.to_string()));
+                let _ = sessions.checked(&db, &session_id, Err::<(), _>(err)).await;
+            }
+            drop(finished);
+        });
+        Ok(dialect)
+    }
+
+    /// Stop a session's analysis stream, if it has one, and wait until the session is free.
+    pub async fn stop_analysis(&self, session_id: &str) {
+        let analysis = self.analyses.lock().remove(session_id);
+        if let Some(Analysis { stop, done }) = analysis {
+            let _ = stop.send(());
+            let _ = done.await;
+        }
+    }
+
+    /// Make the next game move replay the whole game, after the engine's board and the game
+    /// have diverged.
+    pub async fn invalidate(&self, session_id: &str) {
+        if let Some(session) = self.get(session_id) {
+            session.lock().await.board.invalidate();
+        }
+    }
+
+    async fn checked<T>(
+        &self,
+        db: &Database,
+        session_id: &str,
+        response: Result<T, GtpError>,
+    ) -> AppResult<T> {
+        if let Err(GtpError::Exited(_)) = &response {
+            self.mark_crashed(db, session_id).await?;
+        }
+        Ok(response?)
+    }
+
+    /// Stop a session with `quit`, killing the engine if it does not exit in time.
+    pub async fn stop(&self, db: &Database, session_id: &str) -> AppResult<Option<Shutdown>> {
+        self.stop_analysis(session_id).await;
+        let session = self.sessions.lock().remove(session_id);
+        let shutdown = match session {
+            Some(session) => Some(session.lock().await.engine.quit(QUIT_GRACE).await?),
+            None => None,
+        };
+        set_status(db, session_id, "stopped").await?;
+        Ok(shutdown)
+    }
+
+    async fn mark_crashed(&self, db: &Database, session_id: &str) -> AppResult<()> {
+        if self.sessions.lock().remove(session_id).is_some() {
+            set_status(db, session_id, "crashed").await?;
+        }
+        Ok(())
+    }
+
+    /// Poll the process until it exits or the session is stopped.
+    async fn watch(self, db: Database, session_id: String, session: SharedSession) {
+        loop {
+            tokio::time::sleep(WATCH_INTERVAL).await;
+            if self.get(&session_id).is_none() {
+                return;
+            }
+            // A busy engine is checked again on the next tick; `send` notices exits itself.
+            let exited = match session.try_lock() {
+                Ok(mut session) => session.engine.try_exit_status().is_some(),
+                Err(_) => false,
+            };
+            if exited {
+                let _ = self.mark_crashed(&db, &session_id).await;
+                return;
+            }
+        }
+    }
+}
+
+/// The protocol a registered engine speaks, stored in `gtp_engines.kind`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EngineKind {
+    #[default]
+    Gtp,
+    /// KataGo's `analysis` mode, which reads JSON queries instead of GTP commands.
+    KatagoJson,
+}
+
+impl EngineKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EngineKind::Gtp => "gtp",
+            EngineKind::KatagoJson => "katago_json",
+        }
+    }
+
+    pub fn from_db(value: &str) -> Self {
+        match value {
+            "katago_json" => EngineKind::KatagoJson,
+            _ => EngineKind::Gtp,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            EngineKind::Gtp => "GTP",
+            EngineKind::KatagoJson => "KataGo JSON analysis",
+        }
+    }
+}
+
+/// What probing found out about an engine at registration, stored in
+/// `gtp_engines.capabilities`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EngineCapabilities {
+    pub protocol_version: Option<u32>,
+    pub name: String,
+    pub version: String,
+    pub commands: Vec<String>,
+    /// `kata-analyze` or `lz-analyze`.
+    pub analysis: Option<String>,
+    pub final_status_list: bool,
+    pub time_settings: bool,
+    pub kgs_time_settings: bool,
+    pub rectangular_boards: bool,
+    pub free_handicap: bool,
+    pub undo: bool,
+    /// KataGo's JSON analysis protocol.
+    pub json_analysis: bool,
+}
+
+/// Start an engine to check that it runs and speaks its protocol, then stop it. GTP engines
+/// are asked for their version, name and commands; KataGo JSON engines for their version.
+pub async fn probe_engine(
+    command: &EngineCommand,
+    kind: EngineKind,
+) -> AppResult<EngineCapabilities> {
+    let path = command.path.display().to_string();
+    match kind {
+        EngineKind::Gtp => {
+            let mut engine = GtpEngine::spawn(command)?;
+            let probed = gtp::probe(&mut engine, PROBE_TIMEOUT).await;
+            let _ = engine.quit(QUIT_GRACE).await;
+            let info = probed
+                .map_err(|err| AppError::Engine(format!("{path} is not a GTP engine: {err}")))?;
+            let capabilities = info.capabilities;
+            Ok(EngineCapabilities {
+                protocol_version: Some(info.protocol_version),
+                name: info.name,
+                version: info.version,
+                commands: info.commands,
+                analysis: capabilities
+                    .analysis
+                    .map(|dialect| dialect.command_name().to_string()),
+                final_status_list: capabilities.final_status_list,
+                time_settings: capabilities.time_settings,
+                kgs_time_settings: capabilities.kgs_time_settings,
+                rectangular_boards: capabilities.rectangular_boards,
+                free_handicap: capabilities.free_handicap,
+                undo: capabilities.undo,
+                json_analysis: false,
+            })
+        }
+        EngineKind::KatagoJson => {
+            let mut engine = KataGoAnalysis::spawn(command)?;
+            let probed = engine.query_version(PROBE_TIMEOUT).await;
+            let _ = engine.quit(QUIT_GRACE).await;
+            let version = probed.map_err(|err| {
+                AppError::Engine(format!("{path} is not a KataGo analysis engine: {err}"))
+            })?;
+            Ok(EngineCapabilities {
+                name: "KataGo".to_string(),
+                version,
+                json_analysis: true,
+                ..Default::default()
+            })
+        }
+    }
+}
+
+/// A registered engine and how to start it.
+pub struct RegisteredEngine {
+    pub name: String,
+    pub command: EngineCommand,
+}
+
+/// Look up an enabled engine in `gtp_engines`.
+pub async fn load_engine(
+    db: &Database,
+    engine_id: &str,
+    kind: EngineKind,
+) -> AppResult<RegisteredEngine> {
+    let (db, lookup_id) = (db.clone(), engine_id.to_string());
+    let engine = tauri::async_runtime::spawn_blocking(move || {
+        db.with_conn(|conn| {
+            conn.query_row(
+                "SELECT name, path, args, working_directory, enabled, kind FROM gtp_engines WHERE id = ?1",
+                params![lookup_id],
+                |row| {
+                    let args: String = row.get(2)?;
+                    Ok((
+                        RegisteredEngine {
+                            name: row.get(0)?,
+                            command: EngineCommand {
+                                path: PathBuf::from(row.get::<_, String>(1)?),
+                                args: serde_json::from_str(&args).unwrap_or_default(),
+                                working_directory: row
+                                    .get::<_, Option<String>>(3)?
+                                    .map(PathBuf::from),
+                            },
+                        },
+                        row.get::<_, i64>(4)? != 0,
+                        EngineKind::from_db(&row.get::<_, String>(5)?),
+                    ))
+                },
+            )
+            .optional()
+            .map_err(AppError::from)
+        })
+    })
+    .await
+    .map_err(|err| AppError::other(format!("task join error: {err}")))??;
+
+    match engine {
+        Some((engine, true, registered)) if registered == kind => Ok(engine),
+        Some((_, true, registered)) => Err(AppError::Engine(format!(
+            "engine {engine_id} is a {} engine, not a {} engine",
+            registered.label(),
+            kind.label()
+        ))),
+        Some((_, false, _)) => Err(AppError::Engine(format!("engine {engine_id} is disabled"))),
+        None => Err(AppError::Engine(format!(
+            "engine {engine_id} is not registered"
+        ))),
+    }
+}
+
+/// Sessions left running by a previous run of the app no longer have a process.
+pub fn close_stale_sessions(db: &Database) -> AppResult<()> {
+    db.with_conn(|conn| {
+        conn.execute(
+            "UPDATE gtp_sessions SET status = 'stopped', stopped_at = CURRENT_TIMESTAMP
+             WHERE status IN ('pending', 'running')",
+            [],
+        )?;
+        Ok(())
+    })
+}
+
+async fn set_status(db: &Database, session_id: &str, status: &'static str) -> AppResult<()> {
+    let (db, session_id) = (db.clone(), session_id.to_string());
+    tauri::async_runtime::spawn_blocking(move || {
+        db.with_conn(|conn| {
+            conn.execute(
+                "UPDATE gtp_sessions SET status = ?2, stopped_at = CURRENT_TIMESTAMP
+                 WHERE id = ?1 AND status IN ('pending', 'running')",
+                params![session_id, status],
+            )?;
+            Ok(())
+        })
+    })
+    .await
+    .map_err(|err| AppError::other(format!("task join error: {err}")))?
+}
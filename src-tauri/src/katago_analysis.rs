@@ -0,0 +1,376 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use go_rules::{GameEvent, GameState};
+use gtp::{GtpError, KataGoAnalysis, KataGoQuery, PositionAnalysis};
+use parking_lot::Mutex;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::{
+    analysis::CandidateMove,
+    app_state::AppState,
+    errors::{AppError, AppResult},
+    gtp_sessions::{self, EngineKind},
+};
+
+const QUIT_GRACE: Duration = Duration::from_secs(3);
+
+type SharedEngine = Arc<tokio::sync::Mutex<KataGoAnalysis>>;
+
+/// KataGo analysis engines kept running between reviews, keyed by `gtp_engines` id.
+#[derive(Clone, Default)]
+pub struct KataGoEngines {
+    engines: Arc<Mutex<HashMap<String, SharedEngine>>>,
+}
+
+/// The analysis of one position of a game. Rates, score lead and ownership are from Black's
+/// point of view.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PositionReview {
+    pub move_number: usize,
+    pub position_hash: String,
+    pub visits: u64,
+    pub winrate: f64,
+    pub score_lead: f64,
+    pub moves: Vec<CandidateMove>,
+    pub policy: Option<Vec<f64>>,
+    pub ownership: Option<Vec<f64>>,
+    /// Whether the result came from `analysis_cache` rather than the engine.
+    pub cached: bool,
+}
+
+impl KataGoEngines {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stop an engine's analysis process if it is running.
+    pub async fn stop(&self, engine_id: &str) {
+        let engine = self.engines.lock().remove(engine_id);
+        if let Some(engine) = engine {
+            let _ = engine.lock().await.quit(QUIT_GRACE).await;
+        }
+    }
+
+    async fn engine(&self, state: &AppState, engine_id: &str) -> AppResult<SharedEngine> {
+        if let Some(engine) = self.engines.lock().get(engine_id) {
+            return Ok(engine.clone());
+        }
+        let registered =
+            gtp_sessions::load_engine(state.database(), engine_id, EngineKind::KatagoJson).await?;
+        let log_path = state.data_dir().join("gtp-logs").join(format!(
+            "{}-katago-{engine_id}.log",
+            chrono::Utc::now().format("%Y%m%dT%H%M%SZ")
+        ));
+        let engine = Arc::new(tokio::sync::Mutex::new(KataGoAnalysis::spawn_logged(
+            &registered.command,
+            &log_path,
+        )?));
+        Ok(self
+            .engines
+            .lock()
+            .entry(engine_id.to_string())
+            .or_insert(engine)
+            .clone())
+    }
+}
+
+/// Analyse every position of a registry game up to its current move, reusing cached
+/// results and storing new ones in `analysis_cache`. Only results from the same engine with
+/// at least `max_visits` visits are reused.
+pub async fn review_game(
+    state: &AppState,
+    engine_id: &str,
+    game_id: Uuid,
+    max_visits: Option<u32>,
+) -> AppResult<Vec<PositionReview>> {
+    let game = state
+        .rules()
+        .blocking(move |rules| rules.game(game_id))
+        .await
+        .map_err(AppError::other)?;
+    let set_up_later = game
+        .events()
+        .iter()
+        .any(|(played, event)| *played > 0 && matches!(event, GameEvent::Setup(_)));
+    if set_up_later {
+        return Err(AppError::Engine(
+            "KataGo queries cannot hold stones set up between moves".to_string(),
+        ));
+    }
+    let hashes = position_hashes(&game);
+    let config = game.config();
+    let (size, komi, rules) = (config.size, config.komi, config.ruleset.to_string());
+
+    let db = state.database().clone();
+    let (lookup, rules_key, engine_key) = (hashes.clone(), rules.clone(), engine_id.to_string());
+    let min_visits = u64::from(max_visits.unwrap_or(0));
+    let mut reviews = tauri::async_runtime::spawn_blocking(move || {
+        db.with_conn(|conn| {
+            cached_reviews(
+                conn,
+                &engine_key,
+                size,
+                komi,
+                &rules_key,
+                lookup,
+                min_visits,
+            )
+        })
+    })
+    .await
+    .map_err(|err| AppError::other(format!("task join error: {err}")))??;
+
+    let missing: Vec<usize> = (0..reviews.len())
+        .filter(|turn| reviews[*turn].is_none())
+        .collect();
+    if !missing.is_empty() {
+        let mut query = KataGoQuery::from_game(&game);
+        query.analyze_turns = missing;
+        query.max_visits = max_visits;
+        let analyses = analyze(state, engine_id, &query).await?;
+        let fresh: Vec<PositionReview> = analyses
+            .into_iter()
+            .filter_map(|analysis| {
+                let hash = hashes.get(analysis.turn)?.clone();
+                Some(to_review(analysis, hash))
+            })
+            .collect();
+        store(state, engine_id, size, komi, rules, &fresh).await?;
+        for review in fresh {
+            let move_number = review.move_number;
+            reviews[move_number] = Some(review);
+        }
+    }
+    Ok(reviews.into_iter().flatten().collect())
+}
+
+async fn analyze(
+    state: &AppState,
+    engine_id: &str,
+    query: &KataGoQuery,
+) -> AppResult<Vec<PositionAnalysis>> {
+    let katago = state.katago();
+    let engine = katago.engine(state, engine_id).await?;
+    let analyzed = engine.lock().await.analyze(query).await;
+    match analyzed {
+        Ok(analyses) => Ok(analyses),
+        Err(err @ GtpError::Failure { .. }) => Err(err.into()),
+        // A dead or confused process is replaced on the next review.
+        Err(err) => {
+            katago.stop(engine_id).await;
+            Err(err.into())
+        }
+    }
+}
+
+async fn store(
+    state: &AppState,
+    engine_id: &str,
+    size: usize,
+    komi: f64,
+    rules: String,
+    reviews: &[PositionReview],
+) -> AppResult<()> {
+    let db = state.database().clone();
+    let (engine_id, reviews) = (engine_id.to_string(), reviews.to_vec());
+    tauri::async_runtime::spawn_blocking(move || {
+        db.with_conn(|conn| store_reviews(conn, &engine_id, size, komi, &rules, &reviews))
+    })
+    .await
+    .map_err(|err| AppError::other(format!("task join error: {err}")))?
+}
+
+/// Cached analyses of `hashes` by `engine_id` with at least `min_visits` visits, in order.
+fn cached_reviews(
+    conn: &Connection,
+    engine_id: &str,
+    size: usize,
+    komi: f64,
+    rules: &str,
+    hashes: Vec<String>,
+    min_visits: u64,
+) -> AppResult<Vec<Option<PositionReview>>> {
+    let mut stmt = conn.prepare(
+        "SELECT visits, winrate, score_lead, moves, policy, ownership FROM analysis_cache
+         WHERE position_hash = ?1 AND board_size = ?2 AND komi = ?3 AND rules = ?4
+           AND engine_id = ?5 AND visits >= ?6",
+    )?;
+    let mut reviews = Vec::with_capacity(hashes.len());
+    for (move_number, hash) in hashes.into_iter().enumerate() {
+        let row = stmt
+            .query_row(
+                params![hash, size as i64, komi, rules, engine_id, min_visits as i64],
+                |row| {
+                    Ok((
+                        row.get::<_, i64>(0)?,
+                        row.get::<_, f64>(1)?,
+                        row.get::<_, f64>(2)?,
+                        row.get::<_, String>(3)?,
+                        row.get::<_, Option<String>>(4)?,
+                        row.get::<_, Option<String>>(5)?,
+                    ))
+                },
+            )
+            .optional()?;
+        let review = match row {
+            Some((visits, winrate, score_lead, moves, policy, ownership)) => Some(PositionReview {
+                move_number,
+                position_hash: hash,
+                visits: visits as u64,
+                winrate,
+                score_lead,
+                moves: serde_json::from_str(&moves)?,
+                policy: policy.as_deref().map(serde_json::from_str).transpose()?,
+                ownership: ownership.as_deref().map(serde_json::from_str).transpose()?,
+                cached: true,
+            }),
+            None => None,
+        };
+        reviews.push(review);
+    }
+    Ok(reviews)
+}
+
+/// Cache `reviews`, keeping whichever analysis of a position by an engine has more visits.
+fn store_reviews(
+    conn: &Connection,
+    engine_id: &str,
+    size: usize,
+    komi: f64,
+    rules: &str,
+    reviews: &[PositionReview],
+) -> AppResult<()> {
+    let mut stmt = conn.prepare(
+        "INSERT INTO analysis_cache
+            (position_hash, board_size, komi, rules, engine_id, visits, winrate, score_lead, moves, policy, ownership)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+         ON CONFLICT (position_hash, board_size, komi, rules, engine_id) DO UPDATE SET
+            visits = excluded.visits,
+            winrate = excluded.winrate,
+            score_lead = excluded.score_lead,
+            moves = excluded.moves,
+            policy = excluded.policy,
+            ownership = excluded.ownership,
+            created_at = CURRENT_TIMESTAMP
+         WHERE excluded.visits > analysis_cache.visits",
+    )?;
+    for review in reviews {
+        stmt.execute(params![
+            review.position_hash,
+            size as i64,
+            komi,
+            rules,
+            engine_id,
+            review.visits as i64,
+            review.winrate,
+            review.score_lead,
+            serde_json::to_string(&review.moves)?,
+            review
+                .policy
+                .as_ref()
+                .map(serde_json::to_string)
+                .transpose()?,
+            review
+                .ownership
+                .as_ref()
+                .map(serde_json::to_string)
+                .transpose()?,
+        ])?;
+    }
+    Ok(())
+}
+
+fn to_review(analysis: PositionAnalysis, position_hash: String) -> PositionReview {
+    PositionReview {
+        move_number: analysis.turn,
+        position_hash,
+        visits: analysis.visits,
+        winrate: analysis.winrate,
+        score_lead: analysis.score_lead,
+        moves: analysis
+            .moves
+            .into_iter()
+            .map(CandidateMove::from)
+            .collect(),
+        policy: analysis.policy,
+        ownership: analysis.ownership,
+        cached: false,
+    }
+}
+
+/// The position key, with the player to move, after each move of the game so far.
+fn position_hashes(game: &GameState) -> Vec<String> {
+    let mut replay = game.clone();
+    let mut hashes = Vec::with_capacity(game.move_count() + 1);
+    loop {
+        let key = replay.board().position_key(replay.to_move());
+        hashes.push(format!("{key:016x}"));
+        if replay.undo().is_none() {
+            break;
+        }
+    }
+    hashes.reverse();
+    hashes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+
+    fn review(position_hash: &str, visits: u64, winrate: f64) -> PositionReview {
+        PositionReview {
+            move_number: 0,
+            position_hash: position_hash.to_string(),
+            visits,
+            winrate,
+            score_lead: 0.0,
+            moves: Vec::new(),
+            policy: None,
+            ownership: None,
+            cached: false,
+        }
+    }
+
+    #[test]
+    fn cache_keeps_the_deepest_analysis_of_each_engine() {
+        let db = Database::in_memory().unwrap();
+        let cached = db
+            .with_conn(|conn| {
+                for engine in ["fast", "strong"] {
+                    conn.execute(
+                        "INSERT INTO gtp_engines (id, name, path, args) VALUES (?1, ?1, 'katago', '[]')",
+                        params![engine],
+                    )?;
+                }
+                let store = |engine, reviews: &[PositionReview]| {
+                    store_reviews(conn, engine, 9, 7.0, "Chinese", reviews)
+                };
+                store("strong", &[review("a", 400, 0.6)])?;
+                store("fast", &[review("a", 10, 0.1), review("b", 10, 0.2)])?;
+                store("strong", &[review("a", 100, 0.3)])?;
+                let lookup = |engine, min_visits| {
+                    let hashes = vec!["a".to_string(), "b".to_string()];
+                    cached_reviews(conn, engine, 9, 7.0, "Chinese", hashes, min_visits)
+                };
+                Ok((lookup("strong", 200)?, lookup("fast", 0)?, lookup("fast", 50)?))
+            })
+            .unwrap();
+        let visits = |reviews: &[Option<PositionReview>]| {
+            reviews
+                .iter()
+                .map(|review| {
+                    review
+                        .as_ref()
+                        .map(|review| (review.visits, review.winrate))
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(visits(&cached.0), [Some((400, 0.6)), None]);
+        assert_eq!(visits(&cached.1), [Some((10, 0.1)), Some((10, 0.2))]);
+        assert_eq!(visits(&cached.2), [None, None]);
+    }
+}
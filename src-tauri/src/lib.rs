@@ -1,14 +1,31 @@
+mod analysis;
 mod app_state;
 mod commands;
 mod db;
+mod engine_matches;
+mod engine_players;
 mod errors;
+mod game_store;
+mod gtp_sessions;
+mod katago_analysis;
+mod players;
+mod ratings;
 mod rules_registry;
+mod tournaments;
 
 use app_state::AppState;
 use commands::{
-    bootstrap_app, create_game, fetch_sync_operations, get_game_state, launch_gtp_engine,
-    list_games, list_gtp_engines, play_game_move, push_sync_operations, register_gtp_engine,
-    remove_gtp_engine, score_game, stop_gtp_engine, vacuum_database,
+    accept_score, bootstrap_app, cancel_engine_match, create_game, estimate_ownership, export_sgf,
+    export_tournament_results, fetch_sync_operations, get_game_state, get_player,
+    get_rating_history, get_standings, goto_game_move, import_sgf, launch_gtp_engine,
+    list_engine_matches, list_games, list_gtp_engines, list_gtp_sessions, list_pairings,
+    list_players, list_tournament_players, list_tournaments, pair_tournament_round,
+    place_handicap_stone, play_game_move, push_sync_operations, read_gtp_log,
+    record_pairing_result, redo_game_move, register_gtp_engine, remove_gtp_engine, remove_player,
+    remove_tournament, request_engine_move, resign_game, resume_game, review_game_analysis,
+    run_rating_period, save_player, save_tournament, score_game, send_gtp_command,
+    set_tournament_player, start_analysis, start_engine_match, stop_analysis, stop_gtp_engine,
+    toggle_dead_stones, undo_game_move, vacuum_database,
 };
 use tauri::Manager;
 
@@ -31,14 +48,51 @@ pub fn run() {
             list_games,
             get_game_state,
             play_game_move,
+            place_handicap_stone,
+            undo_game_move,
+            redo_game_move,
+            goto_game_move,
+            toggle_dead_stones,
+            accept_score,
+            resume_game,
+            resign_game,
+            request_engine_move,
+            estimate_ownership,
             score_game,
+            import_sgf,
+            export_sgf,
             push_sync_operations,
             fetch_sync_operations,
             list_gtp_engines,
             register_gtp_engine,
             remove_gtp_engine,
             launch_gtp_engine,
+            send_gtp_command,
             stop_gtp_engine,
+            list_gtp_sessions,
+            read_gtp_log,
+            review_game_analysis,
+            start_engine_match,
+            start_analysis,
+            stop_analysis,
+            cancel_engine_match,
+            list_engine_matches,
+            list_players,
+            get_player,
+            save_player,
+            remove_player,
+            run_rating_period,
+            get_rating_history,
+            list_tournaments,
+            save_tournament,
+            remove_tournament,
+            list_tournament_players,
+            set_tournament_player,
+            pair_tournament_round,
+            list_pairings,
+            record_pairing_result,
+            get_standings,
+            export_tournament_results,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
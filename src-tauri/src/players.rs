@@ -0,0 +1,151 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tournament::Rank;
+use uuid::Uuid;
+
+use crate::errors::{AppError, AppResult};
+
+/// Rating given to new players without a rank, the Glicko default.
+pub const DEFAULT_RATING: f64 = 1500.0;
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerInfo {
+    pub id: String,
+    pub name: String,
+    pub display_name: Option<String>,
+    /// The rank the player holds, as entered.
+    pub rank: Option<Rank>,
+    pub rating: f64,
+    pub rating_deviation: f64,
+    pub rating_volatility: f64,
+    /// The rank that `rating` corresponds to.
+    pub rating_rank: Rank,
+    pub federation_id: Option<String>,
+    pub metadata: Option<Value>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpsertPlayer {
+    pub id: Option<String>,
+    pub name: String,
+    pub display_name: Option<String>,
+    pub rank: Option<Rank>,
+    /// Overrides the rating. New players otherwise start at the rating of their rank.
+    pub rating: Option<f64>,
+    pub federation_id: Option<String>,
+    pub metadata: Option<Value>,
+}
+
+const PLAYER_COLUMNS: &str = "SELECT id, name, display_name, rank, rating, rating_deviation, rating_volatility, federation_id, metadata, created_at, updated_at FROM players";
+
+fn player_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<PlayerInfo> {
+    let rating: f64 = row.get(4)?;
+    Ok(PlayerInfo {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        display_name: row.get(2)?,
+        rank: row
+            .get::<_, Option<i64>>(3)?
+            .and_then(|code| Rank::from_code(code).ok()),
+        rating,
+        rating_deviation: row.get(5)?,
+        rating_volatility: row.get(6)?,
+        rating_rank: Rank::from_rating(rating),
+        federation_id: row.get(7)?,
+        metadata: row
+            .get::<_, Option<String>>(8)?
+            .and_then(|json| serde_json::from_str(&json).ok()),
+        created_at: row.get(9)?,
+        updated_at: row.get(10)?,
+    })
+}
+
+pub fn list(conn: &Connection) -> AppResult<Vec<PlayerInfo>> {
+    let mut stmt = conn.prepare(&format!("{PLAYER_COLUMNS} ORDER BY name, id"))?;
+    let players = stmt
+        .query_map([], player_from_row)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(players)
+}
+
+pub fn get(conn: &Connection, player_id: &str) -> AppResult<Option<PlayerInfo>> {
+    conn.query_row(
+        &format!("{PLAYER_COLUMNS} WHERE id = ?1"),
+        params![player_id],
+        player_from_row,
+    )
+    .optional()
+    .map_err(AppError::from)
+}
+
+/// Add a player, or update one when `id` names an existing player. Updates keep the rating
+/// unless one is given, since it is maintained by rating periods.
+pub fn upsert(conn: &Connection, player: UpsertPlayer) -> AppResult<PlayerInfo> {
+    let name = player.name.trim();
+    if name.is_empty() {
+        return Err(AppError::other("player name must not be empty"));
+    }
+    if player.rating.is_some_and(|rating| !rating.is_finite()) {
+        return Err(AppError::other("player rating must be a number"));
+    }
+    let player_id = player.id.unwrap_or_else(|| Uuid::new_v4().to_string());
+    let initial_rating = player
+        .rating
+        .or(player.rank.map(Rank::to_rating))
+        .unwrap_or(DEFAULT_RATING);
+    let metadata = player
+        .metadata
+        .as_ref()
+        .map(serde_json::to_string)
+        .transpose()?;
+    conn.execute(
+        "INSERT INTO players (id, name, display_name, rank, rating, federation_id, metadata, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+         ON CONFLICT(id) DO UPDATE SET
+            name = excluded.name,
+            display_name = excluded.display_name,
+            rank = excluded.rank,
+            rating = COALESCE(?8, players.rating),
+            federation_id = excluded.federation_id,
+            metadata = excluded.metadata,
+            updated_at = CURRENT_TIMESTAMP",
+        params![
+            player_id,
+            name,
+            player.display_name,
+            player.rank.map(Rank::code),
+            initial_rating,
+            player.federation_id,
+            metadata,
+            player.rating,
+        ],
+    )?;
+    get(conn, &player_id)?.ok_or_else(|| AppError::other(format!("player {player_id} vanished")))
+}
+
+/// Delete a player. Their games and pairings stay, without the link to them.
+pub fn remove(conn: &Connection, player_id: &str) -> AppResult<bool> {
+    let removed = conn.execute("DELETE FROM players WHERE id = ?1", params![player_id])?;
+    Ok(removed > 0)
+}
+
+/// Fail unless every given id names a player.
+pub fn ensure_exist<'a>(
+    conn: &Connection,
+    player_ids: impl IntoIterator<Item = &'a String>,
+) -> AppResult<()> {
+    for player_id in player_ids {
+        let exists = conn
+            .prepare("SELECT 1 FROM players WHERE id = ?1")?
+            .exists(params![player_id])?;
+        if !exists {
+            return Err(AppError::other(format!("player {player_id} not found")));
+        }
+    }
+    Ok(())
+}
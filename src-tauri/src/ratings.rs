@@ -0,0 +1,290 @@
+use std::collections::HashMap;
+
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use tournament::{GlickoSettings, RatedGame, Rating, RatingPeriod};
+use uuid::Uuid;
+
+use crate::errors::{AppError, AppResult};
+
+/// What one rating period changed.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RatingPeriodSummary {
+    pub period: String,
+    pub games: usize,
+    pub players: Vec<RatingChange>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RatingChange {
+    pub player_id: String,
+    pub before: Rating,
+    pub after: Rating,
+    pub games: usize,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RatingHistoryEntry {
+    pub period: String,
+    pub rating: f64,
+    pub rating_deviation: f64,
+    pub rating_volatility: f64,
+    pub created_at: String,
+}
+
+/// Black's score from an SGF result, or `None` for results that are not rated: forfeits,
+/// void games and unknown results.
+fn black_score(result: &str) -> Option<f64> {
+    let result = result.trim();
+    if result.ends_with("+F") {
+        return None;
+    }
+    if result.starts_with("B+") {
+        Some(1.0)
+    } else if result.starts_with("W+") {
+        Some(0.0)
+    } else if result == "0"
+        || result.eq_ignore_ascii_case("draw")
+        || result.eq_ignore_ascii_case("jigo")
+    {
+        Some(0.5)
+    } else {
+        None
+    }
+}
+
+/// Rate every finished game between two players that has not been rated yet as one period
+/// named `period`. All players are updated, so those without games grow less certain, and
+/// each gets a `rating_history` row. A period without any game to rate is refused rather
+/// than only making every rating less certain.
+pub fn run_period(
+    conn: &Connection,
+    period: &str,
+    settings: &GlickoSettings,
+) -> AppResult<RatingPeriodSummary> {
+    let tx = conn.unchecked_transaction()?;
+    let ratings: HashMap<String, Rating> = tx
+        .prepare("SELECT id, rating, rating_deviation, rating_volatility FROM players")?
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                Rating {
+                    rating: row.get(1)?,
+                    deviation: row.get(2)?,
+                    volatility: row.get(3)?,
+                },
+            ))
+        })?
+        .collect::<Result<_, _>>()?;
+
+    let candidates = tx
+        .prepare(
+            "SELECT id, black_player_id, white_player_id, result, handicap, komi FROM games
+             WHERE result IS NOT NULL AND rating_period IS NULL
+               AND black_player_id IS NOT NULL AND white_player_id IS NOT NULL
+               AND black_player_id <> white_player_id
+             ORDER BY COALESCE(played_at, updated_at, created_at)",
+        )?
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, i64>(4)?,
+                row.get::<_, f64>(5)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut rated_games = Vec::new();
+    let mut period_games = RatingPeriod::new();
+    let mut games_played: HashMap<String, usize> = HashMap::new();
+    for (game_id, black, white, result, handicap, komi) in candidates {
+        let Some(black_score) = black_score(&result) else {
+            continue;
+        };
+        *games_played.entry(black.clone()).or_default() += 1;
+        *games_played.entry(white.clone()).or_default() += 1;
+        period_games.add_game(RatedGame {
+            black,
+            white,
+            black_score,
+            handicap: u8::try_from(handicap).unwrap_or(0),
+            komi,
+        });
+        rated_games.push(game_id);
+    }
+    if rated_games.is_empty() {
+        return Err(AppError::other(format!(
+            "no unrated games to close rating period {period} with"
+        )));
+    }
+
+    let updated = period_games.rate(&ratings, settings);
+    let mut players = Vec::with_capacity(updated.len());
+    {
+        let mut update = tx.prepare(
+            "UPDATE players SET rating = ?2, rating_deviation = ?3, rating_volatility = ?4,
+                updated_at = CURRENT_TIMESTAMP
+             WHERE id = ?1",
+        )?;
+        let mut history = tx.prepare(
+            "INSERT INTO rating_history (id, player_id, rating, rating_deviation, rating_volatility, period, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, CURRENT_TIMESTAMP)",
+        )?;
+        let mut mark = tx.prepare("UPDATE games SET rating_period = ?2 WHERE id = ?1")?;
+        for (player_id, after) in updated {
+            update.execute(params![
+                player_id,
+                after.rating,
+                after.deviation,
+                after.volatility
+            ])?;
+            history.execute(params![
+                Uuid::new_v4().to_string(),
+                player_id,
+                after.rating,
+                after.deviation,
+                after.volatility,
+                period,
+            ])?;
+            players.push(RatingChange {
+                before: ratings.get(&player_id).copied().unwrap_or_default(),
+                after,
+                games: games_played.get(&player_id).copied().unwrap_or(0),
+                player_id,
+            });
+        }
+        for game_id in &rated_games {
+            mark.execute(params![game_id, period])?;
+        }
+    }
+    tx.commit()?;
+
+    players.sort_by(|a, b| b.after.rating.total_cmp(&a.after.rating));
+    Ok(RatingPeriodSummary {
+        period: period.to_string(),
+        games: rated_games.len(),
+        players,
+    })
+}
+
+/// A player's ratings after each period, oldest first.
+pub fn history(conn: &Connection, player_id: &str) -> AppResult<Vec<RatingHistoryEntry>> {
+    let mut stmt = conn.prepare(
+        "SELECT period, rating, rating_deviation, rating_volatility, created_at FROM rating_history
+         WHERE player_id = ?1 ORDER BY created_at, rowid",
+    )?;
+    let entries = stmt
+        .query_map(params![player_id], |row| {
+            Ok(RatingHistoryEntry {
+                period: row.get(0)?,
+                rating: row.get(1)?,
+                rating_deviation: row.get(2)?,
+                rating_volatility: row.get(3)?,
+                created_at: row.get(4)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::Database,
+        players::{self, UpsertPlayer},
+    };
+
+    fn player(conn: &Connection, name: &str) -> String {
+        let player = players::upsert(
+            conn,
+            UpsertPlayer {
+                id: None,
+                name: name.to_string(),
+                display_name: None,
+                rank: None,
+                rating: None,
+                federation_id: None,
+                metadata: None,
+            },
+        )
+        .unwrap();
+        player.id
+    }
+
+    fn game(conn: &Connection, black: &str, white: &str, result: Option<&str>) -> String {
+        let id = Uuid::new_v4().to_string();
+        conn.execute(
+            "INSERT INTO games (id, black_player_id, white_player_id, result) VALUES (?1, ?2, ?3, ?4)",
+            params![id, black, white, result],
+        )
+        .unwrap();
+        id
+    }
+
+    #[test]
+    fn a_period_rates_the_unrated_games_once() {
+        let db = Database::in_memory().unwrap();
+        db.with_conn(|conn| {
+            let (alice, bob, carol) = (
+                player(conn, "Alice"),
+                player(conn, "Bob"),
+                player(conn, "Carol"),
+            );
+            // Below the starting deviation, so that sitting out the period shows.
+            conn.execute(
+                "UPDATE players SET rating_deviation = 100.0 WHERE id = ?1",
+                params![carol],
+            )?;
+            let won = game(conn, &alice, &bob, Some("B+R"));
+            let unfinished = game(conn, &bob, &alice, None);
+            let forfeit = game(conn, &bob, &alice, Some("W+F"));
+
+            let summary = run_period(conn, "2026-10", &GlickoSettings::default())?;
+            assert_eq!(summary.games, 1);
+            assert_eq!(summary.players.len(), 3);
+            let change = |id: &str| {
+                summary
+                    .players
+                    .iter()
+                    .find(|change| change.player_id == id)
+                    .unwrap()
+                    .clone()
+            };
+            assert!(change(&alice).after.rating > change(&alice).before.rating);
+            assert!(change(&bob).after.rating < change(&bob).before.rating);
+            assert_eq!(change(&carol).games, 0);
+            assert!(change(&carol).after.deviation > change(&carol).before.deviation);
+
+            let stored = players::get(conn, &alice)?.unwrap();
+            assert_eq!(stored.rating, change(&alice).after.rating);
+            assert_eq!(stored.rating_deviation, change(&alice).after.deviation);
+            for id in [&alice, &bob, &carol] {
+                let entries = history(conn, id)?;
+                assert_eq!(entries.len(), 1);
+                assert_eq!(entries[0].period, "2026-10");
+            }
+            let period = |id: &str| -> rusqlite::Result<Option<String>> {
+                conn.query_row(
+                    "SELECT rating_period FROM games WHERE id = ?1",
+                    params![id],
+                    |row| row.get(0),
+                )
+            };
+            assert_eq!(period(&won)?.as_deref(), Some("2026-10"));
+            assert_eq!(period(&unfinished)?, None);
+            assert_eq!(period(&forfeit)?, None);
+
+            // Every game is rated now, so the next period has nothing to rate.
+            assert!(run_period(conn, "2026-11", &GlickoSettings::default()).is_err());
+            assert_eq!(history(conn, &carol)?.len(), 1);
+            Ok(())
+        })
+        .unwrap();
+    }
+}
@@ -1,24 +1,45 @@
 use std::{collections::HashMap, sync::Arc};
 
 use go_rules::{
-    BoardSnapshot, Captures, Color, GameConfig, GameState, Move, MoveOutcome, Point, RuleViolation,
-    ScoreSummary,
+    BoardSnapshot, Captures, Color, GameConfig, GamePhase, GameResult, GameState, Move,
+    MoveOutcome, OwnershipEstimate, Point, RuleViolation, ScoreSummary, ScoringSnapshot,
 };
 use parking_lot::Mutex;
 use serde::Serialize;
 use uuid::Uuid;
 
-#[derive(Clone, Debug)]
+use crate::{db::Database, errors::AppResult, game_store};
+
+type SharedGame = Arc<Mutex<GameState>>;
+
+/// Games in play, kept in memory and written through to the `games` table. Stored games
+/// that are not in memory, such as finished ones, are rebuilt when first used.
+///
+/// Calls block on the database; async code runs them through [`RulesRegistry::blocking`].
+/// A change is kept in memory only once it has been stored, and only holds up other calls
+/// for the same game while it is written.
+#[derive(Clone)]
 pub struct RulesRegistry {
-    games: Arc<Mutex<HashMap<Uuid, GameState>>>,
+    games: Arc<Mutex<HashMap<Uuid, SharedGame>>>,
+    /// Games in progress that failed to replay on startup, with the reason.
+    unreadable: Arc<HashMap<Uuid, String>>,
+    database: Database,
 }
 
 #[derive(Debug, thiserror::Error)]
 pub enum RulesError {
     #[error("game {0} not found")]
     NotFound(Uuid),
+    #[error("game {0} has no move to undo")]
+    NothingToUndo(Uuid),
+    #[error("game {0} has no move to redo")]
+    NothingToRedo(Uuid),
     #[error(transparent)]
     Rule(#[from] RuleViolation),
+    #[error("failed to store game {0}: {1}")]
+    Storage(Uuid, String),
+    #[error("task join error: {0}")]
+    Join(String),
 }
 
 #[derive(Clone, Debug, Serialize)]
@@ -29,8 +50,14 @@ pub struct GameStateSnapshot {
     pub to_move: Color,
     pub legal_moves: Vec<Point>,
     pub consecutive_passes: u8,
+    pub no_result: bool,
+    pub phase: GamePhase,
+    pub scoring: Option<ScoringSnapshot>,
+    pub result: Option<GameResult>,
     pub config: GameConfig,
     pub move_count: usize,
+    pub redo_count: usize,
+    pub handicap_pending: u8,
 }
 
 #[derive(Clone, Debug, Serialize)]
@@ -40,66 +67,257 @@ pub struct GameSummary {
     pub to_move: Color,
     pub move_count: usize,
     pub consecutive_passes: u8,
+    pub phase: GamePhase,
+    /// The result in SGF notation once the game is over.
+    pub result: Option<String>,
+    /// `local`, `sgf` or `engine_match`.
+    pub source: String,
+    pub black_player_id: Option<String>,
+    pub white_player_id: Option<String>,
+    /// Why the stored game could not be replayed, in which case the move count, side to move
+    /// and phase are placeholders.
+    pub load_error: Option<String>,
 }
 
 impl RulesRegistry {
-    pub fn new() -> Self {
-        Self {
-            games: Arc::new(Mutex::new(HashMap::new())),
-        }
+    /// A registry holding the stored games that are still in progress.
+    pub fn load(database: Database) -> AppResult<Self> {
+        let active = database.with_conn(game_store::load_active)?;
+        let games = active
+            .games
+            .into_iter()
+            .map(|(game_id, game)| (game_id, Arc::new(Mutex::new(game))))
+            .collect();
+        Ok(Self {
+            games: Arc::new(Mutex::new(games)),
+            unreadable: Arc::new(active.unreadable.into_iter().collect()),
+            database,
+        })
+    }
+
+    /// Run registry calls on the blocking thread pool.
+    pub async fn blocking<T, F>(&self, call: F) -> Result<T, RulesError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Self) -> Result<T, RulesError> + Send + 'static,
+    {
+        let registry = self.clone();
+        tauri::async_runtime::spawn_blocking(move || call(&registry))
+            .await
+            .map_err(|err| RulesError::Join(err.to_string()))?
+    }
+
+    pub fn create_game(&self, config: GameConfig) -> Result<(Uuid, GameStateSnapshot), RulesError> {
+        let game = GameState::try_new(config)?;
+        let snapshot = self.insert_game(game, "local")?;
+        Ok((snapshot.game_id, snapshot))
     }
 
-    pub fn create_game(&self, config: GameConfig) -> (Uuid, GameStateSnapshot) {
-        let game = GameState::new(config.clone());
+    /// Register an existing game, such as one read from an SGF file. `source` is recorded in
+    /// `games.source`.
+    pub fn insert_game(
+        &self,
+        game: GameState,
+        source: &str,
+    ) -> Result<GameStateSnapshot, RulesError> {
         let game_id = Uuid::new_v4();
-        let mut games = self.games.lock();
-        games.insert(game_id, game);
-        drop(games);
-        let snapshot = self.snapshot(game_id).expect("game just inserted");
-        (game_id, snapshot)
-    }
-
-    pub fn list_games(&self) -> Vec<GameSummary> {
-        let games = self.games.lock();
-        games
-            .iter()
-            .map(|(id, game)| GameSummary {
-                game_id: *id,
-                config: game.config().clone(),
-                to_move: game.to_move(),
-                move_count: game.move_count(),
-                consecutive_passes: game.consecutive_passes(),
-            })
-            .collect()
+        self.save(game_id, source, &game)?;
+        let snapshot = Self::snapshot_of(game_id, &game);
+        self.games
+            .lock()
+            .insert(game_id, Arc::new(Mutex::new(game)));
+        Ok(snapshot)
+    }
+
+    /// Every stored game, most recently changed first, with games in memory reported from
+    /// their live state and games that failed to load on startup flagged with the reason.
+    pub fn list_games(&self) -> AppResult<Vec<GameSummary>> {
+        let mut summaries = self.database.with_conn(game_store::list)?;
+        let games = self.games.lock().clone();
+        for summary in &mut summaries {
+            if let Some(game) = games.get(&summary.game_id) {
+                Self::refresh(summary, &game.lock());
+            } else if let Some(err) = self.unreadable.get(&summary.game_id) {
+                summary.load_error = Some(err.clone());
+            }
+        }
+        Ok(summaries)
+    }
+
+    /// Bring a stored summary up to date with the game in memory.
+    fn refresh(summary: &mut GameSummary, game: &GameState) {
+        summary.config = game.config().clone();
+        summary.to_move = game.to_move();
+        summary.move_count = game.move_count();
+        summary.consecutive_passes = game.consecutive_passes();
+        summary.phase = game.phase();
+        summary.result = game.result().map(|result| result.to_string());
+    }
+
+    /// The game from memory, rebuilt from the `games` table if it is not there yet.
+    fn loaded(&self, game_id: Uuid) -> Result<SharedGame, RulesError> {
+        if let Some(game) = self.games.lock().get(&game_id) {
+            return Ok(game.clone());
+        }
+        let stored = self
+            .database
+            .with_conn(|conn| game_store::load(conn, game_id))
+            .map_err(|err| RulesError::Storage(game_id, err.to_string()))?
+            .ok_or(RulesError::NotFound(game_id))?;
+        Ok(self
+            .games
+            .lock()
+            .entry(game_id)
+            .or_insert_with(|| Arc::new(Mutex::new(stored)))
+            .clone())
+    }
+
+    fn read<T>(&self, game_id: Uuid, view: impl FnOnce(&GameState) -> T) -> Result<T, RulesError> {
+        let game = self.loaded(game_id)?;
+        let game = game.lock();
+        Ok(view(&game))
+    }
+
+    /// Apply `change` to a copy of the game and store it, replacing the game in memory only
+    /// once the copy is stored. On any error the game is left as it was.
+    fn update<T>(
+        &self,
+        game_id: Uuid,
+        change: impl FnOnce(&mut GameState) -> Result<T, RulesError>,
+    ) -> Result<T, RulesError> {
+        let game = self.loaded(game_id)?;
+        let mut game = game.lock();
+        let mut changed = game.clone();
+        let value = change(&mut changed)?;
+        self.save(game_id, "local", &changed)?;
+        *game = changed;
+        Ok(value)
+    }
+
+    fn save(&self, game_id: Uuid, source: &str, game: &GameState) -> Result<(), RulesError> {
+        self.database
+            .with_conn(|conn| game_store::save(conn, game_id, source, game))
+            .map_err(|err| RulesError::Storage(game_id, err.to_string()))
     }
 
     pub fn snapshot(&self, game_id: Uuid) -> Result<GameStateSnapshot, RulesError> {
-        let games = self.games.lock();
-        let game = games.get(&game_id).ok_or(RulesError::NotFound(game_id))?;
-        Ok(GameStateSnapshot {
+        self.read(game_id, |game| Self::snapshot_of(game_id, game))
+    }
+
+    /// Copy of the full game state, for exporting it.
+    pub fn game(&self, game_id: Uuid) -> Result<GameState, RulesError> {
+        self.read(game_id, GameState::clone)
+    }
+
+    fn snapshot_of(game_id: Uuid, game: &GameState) -> GameStateSnapshot {
+        GameStateSnapshot {
             game_id,
             board: game.board_snapshot(),
             captures: game.captures(),
             to_move: game.to_move(),
             legal_moves: game.legal_moves(),
             consecutive_passes: game.consecutive_passes(),
+            no_result: game.no_result(),
+            phase: game.phase(),
+            scoring: game.scoring_snapshot(),
+            result: game.result(),
             config: game.config().clone(),
             move_count: game.move_count(),
-        })
+            redo_count: game.redo_count(),
+            handicap_pending: game.handicap_pending(),
+        }
     }
 
     pub fn play_move(&self, game_id: Uuid, mv: Move) -> Result<MoveOutcome, RulesError> {
-        let mut games = self.games.lock();
-        let game = games
-            .get_mut(&game_id)
-            .ok_or(RulesError::NotFound(game_id))?;
-        let outcome = game.play(mv)?;
-        Ok(outcome)
+        self.update(game_id, |game| Ok(game.play(mv)?))
+    }
+
+    /// Play `mv` only if the game still has `move_count` moves, so that a reply worked out
+    /// for a position the players have since left is dropped. Returns `None` when it was.
+    pub fn play_move_at(
+        &self,
+        game_id: Uuid,
+        move_count: usize,
+        mv: Move,
+    ) -> Result<Option<MoveOutcome>, RulesError> {
+        if self.read(game_id, GameState::move_count)? != move_count {
+            return Ok(None);
+        }
+        self.update(game_id, |game| {
+            if game.move_count() != move_count {
+                return Ok(None);
+            }
+            Ok(Some(game.play(mv)?))
+        })
+    }
+
+    pub fn place_handicap_stone(
+        &self,
+        game_id: Uuid,
+        point: Point,
+    ) -> Result<GameStateSnapshot, RulesError> {
+        self.update(game_id, |game| {
+            game.place_handicap_stone(point)?;
+            Ok(Self::snapshot_of(game_id, game))
+        })
+    }
+
+    pub fn undo_move(&self, game_id: Uuid) -> Result<GameStateSnapshot, RulesError> {
+        self.update(game_id, |game| {
+            game.undo().ok_or(RulesError::NothingToUndo(game_id))?;
+            Ok(Self::snapshot_of(game_id, game))
+        })
+    }
+
+    pub fn redo_move(&self, game_id: Uuid) -> Result<GameStateSnapshot, RulesError> {
+        self.update(game_id, |game| {
+            game.redo()?.ok_or(RulesError::NothingToRedo(game_id))?;
+            Ok(Self::snapshot_of(game_id, game))
+        })
+    }
+
+    pub fn goto_move(
+        &self,
+        game_id: Uuid,
+        move_number: usize,
+    ) -> Result<GameStateSnapshot, RulesError> {
+        self.update(game_id, |game| {
+            game.goto_move(move_number)?;
+            Ok(Self::snapshot_of(game_id, game))
+        })
+    }
+
+    pub fn toggle_dead_stones(
+        &self,
+        game_id: Uuid,
+        point: Point,
+    ) -> Result<ScoringSnapshot, RulesError> {
+        self.update(game_id, |game| Ok(game.toggle_dead(point)?))
+    }
+
+    pub fn accept_score(&self, game_id: Uuid, color: Color) -> Result<ScoringSnapshot, RulesError> {
+        self.update(game_id, |game| Ok(game.accept_score(color)?))
+    }
+
+    pub fn resume_game(&self, game_id: Uuid) -> Result<GameStateSnapshot, RulesError> {
+        self.update(game_id, |game| {
+            game.resume_play()?;
+            Ok(Self::snapshot_of(game_id, game))
+        })
+    }
+
+    pub fn resign(&self, game_id: Uuid, color: Color) -> Result<GameStateSnapshot, RulesError> {
+        self.update(game_id, |game| {
+            game.resign(color)?;
+            Ok(Self::snapshot_of(game_id, game))
+        })
+    }
+
+    pub fn estimate_ownership(&self, game_id: Uuid) -> Result<OwnershipEstimate, RulesError> {
+        self.read(game_id, GameState::ownership_estimate)
     }
 
     pub fn score(&self, game_id: Uuid) -> Result<ScoreSummary, RulesError> {
-        let games = self.games.lock();
-        let game = games.get(&game_id).ok_or(RulesError::NotFound(game_id))?;
-        Ok(game.score())
+        self.read(game_id, GameState::score)
     }
 }
@@ -0,0 +1,805 @@
+use std::collections::HashMap;
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use tournament::{
+    Entrant, PairingSettings, Rank, ResultsFormat, ResultsInfo, ResultsPlayer, Round, RoundGame,
+    RoundResult, Standing, TieBreaker, TournamentError,
+};
+use uuid::Uuid;
+
+use crate::{
+    errors::{AppError, AppResult},
+    players,
+};
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TournamentInfo {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub start_date: Option<String>,
+    pub end_date: Option<String>,
+    /// Planned number of rounds; 0 when open-ended.
+    pub rounds: u32,
+    pub board_size: u32,
+    pub komi: f64,
+    pub ruleset: String,
+    pub pairing_settings: PairingSettings,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpsertTournament {
+    pub id: Option<String>,
+    pub name: String,
+    pub description: Option<String>,
+    pub start_date: Option<String>,
+    pub end_date: Option<String>,
+    pub rounds: Option<u32>,
+    pub board_size: Option<u32>,
+    pub komi: Option<f64>,
+    pub ruleset: Option<String>,
+    pub pairing_settings: Option<PairingSettings>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TournamentPlayer {
+    pub player_id: String,
+    pub name: String,
+    pub rank: Option<Rank>,
+    pub rating: f64,
+    /// `active` or `withdrawn`.
+    pub status: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PairingInfo {
+    pub id: String,
+    pub tournament_id: String,
+    pub round_index: u32,
+    pub table_number: u32,
+    pub black_player_id: Option<String>,
+    /// `None` for a bye.
+    pub white_player_id: Option<String>,
+    pub handicap: u8,
+    pub result: Option<String>,
+    /// `pending`, `finished` or `bye`.
+    pub status: String,
+    pub game_id: Option<String>,
+}
+
+/// The standings after a round.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StandingsTable {
+    pub round: u32,
+    /// The criteria of `rows[].tieBreakers`, in order.
+    pub tie_breakers: Vec<TieBreaker>,
+    pub rows: Vec<StandingRow>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StandingRow {
+    #[serde(flatten)]
+    pub standing: Standing,
+    pub name: String,
+    pub rank: Option<Rank>,
+    pub rating: f64,
+    pub withdrawn: bool,
+}
+
+const TOURNAMENT_COLUMNS: &str = "SELECT id, name, description, start_date, end_date, rounds, board_size, komi, ruleset, pairing_settings, created_at, updated_at FROM tournaments";
+
+fn tournament_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<TournamentInfo> {
+    let settings: String = row.get(9)?;
+    Ok(TournamentInfo {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        description: row.get(2)?,
+        start_date: row.get(3)?,
+        end_date: row.get(4)?,
+        rounds: row.get(5)?,
+        board_size: row.get(6)?,
+        komi: row.get(7)?,
+        ruleset: row.get(8)?,
+        pairing_settings: serde_json::from_str(&settings).unwrap_or_default(),
+        created_at: row.get(10)?,
+        updated_at: row.get(11)?,
+    })
+}
+
+pub fn list(conn: &Connection) -> AppResult<Vec<TournamentInfo>> {
+    let mut stmt = conn.prepare(&format!(
+        "{TOURNAMENT_COLUMNS} ORDER BY COALESCE(start_date, created_at) DESC, name"
+    ))?;
+    let tournaments = stmt
+        .query_map([], tournament_from_row)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(tournaments)
+}
+
+pub fn get(conn: &Connection, tournament_id: &str) -> AppResult<TournamentInfo> {
+    conn.query_row(
+        &format!("{TOURNAMENT_COLUMNS} WHERE id = ?1"),
+        params![tournament_id],
+        tournament_from_row,
+    )
+    .optional()?
+    .ok_or_else(|| AppError::other(format!("tournament {tournament_id} not found")))
+}
+
+pub fn upsert(conn: &Connection, tournament: UpsertTournament) -> AppResult<TournamentInfo> {
+    let name = tournament.name.trim();
+    if name.is_empty() {
+        return Err(AppError::other("tournament name must not be empty"));
+    }
+    let tournament_id = tournament.id.unwrap_or_else(|| Uuid::new_v4().to_string());
+    let settings = serde_json::to_string(&tournament.pairing_settings.unwrap_or_default())?;
+    conn.execute(
+        "INSERT INTO tournaments (id, name, description, start_date, end_date, rounds, board_size, komi, ruleset, pairing_settings, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+         ON CONFLICT(id) DO UPDATE SET
+            name = excluded.name,
+            description = excluded.description,
+            start_date = excluded.start_date,
+            end_date = excluded.end_date,
+            rounds = excluded.rounds,
+            board_size = excluded.board_size,
+            komi = excluded.komi,
+            ruleset = excluded.ruleset,
+            pairing_settings = excluded.pairing_settings,
+            updated_at = CURRENT_TIMESTAMP",
+        params![
+            tournament_id,
+            name,
+            tournament.description,
+            tournament.start_date,
+            tournament.end_date,
+            tournament.rounds.unwrap_or(0),
+            tournament.board_size.unwrap_or(19),
+            tournament.komi.unwrap_or(6.5),
+            tournament.ruleset.as_deref().unwrap_or("AGA"),
+            settings,
+        ],
+    )?;
+    get(conn, &tournament_id)
+}
+
+/// Delete a tournament with its rounds and pairings. Its games stay.
+pub fn remove(conn: &Connection, tournament_id: &str) -> AppResult<bool> {
+    let removed = conn.execute(
+        "DELETE FROM tournaments WHERE id = ?1",
+        params![tournament_id],
+    )?;
+    Ok(removed > 0)
+}
+
+pub fn participants(conn: &Connection, tournament_id: &str) -> AppResult<Vec<TournamentPlayer>> {
+    let mut stmt = conn.prepare(
+        "SELECT p.id, p.name, p.rank, p.rating, t.status
+         FROM tournament_players t JOIN players p ON p.id = t.player_id
+         WHERE t.tournament_id = ?1
+         ORDER BY p.rating DESC, p.id",
+    )?;
+    let players = stmt
+        .query_map(params![tournament_id], |row| {
+            Ok(TournamentPlayer {
+                player_id: row.get(0)?,
+                name: row.get(1)?,
+                rank: row
+                    .get::<_, Option<i64>>(2)?
+                    .and_then(|code| Rank::from_code(code).ok()),
+                rating: row.get(3)?,
+                status: row.get(4)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(players)
+}
+
+/// Enter a player into a tournament, or set whether they still take part. Withdrawn players
+/// are left out of later rounds but keep their results.
+pub fn set_participant(
+    conn: &Connection,
+    tournament_id: &str,
+    player_id: &str,
+    active: bool,
+) -> AppResult<()> {
+    get(conn, tournament_id)?;
+    let status = if active { "active" } else { "withdrawn" };
+    conn.execute(
+        "INSERT INTO tournament_players (tournament_id, player_id, status, created_at)
+         VALUES (?1, ?2, ?3, CURRENT_TIMESTAMP)
+         ON CONFLICT(tournament_id, player_id) DO UPDATE SET status = excluded.status",
+        params![tournament_id, player_id, status],
+    )?;
+    Ok(())
+}
+
+const PAIRING_COLUMNS: &str = "SELECT id, tournament_id, round_index, table_number, black_player_id, white_player_id, handicap, result, status, game_id FROM pairings";
+
+fn pairing_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<PairingInfo> {
+    Ok(PairingInfo {
+        id: row.get(0)?,
+        tournament_id: row.get(1)?,
+        round_index: row.get(2)?,
+        table_number: row.get(3)?,
+        black_player_id: row.get(4)?,
+        white_player_id: row.get(5)?,
+        handicap: row.get(6)?,
+        result: row.get(7)?,
+        status: row.get(8)?,
+        game_id: row.get(9)?,
+    })
+}
+
+/// The pairings of one round, or of every round, by round and table.
+pub fn pairings(
+    conn: &Connection,
+    tournament_id: &str,
+    round_index: Option<u32>,
+) -> AppResult<Vec<PairingInfo>> {
+    let mut stmt = conn.prepare(&format!(
+        "{PAIRING_COLUMNS} WHERE tournament_id = ?1 AND (?2 IS NULL OR round_index = ?2)
+         ORDER BY round_index, table_number"
+    ))?;
+    let pairings = stmt
+        .query_map(params![tournament_id, round_index], pairing_from_row)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(pairings)
+}
+
+/// The games of every paired round, as the pairing systems see them.
+pub fn round_games(conn: &Connection, tournament_id: &str) -> AppResult<Vec<RoundGame>> {
+    Ok(pairings(conn, tournament_id, None)?
+        .into_iter()
+        .filter_map(|pairing| {
+            Some(RoundGame {
+                round: pairing.round_index,
+                table: pairing.table_number,
+                black: pairing.black_player_id?,
+                white: pairing.white_player_id,
+                handicap: pairing.handicap,
+                result: pairing.result.and_then(|result| result.parse().ok()),
+            })
+        })
+        .collect())
+}
+
+/// The standings after `round`, by default the latest paired round, ordered by
+/// `tie_breakers` or the usual ones of the tournament's pairing system. Withdrawn players
+/// keep their place in the table.
+pub fn standings(
+    conn: &Connection,
+    tournament_id: &str,
+    round: Option<u32>,
+    tie_breakers: Option<Vec<TieBreaker>>,
+) -> AppResult<StandingsTable> {
+    let tournament = get(conn, tournament_id)?;
+    let round = match round {
+        Some(round) => round,
+        None => conn.query_row(
+            "SELECT COALESCE(MAX(round_index), 0) FROM rounds WHERE tournament_id = ?1",
+            params![tournament_id],
+            |row| row.get(0),
+        )?,
+    };
+    let tie_breakers =
+        tie_breakers.unwrap_or_else(|| tournament.pairing_settings.default_tie_breakers());
+    let players: HashMap<String, TournamentPlayer> = participants(conn, tournament_id)?
+        .into_iter()
+        .map(|player| (player.player_id.clone(), player))
+        .collect();
+    let entrants: Vec<Entrant> = players
+        .values()
+        .map(|player| Entrant {
+            id: player.player_id.clone(),
+            rating: player.rating,
+            rank: player.rank,
+        })
+        .collect();
+    let games = round_games(conn, tournament_id)?;
+    let rows = tournament::standings(
+        &entrants,
+        &games,
+        round,
+        &tournament.pairing_settings,
+        &tie_breakers,
+    )
+    .into_iter()
+    .map(|standing| {
+        let player = &players[&standing.player];
+        StandingRow {
+            name: player.name.clone(),
+            rank: player.rank,
+            rating: player.rating,
+            withdrawn: player.status == "withdrawn",
+            standing,
+        }
+    })
+    .collect();
+    Ok(StandingsTable {
+        round,
+        tie_breakers,
+        rows,
+    })
+}
+
+/// A tournament's results in the format a rating database takes, from its standings after
+/// the latest round. Countries and clubs come from the `country` and `club` fields of the
+/// players' metadata, and AGA IDs from their federation IDs.
+pub fn export_results(
+    conn: &Connection,
+    tournament_id: &str,
+    format: ResultsFormat,
+) -> AppResult<String> {
+    let tournament = get(conn, tournament_id)?;
+    let table = standings(conn, tournament_id, None, None)?;
+    let mut players = Vec::with_capacity(table.rows.len());
+    for row in &table.rows {
+        let Some(player) = players::get(conn, &row.standing.player)? else {
+            continue;
+        };
+        let metadata = |key: &str| {
+            player
+                .metadata
+                .as_ref()
+                .and_then(|metadata| metadata.get(key))
+                .and_then(|value| value.as_str())
+                .map(str::to_string)
+        };
+        players.push(ResultsPlayer {
+            country: metadata("country"),
+            club: metadata("club"),
+            id: player.id,
+            name: player.name,
+            rank: player.rank.unwrap_or(player.rating_rank),
+            federation_id: player.federation_id,
+        });
+    }
+    let info = ResultsInfo {
+        name: tournament.name,
+        location: None,
+        start_date: tournament.start_date,
+        end_date: tournament.end_date,
+        komi: tournament.komi,
+        ruleset: tournament.ruleset,
+    };
+    let standings: Vec<Standing> = table.rows.into_iter().map(|row| row.standing).collect();
+    Ok(match format {
+        ResultsFormat::Egd => tournament::egd_results(
+            &info,
+            &players,
+            &standings,
+            &table.tie_breakers,
+            table.round,
+        ),
+        ResultsFormat::Aga => tournament::aga_results(&info, &players, &standings),
+    })
+}
+
+/// Pair the next round of a tournament with its pairing system and store it in `rounds` and
+/// `pairings`. Every game of the earlier rounds needs a result first. A round robin is
+/// scheduled in full when it is first paired, which also fixes its number of rounds.
+pub fn pair_next_round(conn: &Connection, tournament_id: &str) -> AppResult<Vec<Round>> {
+    let tx = conn.unchecked_transaction()?;
+    let paired = pair_round(&tx, tournament_id)?;
+    tx.commit()?;
+    Ok(paired)
+}
+
+/// [`pair_next_round`] within a transaction the caller holds.
+fn pair_round(conn: &Connection, tournament_id: &str) -> AppResult<Vec<Round>> {
+    let tournament = get(conn, tournament_id)?;
+    let round: u32 = conn.query_row(
+        "SELECT COALESCE(MAX(round_index), 0) + 1 FROM rounds WHERE tournament_id = ?1",
+        params![tournament_id],
+        |row| row.get(0),
+    )?;
+    if tournament.rounds > 0 && round > tournament.rounds {
+        return Err(TournamentError::Finished.into());
+    }
+    let unfinished: u32 = conn.query_row(
+        "SELECT COUNT(*) FROM pairings
+         WHERE tournament_id = ?1 AND white_player_id IS NOT NULL AND result IS NULL",
+        params![tournament_id],
+        |row| row.get(0),
+    )?;
+    if unfinished > 0 {
+        return Err(AppError::other(format!(
+            "{unfinished} games of earlier rounds have no result yet"
+        )));
+    }
+
+    let entrants: Vec<Entrant> = participants(conn, tournament_id)?
+        .into_iter()
+        .filter(|player| player.status == "active")
+        .map(|player| Entrant {
+            id: player.player_id,
+            rating: player.rating,
+            rank: player.rank,
+        })
+        .collect();
+    let games = round_games(conn, tournament_id)?;
+    let paired = match &tournament.pairing_settings {
+        PairingSettings::Swiss(settings) => {
+            vec![tournament::pair_swiss(&entrants, &games, round, settings)?]
+        }
+        PairingSettings::McMahon(settings) => {
+            vec![tournament::pair_mcmahon(
+                &entrants, &games, round, settings,
+            )?]
+        }
+        PairingSettings::RoundRobin(_) if round > 1 => return Err(TournamentError::Finished.into()),
+        PairingSettings::RoundRobin(settings) => {
+            tournament::schedule_round_robin(&entrants, settings)?
+        }
+        PairingSettings::Elimination(settings) => {
+            vec![tournament::pair_elimination(
+                &entrants, &games, round, settings,
+            )?]
+        }
+    };
+    store_rounds(conn, tournament_id, &paired)?;
+    if matches!(tournament.pairing_settings, PairingSettings::RoundRobin(_)) {
+        conn.execute(
+            "UPDATE tournaments SET rounds = ?2, updated_at = CURRENT_TIMESTAMP WHERE id = ?1",
+            params![tournament_id, paired.len() as u32],
+        )?;
+    }
+    Ok(paired)
+}
+
+fn store_rounds(conn: &Connection, tournament_id: &str, rounds: &[Round]) -> AppResult<()> {
+    let mut insert_round = conn.prepare(
+        "INSERT INTO rounds (id, tournament_id, round_index, status) VALUES (?1, ?2, ?3, 'paired')",
+    )?;
+    let mut insert = conn.prepare(
+        "INSERT INTO pairings (id, tournament_id, round_index, table_number, black_player_id, white_player_id, handicap, status)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+    )?;
+    for paired in rounds {
+        insert_round.execute(params![
+            Uuid::new_v4().to_string(),
+            tournament_id,
+            paired.round
+        ])?;
+        for pairing in &paired.pairings {
+            insert.execute(params![
+                Uuid::new_v4().to_string(),
+                tournament_id,
+                paired.round,
+                pairing.table,
+                pairing.black,
+                pairing.white,
+                pairing.handicap,
+                "pending",
+            ])?;
+        }
+        for bye in &paired.byes {
+            insert.execute(params![
+                Uuid::new_v4().to_string(),
+                tournament_id,
+                paired.round,
+                bye.table,
+                bye.player,
+                None::<String>,
+                0,
+                "bye",
+            ])?;
+        }
+    }
+    Ok(())
+}
+
+/// Record or clear the result of a pairing, such as `B+R`, `W+3.5`, `0` or `B+F`. A round is
+/// finished once all of its games have results, and a finished knockout round pairs the
+/// next one straight away; its results cannot change after that. A knockout result is only
+/// stored if the next round can be paired from it.
+pub fn record_result(
+    conn: &Connection,
+    pairing_id: &str,
+    result: Option<&str>,
+) -> AppResult<PairingInfo> {
+    let pairing = conn
+        .query_row(
+            &format!("{PAIRING_COLUMNS} WHERE id = ?1"),
+            params![pairing_id],
+            pairing_from_row,
+        )
+        .optional()?
+        .ok_or_else(|| AppError::other(format!("pairing {pairing_id} not found")))?;
+    if pairing.white_player_id.is_none() {
+        return Err(AppError::other("a bye has no result to record"));
+    }
+    let result = result.map(str::trim).filter(|result| !result.is_empty());
+    let tournament = get(conn, &pairing.tournament_id)?;
+    let knockout = matches!(tournament.pairing_settings, PairingSettings::Elimination(_));
+    if knockout && result != pairing.result.as_deref() {
+        // Later rounds of the bracket were paired from this result.
+        let later: bool = conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM rounds WHERE tournament_id = ?1 AND round_index > ?2)",
+            params![pairing.tournament_id, pairing.round_index],
+            |row| row.get(0),
+        )?;
+        if later {
+            return Err(AppError::other(format!(
+                "results of knockout round {} are fixed once later rounds are paired",
+                pairing.round_index
+            )));
+        }
+    }
+    if let Some(result) = result {
+        let parsed = result.parse::<RoundResult>()?;
+        if knockout && parsed == RoundResult::Jigo {
+            return Err(AppError::other("a knockout game needs a winner"));
+        }
+    }
+    let status = if result.is_some() {
+        "finished"
+    } else {
+        "pending"
+    };
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(
+        "UPDATE pairings SET result = ?2, status = ?3 WHERE id = ?1",
+        params![pairing_id, result, status],
+    )?;
+    tx.execute(
+        "UPDATE rounds SET status = CASE WHEN EXISTS (
+             SELECT 1 FROM pairings p
+             WHERE p.tournament_id = rounds.tournament_id AND p.round_index = rounds.round_index
+               AND p.white_player_id IS NOT NULL AND p.result IS NULL
+         ) THEN 'paired' ELSE 'finished' END
+         WHERE tournament_id = ?1 AND round_index = ?2",
+        params![pairing.tournament_id, pairing.round_index],
+    )?;
+    if knockout && result.is_some() {
+        advance_bracket(&tx, &pairing.tournament_id)?;
+    }
+    tx.commit()?;
+    Ok(PairingInfo {
+        result: result.map(str::to_string),
+        status: status.to_string(),
+        ..pairing
+    })
+}
+
+/// Pair the next knockout round once every game of the bracket so far has a result. A
+/// round of nothing but byes, left by withdrawals, is passed straight through.
+fn advance_bracket(conn: &Connection, tournament_id: &str) -> AppResult<()> {
+    let pending: u32 = conn.query_row(
+        "SELECT COUNT(*) FROM pairings
+         WHERE tournament_id = ?1 AND white_player_id IS NOT NULL AND result IS NULL",
+        params![tournament_id],
+        |row| row.get(0),
+    )?;
+    if pending > 0 {
+        return Ok(());
+    }
+    loop {
+        match pair_round(conn, tournament_id) {
+            Ok(rounds) if rounds.iter().all(|round| round.pairings.is_empty()) => continue,
+            Ok(_) | Err(AppError::Tournament(TournamentError::Finished)) => return Ok(()),
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tournament::EliminationSettings;
+
+    use super::*;
+    use crate::{db::Database, players::UpsertPlayer};
+
+    /// A knockout of `count` players, seeded in the order they are entered.
+    fn knockout(conn: &Connection, double: bool, count: usize) -> (String, Vec<String>) {
+        let tournament = upsert(
+            conn,
+            UpsertTournament {
+                id: None,
+                name: "Cup".to_string(),
+                description: None,
+                start_date: None,
+                end_date: None,
+                rounds: None,
+                board_size: None,
+                komi: None,
+                ruleset: None,
+                pairing_settings: Some(PairingSettings::Elimination(EliminationSettings {
+                    double,
+                })),
+            },
+        )
+        .unwrap();
+        let players = (0..count)
+            .map(|seed| {
+                let player = players::upsert(
+                    conn,
+                    UpsertPlayer {
+                        id: None,
+                        name: format!("Seed {}", seed + 1),
+                        display_name: None,
+                        rank: None,
+                        rating: Some(2000.0 - 100.0 * seed as f64),
+                        federation_id: None,
+                        metadata: None,
+                    },
+                )
+                .unwrap();
+                set_participant(conn, &tournament.id, &player.id, true).unwrap();
+                player.id
+            })
+            .collect();
+        (tournament.id, players)
+    }
+
+    fn round(conn: &Connection, tournament_id: &str, round_index: u32) -> Vec<PairingInfo> {
+        pairings(conn, tournament_id, Some(round_index)).unwrap()
+    }
+
+    fn paired_rounds(conn: &Connection, tournament_id: &str) -> u32 {
+        conn.query_row(
+            "SELECT COUNT(*) FROM rounds WHERE tournament_id = ?1",
+            params![tournament_id],
+            |row| row.get(0),
+        )
+        .unwrap()
+    }
+
+    /// Let Black win every game of a round.
+    fn black_wins(conn: &Connection, games: &[PairingInfo]) {
+        for game in games.iter().filter(|game| game.white_player_id.is_some()) {
+            record_result(conn, &game.id, Some("B+R")).unwrap();
+        }
+    }
+
+    fn players_of(game: &PairingInfo) -> Vec<String> {
+        let mut players = [&game.black_player_id, &game.white_player_id]
+            .into_iter()
+            .flatten()
+            .cloned()
+            .collect::<Vec<_>>();
+        players.sort();
+        players
+    }
+
+    #[test]
+    fn single_elimination_pairs_the_next_round_from_the_last_result() {
+        let db = Database::in_memory().unwrap();
+        db.with_conn(|conn| {
+            let (cup, _) = knockout(conn, false, 4);
+            pair_next_round(conn, &cup)?;
+            let first = round(conn, &cup, 1);
+            assert_eq!(first.len(), 2);
+
+            // Results can change until the round is complete.
+            record_result(conn, &first[0].id, Some("B+R"))?;
+            assert_eq!(paired_rounds(conn, &cup), 1);
+            record_result(conn, &first[0].id, Some("W+R"))?;
+            record_result(conn, &first[1].id, Some("B+R"))?;
+            let last = round(conn, &cup, 2);
+            assert_eq!(last.len(), 1);
+            let mut finalists = vec![
+                first[0].white_player_id.clone().unwrap(),
+                first[1].black_player_id.clone().unwrap(),
+            ];
+            finalists.sort();
+            assert_eq!(players_of(&last[0]), finalists);
+
+            // The final was paired from the first round, so its results are fixed.
+            assert!(record_result(conn, &first[0].id, Some("B+R")).is_err());
+            assert!(record_result(conn, &first[0].id, None).is_err());
+            record_result(conn, &first[0].id, Some("W+R"))?;
+
+            black_wins(conn, &last);
+            assert_eq!(paired_rounds(conn, &cup), 2);
+            assert!(matches!(
+                pair_next_round(conn, &cup),
+                Err(AppError::Tournament(TournamentError::Finished))
+            ));
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn a_result_the_bracket_cannot_go_on_from_is_not_stored() {
+        let db = Database::in_memory().unwrap();
+        db.with_conn(|conn| {
+            let (cup, players) = knockout(conn, false, 4);
+            pair_next_round(conn, &cup)?;
+            let first = round(conn, &cup, 1);
+            record_result(conn, &first[0].id, Some("B+R"))?;
+            for player in &players[1..] {
+                set_participant(conn, &cup, player, false)?;
+            }
+            assert!(record_result(conn, &first[1].id, Some("B+R")).is_err());
+            assert_eq!(round(conn, &cup, 1)[1].result, None);
+            assert_eq!(paired_rounds(conn, &cup), 1);
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn a_round_of_byes_is_passed_straight_through() {
+        let db = Database::in_memory().unwrap();
+        db.with_conn(|conn| {
+            let (cup, _) = knockout(conn, false, 8);
+            pair_next_round(conn, &cup)?;
+            let first = round(conn, &cup, 1);
+            assert_eq!(first.len(), 4);
+            black_wins(conn, &first[..3]);
+
+            // Withdrawals leave the winners of tables 1 and 3 without opponents.
+            let withdrawn = [
+                &first[1].black_player_id,
+                &first[3].black_player_id,
+                &first[3].white_player_id,
+            ];
+            for player in withdrawn.into_iter().flatten() {
+                set_participant(conn, &cup, player, false)?;
+            }
+            black_wins(conn, &first[3..]);
+
+            let byes = round(conn, &cup, 2);
+            assert_eq!(byes.len(), 2);
+            assert!(byes.iter().all(|bye| bye.white_player_id.is_none()));
+            let last = round(conn, &cup, 3);
+            assert_eq!(last.len(), 1);
+            let mut finalists = vec![
+                first[0].black_player_id.clone().unwrap(),
+                first[2].black_player_id.clone().unwrap(),
+            ];
+            finalists.sort();
+            assert_eq!(players_of(&last[0]), finalists);
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn double_elimination_replays_the_final_when_the_champion_loses_it() {
+        let db = Database::in_memory().unwrap();
+        db.with_conn(|conn| {
+            let (cup, _) = knockout(conn, true, 4);
+            pair_next_round(conn, &cup)?;
+            black_wins(conn, &round(conn, &cup, 1));
+
+            // The winners and the losers of the first round play on in their brackets.
+            let second = round(conn, &cup, 2);
+            assert_eq!(second.len(), 2);
+            black_wins(conn, &second);
+            let champion = second[0].black_player_id.clone().unwrap();
+            let third = round(conn, &cup, 3);
+            assert_eq!(third.len(), 1);
+            assert!(!players_of(&third[0]).contains(&champion));
+            black_wins(conn, &third);
+
+            let last = round(conn, &cup, 4);
+            assert_eq!(last.len(), 1);
+            assert!(players_of(&last[0]).contains(&champion));
+            let upset = if last[0].black_player_id.as_ref() == Some(&champion) {
+                "W+R"
+            } else {
+                "B+R"
+            };
+            record_result(conn, &last[0].id, Some(upset))?;
+            let replay = round(conn, &cup, 5);
+            assert_eq!(players_of(&replay[0]), players_of(&last[0]));
+            black_wins(conn, &replay);
+            assert_eq!(paired_rounds(conn, &cup), 5);
+            Ok(())
+        })
+        .unwrap();
+    }
+}
@@ -67,8 +67,14 @@ export function useGoGameSession(size: number = 19, seed: number = 0) {
             toMove: outcome.toMove,
             legalMoves: outcome.legalMoves,
             consecutivePasses: outcome.consecutivePasses,
+            noResult: outcome.noResult,
+            phase: outcome.phase,
+            scoring: null,
+            result: null,
             config: snapshot.config,
             moveCount: outcome.lastMove.moveNumber,
+            redoCount: 0,
+            handicapPending: 0,
           },
           lastOutcome: outcome,
           history: [...(prev.history ?? []), outcome.lastMove],
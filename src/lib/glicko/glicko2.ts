@@ -4,7 +4,6 @@ const DEFAULT_RATING = 1500;
 const DEFAULT_RD = 350;
 const DEFAULT_VOLATILITY = 0.06;
 const SCALE = 173.7178;
-const Q = Math.log(10) / 400;
 
 export const RatingSchema = z.object({
   rating: z.number(),
@@ -41,7 +40,7 @@ const DEFAULT_CONFIG: GlickoConfig = {
 };
 
 function g(phi: number): number {
-  return 1 / Math.sqrt(1 + ((3 * Q ** 2 * phi ** 2) / Math.PI ** 2));
+  return 1 / Math.sqrt(1 + (3 * phi ** 2) / Math.PI ** 2);
 }
 
 function e(mu: number, muJ: number, phiJ: number): number {
@@ -94,9 +93,11 @@ function volatilityIteration(
   while (Math.abs(B - A) > 1e-6) {
     const C = A + ((A - B) * fA) / (fB - fA);
     const fC = f(C, delta, phi, v, a, tau);
-    if (fC * fB < 0) {
+    if (fC * fB <= 0) {
       A = B;
       fA = fB;
+    } else {
+      fA /= 2;
     }
     B = C;
     fB = fC;
@@ -190,7 +191,7 @@ export function ratePlayer(
 export function advanceRatingPeriod(current: Rating, volatility: number = current.volatility): Rating {
   return {
     rating: current.rating,
-    deviation: Math.sqrt(current.deviation ** 2 + volatility ** 2),
+    deviation: fromPhi(Math.sqrt(toPhi(current.deviation) ** 2 + volatility ** 2)),
     volatility,
   };
 }
@@ -1,4 +1,9 @@
 import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+import type { PointPayload, StoneColor } from "../rules/types";
+
+/** `katago_json` engines run KataGo's `analysis` mode and only serve game reviews. */
+export type EngineKind = "gtp" | "katago_json";
 
 export interface GtpEngineForm {
   id?: string;
@@ -7,6 +12,23 @@ export interface GtpEngineForm {
   args?: string[];
   workingDirectory?: string;
   enabled?: boolean;
+  kind?: EngineKind;
+}
+
+/** Detected when an engine is registered; use it to hide features the engine lacks. */
+export interface EngineCapabilities {
+  protocolVersion: number | null;
+  name: string;
+  version: string;
+  commands: string[];
+  analysis: "kata-analyze" | "lz-analyze" | null;
+  finalStatusList: boolean;
+  timeSettings: boolean;
+  kgsTimeSettings: boolean;
+  rectangularBoards: boolean;
+  freeHandicap: boolean;
+  undo: boolean;
+  jsonAnalysis: boolean;
 }
 
 export interface GtpEngineInfo {
@@ -16,21 +38,135 @@ export interface GtpEngineInfo {
   args: string[];
   workingDirectory?: string | null;
   enabled: boolean;
+  kind: EngineKind;
+  /** `null` for engines registered before capabilities were probed. */
+  capabilities: EngineCapabilities | null;
   createdAt: string;
   updatedAt: string;
 }
 
+export type GtpSessionStatus = "running" | "stopped" | "crashed";
+
 export interface GtpSessionInfo {
   sessionId: string;
   engineId: string;
-  status: string;
+  status: GtpSessionStatus;
+  pid: number | null;
   startedAt: string;
+  stoppedAt: string | null;
+  logPath: string | null;
+}
+
+export interface GtpLogPage {
+  sessionId: string;
+  start: number;
+  total: number;
+  lines: string[];
+}
+
+export interface EngineMatchRequest {
+  /** Engine that takes Black in the first game; colors alternate after that. */
+  engineA: string;
+  engineB: string;
+  games: number;
+  size?: number;
+  komi?: number;
+  ruleset?: string;
+  mainTimeSecs?: number;
+  byoYomiSecs?: number;
+  byoYomiStones?: number;
+  maxMoves?: number;
+}
+
+export interface WinRateInfo {
+  rate: number;
+  lower: number;
+  upper: number;
+}
+
+/** Tallies from engine A's point of view; void games are left out of the win rate. */
+export interface MatchSummary {
+  gamesPlayed: number;
+  gamesTotal: number;
+  engineAWins: number;
+  engineBWins: number;
+  draws: number;
+  /** Games stopped at the move limit. */
+  voidGames: number;
+  engineABlackWins: number;
+  engineAWhiteWins: number;
+  engineAWinRate: WinRateInfo | null;
+}
+
+export type MatchProgressKind = "move" | "game" | "finished" | "cancelled" | "failed";
+
+export interface MatchProgress {
+  matchId: string;
+  kind: MatchProgressKind;
+  gameIndex: number;
+  blackEngineId: string;
+  whiteEngineId: string;
+  moveCount: number;
+  result: string | null;
+  gameId: string | null;
+  summary: MatchSummary;
+  error: string | null;
+}
+
+export interface EngineMatchInfo {
+  matchId: string;
+  engineAId: string;
+  engineBId: string;
+  games: number;
+  settings: EngineMatchRequest;
+  status: "running" | "finished" | "cancelled" | "failed";
+  summary: MatchSummary | null;
+  createdAt: string;
+  finishedAt: string | null;
+}
+
+/** One candidate move; rates are fractions for the player to move. */
+export interface CandidateMove {
+  point: PointPayload | null;
+  visits: number;
+  winrate: number;
+  prior: number;
+  lcb: number;
+  scoreLead: number | null;
+  order: number;
+  pv: Array<PointPayload | null>;
+}
+
+export interface AnalysisUpdate {
+  sessionId: string;
+  gameId: string;
+  toMove: StoneColor;
+  moveCount: number;
+  moves: CandidateMove[];
+  /** Board-index ordered, from -1 to 1 for the player to move. */
+  ownership: number[] | null;
+  error: string | null;
+}
+
+/** KataGo's view of one position; rates, score lead and ownership are for Black. */
+export interface PositionReview {
+  moveNumber: number;
+  positionHash: string;
+  visits: number;
+  winrate: number;
+  scoreLead: number;
+  moves: CandidateMove[];
+  /** Board-index ordered priors followed by the pass prior; negative when illegal. */
+  policy: number[] | null;
+  ownership: number[] | null;
+  cached: boolean;
 }
 
 export async function listEngines(): Promise<GtpEngineInfo[]> {
   return invoke<GtpEngineInfo[]>("list_gtp_engines");
 }
 
+/** Register or update an engine; rejects paths that do not start or do not speak GTP. */
 export async function upsertEngine(input: GtpEngineForm): Promise<GtpEngineInfo> {
   return invoke<GtpEngineInfo>("register_gtp_engine", { payload: input });
 }
@@ -43,6 +179,82 @@ export async function launchEngine(engineId: string): Promise<GtpSessionInfo> {
   return invoke<GtpSessionInfo>("launch_gtp_engine", { engineId });
 }
 
+export async function sendCommand(sessionId: string, command: string): Promise<string> {
+  return invoke<string>("send_gtp_command", { sessionId, command });
+}
+
+export async function listSessions(): Promise<GtpSessionInfo[]> {
+  return invoke<GtpSessionInfo[]>("list_gtp_sessions");
+}
+
+/** Page through a session's log from `start`, or fetch its last lines when `start` is omitted. */
+export async function readLog(
+  sessionId: string,
+  options: { start?: number; limit?: number } = {},
+): Promise<GtpLogPage> {
+  return invoke<GtpLogPage>("read_gtp_log", {
+    sessionId,
+    start: options.start ?? null,
+    limit: options.limit ?? null,
+  });
+}
+
 export async function stopEngine(sessionId: string): Promise<void> {
   await invoke("stop_gtp_engine", { sessionId });
 }
+
+export async function startMatch(request: EngineMatchRequest): Promise<string> {
+  return invoke<string>("start_engine_match", { request });
+}
+
+/** Stop a match once its current game is over. */
+export async function cancelMatch(matchId: string): Promise<boolean> {
+  return invoke<boolean>("cancel_engine_match", { matchId });
+}
+
+export async function listMatches(): Promise<EngineMatchInfo[]> {
+  return invoke<EngineMatchInfo[]>("list_engine_matches");
+}
+
+export async function onMatchProgress(
+  handler: (progress: MatchProgress) => void,
+): Promise<UnlistenFn> {
+  return listen<MatchProgress>("engine-match-progress", (event) => handler(event.payload));
+}
+
+/** Analyse a game's current position; returns `kata-analyze` or `lz-analyze`. */
+export async function startAnalysis(
+  sessionId: string,
+  gameId: string,
+  options: { intervalCentis?: number; ownership?: boolean } = {},
+): Promise<string> {
+  return invoke<string>("start_analysis", {
+    sessionId,
+    gameId,
+    intervalCentis: options.intervalCentis ?? null,
+    ownership: options.ownership ?? null,
+  });
+}
+
+export async function stopAnalysis(sessionId: string): Promise<void> {
+  await invoke("stop_analysis", { sessionId });
+}
+
+export async function onAnalysisUpdate(
+  handler: (update: AnalysisUpdate) => void,
+): Promise<UnlistenFn> {
+  return listen<AnalysisUpdate>("analysis-update", (event) => handler(event.payload));
+}
+
+/** Analyse every position of a game with a KataGo JSON engine, reusing cached results. */
+export async function reviewGame(
+  engineId: string,
+  gameId: string,
+  maxVisits?: number,
+): Promise<PositionReview[]> {
+  return invoke<PositionReview[]>("review_game_analysis", {
+    engineId,
+    gameId,
+    maxVisits: maxVisits ?? null,
+  });
+}
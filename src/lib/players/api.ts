@@ -0,0 +1,94 @@
+import { invoke } from "@tauri-apps/api/core";
+
+/** `"30k"`–`"1k"`, `"1d"`–`"9d"` or `"1p"`–`"9p"`. */
+export type Rank = `${number}${"k" | "d" | "p"}`;
+
+export interface PlayerInfo {
+  id: string;
+  name: string;
+  displayName: string | null;
+  /** The rank the player holds, as entered. */
+  rank: Rank | null;
+  rating: number;
+  ratingDeviation: number;
+  ratingVolatility: number;
+  /** The amateur rank that `rating` corresponds to. */
+  ratingRank: Rank;
+  federationId: string | null;
+  metadata: unknown;
+  createdAt: string;
+  updatedAt: string;
+}
+
+export interface PlayerForm {
+  id?: string;
+  name: string;
+  displayName?: string;
+  rank?: Rank;
+  /** New players otherwise start at the rating of their rank. */
+  rating?: number;
+  federationId?: string;
+  metadata?: unknown;
+}
+
+export interface Rating {
+  rating: number;
+  deviation: number;
+  volatility: number;
+}
+
+export interface GlickoSettings {
+  tau?: number;
+  maxDeviation?: number;
+}
+
+export interface RatingChange {
+  playerId: string;
+  before: Rating;
+  after: Rating;
+  games: number;
+}
+
+export interface RatingPeriodSummary {
+  period: string;
+  /** Games rated in this period. */
+  games: number;
+  /** Every player, strongest first after the update. */
+  players: RatingChange[];
+}
+
+export interface RatingHistoryEntry {
+  period: string;
+  rating: number;
+  ratingDeviation: number;
+  ratingVolatility: number;
+  createdAt: string;
+}
+
+export async function listPlayers(): Promise<PlayerInfo[]> {
+  return invoke<PlayerInfo[]>("list_players");
+}
+
+export async function getPlayer(playerId: string): Promise<PlayerInfo | null> {
+  return invoke<PlayerInfo | null>("get_player", { playerId });
+}
+
+export async function savePlayer(payload: PlayerForm): Promise<PlayerInfo> {
+  return invoke<PlayerInfo>("save_player", { payload });
+}
+
+export async function removePlayer(playerId: string): Promise<boolean> {
+  return invoke<boolean>("remove_player", { playerId });
+}
+
+/** Rate the finished games between players that have not been rated yet, as one period. */
+export async function runRatingPeriod(
+  period?: string,
+  settings?: GlickoSettings,
+): Promise<RatingPeriodSummary> {
+  return invoke<RatingPeriodSummary>("run_rating_period", { period, settings });
+}
+
+export async function getRatingHistory(playerId: string): Promise<RatingHistoryEntry[]> {
+  return invoke<RatingHistoryEntry[]>("get_rating_history", { playerId });
+}
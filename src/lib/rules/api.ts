@@ -1,28 +1,62 @@
 import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
 import type {
+  EngineAction,
+  EngineMoveEvent,
   GameConfig,
+  GameInfo,
+  GamePhase,
+  GameResult,
   GameStateSnapshot,
   GameSummary,
+  HandicapPlacement,
+  ImportedGame,
+  KoRule,
   MoveOutcome,
+  OwnershipEstimate,
   PointPayload,
+  Ruleset,
   ScoreSummary,
+  ScoringSnapshot,
   StoneColor,
 } from "./types";
 
 export type {
+  EngineAction,
+  EngineMoveEvent,
   GameConfig,
+  GameInfo,
+  GamePhase,
+  GameResult,
   GameStateSnapshot,
   GameSummary,
+  HandicapPlacement,
+  ImportedGame,
+  KoRule,
   MoveOutcome,
+  OwnershipEstimate,
   PointPayload,
+  Ruleset,
   ScoreSummary,
+  ScoringSnapshot,
   StoneColor,
 };
 
 export interface CreateGameOptions {
   size?: number;
   komi?: number;
-  superko?: boolean;
+  koRule?: KoRule;
+  handicap?: number;
+  handicapPlacement?: HandicapPlacement;
+  ruleset?: Ruleset;
+  /** Running GTP session that plays Black. */
+  blackEngine?: string;
+  /** Running GTP session that plays White. */
+  whiteEngine?: string;
+  /** Player record for Black. */
+  blackPlayerId?: string;
+  /** Player record for White. */
+  whitePlayerId?: string;
 }
 
 export async function createGame(options: CreateGameOptions = {}): Promise<GameStateSnapshot> {
@@ -54,10 +88,140 @@ export async function playMove(
   return normalizeOutcome(outcome);
 }
 
+export async function placeHandicapStone(
+  gameId: string,
+  point: PointPayload,
+): Promise<GameStateSnapshot> {
+  const snapshot = await invoke("place_handicap_stone", {
+    payload: {
+      gameId,
+      point,
+    },
+  });
+  return normalizeSnapshot(snapshot);
+}
+
+export async function undoMove(gameId: string): Promise<GameStateSnapshot> {
+  const snapshot = await invoke("undo_game_move", { gameId });
+  return normalizeSnapshot(snapshot);
+}
+
+export async function redoMove(gameId: string): Promise<GameStateSnapshot> {
+  const snapshot = await invoke("redo_game_move", { gameId });
+  return normalizeSnapshot(snapshot);
+}
+
+export async function gotoMove(gameId: string, moveNumber: number): Promise<GameStateSnapshot> {
+  const snapshot = await invoke("goto_game_move", { gameId, moveNumber });
+  return normalizeSnapshot(snapshot);
+}
+
+export async function toggleDeadStones(
+  gameId: string,
+  point: PointPayload,
+): Promise<ScoringSnapshot> {
+  const scoring = await invoke("toggle_dead_stones", {
+    payload: {
+      gameId,
+      point,
+    },
+  });
+  return normalizeScoring(scoring);
+}
+
+export async function acceptScore(gameId: string, color: StoneColor): Promise<ScoringSnapshot> {
+  const scoring = await invoke("accept_score", { gameId, color });
+  return normalizeScoring(scoring);
+}
+
+export async function resumeGame(gameId: string): Promise<GameStateSnapshot> {
+  const snapshot = await invoke("resume_game", { gameId });
+  return normalizeSnapshot(snapshot);
+}
+
+export async function resignGame(gameId: string, color: StoneColor): Promise<GameStateSnapshot> {
+  const snapshot = await invoke("resign_game", { gameId, color });
+  return normalizeSnapshot(snapshot);
+}
+
+/** Ask the engine seated for the side to move to play; the move arrives via `onEngineMove`. */
+export async function requestEngineMove(gameId: string): Promise<void> {
+  await invoke("request_engine_move", { gameId });
+}
+
+export async function onEngineMove(
+  handler: (event: EngineMoveEvent) => void,
+): Promise<UnlistenFn> {
+  return listen("engine-move", (event) => {
+    const raw: any = event.payload;
+    handler({
+      gameId: raw.gameId ?? raw.game_id ?? "",
+      color: normalizeColor(raw.color),
+      action: (raw.action ?? null) as EngineAction | null,
+      snapshot: raw.snapshot ? normalizeSnapshot(raw.snapshot) : null,
+      error: raw.error ?? null,
+    });
+  });
+}
+
+export async function estimateOwnership(gameId: string): Promise<OwnershipEstimate> {
+  const raw: any = await invoke("estimate_ownership", { gameId });
+  return {
+    owner: Array.isArray(raw?.owner)
+      ? raw.owner.map((value: unknown) => (value === null ? null : normalizeColor(value)))
+      : [],
+    deadStones: normalizePoints(raw?.deadStones ?? raw?.dead_stones),
+    unconditionallyAlive: normalizePoints(
+      raw?.unconditionallyAlive ?? raw?.unconditionally_alive,
+    ),
+  };
+}
+
 export async function scoreGame(gameId: string): Promise<ScoreSummary> {
   return invoke<ScoreSummary>("score_game", { gameId });
 }
 
+export async function importSgf(path: string): Promise<ImportedGame[]> {
+  const raw: any[] = await invoke("import_sgf", { path });
+  return raw.map((entry) => ({
+    game: normalizeSnapshot(entry.game),
+    info: normalizeGameInfo(entry.info),
+  }));
+}
+
+export async function exportSgf(gameId: string, path: string, info?: GameInfo): Promise<void> {
+  await invoke("export_sgf", { gameId, path, info: info ? toRawGameInfo(info) : null });
+}
+
+const GAME_INFO_FIELDS: Array<[keyof GameInfo, string]> = [
+  ["gameName", "game_name"],
+  ["event", "event"],
+  ["round", "round"],
+  ["date", "date"],
+  ["place", "place"],
+  ["result", "result"],
+  ["blackPlayer", "black_player"],
+  ["whitePlayer", "white_player"],
+  ["blackRank", "black_rank"],
+  ["whiteRank", "white_rank"],
+];
+
+function normalizeGameInfo(raw: any): GameInfo {
+  const info: GameInfo = {};
+  for (const [key, rawKey] of GAME_INFO_FIELDS) {
+    info[key] = raw?.[key] ?? raw?.[rawKey] ?? null;
+  }
+  return info;
+}
+
+function toRawGameInfo(info: GameInfo): Record<string, string | null> {
+  const raw: Record<string, string | null> = {};
+  for (const [key, rawKey] of GAME_INFO_FIELDS) {
+    raw[rawKey] = info[key] ?? null;
+  }
+  return raw;
+}
+
 function normalizeSnapshot(raw: any): GameStateSnapshot {
   const board = normalizeBoard(raw.board ?? raw.board_snapshot);
   const legalMoves = normalizePoints(raw.legalMoves ?? raw.legal_moves);
@@ -68,8 +232,14 @@ function normalizeSnapshot(raw: any): GameStateSnapshot {
     toMove: normalizeColor(raw.toMove ?? raw.to_move),
     legalMoves,
     consecutivePasses: raw.consecutivePasses ?? raw.consecutive_passes ?? 0,
+    noResult: Boolean(raw.noResult ?? raw.no_result ?? false),
+    phase: normalizePhase(raw.phase),
+    scoring: raw.scoring ? normalizeScoring(raw.scoring) : null,
+    result: normalizeResult(raw.result),
     config: normalizeConfig(raw.config),
     moveCount: raw.moveCount ?? raw.move_count ?? 0,
+    redoCount: raw.redoCount ?? raw.redo_count ?? 0,
+    handicapPending: raw.handicapPending ?? raw.handicap_pending ?? 0,
   };
 }
 
@@ -82,6 +252,8 @@ function normalizeOutcome(raw: any): MoveOutcome {
     captures: normalizeCaptures(raw.captures),
     toMove: normalizeColor(raw.toMove ?? raw.to_move),
     gameOver: Boolean(raw.gameOver ?? raw.game_over ?? false),
+    noResult: Boolean(raw.noResult ?? raw.no_result ?? false),
+    phase: normalizePhase(raw.phase),
     consecutivePasses: raw.consecutivePasses ?? raw.consecutive_passes ?? 0,
     legalMoves,
     lastMove: {
@@ -99,6 +271,39 @@ function normalizeOutcome(raw: any): MoveOutcome {
   } as MoveOutcome;
 }
 
+function normalizeScoring(raw: any): ScoringSnapshot {
+  return {
+    phase: normalizePhase(raw?.phase),
+    deadStones: normalizePoints(raw?.deadStones ?? raw?.dead_stones),
+    blackAccepted: Boolean(raw?.blackAccepted ?? raw?.black_accepted ?? false),
+    whiteAccepted: Boolean(raw?.whiteAccepted ?? raw?.white_accepted ?? false),
+    score: raw?.score as ScoreSummary,
+  };
+}
+
+function normalizeResult(raw: any): GameResult | null {
+  switch (raw?.kind) {
+    case "resignation":
+    case "time":
+    case "forfeit":
+      return { kind: raw.kind, winner: normalizeColor(raw.winner) };
+    case "score":
+      return {
+        kind: "score",
+        winner: raw.winner ? normalizeColor(raw.winner) : null,
+        margin: raw.margin ?? 0,
+      };
+    case "no_result":
+      return { kind: "no_result" };
+    default:
+      return null;
+  }
+}
+
+function normalizePhase(raw: any): GamePhase {
+  return raw === "scoring" || raw === "finished" ? raw : "playing";
+}
+
 function normalizeBoard(raw: any): GameStateSnapshot["board"] {
   const size = raw?.size ?? 19;
   const intersections = Array.isArray(raw?.intersections)
@@ -111,12 +316,23 @@ function normalizeBoard(raw: any): GameStateSnapshot["board"] {
 
 function normalizeConfig(raw: any | undefined): GameConfig {
   if (!raw) {
-    return { size: 19, komi: 6.5, superko: true };
+    return {
+      size: 19,
+      komi: 6.5,
+      koRule: null,
+      handicap: 0,
+      handicapPlacement: "fixed",
+      ruleset: "aga",
+    };
   }
   return {
     size: raw.size ?? raw.boardSize ?? 19,
     komi: Number(raw.komi ?? 6.5),
-    superko: raw.superko ?? raw.superKo ?? true,
+    koRule: (raw.koRule ?? raw.ko_rule ?? null) as KoRule | null,
+    handicap: Number(raw.handicap ?? 0),
+    handicapPlacement:
+      (raw.handicapPlacement ?? raw.handicap_placement) === "free" ? "free" : "fixed",
+    ruleset: (raw.ruleset ?? "aga") as Ruleset,
   };
 }
 
@@ -29,6 +29,8 @@ export interface MoveOutcome {
   captures: CapturesSnapshot;
   toMove: StoneColor;
   gameOver: boolean;
+  noResult: boolean;
+  phase: GamePhase;
   consecutivePasses: number;
   lastMove: MoveRecordSnapshot;
   legalMoves: PointPayload[];
@@ -41,14 +43,81 @@ export interface GameStateSnapshot {
   toMove: StoneColor;
   legalMoves: PointPayload[];
   consecutivePasses: number;
+  noResult: boolean;
+  phase: GamePhase;
+  scoring: ScoringSnapshot | null;
+  result: GameResult | null;
   config: GameConfig;
   moveCount: number;
+  redoCount: number;
+  handicapPending: number;
 }
 
+export type GamePhase = "playing" | "scoring" | "finished";
+
+export type GameResult =
+  | { kind: "resignation"; winner: StoneColor }
+  | { kind: "score"; winner: StoneColor | null; margin: number }
+  | { kind: "time"; winner: StoneColor }
+  | { kind: "forfeit"; winner: StoneColor }
+  | { kind: "no_result" };
+
+export type EngineAction = "handicap" | "play" | "pass" | "resign" | "accept";
+
+/** Payload of the `engine-move` event; `error` is set when the engine failed to move. */
+export interface EngineMoveEvent {
+  gameId: string;
+  color: StoneColor;
+  action: EngineAction | null;
+  snapshot: GameStateSnapshot | null;
+  error: string | null;
+}
+
+export interface ScoringSnapshot {
+  phase: GamePhase;
+  deadStones: PointPayload[];
+  blackAccepted: boolean;
+  whiteAccepted: boolean;
+  score: ScoreSummary;
+}
+
+export interface OwnershipEstimate {
+  owner: Array<StoneColor | null>;
+  deadStones: PointPayload[];
+  unconditionallyAlive: PointPayload[];
+}
+
+export interface GameInfo {
+  gameName?: string | null;
+  event?: string | null;
+  round?: string | null;
+  date?: string | null;
+  place?: string | null;
+  result?: string | null;
+  blackPlayer?: string | null;
+  whitePlayer?: string | null;
+  blackRank?: string | null;
+  whiteRank?: string | null;
+}
+
+export interface ImportedGame {
+  game: GameStateSnapshot;
+  info: GameInfo;
+}
+
+export type HandicapPlacement = "fixed" | "free";
+
+export type Ruleset = "japanese" | "chinese" | "aga" | "new_zealand" | "ing" | "tromp_taylor";
+
+export type KoRule = "simple" | "positional_superko" | "situational_superko" | "no_result";
+
 export interface GameConfig {
   size: number;
   komi: number;
-  superko: boolean;
+  koRule: KoRule | null;
+  handicap: number;
+  handicapPlacement: HandicapPlacement;
+  ruleset: Ruleset;
 }
 
 export interface GameSummary {
@@ -57,13 +126,40 @@ export interface GameSummary {
   toMove: StoneColor;
   moveCount: number;
   consecutivePasses: number;
+  phase: GamePhase;
+  /** SGF result notation once the game is over. */
+  result: string | null;
+  source: "local" | "sgf" | "engine_match";
+  blackPlayerId: string | null;
+  whitePlayerId: string | null;
+  /** Why the stored game could not be replayed; the counts and phase are then placeholders. */
+  loadError: string | null;
 }
 
+export type ScoringMethod = "area" | "territory";
+
+export type PointScore =
+  | "black_stone"
+  | "white_stone"
+  | "black_territory"
+  | "white_territory"
+  | "dead_black"
+  | "dead_white"
+  | "dame"
+  | "seki";
+
 export interface ScoreSummary {
+  method: ScoringMethod;
   blackScore: number;
   whiteScore: number;
   territoryBlack: number;
   territoryWhite: number;
+  stonesBlack: number;
+  stonesWhite: number;
+  deadBlack: number;
+  deadWhite: number;
   captures: CapturesSnapshot;
   komi: number;
+  handicapCompensation: number;
+  points: PointScore[];
 }
@@ -0,0 +1,239 @@
+import { invoke } from "@tauri-apps/api/core";
+
+import type { Rank } from "../players";
+
+export interface SwissSettings {
+  system: "swiss";
+  /** Points for a bye, 1 by default. */
+  byePoints?: number;
+}
+
+export interface HandicapRules {
+  /** Grades taken off the rank difference, 1 by default; a smaller difference is an even game. */
+  reduction?: number;
+  maxHandicap?: number;
+}
+
+export interface McMahonSettings {
+  system: "mcmahon";
+  /** Players of this rank and above start in the top group; 1d by default. */
+  bar?: Rank;
+  /** Players of this rank and below start in the bottom group; 20k by default. */
+  floor?: Rank;
+  byePoints?: number;
+  /** Even games when omitted. */
+  handicap?: HandicapRules;
+}
+
+export interface RoundRobinSettings {
+  system: "round_robin";
+  /** Play everyone twice, with colors swapped in the second cycle. */
+  double?: boolean;
+}
+
+export interface EliminationSettings {
+  system: "elimination";
+  /** Players are out after their second loss rather than their first. */
+  double?: boolean;
+}
+
+export type PairingSettings =
+  | SwissSettings
+  | McMahonSettings
+  | RoundRobinSettings
+  | EliminationSettings;
+
+export interface TournamentInfo {
+  id: string;
+  name: string;
+  description: string | null;
+  startDate: string | null;
+  endDate: string | null;
+  /** Planned number of rounds; 0 when open-ended. */
+  rounds: number;
+  boardSize: number;
+  komi: number;
+  ruleset: string;
+  pairingSettings: PairingSettings;
+  createdAt: string;
+  updatedAt: string;
+}
+
+export interface TournamentForm {
+  id?: string;
+  name: string;
+  description?: string;
+  startDate?: string;
+  endDate?: string;
+  rounds?: number;
+  boardSize?: number;
+  komi?: number;
+  ruleset?: string;
+  pairingSettings?: PairingSettings;
+}
+
+export interface TournamentPlayer {
+  playerId: string;
+  name: string;
+  rank: Rank | null;
+  rating: number;
+  status: "active" | "withdrawn";
+}
+
+export interface Pairing {
+  /** Tables are numbered from 1, strongest pairing first. */
+  table: number;
+  black: string;
+  white: string;
+  handicap: number;
+}
+
+export interface PlayerFloat {
+  player: string;
+  direction: "down" | "up";
+}
+
+export interface Bye {
+  /** The table the player would have played at, which keeps their place in a bracket. */
+  table: number;
+  player: string;
+}
+
+export interface PairedRound {
+  round: number;
+  pairings: Pairing[];
+  byes: Bye[];
+  floats: PlayerFloat[];
+  /** Whether some players had to meet again because no pairing avoided it. */
+  rematches: boolean;
+}
+
+export interface PairingInfo {
+  id: string;
+  tournamentId: string;
+  roundIndex: number;
+  tableNumber: number;
+  blackPlayerId: string | null;
+  /** `null` for a bye. */
+  whitePlayerId: string | null;
+  handicap: number;
+  /** `B+R`, `W+3.5`, `0` for jigo, or `B+F`, `W+F` and `0+F` for forfeits. */
+  result: string | null;
+  status: "pending" | "finished" | "bye";
+  gameId: string | null;
+}
+
+/** Standings criteria, listed from the most important. */
+export type TieBreaker =
+  | "points"
+  | "mms"
+  | "sos"
+  | "sosos"
+  | "sodos"
+  | "cumulative"
+  | "direct_encounter";
+
+export interface StandingRound {
+  round: number;
+  /** `null` for a bye or a round the player was not paired in. */
+  opponent: string | null;
+  color: "black" | "white" | null;
+  handicap: number;
+  bye: boolean;
+  result: string | null;
+  /** Points earned in the round, once known. */
+  points: number | null;
+}
+
+export interface StandingRow {
+  /** Players tied on every criterion share a place. */
+  place: number;
+  player: string;
+  name: string;
+  rank: Rank | null;
+  rating: number;
+  withdrawn: boolean;
+  points: number;
+  mms: number;
+  /** Values of `StandingsTable.tieBreakers`, in order. */
+  tieBreakers: number[];
+  rounds: StandingRound[];
+}
+
+export interface StandingsTable {
+  round: number;
+  tieBreakers: TieBreaker[];
+  rows: StandingRow[];
+}
+
+/** `egd` is the European Go Database `.h` file, `aga` the AGA results file. */
+export type ResultsFormat = "egd" | "aga";
+
+export async function listTournaments(): Promise<TournamentInfo[]> {
+  return invoke<TournamentInfo[]>("list_tournaments");
+}
+
+export async function saveTournament(payload: TournamentForm): Promise<TournamentInfo> {
+  return invoke<TournamentInfo>("save_tournament", { payload });
+}
+
+export async function removeTournament(tournamentId: string): Promise<boolean> {
+  return invoke<boolean>("remove_tournament", { tournamentId });
+}
+
+export async function listTournamentPlayers(tournamentId: string): Promise<TournamentPlayer[]> {
+  return invoke<TournamentPlayer[]>("list_tournament_players", { tournamentId });
+}
+
+/** Enter a player, or withdraw them with `active: false`; withdrawn players keep their results. */
+export async function setTournamentPlayer(
+  tournamentId: string,
+  playerId: string,
+  active = true,
+): Promise<TournamentPlayer[]> {
+  return invoke<TournamentPlayer[]>("set_tournament_player", { tournamentId, playerId, active });
+}
+
+/**
+ * Pair the next round. Every game of the earlier rounds needs a result first. A round robin
+ * is scheduled in full, so all of its rounds come back at once.
+ */
+export async function pairTournamentRound(tournamentId: string): Promise<PairedRound[]> {
+  return invoke<PairedRound[]>("pair_tournament_round", { tournamentId });
+}
+
+export async function listPairings(
+  tournamentId: string,
+  roundIndex?: number,
+): Promise<PairingInfo[]> {
+  return invoke<PairingInfo[]>("list_pairings", { tournamentId, roundIndex });
+}
+
+/** Record or clear a result; knockouts pair their next round once the current one is done. */
+export async function recordPairingResult(
+  pairingId: string,
+  result: string | null,
+): Promise<PairingInfo> {
+  return invoke<PairingInfo>("record_pairing_result", { pairingId, result });
+}
+
+/** Standings after a round, the latest by default, optionally with other tie-breakers. */
+export async function getStandings(
+  tournamentId: string,
+  roundIndex?: number,
+  tieBreakers?: TieBreaker[],
+): Promise<StandingsTable> {
+  return invoke<StandingsTable>("get_standings", { tournamentId, roundIndex, tieBreakers });
+}
+
+/**
+ * Writes the tournament's results to `path`. Countries and clubs come from the `country` and
+ * `club` fields of the players' metadata, and AGA IDs from their federation IDs.
+ */
+export async function exportTournamentResults(
+  tournamentId: string,
+  format: ResultsFormat,
+  path: string,
+): Promise<void> {
+  await invoke("export_tournament_results", { tournamentId, format, path });
+}